@@ -1,4 +1,10 @@
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/SecureContainer.proto")?;
-    Ok(())
-}
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_build::configure()
+        // The messages are also used as JSON bodies by the REST gateway of the daemon.
+        .type_attribute(
+            ".SecureContainerService",
+            "#[derive(serde::Serialize, serde::Deserialize)] #[serde(rename_all = \"camelCase\", default)]",
+        )
+        .compile(&["proto/SecureContainer.proto"], &["proto"])?;
+    Ok(())
+}
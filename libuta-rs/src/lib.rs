@@ -1,6 +1,7 @@
 //! # Libuta Wrapper
 //! This module provides a wrapper for the libuta library.
-//! This module provides the functionality to derive a key from a string using the libuta library.
+//! This module provides the functionality to derive a key from a string using the libuta library
+//! and to get random data from the random number generator of the device.
 //!
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
@@ -9,7 +10,11 @@
 pub mod bindings;
 use bindings::*;
 
+/// The number of bytes of the derivation string that `libuta_derive_key` passes to the UTA.
+pub const LEGACY_DERIVATION_LENGTH: usize = 8;
 
+/// The maximum length in bytes of a derivation string that `libuta_derive_key_full` passes to the UTA.
+pub const MAX_DERIVATION_STRING_LENGTH: usize = 64;
 
 
 /// Derive a key from a string using the libuta library.
@@ -19,15 +24,50 @@ use bindings::*;
 /// * `Result<Vec<u8>>` -
 /// Returns a `Vec<u8>` containing a bytstream derived from the derivation_string if successful otherwise an error is returned.
 /// # Errors
-/// * `Err` - An error occurred while deriving the key.
+/// * `Err` - An error occurred while deriving the key or the derivation_string is empty.
 /// # Note
 /// This function uses unsafe code to interact with the libuta library that is written in C.
-/// Only up to the first eight characters of the derivation_string are used. The rest is ignored.
+/// Only up to the first `LEGACY_DERIVATION_LENGTH` characters of the derivation_string are used. The rest is ignored.
+/// Shorter strings are padded with zeros, so the same string always derives the same key.
+/// The keys of existing containers depend on this, use `libuta_derive_key_full` for the whole string.
 ///
 pub fn libuta_derive_key(derivation_string: &str) -> Result<Vec<u8>, String>{
     if derivation_string.is_empty() {
-        return Err("Error: Derivation string must be at least 8 characters long".into());
+        return Err("Error: Derivation string must not be empty".into());
     }
+    let mut derivation = [0u8; LEGACY_DERIVATION_LENGTH];
+    let length = derivation_string.len().min(LEGACY_DERIVATION_LENGTH);
+    derivation[..length].copy_from_slice(&derivation_string.as_bytes()[..length]);
+    derive_key(&derivation)
+}
+
+/// Derive a key from the whole string using the libuta library.
+/// # Arguments
+/// * `derivation_string` - The string to derive the key from.
+/// # Returns
+/// * `Result<Vec<u8>>` -
+/// Returns a `Vec<u8>` containing a bytstream derived from the derivation_string if successful otherwise an error is returned.
+/// # Errors
+/// * `Err` - An error occurred while deriving the key or the derivation_string is empty or longer than `MAX_DERIVATION_STRING_LENGTH` bytes.
+/// # Note
+/// This function uses unsafe code to interact with the libuta library that is written in C.
+/// The whole derivation_string is used, so two strings that only differ after the eighth character derive different keys.
+/// A string of exactly `LEGACY_DERIVATION_LENGTH` bytes derives the same key as with `libuta_derive_key`.
+///
+pub fn libuta_derive_key_full(derivation_string: &str) -> Result<Vec<u8>, String>{
+    if derivation_string.is_empty() {
+        return Err("Error: Derivation string must not be empty".into());
+    }
+    if derivation_string.len() > MAX_DERIVATION_STRING_LENGTH {
+        return Err(format!("Error: Derivation string must not be longer than {} bytes", MAX_DERIVATION_STRING_LENGTH));
+    }
+    derive_key(derivation_string.as_bytes())
+}
+
+/// Derive a key from bytes using the libuta library.
+/// # Note
+/// This function is not meant to be called directly.
+fn derive_key(derivation: &[u8]) -> Result<Vec<u8>, String>{
     unsafe {
         let mut uta: uta_api_v1_t = uta_api_v1_t {
             close: None,
@@ -46,15 +86,16 @@ pub fn libuta_derive_key(derivation_string: &str) -> Result<Vec<u8>, String>{
         }
 
         //UTA Open
-        let mut context: uta_context_v1_t = _uta_context_v1_t { _unused: [] };
-        rc = (uta.open.unwrap())(&mut context as *mut _);
+        let mut buffer = allocate_context(&uta);
+        let context = buffer.as_mut_ptr().cast::<uta_context_v1_t>();
+        rc = (uta.open.unwrap())(context);
         if rc != 0 {
             return Err("Error: UTA Open".into());
         }
 
         //UTA Get Device UUID
         let mut key_ptr = vec![0u8; 32];
-        rc = (uta.get_device_uuid.unwrap())(&mut context as *mut _, key_ptr.as_mut_ptr());
+        rc = (uta.get_device_uuid.unwrap())(context, key_ptr.as_mut_ptr());
         if rc != 0 {
             return Err("Error: UTA Get Device UUID".into());
         }
@@ -64,11 +105,11 @@ pub fn libuta_derive_key(derivation_string: &str) -> Result<Vec<u8>, String>{
 
         //UTA Derive Key
         rc = (uta.derive_key.unwrap())(
-            &mut context as *mut _,
+            context,
             key_ptr.as_mut_ptr(),
             len_key_max,
-            derivation_string.as_ptr(),
-            8,
+            derivation.as_ptr(),
+            derivation.len() as size_t,
             0,
         );
         if rc != 0 {
@@ -76,7 +117,7 @@ pub fn libuta_derive_key(derivation_string: &str) -> Result<Vec<u8>, String>{
         }
 
         //UTA Close
-        rc = (uta.close.unwrap())(&mut context as *mut _);
+        rc = (uta.close.unwrap())(context);
         if rc != 0 {
             return Err("Error: UTA Close".into());
         }
@@ -88,6 +129,133 @@ pub fn libuta_derive_key(derivation_string: &str) -> Result<Vec<u8>, String>{
     }
 }
 
+/// Get random data from the random number generator of the libuta library,
+/// e.g. for salts or passwords that do not have to be derived again.
+/// # Arguments
+/// * `length` - The number of random bytes.
+/// # Returns
+/// * `Result<Vec<u8>>` -
+/// Returns a `Vec<u8>` containing `length` random bytes if successful otherwise an error is returned.
+/// # Errors
+/// * `Err` - An error occurred while getting the random data.
+/// # Note
+/// This function uses unsafe code to interact with the libuta library that is written in C.
+///
+pub fn libuta_get_random(length: usize) -> Result<Vec<u8>, String>{
+    if length == 0 {
+        return Err("Error: Length must be at least 1".into());
+    }
+    unsafe {
+        let mut uta: uta_api_v1_t = uta_api_v1_t {
+            close: None,
+            context_v1_size: None,
+            derive_key: None,
+            get_device_uuid: None,
+            get_random: None,
+            len_key_max: None,
+            open: None,
+        };
+
+        //UTA Init
+        let mut rc: uta_rc = uta_init_v1(&mut uta as *mut _);
+        if rc != 0 {
+            return Err("Error: UTA Init".into());
+        }
+
+        //UTA Open
+        let mut buffer = allocate_context(&uta);
+        let context = buffer.as_mut_ptr().cast::<uta_context_v1_t>();
+        rc = (uta.open.unwrap())(context);
+        if rc != 0 {
+            return Err("Error: UTA Open".into());
+        }
+
+        //UTA Get Random
+        let mut random = vec![0u8; length];
+        rc = (uta.get_random.unwrap())(context, random.as_mut_ptr(), length as size_t);
+        if rc != 0 {
+            // The context is closed before the error of the random number generator is returned.
+            if (uta.close.unwrap())(context) != 0 {
+                return Err("Error: UTA Get Random and UTA Close".into());
+            }
+            return Err("Error: UTA Get Random".into());
+        }
+
+        //UTA Close
+        rc = (uta.close.unwrap())(context);
+        if rc != 0 {
+            return Err("Error: UTA Close".into());
+        }
+        Ok(random)
+    }
+}
+
+/// Get the UUID of the device from the libuta library, e.g. to bind a container to the device it was created on.
+/// # Returns
+/// * `Result<[u8; 32]>` - Returns the 32 bytes of the device UUID if successful otherwise an error is returned.
+/// # Errors
+/// * `Err` - An error occurred while getting the device UUID.
+/// # Note
+/// This function uses unsafe code to interact with the libuta library that is written in C.
+///
+pub fn libuta_get_device_uuid() -> Result<[u8; 32], String>{
+    unsafe {
+        let mut uta: uta_api_v1_t = uta_api_v1_t {
+            close: None,
+            context_v1_size: None,
+            derive_key: None,
+            get_device_uuid: None,
+            get_random: None,
+            len_key_max: None,
+            open: None,
+        };
+
+        //UTA Init
+        let mut rc: uta_rc = uta_init_v1(&mut uta as *mut _);
+        if rc != 0 {
+            return Err("Error: UTA Init".into());
+        }
+
+        //UTA Open
+        let mut buffer = allocate_context(&uta);
+        let context = buffer.as_mut_ptr().cast::<uta_context_v1_t>();
+        rc = (uta.open.unwrap())(context);
+        if rc != 0 {
+            return Err("Error: UTA Open".into());
+        }
+
+        //UTA Get Device UUID
+        let mut uuid = [0u8; 32];
+        rc = (uta.get_device_uuid.unwrap())(context, uuid.as_mut_ptr());
+        if rc != 0 {
+            if (uta.close.unwrap())(context) != 0 {
+                return Err("Error: UTA Get Device UUID and UTA Close".into());
+            }
+            return Err("Error: UTA Get Device UUID".into());
+        }
+
+        //UTA Close
+        rc = (uta.close.unwrap())(context);
+        if rc != 0 {
+            return Err("Error: UTA Close".into());
+        }
+        Ok(uuid)
+    }
+}
+
+/// Allocates the memory of a UTA context with the size that is reported by the library.
+/// `uta_context_v1_t` is opaque and has no size in Rust, so it can not be placed on the stack.
+/// # Arguments
+/// * `uta` - The initialized API of the library.
+/// # Returns
+/// * `Vec<u128>` - The zeroed memory of the context, it is aligned for every type of the context.
+/// # Note
+/// This function is not meant to be called directly.
+unsafe fn allocate_context(uta: &uta_api_v1_t) -> Vec<u128> {
+    let size = (uta.context_v1_size.unwrap())() as usize;
+    vec![0u128; size.div_ceil(std::mem::size_of::<u128>()).max(1)]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,30 +278,64 @@ mod tests {
 
 
     ///Test the libuta_derive_key function with multiple derivation_string with different length.
-    /// The function should return an error if the key is the same for two different derivation_string with length <= 8.
+    /// The function should return the same key for every derivation_string that only differs after the eighth byte.
     #[test]
     fn test_libuta_derive_key_length() {
         let mut derivation_string: String = "".to_owned();
         let mut result_old: Vec<u8> = vec![];
-        for _ in 0..1000 {
+        for length in 1..=MAX_DERIVATION_STRING_LENGTH {
             derivation_string = derivation_string.to_owned() + "a";
             let result = libuta_derive_key(&derivation_string);
-            if result.is_err() {
-                assert!(false);
-            }
-            else {
-                let result = result.unwrap();
-                if result == result_old && derivation_string.len() > 8{
-                    assert!(true);
-                    return;
-                } else {
-                    result_old = result;
-                }
+            assert!(result.is_ok());
+            let result = result.unwrap();
+            assert_eq!(result == result_old, length > LEGACY_DERIVATION_LENGTH);
+            result_old = result;
+        }
+    }
 
-            }
+    ///Test that the legacy output of libuta_derive_key is kept, the passwords of existing containers depend on it.
+    /// The function should use the first eight bytes and pad shorter derivation_string with zeros.
+    #[test]
+    fn test_libuta_derive_key_legacy() {
+        let legacy = libuta_derive_key("abcdefgh").unwrap();
+        assert_eq!(libuta_derive_key("abcdefgh1").unwrap(), legacy);
+        assert_eq!(libuta_derive_key("abcdefgh2").unwrap(), legacy);
+        assert_eq!(derive_key(b"abcdefgh").unwrap(), legacy);
+        assert_eq!(libuta_derive_key_full("abcdefgh").unwrap(), legacy);
+        assert_eq!(
+            libuta_derive_key("test").unwrap(),
+            derive_key(b"test\0\0\0\0").unwrap()
+        );
+    }
 
+    ///Test the libuta_derive_key_full function with multiple derivation_string with different length.
+    /// The function should return a different key for every length and an error for a too long derivation_string.
+    #[test]
+    fn test_libuta_derive_key_full_length() {
+        let mut derivation_string: String = "".to_owned();
+        let mut result_old: Vec<u8> = vec![];
+        for _ in 0..MAX_DERIVATION_STRING_LENGTH {
+            derivation_string = derivation_string.to_owned() + "a";
+            let result = libuta_derive_key_full(&derivation_string);
+            assert!(result.is_ok());
+            let result = result.unwrap();
+            assert!(result != result_old);
+            result_old = result;
         }
-        assert!(false)
+        derivation_string = derivation_string.to_owned() + "a";
+        assert!(libuta_derive_key_full(&derivation_string).is_err());
+        assert!(libuta_derive_key_full("").is_err());
+    }
+
+    ///Test the libuta_derive_key_full function with two derivation_string that only differ after the eighth byte.
+    /// The function should return a different key for both.
+    #[test]
+    fn test_libuta_derive_key_full_after_eighth_byte() {
+        let result = libuta_derive_key_full("abcdefgh1");
+        let result2 = libuta_derive_key_full("abcdefgh2");
+        assert!(result.is_ok());
+        assert!(result2.is_ok());
+        assert!(result.unwrap() != result2.unwrap());
     }
 
     ///Test the libuta_derive_key function with multiple derivation_string with different content.
@@ -169,4 +371,25 @@ mod tests {
         assert!(result2.is_ok());
         assert!(result.unwrap() == result2.unwrap());
     }
+
+    ///Test the libuta_get_random function.
+    /// The function should return the requested number of bytes and an error for zero bytes.
+    #[test]
+    fn test_libuta_get_random() {
+        let result = libuta_get_random(64);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 64);
+        assert!(libuta_get_random(0).is_err());
+    }
+
+    ///Test the libuta_get_device_uuid function for consistency.
+    /// The function should return the same UUID on the same device.
+    #[test]
+    fn test_libuta_get_device_uuid() {
+        let result = libuta_get_device_uuid();
+        let result2 = libuta_get_device_uuid();
+        assert!(result.is_ok());
+        assert!(result2.is_ok());
+        assert_eq!(result.unwrap(), result2.unwrap());
+    }
 }
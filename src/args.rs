@@ -1,5 +1,6 @@
 /// This file contains the structr and arguments for the command line interface.
 use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
 
 #[derive(Debug, Parser)]
 #[clap(
@@ -10,6 +11,12 @@ use clap::{Args, Parser, Subcommand};
 pub struct SecureContainerCli {
     #[clap(subcommand)]
     pub subcmd: SubCommand,
+    /// Abort the command if it does not finish within the given number of seconds
+    #[clap(short, long, global = true)]
+    pub timeout: Option<u64>,
+    /// URL of the daemon, unix:<path> for a Unix socket (default: SECURE_CONTAINER_URL or http://[::1]:50051)
+    #[clap(long, global = true)]
+    pub server: Option<String>,
 }
 
 /// Here are all possible subcommands for the CLI defined.
@@ -17,18 +24,72 @@ pub struct SecureContainerCli {
 pub enum SubCommand {
     /// Create a new container
     Create(Create),
+    /// Create a new container without file system and leave it unlocked
+    FormatOnly(FormatOnly),
     /// Open an existing container
     Open(Open),
     /// Close an existing container
     Close(Close),
+    /// Open an existing container without mounting it
+    OpenUnmounted(OpenUnmounted),
+    /// Mount a container that is already open
+    Mount(Mount),
     /// Export an existing container
     Export(Export),
     /// Import an existing container
     Import(Import),
+    /// Export an existing container into a bundle
+    ExportBundle(ExportBundle),
+    /// Import a container from a bundle
+    ImportBundle(ImportBundle),
     /// Add a container to auto open
     AddAutoOpen(AddAutoOpen),
     /// Remove a container from auto open
     RemoveAutoOpen(RemoveAutoOpen),
+    /// Change the mount point of a container in auto open
+    UpdateAutoOpen(UpdateAutoOpen),
+    /// Check the dependencies of the secure container service
+    Doctor,
+    /// Show the status of the containers
+    Status(Status),
+    /// List all known containers and their state in a table
+    List,
+    /// Rotate the keys of all containers in auto open to a new id
+    RotateKeys(RotateKeys),
+    /// Verify the integrity of an open container
+    Verify(Verify),
+    /// Validate inputs without creating or changing anything
+    Validate(Validate),
+    /// Bind-mount a directory of an open container to another directory
+    BindMount(BindMount),
+    /// Create an encrypted swap device with a random key and enable it
+    CreateSwap(CreateSwap),
+    /// Check if a container would open, without opening or mounting it
+    TestOpen(TestOpen),
+    /// Rebuild the auto open file from the containers that are open and mounted
+    #[command(name = "rebuild-autoopen", alias = "rebuild-auto-open")]
+    RebuildAutoOpen(RebuildAutoOpen),
+    /// Search a directory for LUKS containers
+    Scan(Scan),
+    /// Compare the auto open file with the containers that are open
+    Diff,
+    /// Report the public metadata and the state of every container
+    Inventory(Inventory),
+    /// Back up the LUKS header of a container to a file
+    BackupHeader(BackupHeader),
+    /// Restore the LUKS header of a container from a backup
+    RestoreHeader(RestoreHeader),
+    /// Show the cipher, key size, PBKDF and integrity algorithm of a container
+    Info(Info),
+    /// Replace the key of a closed container with the key of a new id
+    Rekey(Rekey),
+    /// Add a secret to a free key slot of a container, e.g. a recovery secret
+    AddKeySlot(AddKeySlot),
+    /// Remove the key slot of a container that is unlocked by a secret
+    RemoveKeySlot(RemoveKeySlot),
+    /// Print the completion script for a shell
+    #[command(hide = true)]
+    Completions(Completions),
 }
 
 /// Definition of the subcommand 'create' with all its arguments.
@@ -39,15 +100,32 @@ pub struct Create {
     pub size: i32,
     /// Mount point of the container
     pub mount_point: String,
-    /// Path of the container
+    /// Path of the container or a block device
     pub path: String,
     /// Name of the container
     pub namespace: String,
-    /// ID of the container
+    /// ID of the container (max 64 characters)
     pub id: String,
     /// Auto open the container
     #[clap(short, long)]
     pub auto_open: bool,
+    /// File system of the container: ext4, xfs or btrfs
+    #[clap(long, default_value = "ext4")]
+    pub fs_type: String,
+}
+
+/// Definition of the subcommand 'format-only' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct FormatOnly {
+    /// Size of the container in MB
+    pub size: i32,
+    /// Path of the container or a block device
+    pub path: String,
+    /// Name of the container
+    pub namespace: String,
+    /// ID of the container (max 64 characters)
+    pub id: String,
 }
 
 /// Definition of the subcommand 'open' with all its arguments.
@@ -60,14 +138,52 @@ pub struct Open {
     pub path: String,
     /// Name of the container
     pub namespace: String,
-    /// ID of the container
+    /// ID of the container (max 64 characters)
     pub id: String,
+    /// Use the password cached in the session keyring of the daemon and cache it there
+    #[arg(long)]
+    pub keyring: bool,
+    /// Mount the container read-only
+    #[arg(long)]
+    pub read_only: bool,
+    /// Open the dm-crypt mapping read-only, implies --read-only
+    #[arg(long)]
+    pub read_only_device: bool,
+    /// Only try the key of the ID on this key slot
+    #[arg(long)]
+    pub key_slot: Option<u32>,
 }
 
 /// Definition of the subcommand 'close' with all its arguments.
 #[derive(Debug, Args)]
 #[command(arg_required_else_help = true)]
 pub struct Close {
+    /// Mount point of the container, or its name if the name is not given
+    #[clap(value_name = "MOUNT_POINT|NAMESPACE")]
+    pub mount_point: String,
+    /// Name of the container
+    pub namespace: Option<String>,
+    /// Succeed if the container is already closed
+    #[arg(long)]
+    pub if_open: bool,
+}
+
+/// Definition of the subcommand 'open-unmounted' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct OpenUnmounted {
+    /// Path of the container
+    pub path: String,
+    /// Name of the container
+    pub namespace: String,
+    /// ID of the container (max 64 characters)
+    pub id: String,
+}
+
+/// Definition of the subcommand 'mount' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct Mount {
     /// Mount point of the container
     pub mount_point: String,
     /// Name of the container
@@ -82,10 +198,14 @@ pub struct Export {
     pub path: String,
     /// Name of the container
     pub namespace: String,
-    /// ID of the container
+    /// ID of the container (max 64 characters)
     pub id: String,
     /// Secret phrase of the container (needed for importing the container)
-    pub secret: String,
+    #[clap(required_unless_present = "keyfile")]
+    pub secret: Option<String>,
+    /// Keyfile that is used instead of the secret phrase
+    #[clap(short, long, conflicts_with = "secret")]
+    pub keyfile: Option<String>,
 }
 
 /// Definition of the subcommand 'import' with all its arguments.
@@ -96,10 +216,52 @@ pub struct Import {
     pub path: String,
     /// Name of the container
     pub namespace: String,
-    /// ID of the container
+    /// ID of the container (max 64 characters)
     pub id: String,
     /// Secret phrase of the container
-    pub secret: String,
+    #[clap(required_unless_present = "keyfile")]
+    pub secret: Option<String>,
+    /// Keyfile that is used instead of the secret phrase
+    #[clap(short, long, conflicts_with = "secret")]
+    pub keyfile: Option<String>,
+}
+
+/// Definition of the subcommand 'export-bundle' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct ExportBundle {
+    /// Path of the container
+    pub path: String,
+    /// Name of the container
+    pub namespace: String,
+    /// ID of the container (max 64 characters)
+    pub id: String,
+    /// Path of the bundle that is created
+    pub bundle: String,
+    /// Secret phrase for the bundle (needed for importing the bundle)
+    #[clap(required_unless_present = "keyfile")]
+    pub secret: Option<String>,
+    /// Keyfile that is used instead of the secret phrase
+    #[clap(short, long, conflicts_with = "secret")]
+    pub keyfile: Option<String>,
+}
+
+/// Definition of the subcommand 'import-bundle' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct ImportBundle {
+    /// Path of the bundle
+    pub bundle: String,
+    /// Path where the container is placed
+    pub path: String,
+    /// ID of the container (max 64 characters)
+    pub id: String,
+    /// Secret phrase the bundle was exported with
+    #[clap(required_unless_present = "keyfile")]
+    pub secret: Option<String>,
+    /// Keyfile that is used instead of the secret phrase
+    #[clap(short, long, conflicts_with = "secret")]
+    pub keyfile: Option<String>,
 }
 
 /// Definition of the subcommand 'add-auto-open' with all its arguments.
@@ -112,8 +274,11 @@ pub struct AddAutoOpen {
     pub path: String,
     /// Name of the container
     pub namespace: String,
-    /// ID of the container
+    /// ID of the container (max 64 characters)
     pub id: String,
+    /// Wait on startup for the path of the container to appear, e.g. on a network file system
+    #[arg(long)]
+    pub wait_for_path: bool,
 }
 
 /// Definition of the subcommand 'remove-auto-open' with all its arguments.
@@ -126,6 +291,206 @@ pub struct RemoveAutoOpen {
     pub path: String,
     /// Name of the container
     pub namespace: String,
-    /// ID of the container
+    /// ID of the container (max 64 characters)
     pub id: String,
 }
+
+/// Definition of the subcommand 'update-auto-open' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct UpdateAutoOpen {
+    /// Name of the container
+    pub namespace: String,
+    /// New mount point of the container
+    pub mount_point: String,
+}
+
+/// Definition of the subcommand 'status' with all its arguments.
+#[derive(Debug, Args)]
+pub struct Status {
+    /// Name of the container
+    pub namespace: Option<String>,
+    /// Also show open containers that are not in the auto open file
+    #[clap(short, long)]
+    pub all: bool,
+    /// Only check if the container is open and mounted, also if it is not in the auto open file
+    #[clap(short, long, requires = "namespace", conflicts_with = "all")]
+    pub state: bool,
+}
+
+/// Definition of the subcommand 'rotate-keys' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct RotateKeys {
+    /// New ID of the containers (max 64 characters)
+    pub new_id: String,
+}
+
+/// Definition of the subcommand 'verify' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct Verify {
+    /// Name of the container
+    pub namespace: String,
+    /// Only report integrity errors since this time, e.g. 2024-03-01T12:00 (default: when the container was opened)
+    #[clap(short, long)]
+    pub since: Option<String>,
+}
+
+/// Definition of the subcommand 'validate' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct Validate {
+    /// Size of the container in MB
+    #[clap(long)]
+    pub size: Option<i32>,
+    /// Mount point of the container
+    #[clap(long)]
+    pub mount_point: Option<String>,
+    /// Path of the container
+    #[clap(long)]
+    pub path: Option<String>,
+    /// Name of the container
+    #[clap(long)]
+    pub namespace: Option<String>,
+    /// ID of the container (max 64 characters)
+    #[clap(long)]
+    pub id: Option<String>,
+}
+
+/// Definition of the subcommand 'bind-mount' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct BindMount {
+    /// Name of the container
+    pub namespace: String,
+    /// Directory relative to the mount point of the container
+    pub source: String,
+    /// Directory where the source is mounted to
+    pub target: String,
+    /// Mount the target read-only
+    #[clap(short, long)]
+    pub read_only: bool,
+}
+
+/// Definition of the subcommand 'create-swap' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct CreateSwap {
+    /// Size of the swap device in MB
+    pub size: i32,
+    /// Name of the container
+    pub namespace: String,
+}
+
+/// Definition of the subcommand 'test-open' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct TestOpen {
+    /// Path of the container
+    pub path: String,
+    /// ID of the container (max 64 characters)
+    pub id: String,
+}
+
+/// Definition of the subcommand 'rebuild-autoopen' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct RebuildAutoOpen {
+    /// ID of a container, can be given multiple times
+    #[clap(short, long = "id", value_name = "NAMESPACE=ID")]
+    pub ids: Vec<String>,
+    /// File with one NAMESPACE=ID per line
+    #[clap(short, long)]
+    pub mapping: Option<String>,
+    /// ID of the containers without an ID of their own
+    #[clap(short, long)]
+    pub default_id: Option<String>,
+}
+
+/// Definition of the subcommand 'scan' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct Scan {
+    /// Directory that is searched, including its subdirectories
+    pub directory: String,
+}
+
+/// Definition of the subcommand 'inventory' with all its arguments.
+#[derive(Debug, Args)]
+pub struct Inventory {
+    /// Format of the report
+    #[arg(short, long, default_value = "json", value_parser = ["json", "csv"])]
+    pub format: String,
+}
+
+/// Definition of the subcommand 'backup-header' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct BackupHeader {
+    /// Path of the container
+    pub path: String,
+    /// Path of the backup, must not exist yet
+    pub out_file: String,
+}
+
+/// Definition of the subcommand 'restore-header' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct RestoreHeader {
+    /// Path of the container
+    pub path: String,
+    /// Path of the backup
+    pub in_file: String,
+}
+
+/// Definition of the subcommand 'info' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct Info {
+    /// Path of the container
+    pub path: String,
+}
+
+/// Definition of the subcommand 'rekey' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct Rekey {
+    /// Path of the container
+    pub path: String,
+    /// Current ID of the container (max 64 characters)
+    pub old_id: String,
+    /// New ID of the container (max 64 characters)
+    pub new_id: String,
+}
+
+/// Definition of the subcommand 'add-key-slot' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct AddKeySlot {
+    /// Path of the container
+    pub path: String,
+    /// ID of the container (max 64 characters)
+    pub id: String,
+    /// Secret that is added to a free key slot
+    pub secret: String,
+}
+
+/// Definition of the subcommand 'remove-key-slot' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct RemoveKeySlot {
+    /// Path of the container
+    pub path: String,
+    /// Secret of the key slot that is removed
+    pub secret: String,
+}
+
+/// Definition of the subcommand 'completions' with all its arguments.
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct Completions {
+    /// Shell the completion script is printed for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
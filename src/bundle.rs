@@ -0,0 +1,521 @@
+//! # Bundle
+//! This module exports containers as self-describing bundles and imports them again.
+//! A bundle is a tar archive with a manifest and the exported container.
+//! The manifest describes how the container was protected for the transfer,
+//! so the importing system can check the bundle before it touches the container.
+//!
+//! ## Format
+//! ```bash
+//! manifest.json  The manifest of the bundle
+//! container      The container, protected with the password derived from the secret or keyfile
+//! ```
+//! The manifest looks like this:
+//! ```json
+//! {
+//!   "version": 1,
+//!   "namespace": "MyContainer",
+//!   "uuid": "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11",
+//!   "cipher": "aes-xts-plain64",
+//!   "integrity": "hmac-sha256",
+//!   "kdf": { "algorithm": "argon2id", "iterations": 3, "salt": "q0MeF2bY7Zq8hXo1GJ4bXg" }
+//! }
+//! ```
+//! Bundles that were exported before Argon2id was used have the key derivation
+//! `{ "algorithm": "pbkdf2-hmac-sha256", "iterations": 600000 }` without salt, they can still be imported.
+//!
+
+use crate::error_handling;
+use error_handling::{Result, SecureContainerErr};
+
+use crate::input_validation::{check_input, device_name};
+
+use crate::cryptsetup_wrapper;
+use cryptsetup_wrapper::{
+    export_container, get_container_uuid, import_container_with_salt, transfer_salt,
+    TransferCredential, SECRET_KDF,
+};
+
+use crate::config::{config, CIPHERS};
+use crate::cryptsetup_backend::INTEGRITY;
+
+use crate::metadata::{
+    read_metadata, store_metadata, ContainerMetadata, LEGACY_KEY_DERIVATION, METADATA_VERSION,
+};
+
+use crate::file_system_operations::check_if_file_exists;
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::OpenOptionsExt;
+
+/// The version of the bundle format.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// The name of the manifest in the bundle.
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// The name of the container in the bundle.
+const CONTAINER_NAME: &str = "container";
+
+/// The key derivation of the password that protects the container in the bundle.
+/// * `algorithm` - The name of the algorithm.
+/// * `iterations` - The number of iterations of the algorithm.
+/// * `salt` - The salt of Argon2id, empty for the other algorithms.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Kdf {
+    pub algorithm: String,
+    pub iterations: u32,
+    #[serde(default)]
+    pub salt: String,
+}
+
+/// The manifest of a bundle.
+/// * `version` - The version of the bundle format.
+/// * `namespace` - The name of the container, it is the salt of the key derivation of bundles without salt.
+/// * `uuid` - The UUID of the LUKS header of the container.
+/// * `cipher` - The cipher of the encrypted data.
+/// * `integrity` - The algorithm of the integrity protection.
+/// * `kdf` - The key derivation of the password that protects the container.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub namespace: String,
+    pub uuid: String,
+    pub cipher: String,
+    pub integrity: String,
+    pub kdf: Kdf,
+}
+
+/// Returns the key derivation that is used for the given credential, without salt.
+fn kdf(credential: &TransferCredential) -> Kdf {
+    let (algorithm, iterations) = credential.kdf();
+    Kdf {
+        algorithm: algorithm.to_string(),
+        iterations,
+        salt: String::new(),
+    }
+}
+
+/// Exports a container into a bundle.
+/// The container is exported like with `export_container` and then written to the bundle
+/// together with its manifest.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// * `credential` - The secret or keyfile for the transfer (is needed when the bundle is imported).
+/// * `bundle_path` - The path of the bundle that is created.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the bundle was created successfully otherwise an error is returned.
+/// # Errors
+/// * `FileExists` - A file already exists at the path of the bundle.
+/// * `FileCreationError` - The bundle could not be created.
+/// * `FileWriteError` - An error occurred while writing the bundle.
+/// * `FileReadError` - An error occurred while reading the container.
+/// * All errors of `export_container`.
+/// # Example
+/// ```
+/// let credential = TransferCredential::Secret("mySecret".to_string());
+/// let result = export_bundle("/home/Container", "MyContainer", "myId", &credential, "/home/Container.tar");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn export_bundle(
+    path: &str,
+    namespace: &str,
+    id: &str,
+    credential: &TransferCredential,
+    bundle_path: &str,
+) -> Result<()> {
+    if check_if_file_exists(bundle_path) {
+        return Err(SecureContainerErr::FileExists);
+    }
+    export_container(path, namespace, id, credential)?;
+
+    let manifest = Manifest {
+        version: BUNDLE_VERSION,
+        namespace: namespace.to_string(),
+        uuid: get_container_uuid(path)?,
+        cipher: config().cipher.clone(),
+        integrity: INTEGRITY.to_string(),
+        kdf: Kdf {
+            salt: transfer_salt(path, credential),
+            ..kdf(credential)
+        },
+    };
+    let manifest = match serde_json::to_vec_pretty(&manifest) {
+        Ok(manifest) => manifest,
+        Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+    };
+
+    let mut container = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    // The size of a block device is only known after seeking to its end.
+    let size = match container
+        .seek(SeekFrom::End(0))
+        .and_then(|size| container.rewind().map(|_| size))
+    {
+        Ok(size) => size,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+
+    let bundle = match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(bundle_path)
+    {
+        Ok(file) => file,
+        Err(err) => return Err(SecureContainerErr::FileCreationError(err.to_string())),
+    };
+    let mut builder = tar::Builder::new(bundle);
+    let result = append(
+        &mut builder,
+        MANIFEST_NAME,
+        manifest.len() as u64,
+        &manifest[..],
+    )
+    .and_then(|_| append(&mut builder, CONTAINER_NAME, size, container))
+    .and_then(|_| builder.finish());
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            let _ = std::fs::remove_file(bundle_path);
+            Err(SecureContainerErr::FileWriteError(err.to_string()))
+        }
+    }
+}
+
+/// Appends a regular file with mode 600 to a bundle.
+fn append<R: Read>(
+    builder: &mut tar::Builder<File>,
+    name: &str,
+    size: u64,
+    data: R,
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o600);
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data)
+}
+
+/// Checks that a manifest can be imported with the given credential.
+/// # Arguments
+/// * `manifest` - The manifest of the bundle.
+/// * `credential` - The secret or keyfile for the transfer.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the manifest is valid otherwise an error is returned.
+/// # Errors
+/// * `BundleNotValid` -
+/// The version, the cipher or the key derivation of the bundle is not supported
+/// or the salt is missing for Argon2id.
+/// * `SecertError` - The bundle was exported with the other kind of credential.
+/// * `NamespaceNotValid` - The namespace of the manifest is not valid.
+fn check_manifest(manifest: &Manifest, credential: &TransferCredential) -> Result<()> {
+    if manifest.version != BUNDLE_VERSION
+        || !CIPHERS.contains(&manifest.cipher.as_str())
+        || manifest.integrity != INTEGRITY
+    {
+        return Err(SecureContainerErr::BundleNotValid);
+    }
+    check_input(None, None, None, Some(&manifest.namespace), None)?;
+    // Only Argon2id derives the password with the salt of the manifest.
+    if manifest.kdf.salt.is_empty() == (manifest.kdf.algorithm == SECRET_KDF) {
+        return Err(SecureContainerErr::BundleNotValid);
+    }
+    let used = Some((manifest.kdf.algorithm.as_str(), manifest.kdf.iterations));
+    if used == Some(credential.kdf()) || used == credential.legacy_kdf() {
+        return Ok(());
+    }
+    let other = match credential {
+        TransferCredential::Secret(_) => TransferCredential::Keyfile(Default::default()),
+        TransferCredential::Keyfile(_) => TransferCredential::Secret(String::new()),
+    };
+    if used == Some(other.kdf()) || used == other.legacy_kdf() {
+        return Err(SecureContainerErr::SecertError);
+    }
+    Err(SecureContainerErr::BundleNotValid)
+}
+
+/// Imports a container from a bundle.
+/// The manifest is checked, the container is extracted to the given path
+/// and imported like with `import_container`.
+/// Afterwards the metadata of the container is stored in its LUKS2 header.
+/// If the import fails, the extracted container is removed again.
+/// # Arguments
+/// * `bundle_path` - The path to the bundle.
+/// * `path` - The path where the container is placed, it must not exist.
+/// * `id` - The id of the container.
+/// * `credential` - The secret or keyfile the bundle was exported with.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the container was imported successfully otherwise an error is returned.
+/// # Errors
+/// * `FileExists` - A file already exists at the given path.
+/// * `FileOpenError` - The bundle could not be opened.
+/// * `FileReadError` - An error occurred while reading the bundle.
+/// * `FileCreationError` - The container could not be created.
+/// * `FileWriteError` - An error occurred while writing the container or its metadata.
+/// * `BundleNotValid` - The bundle is not a valid bundle or its version is not supported.
+/// * All errors of `import_container`.
+/// # Example
+/// ```
+/// let credential = TransferCredential::Secret("mySecret".to_string());
+/// let result = import_bundle("/home/Container.tar", "/home/Container", "myId", &credential);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn import_bundle(
+    bundle_path: &str,
+    path: &str,
+    id: &str,
+    credential: &TransferCredential,
+) -> Result<()> {
+    check_input(None, None, None, None, Some(id))?;
+    if !path.is_ascii() || path.contains('|') {
+        return Err(SecureContainerErr::PathNotValid);
+    }
+    if check_if_file_exists(path) {
+        return Err(SecureContainerErr::FileExists);
+    }
+
+    let manifest = extract_bundle(bundle_path, path, credential)?;
+    match register_container(path, &manifest, id, credential) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            let _ = std::fs::remove_file(path);
+            Err(err)
+        }
+    }
+}
+
+/// Reads the manifest of a bundle and extracts the container to the given path.
+/// # Note
+/// This function is not meant to be called directly.
+fn extract_bundle(
+    bundle_path: &str,
+    path: &str,
+    credential: &TransferCredential,
+) -> Result<Manifest> {
+    let bundle = match File::open(bundle_path) {
+        Ok(file) => file,
+        Err(err) => return Err(SecureContainerErr::FileOpenError(err.to_string())),
+    };
+    let mut archive = tar::Archive::new(bundle);
+    let mut entries = match archive.entries() {
+        Ok(entries) => entries,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+
+    // The manifest is the first entry, so the bundle is checked before the container is written.
+    let mut manifest = match entries.next() {
+        Some(Ok(entry)) => entry,
+        Some(Err(err)) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+        None => return Err(SecureContainerErr::BundleNotValid),
+    };
+    if !is_entry(&manifest, MANIFEST_NAME) {
+        return Err(SecureContainerErr::BundleNotValid);
+    }
+    let mut json = String::new();
+    if manifest.read_to_string(&mut json).is_err() {
+        return Err(SecureContainerErr::BundleNotValid);
+    }
+    let manifest: Manifest = match serde_json::from_str(&json) {
+        Ok(manifest) => manifest,
+        Err(_) => return Err(SecureContainerErr::BundleNotValid),
+    };
+    check_manifest(&manifest, credential)?;
+
+    let mut container = match entries.next() {
+        Some(Ok(entry)) => entry,
+        Some(Err(err)) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+        None => return Err(SecureContainerErr::BundleNotValid),
+    };
+    if !is_entry(&container, CONTAINER_NAME) {
+        return Err(SecureContainerErr::BundleNotValid);
+    }
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(err) => return Err(SecureContainerErr::FileCreationError(err.to_string())),
+    };
+    if let Err(err) = std::io::copy(&mut container, &mut file) {
+        let _ = std::fs::remove_file(path);
+        return Err(SecureContainerErr::FileWriteError(err.to_string()));
+    }
+    Ok(manifest)
+}
+
+/// Checks that an entry of a bundle is a regular file with the given name.
+fn is_entry<R: Read>(entry: &tar::Entry<R>, name: &str) -> bool {
+    entry.header().entry_type().is_file()
+        && entry
+            .path()
+            .map(|path| path.to_str() == Some(name))
+            .unwrap_or(false)
+}
+
+/// Imports an extracted container and stores its metadata.
+/// # Note
+/// This function is not meant to be called directly.
+fn register_container(
+    path: &str,
+    manifest: &Manifest,
+    id: &str,
+    credential: &TransferCredential,
+) -> Result<()> {
+    if get_container_uuid(path)? != manifest.uuid {
+        return Err(SecureContainerErr::BundleNotValid);
+    }
+    import_container_with_salt(
+        path,
+        &manifest.namespace,
+        id,
+        credential,
+        &manifest.kdf.salt,
+    )?;
+    // The token travels inside the container, so the time it was created on the other system
+    // and the key salt and key derivation the container was imported with are kept.
+    let (created, key_salt, key_derivation) = match read_metadata(path)? {
+        Some(metadata) => (metadata.created, metadata.key_salt, metadata.key_derivation),
+        None => (String::new(), String::new(), LEGACY_KEY_DERIVATION),
+    };
+    store_metadata(
+        path,
+        &ContainerMetadata {
+            version: METADATA_VERSION,
+            namespace: manifest.namespace.clone(),
+            device_name: device_name(&manifest.namespace),
+            uuid: manifest.uuid.clone(),
+            cipher: manifest.cipher.clone(),
+            integrity: manifest.integrity.clone(),
+            // The key slot was replaced by the import, so it uses the PBKDF of this system.
+            pbkdf: config().luks_pbkdf.clone(),
+            iter_time: config().luks_iter_time,
+            created,
+            transfer_salt: String::new(),
+            // The manifest does not record the file system.
+            file_system: String::new(),
+            key_salt,
+            key_derivation,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(credential: &TransferCredential) -> Manifest {
+        let salt = match credential {
+            TransferCredential::Secret(_) => "q0MeF2bY7Zq8hXo1GJ4bXg".to_string(),
+            TransferCredential::Keyfile(_) => String::new(),
+        };
+        Manifest {
+            version: BUNDLE_VERSION,
+            namespace: "test".to_string(),
+            uuid: "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11".to_string(),
+            cipher: config().cipher.clone(),
+            integrity: INTEGRITY.to_string(),
+            kdf: Kdf {
+                salt,
+                ..kdf(credential)
+            },
+        }
+    }
+
+    #[test]
+    fn test_check_manifest() {
+        let secret = TransferCredential::Secret("secret".to_string());
+        let keyfile = TransferCredential::Keyfile("/tmp/transfer.key".into());
+        assert_eq!(check_manifest(&manifest(&secret), &secret), Ok(()));
+        assert_eq!(check_manifest(&manifest(&keyfile), &keyfile), Ok(()));
+        assert_eq!(
+            check_manifest(&manifest(&keyfile), &secret),
+            Err(SecureContainerErr::SecertError)
+        );
+
+        let mut newer = manifest(&secret);
+        newer.version = BUNDLE_VERSION + 1;
+        assert_eq!(
+            check_manifest(&newer, &secret),
+            Err(SecureContainerErr::BundleNotValid)
+        );
+
+        let mut cipher = manifest(&secret);
+        cipher.cipher = "aes-cbc-essiv:sha256".to_string();
+        assert_eq!(
+            check_manifest(&cipher, &secret),
+            Err(SecureContainerErr::BundleNotValid)
+        );
+
+        let mut iterations = manifest(&secret);
+        iterations.kdf.iterations = 1000;
+        assert_eq!(
+            check_manifest(&iterations, &secret),
+            Err(SecureContainerErr::BundleNotValid)
+        );
+
+        let mut unsalted = manifest(&secret);
+        unsalted.kdf.salt = String::new();
+        assert_eq!(
+            check_manifest(&unsalted, &secret),
+            Err(SecureContainerErr::BundleNotValid)
+        );
+
+        // Bundles that were exported with PBKDF2 can still be imported.
+        let (algorithm, iterations) = secret.legacy_kdf().unwrap();
+        let mut legacy = manifest(&keyfile);
+        legacy.kdf.algorithm = algorithm.to_string();
+        legacy.kdf.iterations = iterations;
+        assert_eq!(check_manifest(&legacy, &secret), Ok(()));
+        assert_eq!(
+            check_manifest(&legacy, &keyfile),
+            Err(SecureContainerErr::SecertError)
+        );
+
+        let mut namespace = manifest(&secret);
+        namespace.namespace = "test|".to_string();
+        assert_eq!(
+            check_manifest(&namespace, &secret),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_extract_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = TransferCredential::Secret("secret".to_string());
+        let bundle_path = dir.path().join("bundle.tar");
+        let path = dir.path().join("container");
+        let json = serde_json::to_vec(&manifest(&secret)).unwrap();
+        let mut builder = tar::Builder::new(File::create(&bundle_path).unwrap());
+        append(&mut builder, MANIFEST_NAME, json.len() as u64, &json[..]).unwrap();
+        append(&mut builder, CONTAINER_NAME, 4, &b"LUKS"[..]).unwrap();
+        builder.finish().unwrap();
+
+        let result = extract_bundle(
+            bundle_path.to_str().unwrap(),
+            path.to_str().unwrap(),
+            &secret,
+        );
+        assert_eq!(result, Ok(manifest(&secret)));
+        assert_eq!(std::fs::read(&path).unwrap(), b"LUKS");
+
+        let other = dir.path().join("other");
+        let result = extract_bundle(path.to_str().unwrap(), other.to_str().unwrap(), &secret);
+        assert!(result.is_err());
+        assert!(!other.exists());
+    }
+}
@@ -8,6 +8,12 @@
 //! ```bash
 //! secure_container_cli [SUBCOMMAND] [OPTIONS]
 //! ```
+//! Every subcommand accepts the option `-t, --timeout <SECONDS>`.
+//! If the command does not finish within the given number of seconds, it is aborted with exit code 34.
+//! The daemon receives the timeout as well and stops the commands it runs for the request.
+//! Every subcommand also accepts the option `--server <URL>` to reach a daemon that does not listen on `http://[::1]:50051`,
+//! without it the URL is taken from the environment variable `SECURE_CONTAINER_URL` if it is set.
+//! A daemon that listens on a Unix socket is reached with `--server unix:/run/secure_container.sock`.
 //! The following subcommands are available:
 //! ### Create
 //! This is a subcommand to create a new Container.
@@ -20,44 +26,120 @@
 //! ```bash
 //!   <SIZE>         Size of the container in MB (at least 16MB)
 //!   <MOUNT_POINT>  Mount point of the container
-//!   <PATH>         Path where the container should be stored
-//!   <NAMESPACE>    Name of the container
-//!   <ID>           ID of the container (max 8 characters)
+//!   <PATH>         Path where the container should be stored or a block device that is used as container
+//!   <NAMESPACE>    Name of the container (max 64 characters)
+//!   <ID>           ID of the container (max 64 characters)
 //! ```
 //! <u> Options: </u>
 //! ```bash
-//!  -a, --auto-open   To add the container to the AutoOpen file so that it is automatically opened when the system starts.
-//!  -h, --help        Print help
+//!  -a, --auto-open          To add the container to the AutoOpen file so that it is automatically opened when the system starts.
+//!      --fs-type <FS_TYPE>  File system of the container: ext4, xfs or btrfs [default: ext4]
+//!  -h, --help               Print help
 //! ```
+//! On success the mapper device, mount point, size and UUID of the new container are printed.
+//! If stderr is a terminal, the progress of the creation is shown as a bar while the container is created.
+//! The file system is only created here, opening the container later mounts the existing file system.
+//!
+//! ### Format-only
+//! This is a subcommand to create a new Container without a file system.
+//! The container is left unlocked and not mounted,
+//! so that the mapper device can be formatted or used directly.
+//! It is not added to the AutoOpen file.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli format-only <SIZE> <PATH> <NAMESPACE> <ID>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <SIZE>       Size of the container in MB (at least 16MB)
+//!   <PATH>       Path where the container should be stored or a block device that is used as container
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//!   <ID>         ID of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//!  -h, --help  Print help
+//! ```
+//! On success the mapper device, size and UUID of the new container are printed.
 //!
 //! ### Open
 //! This is a subcommand to open an existing Container.
 //! <u> Usage: </u>
 //! ```bash
-//! secure_container_cli open <MOUNT_POINT> <PATH> <NAMESPACE> <ID>
+//! secure_container_cli open [OPTIONS] <MOUNT_POINT> <PATH> <NAMESPACE> <ID>
 //! ```
 //! <u> Arguments: </u>
 //! ```bash
 //!   <MOUNT_POINT>  Mount point of the container
 //!   <PATH>         Path of the container
-//!   <NAMESPACE>    Name of the container
-//!   <ID>           ID of the container (max 8 characters)
+//!   <NAMESPACE>    Name of the container (max 64 characters)
+//!   <ID>           ID of the container (max 64 characters)
 //! ```
 //! <u> Options: </u>
 //! ```bash
-//! -h, --help  Print help
+//!     --keyring                Use the password cached in the session keyring of the daemon and cache it there
+//!     --read-only              Mount the container read-only
+//!     --read-only-device       Open the dm-crypt mapping read-only, implies --read-only
+//!     --key-slot <KEY_SLOT>    Only try the key of the ID on this key slot
+//! -h, --help                   Print help
 //! ```
+//! A container that is already mounted keeps the options it was mounted with.
+//! With `--read-only-device` nothing can be written to the container at all, not even by the file system,
+//! e.g. to inspect a container for forensics.
 //!
 //! ### Close
 //! This is a subcommand to close an existing Container.
 //! <u> Usage: </u>
 //! ```bash
 //! secure_container_cli close <MOUNT_POINT> <NAMESPACE>
+//! secure_container_cli close <NAMESPACE>
+//! ```
+//! If only the name is given, the mount point is taken from the AutoOpen file
+//! or, for a container that is not in the AutoOpen file, from the mounted file systems.
+//! With `--if-open` a container that is already closed is not an error, e.g. for cleanup scripts.
+//!
+//! <u> Arguments: </u>
+//! ```bash
+//!   <MOUNT_POINT>  Mount point of the container ("" for a container created with format-only or create-swap)
+//!   <NAMESPACE>    Name of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//!     --if-open  Succeed if the container is already closed
+//! -h, --help     Print help
+//! ```
+//! ### Open-unmounted
+//! This is a subcommand to open an existing Container without mounting it,
+//! e.g. to take an image of the mapper device or to check its file system.
+//! The integrity check is done as with `open`, the mapper device is printed.
+//! The container can be mounted later with `mount` and is closed with `close "" <NAMESPACE>` as long as it is not mounted.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli open-unmounted <PATH> <NAMESPACE> <ID>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>       Path of the container
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//!   <ID>         ID of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//! ### Mount
+//! This is a subcommand to mount a Container that is already open, e.g. after `open-unmounted`.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli mount <MOUNT_POINT> <NAMESPACE>
 //! ```
 //! <u> Arguments: </u>
 //! ```bash
 //!   <MOUNT_POINT>  Mount point of the container
-//!   <NAMESPACE>    Name of the container
+//!   <NAMESPACE>    Name of the container (max 64 characters)
 //! ```
 //! <u> Options: </u>
 //! ```bash
@@ -67,57 +149,102 @@
 //! This is a subcommand to export an existing Container to transfer it to a different system.
 //! <u> Usage: </u>
 //! ```bash
-//! secure_container_cli export <PATH> <NAMESPACE> <ID> <SECRET>
+//! secure_container_cli export <PATH> <NAMESPACE> <ID> [SECRET]
 //! ```
 //! <u> Arguments: </u>
 //! ```bash
 //!   <PATH>       Path of the container
-//!   <NAMESPACE>  Name of the container
-//!   <ID>         ID of the container (max 8 characters)
-//!   <SECRET>     Secret phrase of the container (needed for importing the container)
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//!   <ID>         ID of the container (max 64 characters)
+//!   [SECRET]     Secret phrase of the container (needed for importing the container)
 //! ```bash
 //! <u> Options: </u>
 //! ```bash
-//! -h, --help  Print help
+//! -k, --keyfile <KEYFILE>  Keyfile that is used instead of the secret phrase
+//! -h, --help               Print help
 //! ```
 //! ### Import
 //! This is a subcommand to import an existing Container that was exported on another system.
 //!
 //! <u> Usage: </u>
 //! ```bash
-//! secure_container_cli import <PATH> <NAMESPACE> <ID> <SECRET>
+//! secure_container_cli import <PATH> <NAMESPACE> <ID> [SECRET]
 //! ```
 //! <u> Arguments: </u>
 //! ```bash
 //!   <PATH>       Path of the container
-//!   <NAMESPACE>  Name of the container
-//!   <ID>         ID of the container (max 8 characters)
-//!   <SECRET>     Secret phrase of the container
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//!   <ID>         ID of the container (max 64 characters)
+//!   [SECRET]     Secret phrase of the container
 //! ```
 //! <u> Options: </u>
 //! ```bash
-//! -h, --help  Print help
+//! -k, --keyfile <KEYFILE>  Keyfile the container was exported with, used instead of the secret phrase
+//! -h, --help               Print help
+//! ```
+//! ### ExportBundle
+//! This is a subcommand to export an existing Container into a bundle.
+//! The bundle contains the container and a manifest that describes it.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli export-bundle <PATH> <NAMESPACE> <ID> <BUNDLE> [SECRET]
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>       Path of the container
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//!   <ID>         ID of the container (max 64 characters)
+//!   <BUNDLE>     Path of the bundle that is created
+//!   [SECRET]     Secret phrase for the bundle (needed for importing the bundle)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -k, --keyfile <KEYFILE>  Keyfile that is used instead of the secret phrase
+//! -h, --help               Print help
+//! ```
+//! ### ImportBundle
+//! This is a subcommand to import a Container from a bundle that was exported on another system.
+//! The name of the container is taken from the bundle.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli import-bundle <BUNDLE> <PATH> <ID> [SECRET]
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <BUNDLE>     Path of the bundle
+//!   <PATH>       Path where the container is placed (must not exist)
+//!   <ID>         ID of the container (max 64 characters)
+//!   [SECRET]     Secret phrase the bundle was exported with
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -k, --keyfile <KEYFILE>  Keyfile the bundle was exported with, used instead of the secret phrase
+//! -h, --help               Print help
 //! ```
 //!
 //! ### AddAutoOpen
 //! This is a subcommand
 //! for adding an existing Container to the AutoOpen file
 //! so that it gets automatically opened on startup.
+//! A container that is already in the AutoOpen file with the same arguments is not added again.
 //!
 //! <u> Usage: </u>
 //! ```bash
-//! secure_container_cli add-auto-open <MOUNT_POINT> <PATH> <NAMESPACE> <ID>
+//! secure_container_cli add-auto-open [OPTIONS] <MOUNT_POINT> <PATH> <NAMESPACE> <ID>
 //! ```
 //! <u> Arguments: </u>
 //! ```bash
 //!   <MOUNT_POINT>  Mount point of the container
 //!   <PATH>         Path of the container
-//!   <NAMESPACE>    Name of the container
-//!   <ID>           ID of the container (max 8 characters)
+//!   <NAMESPACE>    Name of the container (max 64 characters)
+//!   <ID>           ID of the container (max 64 characters)
 //! ```
 //! <u> Options: </u>
 //! ```bash
-//! -h, --help  Print help
+//!     --wait-for-path  Wait on startup for the path of the container to appear, e.g. on a network file system
+//! -h, --help           Print help
 //! ```
 //! ### RemoveAutoOpen
 //! This is a subcommand
@@ -126,14 +253,414 @@
 //!
 //! <u> Usage: </u>
 //! ```bash
-//! secure_container_cli remove-auto-open <MOUNT_POINT> <PATH> <NAMESPACE> <ID>
+//! secure_container_cli remove-auto-open <MOUNT_POINT> <PATH> <NAMESPACE> <ID>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <MOUNT_POINT>  Mount point of the container
+//!   <PATH>         Path of the container
+//!   <NAMESPACE>    Name of the container (max 64 characters)
+//!   <ID>           ID of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### UpdateAutoOpen
+//! This is a subcommand
+//! for changing the mount point of a Container in the AutoOpen file,
+//! without removing and adding it again.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli update-auto-open <NAMESPACE> <MOUNT_POINT>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <NAMESPACE>    Name of the container (max 64 characters)
+//!   <MOUNT_POINT>  New mount point of the container
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### Doctor
+//! This is a subcommand
+//! for checking that all binaries the daemon depends on are present,
+//! that the libuta device responds and that the kernel supports the integrity check.
+//! A report is printed and the CLI exits with a non-zero code if an essential dependency is missing.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli doctor
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### Status
+//! This is a subcommand
+//! for showing whether the containers are open and mounted.
+//! By default only the containers in the autoOpen file (registered) are shown.
+//! With `--all` every open crypt device is shown, also the ones opened outside of the secure container service (ad-hoc).
+//! For registered containers the number of used and free key slots is shown as well.
+//! While an operation of the daemon is in progress for a container, e.g. `create` or `close`,
+//! the operation is shown instead of the state, because the state changes with the operation.
+//! With `--state` only the state of the given container is checked,
+//! it is printed as `<NAMESPACE> closed`, `<NAMESPACE> open` or `<NAMESPACE> mounted <MOUNT_POINT>`,
+//! so scripts can poll it.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli status [NAMESPACE] [OPTIONS]
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   [NAMESPACE]    Name of the container
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -a, --all    Also show open containers that are not in the auto open file
+//! -s, --state  Only check if the container is open and mounted, also if it is not in the auto open file
+//! -h, --help   Print help
+//! ```
+//! ### List
+//! This is a subcommand
+//! for listing all known containers in a table, like `status --all`.
+//! The table contains the registered containers of the autoOpen file and the open ad-hoc containers
+//! with their namespace, source, state, mount point and path.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli list
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help   Print help
+//! ```
+//! ### RotateKeys
+//! This is a subcommand
+//! for changing the passwords of all containers in the autoOpen file to the ones derived from a new ID,
+//! e.g. before the hardware that derives the keys is replaced.
+//! Open containers are not rotated, they have to be closed first.
+//! The command can be repeated until every container was rotated.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli rotate-keys <NEW_ID>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <NEW_ID>  New ID of the containers (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//! ### RebuildAutoOpen
+//! This is a subcommand
+//! for rebuilding the autoOpen file from the containers that are open and mounted, e.g. after it was lost.
+//! The IDs of the containers can not be read from the system, so they have to be given.
+//! A container is only written to the autoOpen file if the password of its ID unlocks it,
+//! the other containers are reported and the CLI exits with the exit code of the first of them.
+//! All entries of the autoOpen file are replaced.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli rebuild-autoopen [OPTIONS]
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -i, --id <NAMESPACE=ID>          ID of a container, can be given multiple times
+//! -m, --mapping <MAPPING>          File with one NAMESPACE=ID per line, lines starting with # are ignored
+//! -d, --default-id <DEFAULT_ID>    ID of the containers without an ID of their own
+//! -h, --help                       Print help
+//! ```
+//! ### Scan
+//! This is a subcommand
+//! for searching a directory and its subdirectories for LUKS containers, e.g. to add existing containers to the AutoOpen file.
+//! The path, the LUKS version and the UUID of every container that is found are printed.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli scan <DIRECTORY>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <DIRECTORY>  Directory that is searched, including its subdirectories
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//! ### Diff
+//! This is a subcommand
+//! for comparing the AutoOpen file with the containers that are open.
+//! A table with the state of every container is printed:
+//! `open` and `closed` for registered containers, `missing` for registered containers whose file does not exist
+//! and `unregistered` for open containers that are not in the AutoOpen file.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli diff
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//! ### Inventory
+//! This is a subcommand
+//! for reporting the public metadata and the state of every container, e.g. for an audit.
+//! The report contains the registered containers and the open ad-hoc containers
+//! with their namespace, path, mount point, UUID, cipher, integrity, creation time and state, it contains no secrets.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli inventory [OPTIONS]
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -f, --format <FORMAT>  Format of the report [default: json] [possible values: json, csv]
+//! -h, --help             Print help
+//! ```
+//! ### Verify
+//! This is a subcommand
+//! for checking an open container for integrity errors in the kernel log.
+//! By default only the errors since the daemon opened the container are reported,
+//! with `--since` the errors since the given time, e.g. since the last check.
+//! If the daemon did not open the container and no time is given, the whole kernel log is checked.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli verify <NAMESPACE> [OPTIONS]
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -s, --since <SINCE>  Only report integrity errors since this time, e.g. 2024-03-01T12:00
+//! -h, --help           Print help
+//! ```
+//! ### BindMount
+//! This is a subcommand
+//! for mounting a directory of an open container additionally to another directory,
+//! e.g. into a chroot or the root file system of a container runtime.
+//! The bind mounts are unmounted before the container is closed.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli bind-mount <NAMESPACE> <SOURCE> <TARGET> [OPTIONS]
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//!   <SOURCE>     Directory relative to the mount point of the container
+//!   <TARGET>     Directory where the source is mounted to
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -r, --read-only  Mount the target read-only
+//! -h, --help       Print help
+//! ```
+//! ### CreateSwap
+//! This is a subcommand
+//! for creating an encrypted swap device and enabling it.
+//! The container is keyed with random data from libuta that is not stored,
+//! so it is created again on every boot and its content is lost when it is closed.
+//! It is closed with `close "" <NAMESPACE>`, which disables swapping on it first.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli create-swap <SIZE> <NAMESPACE>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <SIZE>       Size of the swap device in MB (at least 16MB)
+//!   <NAMESPACE>  Name of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//! On success the mapper device, size and UUID of the swap device are printed.
+//!
+//! ### TestOpen
+//! This is a subcommand
+//! for checking if the key of a container still works and its header is intact, e.g. for health monitoring.
+//! Nothing is opened or mounted.
+//! If the key does not unlock the container, the CLI exits with exit code 12.
+//! If cryptsetup fails for another reason, e.g. because the header can not be read, the error of cryptsetup is printed.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli test-open <PATH> <ID>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>  Path of the container
+//!   <ID>    ID of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### BackupHeader
+//! This is a subcommand
+//! for writing a copy of the LUKS header and the key slots of a container to a file.
+//! A container whose header was damaged can be repaired with the backup by `restore-header`.
+//! The backup is only readable by root, an existing file is not overwritten.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli backup-header <PATH> <OUT_FILE>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>      Path of the container
+//!   <OUT_FILE>  Path of the backup, must not exist yet
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### RestoreHeader
+//! This is a subcommand
+//! for replacing the LUKS header and the key slots of a closed container with a backup of `backup-header`.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli restore-header <PATH> <IN_FILE>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>     Path of the container
+//!   <IN_FILE>  Path of the backup
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### Info
+//! This is a subcommand
+//! for showing the LUKS metadata of a container for an audit,
+//! e.g. its cipher, key size, PBKDF parameters and integrity algorithm.
+//! The output of `cryptsetup luksDump` is printed, it contains no keys.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli info <PATH>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>  Path of the container
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### Rekey
+//! This is a subcommand
+//! for replacing the key of a closed container with the key of a new id, e.g. if the old id is compromised.
+//! The container stays in place, entries in the auto open file with the old id are changed to the new id.
+//! If the container is open, the CLI exits with exit code 21.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli rekey <PATH> <OLD_ID> <NEW_ID>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>    Path of the container
+//!   <OLD_ID>  Current ID of the container (max 64 characters)
+//!   <NEW_ID>  New ID of the container (max 64 characters)
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### AddKeySlot
+//! This is a subcommand
+//! for adding a secret to a free key slot of a container, the key of the ID stays valid.
+//! The secret is stored as it is, so it can be kept out of band as a recovery secret
+//! and the container can be opened with `cryptsetup luksOpen` if the ID is lost.
+//! The key slot the secret was added to is printed.
+//! If all key slots are used, the CLI exits with exit code 33.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli add-key-slot <PATH> <ID> <SECRET>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>    Path of the container
+//!   <ID>      ID of the container (max 64 characters)
+//!   <SECRET>  Secret that is added to a free key slot
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### RemoveKeySlot
+//! This is a subcommand
+//! for removing the key slot of a container that is unlocked by a secret, e.g. a recovery secret that was leaked.
+//! The last key slot of a container is not removed, the CLI exits with exit code 48 then.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli remove-key-slot <PATH> <SECRET>
+//! ```
+//! <u> Arguments: </u>
+//! ```bash
+//!   <PATH>    Path of the container
+//!   <SECRET>  Secret of the key slot that is removed
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//! -h, --help  Print help
+//! ```
+//!
+//! ### Validate
+//! This is a subcommand
+//! for checking inputs before they are used, nothing is created or changed.
+//! Only the given inputs are checked, the first invalid one is reported and the CLI exits with its exit code.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli validate [OPTIONS]
+//! ```
+//! <u> Options: </u>
+//! ```bash
+//!     --size <SIZE>                Size of the container in MB
+//!     --mount-point <MOUNT_POINT>  Mount point of the container
+//!     --path <PATH>                Path of the container
+//!     --namespace <NAMESPACE>      Name of the container
+//!     --id <ID>                    ID of the container
+//! -h, --help                       Print help
+//! ```
+//!
+//! ### Completions
+//! This is a hidden subcommand
+//! for printing the completion script of a shell to stdout, the daemon does not have to run.
+//! The shell is one of `bash`, `zsh`, `fish`, `powershell` or `elvish`.
+//!
+//! <u> Usage: </u>
+//! ```bash
+//! secure_container_cli completions <SHELL> > /usr/share/bash-completion/completions/secure_container_cli
 //! ```
 //! <u> Arguments: </u>
 //! ```bash
-//!   <MOUNT_POINT>  Mount point of the container
-//!   <PATH>         Path of the container
-//!   <NAMESPACE>    Name of the container
-//!   <ID>           ID of the container (max 8 characters)
+//!   <SHELL>  Shell the completion script is printed for
 //! ```
 //! <u> Options: </u>
 //! ```bash
@@ -148,8 +675,8 @@
 //! 1  - The given size of the Container is too small. It must be at least 16MB.
 //! 2  - The given mountpoint does not exist.
 //! 3  - The given path to the Container file dose not.
-//! 4  - The given Namespace for the Container is not valid. The namespace must be a string containing only ascii characters and no '|'.
-//! 5  - The given ID for the Container is not valid. The ID must be a string containing only ascii characters and no '|'.
+//! 4  - The given Namespace for the Container is not valid. Its ASCII characters must be letters, digits, '_' or '-', it must not start with '-' and be at most 64 characters long, every non-ascii character counts as 6 to 12 characters.
+//! 5  - The given ID for the Container is not valid. The ID must be a string containing only ascii characters and no '|' and be at most 64 characters long.
 //! 6  - A container with the given name already exists and is in use.
 //! 7  - An error occurred while reading the stdout of a command.
 //! 8  - An error occurred while unmounting the Container.
@@ -173,6 +700,27 @@
 //! 26 - The given path is not valid.
 //! 27 - The given path is not a LUKS device.
 //! 28 - An unknown error occurred.
+//! 29 - An essential dependency of the daemon is missing.
+//! 30 - The mount point of the container is busy.
+//! 31 - The given path is not writable.
+//! 32 - The given container is already open and mounted at a different mount point.
+//! 33 - All key slots of the given container are used.
+//! 34 - The command did not finish within the given timeout.
+//! 35 - The given bundle is not a valid container bundle.
+//! 36 - The given time is not valid. It must have the format YYYY-MM-DDTHH:MM[:SS].
+//! 37 - The given container is not open.
+//! 38 - An error occurred while getting random data from libuta for the key of a swap container.
+//! 39 - The given mount point is the container itself or contains the container.
+//! 40 - The given container is not the file system that is mounted at the given mount point.
+//! 41 - An error occurred while accessing the keyring of the daemon.
+//! 42 - The given container is not in the auto open file.
+//! 43 - The path of a container in the auto open file did not appear within the wait timeout.
+//! 44 - The given file system is not supported, use ext4, xfs or btrfs.
+//! 45 - A string could not be decoded from base64.
+//! 46 - A mount option is not one of the allowed options.
+//! 47 - The mount point is not an empty directory or something is already mounted on it.
+//! 48 - The key slot is the last one of the container and can not be removed.
+//! 49 - The given key slot is not one of the key slots of a LUKS2 container.
 //! ```
 //!
 
@@ -180,9 +728,13 @@
 
 mod args;
 use args::{SecureContainerCli, SubCommand};
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
 use signal_hook::low_level::exit;
 use secure_container_lib::*;
+use secure_container_lib::secure_container_service::{ContainerInventoryItem, ContainerStatus};
+use std::collections::HashMap;
+use std::io::IsTerminal;
 
 
 /// Import the generated gRPC code.
@@ -193,74 +745,158 @@ pub mod secure_container_service {
 /// Main function of the CLI that handles the connection to the gRPC server (demon) and the different subcommands.
 /// # Return
 /// 'Result<(), String>' - A result that is OK(()) if the function was successful and an error message if an error occurred.
-
-
 fn main() -> Result<(), String> {
     let args = SecureContainerCli::parse();
+    if let Some(timeout) = args.timeout {
+        set_timeout(Some(std::time::Duration::from_secs(timeout)));
+    }
+    if args.server.is_some() {
+        set_server_url(args.server);
+    }
     match args.subcmd {
         SubCommand::Create(create_args) => {
-            match create_container_sync(
-                create_args.size,
-                create_args.mount_point,
-                create_args.path,
-                create_args.namespace,
-                create_args.id,
-                create_args.auto_open,
-            ){
-                Ok(_) => {
+            // The progress is only streamed to a terminal, scripts keep using the unary request.
+            let interactive = std::io::stderr().is_terminal();
+            let result = if interactive {
+                let result = create_container_with_progress_sync(
+                    create_args.size,
+                    create_args.mount_point,
+                    create_args.path,
+                    create_args.namespace,
+                    create_args.id,
+                    create_args.auto_open,
+                    create_args.fs_type,
+                    |phase, percent| eprint!("\r\x1b[K{}", progress_bar(phase, percent)),
+                );
+                eprintln!();
+                result
+            } else {
+                create_container_sync(
+                    create_args.size,
+                    create_args.mount_point,
+                    create_args.path,
+                    create_args.namespace,
+                    create_args.id,
+                    create_args.auto_open,
+                    create_args.fs_type,
+                )
+            };
+            match result {
+                Ok(container) => {
                     println!("Container created successfully.");
+                    println!("Device: {}", container.mapper_device);
+                    println!("Mount point: {}", container.mount_point);
+                    println!("Size: {} bytes", container.usable_bytes);
+                    println!("UUID: {}", container.uuid);
                 }
                 Err(err) => {
                     eprintln!("Error creating container: {}", err);
-                    exit(error_to_exit_code(err));
+                    exit(err.code);
                 }
             }
 
         }
+        SubCommand::FormatOnly(format_args) => {
+            match create_raw_container_sync(
+                format_args.size,
+                format_args.path,
+                format_args.namespace,
+                format_args.id,
+            ){
+                Ok(container) => {
+                    println!("Container created successfully.");
+                    println!("Device: {}", container.mapper_device);
+                    println!("Size: {} bytes", container.usable_bytes);
+                    println!("UUID: {}", container.uuid);
+                }
+                Err(err) => {
+                    eprintln!("Error creating container: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
         SubCommand::Open(open_args) => {
-            match open_container_sync(
+            let mount_options = if open_args.read_only { vec!["ro".to_string()] } else { Vec::new() };
+            match open_container_with_key_slot_sync(
                 open_args.mount_point,
                 open_args.path,
                 open_args.namespace,
                 open_args.id,
+                open_args.keyring,
+                mount_options,
+                open_args.read_only_device,
+                open_args.key_slot,
             ){
                 Ok(_) => {
                     println!("Container opened successfully.");
                 }
                 Err(err) => {
                     eprintln!("Error opening container: {}", err);
-                    exit(error_to_exit_code(err));
+                    exit(err.code);
                 }
             }
         }
         SubCommand::Close(close_args) => {
-            match close_container_sync(
-                close_args.mount_point,
-                close_args.namespace,
-            ){
+            let result = match close_args.namespace {
+                Some(namespace) if close_args.if_open => close_container_if_open_sync(close_args.mount_point, namespace),
+                Some(namespace) => close_container_sync(close_args.mount_point, namespace),
+                // With a single argument it is the name, the daemon looks up the mount point.
+                None => match close_container_by_namespace_sync(close_args.mount_point) {
+                    Err(err) if close_args.if_open && err.code == error_handling::SecureContainerErr::ContainerNotOpen.exit_code() => Ok(()),
+                    result => result,
+                },
+            };
+            match result {
                 Ok(_) => {
                     println!("Container closed successfully.");
                 }
                 Err(err) => {
                     eprintln!("Error closing container: {}", err);
-                    exit(error_to_exit_code(err));
+                    exit(err.code);
                 }
             }
 
         }
+        SubCommand::OpenUnmounted(open_args) => {
+            match open_unmounted_sync(
+                open_args.path,
+                open_args.namespace,
+                open_args.id,
+            ){
+                Ok(mapper_device) => {
+                    println!("Container opened successfully.");
+                    println!("Mapper device: {}", mapper_device);
+                }
+                Err(err) => {
+                    eprintln!("Error opening container: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Mount(mount_args) => {
+            match mount_open_sync(mount_args.mount_point, mount_args.namespace) {
+                Ok(_) => {
+                    println!("Container mounted successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error mounting container: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
         SubCommand::Export(export_args) => {
             match export_container_sync(
                 export_args.path,
                 export_args.namespace,
                 export_args.id,
-                export_args.secret,
+                transfer_credential(export_args.secret, export_args.keyfile),
             ){
                 Ok(_) => {
                     println!("Container exported successfully.");
                 }
                 Err(err) => {
                     eprintln!("Error exporting container: {}", err);
-                    exit(error_to_exit_code(err));
+                    exit(err.code);
                 }
             }
 
@@ -270,20 +906,56 @@ fn main() -> Result<(), String> {
                 import_args.path,
                 import_args.namespace,
                 import_args.id,
-                import_args.secret,
+                transfer_credential(import_args.secret, import_args.keyfile),
             ){
                 Ok(_) => {
                     println!("Container imported successfully.");
                 }
                 Err(err) => {
                     eprintln!("Error importing container: {}", err);
-                    exit(error_to_exit_code(err));
+                    exit(err.code);
+                }
+            }
+
+        }
+        SubCommand::ExportBundle(export_args) => {
+            match export_bundle_sync(
+                export_args.path,
+                export_args.namespace,
+                export_args.id,
+                transfer_credential(export_args.secret, export_args.keyfile),
+                export_args.bundle,
+            ){
+                Ok(_) => {
+                    println!("Container exported to bundle successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error exporting bundle: {}", err);
+                    exit(err.code);
+                }
+            }
+
+        }
+        SubCommand::ImportBundle(import_args) => {
+            match import_bundle_sync(
+                import_args.bundle,
+                import_args.path,
+                import_args.id,
+                transfer_credential(import_args.secret, import_args.keyfile),
+            ){
+                Ok(_) => {
+                    println!("Container imported from bundle successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error importing bundle: {}", err);
+                    exit(err.code);
                 }
             }
 
         }
         SubCommand::AddAutoOpen(auto_open_args) => {
-            match add_container_to_auto_open_sync(
+            let add = if auto_open_args.wait_for_path { add_container_to_auto_open_with_wait_sync } else { add_container_to_auto_open_sync };
+            match add(
                 auto_open_args.mount_point,
                 auto_open_args.path,
                 auto_open_args.namespace,
@@ -294,7 +966,7 @@ fn main() -> Result<(), String> {
                 }
                 Err(err) => {
                     eprintln!("Error adding container to AutoOpen: {}", err);
-                    exit(error_to_exit_code(err));
+                    exit(err.code);
                 }
             }
 
@@ -311,17 +983,506 @@ fn main() -> Result<(), String> {
                 }
                 Err(err) => {
                     eprintln!("Error removing container from AutoOpen: {}", err);
-                    exit(error_to_exit_code(err));
+                    exit(err.code);
                 }
             }
 
         }
+        SubCommand::UpdateAutoOpen(auto_open_args) => {
+            match update_container_in_auto_open_sync(
+                auto_open_args.mount_point,
+                auto_open_args.namespace,
+            ){
+                Ok(_) => {
+                    println!("Mount point in AutoOpen changed successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error changing mount point in AutoOpen: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Doctor => {
+            match doctor_sync() {
+                Ok(checks) => {
+                    for check in &checks {
+                        println!("{}", check);
+                    }
+                    if checks.iter().any(|check| check.essential && !check.available) {
+                        eprintln!("Essential dependencies are missing.");
+                        exit(error_to_exit_code("Essential dependency missing".to_string()));
+                    }
+                    println!("All essential dependencies are present.");
+                }
+                Err(err) => {
+                    eprintln!("Error checking dependencies: {}", err);
+                    exit(error_to_exit_code(err));
+                }
+            }
+        }
+        SubCommand::Status(status_args) if status_args.state => {
+            let namespace = status_args.namespace.unwrap_or_default();
+            match status_container_sync(namespace.clone()) {
+                Ok(state) => {
+                    match (state.open, state.mounted) {
+                        (true, true) => println!("{} mounted {}", namespace, state.mount_point),
+                        (true, false) => println!("{} open", namespace),
+                        (false, _) => println!("{} closed", namespace),
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error checking the state of the container: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Status(status_args) => {
+            match list_containers_sync(status_args.namespace, status_args.all) {
+                Ok(containers) => {
+                    if containers.is_empty() {
+                        println!("No containers found.");
+                    }
+                    for container in &containers {
+                        let mut line = format!("{} [{}] {} {} {}", container.namespace, container.source, container_state(container), container.mount_point, container.path);
+                        if container.used_keyslots > 0 {
+                            line.push_str(&format!(" keyslots: {} used, {} free", container.used_keyslots, container.free_keyslots));
+                        }
+                        if !container.cipher.is_empty() {
+                            line.push_str(&format!(" cipher: {} integrity: {}", container.cipher, container.integrity));
+                        }
+                        println!("{}", line);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error listing containers: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::List => {
+            match list_containers_sync(None, true) {
+                Ok(containers) => {
+                    if containers.is_empty() {
+                        println!("No containers found.");
+                    } else {
+                        print!("{}", container_table(&containers));
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error listing containers: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::RotateKeys(rotate_args) => {
+            match rotate_keys_sync(rotate_args.new_id) {
+                Ok(containers) => {
+                    for container in &containers {
+                        if container.status {
+                            println!("Rotated key of container {}", container.namespace);
+                        } else {
+                            println!("Error rotating key of container {}: {}", container.namespace, container.error);
+                        }
+                    }
+                    if let Some(failed) = containers.iter().find(|container| !container.status) {
+                        eprintln!("Not all keys were rotated.");
+                        exit(DaemonError::from_response(failed.code, failed.error.clone()).code);
+                    }
+                    println!("Rotated keys of {} containers.", containers.len());
+                }
+                Err(err) => {
+                    eprintln!("Error rotating keys: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Verify(verify_args) => {
+            match verify_integrity_sync(
+                verify_args.namespace,
+                verify_args.since,
+            ){
+                Ok(_) => {
+                    println!("No integrity errors found.");
+                }
+                Err(err) => {
+                    eprintln!("Error verifying integrity: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::BindMount(bind_args) => {
+            match bind_mount_sync(
+                bind_args.namespace,
+                bind_args.source,
+                bind_args.target,
+                bind_args.read_only,
+            ){
+                Ok(_) => {
+                    println!("Directory bind-mounted successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error bind-mounting directory: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::CreateSwap(swap_args) => {
+            match create_swap_container_sync(
+                swap_args.size,
+                swap_args.namespace,
+            ){
+                Ok(container) => {
+                    println!("Swap container created successfully.");
+                    println!("Device: {}", container.mapper_device);
+                    println!("Size: {} bytes", container.usable_bytes);
+                    println!("UUID: {}", container.uuid);
+                }
+                Err(err) => {
+                    eprintln!("Error creating swap container: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::RebuildAutoOpen(rebuild_args) => {
+            let ids = match container_ids(&rebuild_args.ids, rebuild_args.mapping) {
+                Ok(ids) => ids,
+                Err(err) => {
+                    eprintln!("Error reading ids: {}", err);
+                    exit(error_to_exit_code(err));
+                }
+            };
+            match rebuild_auto_open_sync(ids, rebuild_args.default_id.unwrap_or_default()) {
+                Ok(containers) => {
+                    for container in &containers {
+                        if container.status {
+                            println!("Added container {} ({} at {})", container.namespace, container.path, container.mount_point);
+                        } else {
+                            println!("Skipped container {}: {}", container.namespace, container.error);
+                        }
+                    }
+                    if let Some(skipped) = containers.iter().find(|container| !container.status) {
+                        eprintln!("Not all open containers were added to the auto open file.");
+                        exit(DaemonError::from_response(skipped.code, skipped.error.clone()).code);
+                    }
+                    println!("Rebuilt auto open file with {} containers.", containers.len());
+                }
+                Err(err) => {
+                    eprintln!("Error rebuilding auto open file: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::TestOpen(test_args) => {
+            match test_open_sync(
+                test_args.path,
+                test_args.id,
+            ){
+                Ok(true) => {
+                    println!("Container would open.");
+                }
+                Ok(false) => {
+                    eprintln!("Container would not open: the key of the id does not unlock the container.");
+                    exit(error_to_exit_code("Cryptsetup error".to_string()));
+                }
+                Err(err) => {
+                    eprintln!("Error testing container: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Validate(validate_args) => {
+            match validate_input_sync(
+                validate_args.size,
+                validate_args.mount_point,
+                validate_args.path,
+                validate_args.namespace,
+                validate_args.id,
+            ){
+                Ok(response) => {
+                    if response.status {
+                        println!("Input is valid.");
+                    } else {
+                        eprintln!("Invalid {}: {}", response.field, response.error);
+                        exit(DaemonError::from_response(response.code, response.error).code);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error validating input: {}", err);
+                    exit(error_to_exit_code(err));
+                }
+            }
+        }
+        SubCommand::Scan(scan_args) => {
+            match scan_directory_sync(scan_args.directory) {
+                Ok(containers) => {
+                    for container in &containers {
+                        println!("{} (LUKS{}, UUID {})", container.path, container.version, container.uuid);
+                    }
+                    println!("Found {} containers.", containers.len());
+                }
+                Err(err) => {
+                    eprintln!("Error scanning directory: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Diff => {
+            match diff_state_sync() {
+                Ok(diff) => {
+                    println!("{:<64} STATE", "NAMESPACE");
+                    let states = [
+                        ("open", &diff.open),
+                        ("closed", &diff.closed),
+                        ("missing", &diff.missing),
+                        ("unregistered", &diff.unregistered),
+                    ];
+                    for (state, namespaces) in states {
+                        for namespace in namespaces {
+                            println!("{:<64} {}", namespace, state);
+                        }
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error comparing state: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Inventory(inventory_args) => {
+            match inventory_sync() {
+                Ok(containers) => {
+                    if inventory_args.format == "csv" {
+                        print!("{}", inventory_csv(&containers));
+                    } else {
+                        println!("{}", serde_json::to_string_pretty(&containers).map_err(|err| err.to_string())?);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error collecting inventory: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::BackupHeader(backup_args) => {
+            match backup_header_sync(backup_args.path, backup_args.out_file) {
+                Ok(_) => {
+                    println!("Header backed up successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error backing up header: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::RestoreHeader(restore_args) => {
+            match restore_header_sync(restore_args.path, restore_args.in_file) {
+                Ok(_) => {
+                    println!("Header restored successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error restoring header: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Info(info_args) => {
+            match container_info_sync(info_args.path) {
+                Ok(info) => {
+                    print!("{}", info);
+                }
+                Err(err) => {
+                    eprintln!("Error reading container info: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Rekey(rekey_args) => {
+            match rekey_container_sync(rekey_args.path, rekey_args.old_id, rekey_args.new_id) {
+                Ok(_) => {
+                    println!("Container rekeyed successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error rekeying container: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::AddKeySlot(add_args) => {
+            match add_key_slot_sync(add_args.path, add_args.id, add_args.secret) {
+                Ok(key_slot) => {
+                    println!("Key slot added successfully.");
+                    println!("Key slot: {}", key_slot);
+                }
+                Err(err) => {
+                    eprintln!("Error adding key slot: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::RemoveKeySlot(remove_args) => {
+            match remove_key_slot_sync(remove_args.path, remove_args.secret) {
+                Ok(_) => {
+                    println!("Key slot removed successfully.");
+                }
+                Err(err) => {
+                    eprintln!("Error removing key slot: {}", err);
+                    exit(err.code);
+                }
+            }
+        }
+        SubCommand::Completions(completions_args) => {
+            completions(completions_args.shell, &mut std::io::stdout());
+        }
     }
 
     Ok(())
 }
 
+/// Function that selects the credential for exporting or importing a container.
+/// # Arguments
+/// * `secret` - The secret phrase given on the command line.
+/// * `keyfile` - The keyfile given on the command line.
+/// # Returns
+/// 'TransferCredential' - The keyfile as absolute path if one was given, otherwise the secret.
+fn transfer_credential(secret: Option<String>, keyfile: Option<String>) -> TransferCredential {
+    match keyfile {
+        // The daemon runs in a different directory, so relative paths have to be resolved here.
+        Some(keyfile) => TransferCredential::Keyfile(
+            std::fs::canonicalize(&keyfile)
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or(keyfile),
+        ),
+        None => TransferCredential::Secret(secret.unwrap_or_default()),
+    }
+}
+
+/// Function that collects the ids of the containers for rebuilding the auto open file.
+/// # Arguments
+/// * `ids` - The ids given on the command line as `NAMESPACE=ID`.
+/// * `mapping` - The path to a file with one `NAMESPACE=ID` per line, lines starting with `#` are ignored.
+/// # Returns
+/// 'Result<HashMap<String, String>, String>' -
+/// The ids by namespace, the ones of the command line replace the ones of the file,
+/// or an error message if the file could not be read or an entry has no `=`.
+fn container_ids(ids: &[String], mapping: Option<String>) -> Result<HashMap<String, String>, String> {
+    let contents = match mapping {
+        Some(mapping) => std::fs::read_to_string(mapping).map_err(|err| format!("File read error: {}", err))?,
+        None => String::new(),
+    };
+    let lines = contents.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let mut container_ids = HashMap::new();
+    for entry in lines.chain(ids.iter().map(String::as_str)) {
+        // The id is after the last `=`, because the namespace may contain one.
+        match entry.rsplit_once('=') {
+            Some((namespace, id)) => container_ids.insert(namespace.to_string(), id.to_string()),
+            None => return Err(format!("Not valid id: {} is not NAMESPACE=ID", entry)),
+        };
+    }
+    Ok(container_ids)
+}
+
+/// Function that describes the state of a container for `status` and `list`.
+/// # Arguments
+/// * `container` - The status of the container.
+/// # Returns
+/// 'String' - The operation that is in progress, `mounted`, `open` or `closed`.
+fn container_state(container: &ContainerStatus) -> String {
+    match (&container.busy, container.open, container.mounted) {
+        (Some(operation), _, _) => format!("{} in progress", operation),
+        (None, true, true) => "mounted".to_string(),
+        (None, true, false) => "open".to_string(),
+        (None, false, _) => "closed".to_string(),
+    }
+}
+
+/// Function that formats the containers as a table with a header line and aligned columns.
+/// # Arguments
+/// * `containers` - The containers to list.
+/// # Returns
+/// 'String' - The table with one line per container.
+fn container_table(containers: &[ContainerStatus]) -> String {
+    let mut rows = vec![["NAMESPACE", "SOURCE", "STATE", "MOUNT POINT", "PATH"].map(String::from)];
+    for container in containers {
+        rows.push([
+            container.namespace.clone(),
+            container.source.clone(),
+            container_state(container),
+            container.mount_point.clone(),
+            container.path.clone(),
+        ]);
+    }
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (width, field) in widths.iter_mut().zip(row) {
+            *width = (*width).max(field.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in &rows {
+        let fields: Vec<String> = row.iter().zip(widths).map(|(field, width)| format!("{:<width$}", field, width = width)).collect();
+        table.push_str(fields.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// Function that formats the inventory as CSV with a header line.
+/// Fields that contain a comma, a quote or a line break are quoted.
+/// # Arguments
+/// * `containers` - The containers of the inventory.
+/// # Returns
+/// 'String' - The CSV with one line per container.
+fn inventory_csv(containers: &[ContainerInventoryItem]) -> String {
+    let quote = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+    let mut csv = "namespace,path,mountPoint,uuid,cipher,integrity,created,open,mounted,source\n".to_string();
+    for container in containers {
+        let fields = [
+            quote(&container.namespace),
+            quote(&container.path),
+            quote(&container.mount_point),
+            quote(&container.uuid),
+            quote(&container.cipher),
+            quote(&container.integrity),
+            quote(&container.created),
+            container.open.to_string(),
+            container.mounted.to_string(),
+            quote(&container.source),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Function that renders the progress of creating a container as a bar.
+/// # Arguments
+/// * `phase` - The phase of the creation, e.g. `formatting`.
+/// * `percent` - The estimated part of the creation that is finished, from 0 to 100.
+/// # Returns
+/// 'String' - The bar with 30 characters followed by the percentage and the phase.
+fn progress_bar(phase: &str, percent: u32) -> String {
+    let filled = (percent.min(100) * 30 / 100) as usize;
+    format!("[{}{}] {:>3}% {}", "#".repeat(filled), " ".repeat(30 - filled), percent.min(100), phase)
+}
+
+/// Function that writes the completion script of a shell for the CLI.
+/// # Arguments
+/// * `shell` - The shell the completion script is written for.
+/// * `out` - The output the completion script is written to, e.g. stdout.
+fn completions(shell: Shell, out: &mut dyn std::io::Write) {
+    clap_complete::generate(shell, &mut SecureContainerCli::command(), "secure_container_cli", out);
+}
+
 /// Function that covert Rust error into exit codes.
+/// The message is turned back into the error of the daemon with `SecureContainerErr::from_daemon_string`,
+/// so messages with details, e.g. `Cryptsetup error: ...`, get the exit code of their variant.
+/// The exit codes are defined in `error_handling` of the library.
 /// # Arguments
 /// * `err` - A string that represents the error.
 /// # Returns
@@ -332,37 +1493,7 @@ fn main() -> Result<(), String> {
 /// assert_eq!(exit_code, 1);
 /// ```
 fn error_to_exit_code(err: String) -> i32 {
-    match err.as_str() {
-        "Size of container to small" => 1,
-        "Mountpoint wrong" => 2,
-        "Not valid path" => 3,
-        "Not valid namespace" => 4,
-        "Not valid id" => 5,
-        "Lsblk error" => 6,
-        "Reading stdout error" => 7,
-        "Umount error" => 8,
-        "Mount error" => 9,
-        "Mkfs error" => 10,
-        "Ls error" => 11,
-        "Cryptsetup error" => 12,
-        "Stdin error" => 13,
-        "File creation error" => 14,
-        "File write error" => 15,
-        "Libuta derive key error" => 16,
-        "File read error" => 17,
-        "File open error" => 18,
-        "Integrity error" => 19,
-        "Container mounted" => 20,
-        "Container open" => 21,
-        "Container with that name already exists" => 22,
-        "File already exists" => 23,
-        "Secret not valid" => 24,
-        "Path is not a luks container" => 25,
-        "Path not valid" => 26,
-        "Path is not a luks divice" => 27,
-        "OK" => 0,
-        _ => 28,
-    }
+    error_handling::exit_code_from_message(&err)
 }
 
 #[test]
@@ -406,9 +1537,126 @@ fn test_error_to_exitcode() {
     );
     assert_eq!(error_to_exit_code("Path not valid".to_string()), 26);
     assert_eq!(
-        error_to_exit_code("Path is not a luks divice".to_string()),
+        error_to_exit_code("Path is not a luks device".to_string()),
         27
     );
+    assert_eq!(
+        error_to_exit_code("Essential dependency missing".to_string()),
+        29
+    );
+    assert_eq!(error_to_exit_code("Mount point busy".to_string()), 30);
+    assert_eq!(error_to_exit_code("Path not writable".to_string()), 31);
+    assert_eq!(
+        error_to_exit_code("Container mounted elsewhere".to_string()),
+        32
+    );
+    assert_eq!(error_to_exit_code("No free keyslots".to_string()), 33);
+    assert_eq!(error_to_exit_code("Deadline exceeded".to_string()), 34);
+    assert_eq!(error_to_exit_code("Bundle not valid".to_string()), 35);
+    assert_eq!(error_to_exit_code("Time not valid".to_string()), 36);
+    assert_eq!(error_to_exit_code("Container not open".to_string()), 37);
+    assert_eq!(error_to_exit_code("Libuta random error: UTA Open".to_string()), 38);
+    assert_eq!(error_to_exit_code("Mount point conflicts with path".to_string()), 39);
+    assert_eq!(error_to_exit_code("Container not mounted at mount point".to_string()), 40);
+    assert_eq!(error_to_exit_code("Keyring error: Access denied".to_string()), 41);
+    assert_eq!(error_to_exit_code("Container not in auto open".to_string()), 42);
+    assert_eq!(error_to_exit_code("Timed out waiting for path".to_string()), 43);
+    assert_eq!(error_to_exit_code("File system not supported".to_string()), 44);
+    assert_eq!(error_to_exit_code("Base64 error: Invalid symbol 42, offset 0.".to_string()), 45);
+    assert_eq!(error_to_exit_code("Mount option not valid".to_string()), 46);
+    assert_eq!(error_to_exit_code("Mount point not empty".to_string()), 47);
+    assert_eq!(error_to_exit_code("Last keyslot".to_string()), 48);
+    assert_eq!(error_to_exit_code("Key slot not valid".to_string()), 49);
     assert_eq!(error_to_exit_code("OK".to_string()), 0);
     assert_eq!(error_to_exit_code("Not valid".to_string()), 28);
+    assert_eq!(error_to_exit_code("Lsblk error: not found".to_string()), 6);
+    assert_eq!(
+        error_to_exit_code(error_handling::SecureContainerErr::IsNotLuks("boom".into()).to_string()),
+        27
+    );
+    // Older daemons spell the error differently.
+    assert_eq!(
+        error_to_exit_code("Path is not a luks divice: not a LUKS device".to_string()),
+        27
+    );
+}
+
+#[test]
+fn test_completions() {
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+        let mut out = Vec::new();
+        completions(shell, &mut out);
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("secure_container_cli"));
+        assert!(script.contains("open-unmounted"));
+    }
+}
+
+#[test]
+fn test_progress_bar() {
+    assert_eq!(progress_bar("allocating", 0), format!("[{}]   0% allocating", " ".repeat(30)));
+    assert_eq!(progress_bar("formatting", 10), format!("[###{}]  10% formatting", " ".repeat(27)));
+    assert_eq!(progress_bar("done", 150), format!("[{}] 100% done", "#".repeat(30)));
+}
+
+#[test]
+fn test_container_ids() {
+    let dir = tempfile::tempdir().unwrap();
+    let mapping = dir.path().join("mapping");
+    std::fs::write(&mapping, "# namespace=id\nfirst=one\n\na=b=two\nsecond=file\n").unwrap();
+    let ids = container_ids(&["second=cli".to_string()], Some(mapping.to_string_lossy().to_string())).unwrap();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids["first"], "one");
+    assert_eq!(ids["a=b"], "two");
+    assert_eq!(ids["second"], "cli");
+    assert!(container_ids(&["first".to_string()], None).is_err());
+    assert_eq!(error_to_exit_code(container_ids(&[], Some("/not/existing".to_string())).unwrap_err()), 17);
+}
+
+#[test]
+fn test_inventory_csv() {
+    let container = ContainerInventoryItem {
+        namespace: "Daten, alt".to_string(),
+        path: "/home/Container".to_string(),
+        mount_point: "/home/MountMe".to_string(),
+        uuid: "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11".to_string(),
+        cipher: "aes-xts-plain64".to_string(),
+        integrity: "hmac-sha256".to_string(),
+        created: "2024-03-01T12:00".to_string(),
+        open: true,
+        mounted: false,
+        source: "registered".to_string(),
+    };
+    let csv = inventory_csv(&[container]);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("namespace,path,mountPoint,uuid"));
+    assert_eq!(
+        lines[1],
+        "\"Daten, alt\",/home/Container,/home/MountMe,5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11,aes-xts-plain64,hmac-sha256,2024-03-01T12:00,true,false,registered"
+    );
+}
+
+#[test]
+fn test_container_table() {
+    let container = ContainerStatus {
+        namespace: "Daten".to_string(),
+        mount_point: "/home/MountMe".to_string(),
+        path: "/home/Container".to_string(),
+        open: true,
+        mounted: true,
+        source: "registered".to_string(),
+        ..Default::default()
+    };
+    let closed = ContainerStatus {
+        namespace: "Überweisungen".to_string(),
+        source: "ad-hoc".to_string(),
+        ..Default::default()
+    };
+    let table = container_table(&[container, closed]);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "NAMESPACE      SOURCE      STATE    MOUNT POINT    PATH");
+    assert_eq!(lines[1], "Daten          registered  mounted  /home/MountMe  /home/Container");
+    assert_eq!(lines[2], "Überweisungen  ad-hoc      closed");
 }
@@ -0,0 +1,642 @@
+//! # Config
+//! This module contains the configuration of the daemon.
+//! The configuration is read once from environment variables.
+//! Every value that is not set falls back to a sensible default,
+//! so the daemon works without any configuration on a standard Linux system.
+//!
+//! ## Environment variables
+//! ```bash
+//! SECURE_CONTAINER_CRYPTSETUP  Path to the cryptsetup binary (default: cryptsetup)
+//! SECURE_CONTAINER_LSBLK       Path to the lsblk binary (default: lsblk)
+//! SECURE_CONTAINER_MOUNT       Path to the mount binary (default: mount)
+//! SECURE_CONTAINER_UMOUNT      Path to the umount binary (default: umount)
+//! SECURE_CONTAINER_MKFS_EXT4   Path to the mkfs.ext4 binary (default: mkfs.ext4)
+//! SECURE_CONTAINER_MKFS_XFS    Path to the mkfs.xfs binary (default: mkfs.xfs)
+//! SECURE_CONTAINER_MKFS_BTRFS  Path to the mkfs.btrfs binary (default: mkfs.btrfs)
+//! SECURE_CONTAINER_DMESG       Path to the dmesg binary (default: dmesg)
+//! SECURE_CONTAINER_DMSETUP     Path to the dmsetup binary (default: dmsetup)
+//! SECURE_CONTAINER_MKSWAP      Path to the mkswap binary (default: mkswap)
+//! SECURE_CONTAINER_SWAPON      Path to the swapon binary (default: swapon)
+//! SECURE_CONTAINER_SWAPOFF     Path to the swapoff binary (default: swapoff)
+//! SECURE_CONTAINER_SWAP_DIR    Directory of the backing files of swap containers (default: /var/lib/secure_container/swap)
+//! SECURE_CONTAINER_AUTO_OPEN   Path to the autoOpen file (default: /usr/bin/auto_open)
+//! SECURE_CONTAINER_ADDRESS     Address the gRPC server listens on (default: [::1]:50051)
+//! SECURE_CONTAINER_SOCKET      Unix socket the gRPC server listens on instead of the address (default: not set)
+//! SECURE_CONTAINER_TLS_CERT    PEM certificate of the gRPC server, enables TLS (default: not set)
+//! SECURE_CONTAINER_TLS_KEY     PEM private key of the certificate (default: not set)
+//! SECURE_CONTAINER_TLS_CLIENT_CA
+//!                              PEM CA that must have signed the certificates of the clients (default: not set)
+//! SECURE_CONTAINER_REST_ADDRESS
+//!                              Address the REST gateway listens on with the TLS files of the gRPC server (default: not set)
+//! SECURE_CONTAINER_REST_SOCKET Unix socket the REST gateway listens on instead of the address (default: not set)
+//! SECURE_CONTAINER_CIPHER      Cipher of new containers (default: aes-xts-plain64)
+//! SECURE_CONTAINER_PBKDF2_ITERATIONS
+//!                              Iterations of PBKDF2 for the secret of exported containers (default: 600000)
+//! SECURE_CONTAINER_LUKS_PBKDF  PBKDF of the LUKS key slots of containers (default: argon2id)
+//! SECURE_CONTAINER_LUKS_ITER_TIME
+//!                              Milliseconds the PBKDF of a LUKS key slot takes (default: 2000)
+//! SECURE_CONTAINER_AUTO_OPEN_PARALLELISM
+//!                              Number of containers that are opened at the same time on startup (default: 4)
+//! SECURE_CONTAINER_KEYRING_TIMEOUT
+//!                              Seconds until a password cached in the keyring expires (default: 600)
+//! SECURE_CONTAINER_AUTO_OPEN_WAIT_TIMEOUT
+//!                              Seconds auto open waits for the path of a container marked with wait (default: 120)
+//! SECURE_CONTAINER_LOG         Filter of the log of the daemon, e.g. `info` or `secure_container_daemon=debug`,
+//!                              falls back to `RUST_LOG` (default: `warn`, only warnings and errors are logged)
+//! ```
+//! Binaries given without a path are looked up in the `PATH` of the daemon.
+//! The daemon checks the configuration with `Config::validate` on startup and refuses to serve if it is not valid.
+//!
+
+use crate::file_system_operations::check_if_dir_writable;
+
+use std::env;
+use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing_subscriber::EnvFilter;
+
+/// The ciphers that can be used for new containers.
+/// They all use 512 bit keys in XTS mode, like the default `aes-xts-plain64`.
+pub const CIPHERS: [&str; 3] = [
+    "aes-xts-plain64",
+    "serpent-xts-plain64",
+    "twofish-xts-plain64",
+];
+
+/// The smallest number of PBKDF2 iterations that is accepted.
+pub const MIN_PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// The largest number of PBKDF2 iterations that is accepted.
+pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+
+/// The PBKDFs that can be used for the LUKS key slots of containers.
+pub const LUKS_PBKDFS: [&str; 3] = ["argon2id", "argon2i", "pbkdf2"];
+
+/// The shortest time in milliseconds the PBKDF of a LUKS key slot may take.
+pub const MIN_LUKS_ITER_TIME: u32 = 100;
+
+/// The longest time in milliseconds the PBKDF of a LUKS key slot may take.
+pub const MAX_LUKS_ITER_TIME: u32 = 60_000;
+
+/// The largest number of containers that are opened at the same time.
+pub const MAX_AUTO_OPEN_PARALLELISM: usize = 64;
+
+/// The longest time in seconds a password is cached in the keyring.
+pub const MAX_KEYRING_TIMEOUT: u64 = 86_400;
+
+/// The longest time in seconds auto open waits for the path of a container.
+pub const MAX_AUTO_OPEN_WAIT_TIMEOUT: u64 = 3_600;
+
+/// The filter of the log if neither `SECURE_CONTAINER_LOG` nor `RUST_LOG` is set.
+pub const DEFAULT_LOG_FILTER: &str = "warn";
+
+/// The configuration that is used by the whole project.
+/// * `cryptsetup_path` - The cryptsetup binary.
+/// * `lsblk_path` - The lsblk binary.
+/// * `mount_path` - The mount binary.
+/// * `umount_path` - The umount binary.
+/// * `mkfs_ext4_path` - The mkfs.ext4 binary.
+/// * `mkfs_xfs_path` - The mkfs.xfs binary, it is only needed for containers with XFS.
+/// * `mkfs_btrfs_path` - The mkfs.btrfs binary, it is only needed for containers with Btrfs.
+/// * `dmesg_path` - The dmesg binary.
+/// * `dmsetup_path` - The dmsetup binary, it reads the mismatch counters of the integrity devices.
+/// * `mkswap_path` - The mkswap binary.
+/// * `swapon_path` - The swapon binary.
+/// * `swapoff_path` - The swapoff binary.
+/// * `swap_dir` - The directory of the backing files of swap containers, it is created if it does not exist.
+/// * `auto_open_path` - The autoOpen file with the containers that are opened on startup (see `file_io_operations`).
+/// * `listen_address` - The address the gRPC server listens on.
+/// * `socket_path` -
+/// The Unix socket the gRPC server listens on instead of `listen_address`, e.g. `/run/secure_container.sock`.
+/// It is empty by default, so the daemon listens on `listen_address`.
+/// * `tls_cert_path` - The PEM certificate of the gRPC server, if it is empty the server does not use TLS.
+/// * `tls_key_path` - The PEM private key of `tls_cert_path`.
+/// * `tls_client_ca_path` -
+/// The PEM CA the certificates of the clients must be signed by (mutual TLS),
+/// if it is empty the clients are not authenticated.
+/// * `rest_address` -
+/// The address the REST gateway listens on, it is only used with the `rest` feature.
+/// The gateway uses the same certificate, key and client CA as the gRPC server, so it needs `tls_cert_path`.
+/// It is empty by default, so the gateway is not started unless `rest_socket_path` is set.
+/// * `rest_socket_path` -
+/// The Unix socket the REST gateway listens on instead of `rest_address`, only root can connect to it.
+/// * `cipher` - The cipher of new containers, one of `CIPHERS`.
+/// * `pbkdf2_iterations` -
+/// The iterations of PBKDF2 that derive the password of a container from the secret,
+/// if it was exported before Argon2id was used.
+/// * `luks_pbkdf` - The PBKDF of the LUKS key slots of containers, one of `LUKS_PBKDFS`.
+/// * `luks_iter_time` -
+/// The milliseconds the PBKDF of a LUKS key slot takes,
+/// so the cost of a key slot does not depend on the benchmark of the machine that formatted it.
+/// * `auto_open_parallelism` - The number of containers that are opened at the same time on startup.
+/// * `keyring_timeout` - The seconds until a password that is cached in the keyring expires (see `keyring`).
+/// * `auto_open_wait_timeout` -
+/// The seconds auto open waits for the path of a container that is marked with `wait` in the autoOpen file,
+/// e.g. because it is on a network file system that is not mounted yet.
+/// * `log_filter` -
+/// The filter of the structured log of the daemon, in the syntax of `RUST_LOG`.
+/// It is `DEFAULT_LOG_FILTER` by default, so warnings and errors are logged but the requests are not traced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub cryptsetup_path: String,
+    pub lsblk_path: String,
+    pub mount_path: String,
+    pub umount_path: String,
+    pub mkfs_ext4_path: String,
+    pub mkfs_xfs_path: String,
+    pub mkfs_btrfs_path: String,
+    pub dmesg_path: String,
+    pub dmsetup_path: String,
+    pub mkswap_path: String,
+    pub swapon_path: String,
+    pub swapoff_path: String,
+    pub swap_dir: String,
+    pub auto_open_path: String,
+    pub listen_address: String,
+    pub socket_path: String,
+    pub tls_cert_path: String,
+    pub tls_key_path: String,
+    pub tls_client_ca_path: String,
+    pub rest_address: String,
+    pub rest_socket_path: String,
+    pub cipher: String,
+    pub pbkdf2_iterations: u32,
+    pub luks_pbkdf: String,
+    pub luks_iter_time: u32,
+    pub auto_open_parallelism: usize,
+    pub keyring_timeout: u64,
+    pub auto_open_wait_timeout: u64,
+    pub log_filter: String,
+}
+
+/// The default configuration resolves all binaries through the `PATH`.
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cryptsetup_path: "cryptsetup".to_string(),
+            lsblk_path: "lsblk".to_string(),
+            mount_path: "mount".to_string(),
+            umount_path: "umount".to_string(),
+            mkfs_ext4_path: "mkfs.ext4".to_string(),
+            mkfs_xfs_path: "mkfs.xfs".to_string(),
+            mkfs_btrfs_path: "mkfs.btrfs".to_string(),
+            dmesg_path: "dmesg".to_string(),
+            dmsetup_path: "dmsetup".to_string(),
+            mkswap_path: "mkswap".to_string(),
+            swapon_path: "swapon".to_string(),
+            swapoff_path: "swapoff".to_string(),
+            swap_dir: "/var/lib/secure_container/swap".to_string(),
+            auto_open_path: "/usr/bin/auto_open".to_string(),
+            listen_address: "[::1]:50051".to_string(),
+            socket_path: String::new(),
+            tls_cert_path: String::new(),
+            tls_key_path: String::new(),
+            tls_client_ca_path: String::new(),
+            rest_address: String::new(),
+            rest_socket_path: String::new(),
+            cipher: "aes-xts-plain64".to_string(),
+            pbkdf2_iterations: 600_000,
+            luks_pbkdf: "argon2id".to_string(),
+            luks_iter_time: 2000,
+            auto_open_parallelism: 4,
+            keyring_timeout: 600,
+            auto_open_wait_timeout: 120,
+            log_filter: DEFAULT_LOG_FILTER.to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Creates the configuration from the environment variables.
+    /// # Arguments
+    /// # Returns
+    /// * `Config` -
+    /// Returns the configuration with every value that is not set in the environment set to its default.
+    /// # Example
+    /// ```
+    /// let config = Config::from_env();
+    /// println!("{}", config.cryptsetup_path);
+    /// ```
+    ///
+    pub fn from_env() -> Config {
+        let default = Config::default();
+        Config {
+            cryptsetup_path: env_or("SECURE_CONTAINER_CRYPTSETUP", default.cryptsetup_path),
+            lsblk_path: env_or("SECURE_CONTAINER_LSBLK", default.lsblk_path),
+            mount_path: env_or("SECURE_CONTAINER_MOUNT", default.mount_path),
+            umount_path: env_or("SECURE_CONTAINER_UMOUNT", default.umount_path),
+            mkfs_ext4_path: env_or("SECURE_CONTAINER_MKFS_EXT4", default.mkfs_ext4_path),
+            mkfs_xfs_path: env_or("SECURE_CONTAINER_MKFS_XFS", default.mkfs_xfs_path),
+            mkfs_btrfs_path: env_or("SECURE_CONTAINER_MKFS_BTRFS", default.mkfs_btrfs_path),
+            dmesg_path: env_or("SECURE_CONTAINER_DMESG", default.dmesg_path),
+            dmsetup_path: env_or("SECURE_CONTAINER_DMSETUP", default.dmsetup_path),
+            mkswap_path: env_or("SECURE_CONTAINER_MKSWAP", default.mkswap_path),
+            swapon_path: env_or("SECURE_CONTAINER_SWAPON", default.swapon_path),
+            swapoff_path: env_or("SECURE_CONTAINER_SWAPOFF", default.swapoff_path),
+            swap_dir: env_or("SECURE_CONTAINER_SWAP_DIR", default.swap_dir),
+            auto_open_path: env_or("SECURE_CONTAINER_AUTO_OPEN", default.auto_open_path),
+            listen_address: env_or("SECURE_CONTAINER_ADDRESS", default.listen_address),
+            socket_path: env_or("SECURE_CONTAINER_SOCKET", default.socket_path),
+            tls_cert_path: env_or("SECURE_CONTAINER_TLS_CERT", default.tls_cert_path),
+            tls_key_path: env_or("SECURE_CONTAINER_TLS_KEY", default.tls_key_path),
+            tls_client_ca_path: env_or(
+                "SECURE_CONTAINER_TLS_CLIENT_CA",
+                default.tls_client_ca_path,
+            ),
+            rest_address: env_or("SECURE_CONTAINER_REST_ADDRESS", default.rest_address),
+            rest_socket_path: env_or("SECURE_CONTAINER_REST_SOCKET", default.rest_socket_path),
+            cipher: env_or("SECURE_CONTAINER_CIPHER", default.cipher),
+            // A value that is not a number is kept as 0, so `validate` reports it.
+            pbkdf2_iterations: env_or(
+                "SECURE_CONTAINER_PBKDF2_ITERATIONS",
+                default.pbkdf2_iterations.to_string(),
+            )
+            .parse()
+            .unwrap_or(0),
+            luks_pbkdf: env_or("SECURE_CONTAINER_LUKS_PBKDF", default.luks_pbkdf),
+            luks_iter_time: env_or(
+                "SECURE_CONTAINER_LUKS_ITER_TIME",
+                default.luks_iter_time.to_string(),
+            )
+            .parse()
+            .unwrap_or(0),
+            auto_open_parallelism: env_or(
+                "SECURE_CONTAINER_AUTO_OPEN_PARALLELISM",
+                default.auto_open_parallelism.to_string(),
+            )
+            .parse()
+            .unwrap_or(0),
+            keyring_timeout: env_or(
+                "SECURE_CONTAINER_KEYRING_TIMEOUT",
+                default.keyring_timeout.to_string(),
+            )
+            .parse()
+            .unwrap_or(0),
+            auto_open_wait_timeout: env_or(
+                "SECURE_CONTAINER_AUTO_OPEN_WAIT_TIMEOUT",
+                default.auto_open_wait_timeout.to_string(),
+            )
+            .parse()
+            .unwrap_or(0),
+            log_filter: env_or(
+                "SECURE_CONTAINER_LOG",
+                env_or("RUST_LOG", default.log_filter),
+            ),
+        }
+    }
+
+    /// Checks the configuration before the daemon serves requests.
+    /// # Arguments
+    /// # Returns
+    /// * `Vec<String>` -
+    /// Returns one message for every problem of the configuration, it is empty if the configuration is valid.
+    /// # Example
+    /// ```
+    /// for problem in config().validate() {
+    ///     println!("{}", problem);
+    /// }
+    /// ```
+    ///
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.listen_address.parse::<SocketAddr>().is_err() {
+            problems.push(format!(
+                "listen address '{}' is not a valid socket address",
+                self.listen_address
+            ));
+        }
+
+        for socket_path in [&self.socket_path, &self.rest_socket_path] {
+            if socket_path.is_empty() {
+                continue;
+            }
+            match Path::new(socket_path).parent() {
+                Some(dir) if Path::new(socket_path).is_absolute() && dir.is_dir() => (),
+                _ => problems.push(format!(
+                    "socket path '{}' is not an absolute path in an existing directory",
+                    socket_path
+                )),
+            }
+        }
+
+        if !self.rest_address.is_empty() {
+            if self.rest_address.parse::<SocketAddr>().is_err() {
+                problems.push(format!(
+                    "REST address '{}' is not a valid socket address",
+                    self.rest_address
+                ));
+            }
+            if !self.rest_socket_path.is_empty() {
+                problems.push("REST address and REST socket can not be set together".to_string());
+            }
+            // The gateway has no authentication of its own, so it is never served in plaintext over TCP.
+            if self.tls_cert_path.is_empty() {
+                problems.push("REST address needs a TLS certificate and key".to_string());
+            }
+        }
+
+        if self.tls_cert_path.is_empty() != self.tls_key_path.is_empty() {
+            problems.push("TLS certificate and key must be set together".to_string());
+        }
+        if !self.tls_client_ca_path.is_empty() && self.tls_cert_path.is_empty() {
+            problems.push("TLS client CA needs a TLS certificate and key".to_string());
+        }
+        for path in [
+            &self.tls_cert_path,
+            &self.tls_key_path,
+            &self.tls_client_ca_path,
+        ] {
+            if !path.is_empty() && !Path::new(path).is_file() {
+                problems.push(format!("TLS file '{}' not found", path));
+            }
+        }
+
+        let auto_open_dir = match Path::new(&self.auto_open_path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+            _ => ".".to_string(),
+        };
+        if !check_if_dir_writable(&auto_open_dir) {
+            problems.push(format!(
+                "directory '{}' of the autoOpen file is not writable",
+                auto_open_dir
+            ));
+        }
+
+        if !CIPHERS.contains(&self.cipher.as_str()) {
+            problems.push(format!(
+                "cipher '{}' is not supported, use one of {}",
+                self.cipher,
+                CIPHERS.join(", ")
+            ));
+        }
+
+        if !(MIN_PBKDF2_ITERATIONS..=MAX_PBKDF2_ITERATIONS).contains(&self.pbkdf2_iterations) {
+            problems.push(format!(
+                "PBKDF2 iterations must be a number between {} and {}",
+                MIN_PBKDF2_ITERATIONS, MAX_PBKDF2_ITERATIONS
+            ));
+        }
+
+        if !LUKS_PBKDFS.contains(&self.luks_pbkdf.as_str()) {
+            problems.push(format!(
+                "LUKS PBKDF '{}' is not supported, use one of {}",
+                self.luks_pbkdf,
+                LUKS_PBKDFS.join(", ")
+            ));
+        }
+
+        if !(MIN_LUKS_ITER_TIME..=MAX_LUKS_ITER_TIME).contains(&self.luks_iter_time) {
+            problems.push(format!(
+                "LUKS iteration time must be a number of milliseconds between {} and {}",
+                MIN_LUKS_ITER_TIME, MAX_LUKS_ITER_TIME
+            ));
+        }
+
+        if !(1..=MAX_AUTO_OPEN_PARALLELISM).contains(&self.auto_open_parallelism) {
+            problems.push(format!(
+                "auto open parallelism must be a number between 1 and {}",
+                MAX_AUTO_OPEN_PARALLELISM
+            ));
+        }
+
+        if !(1..=MAX_KEYRING_TIMEOUT).contains(&self.keyring_timeout) {
+            problems.push(format!(
+                "keyring timeout must be a number of seconds between 1 and {}",
+                MAX_KEYRING_TIMEOUT
+            ));
+        }
+
+        if !(1..=MAX_AUTO_OPEN_WAIT_TIMEOUT).contains(&self.auto_open_wait_timeout) {
+            problems.push(format!(
+                "auto open wait timeout must be a number of seconds between 1 and {}",
+                MAX_AUTO_OPEN_WAIT_TIMEOUT
+            ));
+        }
+
+        if EnvFilter::try_new(&self.log_filter).is_err() {
+            problems.push(format!("log filter '{}' is not valid", self.log_filter));
+        }
+
+        for (name, binary) in self.required_binaries() {
+            if !binary_exists(binary) {
+                problems.push(format!("{} binary '{}' not found", name, binary));
+            }
+        }
+        problems
+    }
+
+    /// Returns true if the REST gateway is started, either on `rest_address` or on `rest_socket_path`.
+    #[cfg_attr(not(feature = "rest"), allow(dead_code))]
+    pub fn rest_enabled(&self) -> bool {
+        !self.rest_address.is_empty() || !self.rest_socket_path.is_empty()
+    }
+
+    /// Returns the binaries the daemon can not work without.
+    /// The binaries that are replaced by a feature are not required.
+    /// # Note
+    /// This function is not meant to be called directly.
+    fn required_binaries(&self) -> Vec<(&'static str, &str)> {
+        let mut binaries = vec![
+            ("lsblk", self.lsblk_path.as_str()),
+            ("mkfs.ext4", self.mkfs_ext4_path.as_str()),
+            ("dmesg", self.dmesg_path.as_str()),
+        ];
+        if !cfg!(feature = "libcryptsetup") {
+            binaries.push(("cryptsetup", self.cryptsetup_path.as_str()));
+        }
+        if !cfg!(feature = "native-mount") {
+            binaries.push(("mount", self.mount_path.as_str()));
+            binaries.push(("umount", self.umount_path.as_str()));
+        }
+        binaries
+    }
+}
+
+/// Checks if a binary exists and is executable.
+/// # Arguments
+/// * `binary` - The binary, a name without a path is looked up in the `PATH`.
+/// # Returns
+/// * `bool` - Returns true if the binary was found.
+/// # Note
+/// This function is not meant to be called directly.
+fn binary_exists(binary: &str) -> bool {
+    let is_executable = |path: &Path| {
+        path.metadata()
+            .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+    if binary.contains('/') {
+        return is_executable(Path::new(binary));
+    }
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| is_executable(&dir.join(binary))))
+        .unwrap_or(false)
+}
+
+/// The configuration of the running process, it is created on first use.
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Returns the configuration of the running process.
+/// # Arguments
+/// # Returns
+/// * `&'static Config` -
+/// Returns the configuration that was read from the environment on the first call.
+/// # Example
+/// ```
+/// let cryptsetup = &config().cryptsetup_path;
+/// ```
+///
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::from_env)
+}
+
+/// Reads an environment variable.
+/// # Arguments
+/// * `name` - The name of the environment variable.
+/// * `default` - The value that is used if the variable is not set or empty.
+/// # Returns
+/// * `String` -
+/// Returns the value of the environment variable or the default.
+/// # Note
+/// This function is not meant to be called directly.
+fn env_or(name: &str, default: String) -> String {
+    match env::var(name) {
+        Ok(value) if !value.is_empty() => value,
+        _ => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+        assert_eq!(config.cryptsetup_path, "cryptsetup");
+        assert_eq!(config.mkfs_ext4_path, "mkfs.ext4");
+        assert_eq!(config.mkfs_xfs_path, "mkfs.xfs");
+        assert_eq!(config.mkfs_btrfs_path, "mkfs.btrfs");
+        assert_eq!(config.dmsetup_path, "dmsetup");
+        assert_eq!(
+            env_or("SECURE_CONTAINER_NOT_EXISTING", "default".to_string()),
+            "default"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let problems = Config::default().validate();
+        assert!(problems
+            .iter()
+            .all(|problem| !problem.contains("listen address")
+                && !problem.contains("socket path")
+                && !problem.contains("TLS")
+                && !problem.contains("REST")
+                && !problem.contains("cipher")
+                && !problem.contains("PBKDF")
+                && !problem.contains("LUKS iteration time")
+                && !problem.contains("parallelism")
+                && !problem.contains("keyring")
+                && !problem.contains("wait timeout")
+                && !problem.contains("log filter")));
+
+        let config = Config {
+            listen_address: "localhost".to_string(),
+            socket_path: "secure_container.sock".to_string(),
+            tls_key_path: "/not/existing/key.pem".to_string(),
+            tls_client_ca_path: "/not/existing/ca.pem".to_string(),
+            cipher: "aes-cbc-essiv:sha256".to_string(),
+            pbkdf2_iterations: 1000,
+            luks_pbkdf: "scrypt".to_string(),
+            luks_iter_time: 0,
+            auto_open_parallelism: 0,
+            keyring_timeout: 0,
+            auto_open_wait_timeout: 0,
+            log_filter: "secure_container_daemon=loud".to_string(),
+            mkfs_ext4_path: "/not/existing/mkfs.ext4".to_string(),
+            auto_open_path: "/not/existing/auto_open".to_string(),
+            ..Config::default()
+        };
+        let problems = config.validate();
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("listen address")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("socket path")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("certificate and key must be set together")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("client CA needs a TLS certificate")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("TLS file '/not/existing/ca.pem' not found")));
+        let config = Config {
+            rest_address: "[::1]:50052".to_string(),
+            ..Config::default()
+        };
+        assert!(config.rest_enabled());
+        assert!(config
+            .validate()
+            .iter()
+            .any(|problem| problem.contains("REST address needs a TLS certificate")));
+        let config = Config {
+            rest_address: "localhost".to_string(),
+            rest_socket_path: "/tmp/secure_container_rest.sock".to_string(),
+            ..Config::default()
+        };
+        let rest_problems = config.validate();
+        assert!(rest_problems
+            .iter()
+            .any(|problem| problem.contains("REST address 'localhost'")));
+        assert!(rest_problems
+            .iter()
+            .any(|problem| problem.contains("can not be set together")));
+        assert!(!Config::default().rest_enabled());
+        let config = Config {
+            socket_path: "/tmp/secure_container.sock".to_string(),
+            ..Config::default()
+        };
+        assert!(config
+            .validate()
+            .iter()
+            .all(|problem| !problem.contains("socket path")));
+        assert!(problems.iter().any(|problem| problem.contains("cipher")));
+        assert!(problems.iter().any(|problem| problem.contains("PBKDF2")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("LUKS PBKDF")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("LUKS iteration time")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("parallelism")));
+        assert!(problems.iter().any(|problem| problem.contains("keyring")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("wait timeout")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("log filter")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("/not/existing/mkfs.ext4")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("'/not/existing' of the autoOpen file")));
+    }
+
+    #[test]
+    fn test_binary_exists() {
+        assert!(binary_exists("sh"));
+        assert!(binary_exists("/bin/sh"));
+        assert!(!binary_exists("not-existing-binary"));
+        assert!(!binary_exists("/etc/passwd"));
+    }
+}
@@ -0,0 +1,995 @@
+//! # Cryptsetup Backend
+//! This module provides the backends that execute the LUKS operations for the `cryptsetup_wrapper`.
+//! By default the `cryptsetup` binary is executed.
+//! When the project is built with the `libcryptsetup` feature,
+//! the libcryptsetup C library is called directly instead.
+//!
+//! ## Usage
+//! ```bash
+//! cargo build --release --features libcryptsetup
+//! ```
+//!
+
+use crate::error_handling;
+use error_handling::{Result, SecureContainerErr};
+
+use crate::config::config;
+use crate::deadline;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The number of key slots of a LUKS2 header.
+pub const LUKS2_KEYSLOTS: u8 = 32;
+
+/// The algorithm of the integrity protection of a container.
+pub const INTEGRITY: &str = "hmac-sha256";
+
+/// The type of the LUKS2 token that holds the metadata of a container.
+pub const TOKEN_TYPE: &str = "secure-container";
+
+/// The operations on LUKS containers that are needed by the project.
+/// Every backend reports its errors as `CryptsetupError` or `IsNotLuks`.
+pub trait CryptsetupBackend: Sync {
+    /// Formats a file as a LUKS2 container with integrity protection.
+    /// The key slot uses the PBKDF and iteration time of the configuration.
+    /// # Arguments
+    /// * `device_path` - The path to the file that will be the LUKS container.
+    /// * `password` - The password of the container.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the container was formatted successfully otherwise an error is returned.
+    fn format(&self, device_path: &str, password: &str) -> Result<()>;
+
+    /// Opens a LUKS container as `/dev/mapper/<namespace>`.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `password` - The password of the container.
+    /// * `read_only` - Opens the mapping read-only, so nothing can be written to the container.
+    /// * `keyslot` - The only key slot that is tried, if `None` every key slot is tried.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the container was opened successfully otherwise an error is returned.
+    fn open(
+        &self,
+        path: &str,
+        namespace: &str,
+        password: &str,
+        read_only: bool,
+        keyslot: Option<u8>,
+    ) -> Result<()>;
+
+    /// Closes the open LUKS container `/dev/mapper/<namespace>`.
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the container was closed successfully otherwise an error is returned.
+    fn close(&self, namespace: &str) -> Result<()>;
+
+    /// Replaces the password of a LUKS container.
+    /// The new key slot uses the PBKDF and iteration time of the configuration.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `old_password` - The current password of the container.
+    /// * `password` - The new password of the container.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the password was changed successfully otherwise an error is returned.
+    fn change_key(&self, path: &str, old_password: &str, password: &str) -> Result<()>;
+
+    /// Adds a password to a free key slot of a LUKS container, the other key slots are kept.
+    /// The new key slot uses the PBKDF and iteration time of the configuration.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `password` - A password that already unlocks a key slot of the container.
+    /// * `new_password` - The password that is added.
+    /// * `keyslot` - The free key slot the password is added to.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the password was added successfully otherwise an error is returned.
+    fn add_key(&self, path: &str, password: &str, new_password: &str, keyslot: u8) -> Result<()>;
+
+    /// Removes the key slot of a LUKS container that is unlocked by a password.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `password` - The password of the key slot that is removed.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the key slot was removed successfully otherwise an error is returned.
+    fn remove_key(&self, path: &str, password: &str) -> Result<()>;
+
+    /// Checks if a password unlocks a key slot of a LUKS container without opening it.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `password` - The password that is checked.
+    /// # Returns
+    /// * `Result<bool>` -
+    /// Returns true if the password unlocks a key slot, false if it unlocks none,
+    /// otherwise an error is returned, e.g. if the header can not be read.
+    fn check_key(&self, path: &str, password: &str) -> Result<bool>;
+
+    /// Checks if a file is a LUKS container.
+    /// # Arguments
+    /// * `path` - The path to the file.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the file is a LUKS container otherwise `IsNotLuks` is returned.
+    fn is_luks(&self, path: &str) -> Result<()>;
+
+    /// Reads the UUID of the LUKS header of a container.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// # Returns
+    /// * `Result<String>` -
+    /// Returns the UUID of the container otherwise an error is returned.
+    fn uuid(&self, path: &str) -> Result<String>;
+
+    /// Lists the key slots of a LUKS container that hold a key.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// # Returns
+    /// * `Result<Vec<u8>>` -
+    /// Returns the sorted indices of the used key slots otherwise an error is returned.
+    fn keyslots(&self, path: &str) -> Result<Vec<u8>>;
+
+    /// Stores a token of the type `TOKEN_TYPE` in the LUKS2 header of a container,
+    /// an existing token of this type is replaced.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `token_json` - The token as JSON, it must contain the fields `type` and `keyslots`.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the token was stored otherwise an error is returned,
+    /// e.g. for LUKS1 containers that have no tokens.
+    fn set_token(&self, path: &str, token_json: &str) -> Result<()>;
+
+    /// Reads the token of the type `TOKEN_TYPE` from the LUKS2 header of a container.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// # Returns
+    /// * `Result<Option<String>>` -
+    /// Returns the token as JSON or `None` if the header has no such token, otherwise an error is returned.
+    fn get_token(&self, path: &str) -> Result<Option<String>>;
+
+    /// Writes a copy of the LUKS header and the key slots of a container to a file.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `out_file` - The path of the backup, it must not exist yet.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the backup was written otherwise an error is returned.
+    fn header_backup(&self, path: &str, out_file: &str) -> Result<()>;
+
+    /// Replaces the LUKS header and the key slots of a container with a backup.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `in_file` - The path of the backup.
+    /// # Returns
+    /// * `Result<()>` -
+    /// Returns OK(()) if the header was restored otherwise an error is returned.
+    fn header_restore(&self, path: &str, in_file: &str) -> Result<()>;
+
+    /// Describes the LUKS header of a container, e.g. its cipher, key size, PBKDF and integrity algorithm.
+    /// The description contains no keys or passwords.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// # Returns
+    /// * `Result<String>` -
+    /// Returns the description in the format of `cryptsetup luksDump` otherwise an error is returned.
+    fn dump(&self, path: &str) -> Result<String>;
+}
+
+/// The backend that executes the `cryptsetup` binary that is configured in `config`.
+pub struct CliBackend;
+
+/// The backend that calls the libcryptsetup C library.
+#[cfg(feature = "libcryptsetup")]
+pub struct LibcryptsetupBackend;
+
+/// Returns the backend that is used by the project.
+/// # Arguments
+/// # Returns
+/// * `&'static dyn CryptsetupBackend` -
+/// Returns the libcryptsetup backend if the `libcryptsetup` feature is enabled,
+/// otherwise the backend that executes the `cryptsetup` binary.
+/// # Example
+/// ```
+/// let result = backend().is_luks("/home/Container");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn backend() -> &'static dyn CryptsetupBackend {
+    #[cfg(feature = "libcryptsetup")]
+    {
+        &LibcryptsetupBackend
+    }
+    #[cfg(not(feature = "libcryptsetup"))]
+    {
+        &CliBackend
+    }
+}
+
+/// The exit code of cryptsetup if no key slot is unlocked by the passphrase.
+const EXIT_CODE_BAD_PASSPHRASE: i32 = 2;
+
+/// Executes cryptsetup with the given arguments and writes the given input to its stdin.
+/// # Arguments
+/// * `args` - The arguments for cryptsetup.
+/// * `input` - The input that is written to stdin, if any.
+/// # Returns
+/// * `Result<std::process::Output>` -
+/// Returns the output of cryptsetup if it was executed successfully otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// # Note
+/// This function is not meant to be called directly.
+fn run_cryptsetup(args: &[&str], input: Option<&str>) -> Result<std::process::Output> {
+    if deadline::expired() {
+        return Err(SecureContainerErr::CryptsetupError(
+            "Deadline exceeded".to_string(),
+        ));
+    }
+    let mut child = match Command::new(&config().cryptsetup_path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
+    };
+    if let Some(input) = input {
+        let stdin = match child.stdin.as_mut() {
+            Some(stdin) => stdin,
+            None => {
+                return Err(SecureContainerErr::CryptsetupError(
+                    "Failed to open stdin".to_string(),
+                ))
+            }
+        };
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    // Close stdin so cryptsetup does not wait for further input.
+    drop(child.stdin.take());
+
+    // A running cryptsetup is never killed, it may be writing the header of the container.
+    match child.wait_with_output() {
+        Ok(output) => Ok(output),
+        Err(err) => Err(SecureContainerErr::CryptsetupError(err.to_string())),
+    }
+}
+
+/// Executes cryptsetup and turns an unsuccessful exit status into a `CryptsetupError`.
+/// # Arguments
+/// * `args` - The arguments for cryptsetup.
+/// * `input` - The input that is written to stdin, if any.
+/// # Returns
+/// * `Result<std::process::Output>` -
+/// Returns the output of cryptsetup if it exited successfully otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// # Note
+/// This function is not meant to be called directly.
+fn run_cryptsetup_checked(args: &[&str], input: Option<&str>) -> Result<std::process::Output> {
+    let output = run_cryptsetup(args, input)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::CryptsetupError(stderr.to_string()));
+    }
+    Ok(output)
+}
+
+/// Builds the arguments of `cryptsetup luksOpen`.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `namespace` - The name of the container.
+/// * `read_only` - Adds `--readonly`, so the mapping is opened read-only.
+/// * `keyslot` - Adds `--key-slot`, so only this key slot is tried.
+/// # Returns
+/// * `Vec<String>` - The arguments for cryptsetup.
+/// # Note
+/// This function is not meant to be called directly.
+fn open_args(path: &str, namespace: &str, read_only: bool, keyslot: Option<u8>) -> Vec<String> {
+    let mut args = vec!["luksOpen".to_string()];
+    if read_only {
+        args.push("--readonly".to_string());
+    }
+    if let Some(keyslot) = keyslot {
+        args.push("--key-slot".to_string());
+        args.push(keyslot.to_string());
+    }
+    args.push(path.to_string());
+    args.push(namespace.to_string());
+    args
+}
+
+/// Interprets the output of `cryptsetup open --test-passphrase`.
+/// # Arguments
+/// * `output` - The output of cryptsetup.
+/// # Returns
+/// * `Result<bool>` -
+/// Returns true if the passphrase is valid, false if cryptsetup exits with `EXIT_CODE_BAD_PASSPHRASE`.
+/// # Errors
+/// * `CryptsetupError` - Cryptsetup failed for another reason.
+/// # Note
+/// This function is not meant to be called directly.
+fn passphrase_result(output: &std::process::Output) -> Result<bool> {
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(EXIT_CODE_BAD_PASSPHRASE) => Ok(false),
+        _ => Err(SecureContainerErr::CryptsetupError(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        )),
+    }
+}
+
+impl CryptsetupBackend for CliBackend {
+    fn format(&self, device_path: &str, password: &str) -> Result<()> {
+        run_cryptsetup_checked(
+            &[
+                "luksFormat",
+                device_path,
+                "--type",
+                "luks2",
+                "--cipher",
+                &config().cipher,
+                "--integrity",
+                INTEGRITY,
+                "--pbkdf",
+                &config().luks_pbkdf,
+                "--iter-time",
+                &config().luks_iter_time.to_string(),
+            ],
+            Some(password),
+        )?;
+        Ok(())
+    }
+
+    fn open(
+        &self,
+        path: &str,
+        namespace: &str,
+        password: &str,
+        read_only: bool,
+        keyslot: Option<u8>,
+    ) -> Result<()> {
+        let args = open_args(path, namespace, read_only, keyslot);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_cryptsetup_checked(&args, Some(password))?;
+        Ok(())
+    }
+
+    fn close(&self, namespace: &str) -> Result<()> {
+        run_cryptsetup_checked(&["luksClose", namespace], None)?;
+        Ok(())
+    }
+
+    fn change_key(&self, path: &str, old_password: &str, password: &str) -> Result<()> {
+        let input = format!("{}\n{}", old_password, password);
+        run_cryptsetup_checked(
+            &[
+                "luksChangeKey",
+                path,
+                "--pbkdf",
+                &config().luks_pbkdf,
+                "--iter-time",
+                &config().luks_iter_time.to_string(),
+            ],
+            Some(&input),
+        )?;
+        Ok(())
+    }
+
+    fn add_key(&self, path: &str, password: &str, new_password: &str, keyslot: u8) -> Result<()> {
+        let input = format!("{}\n{}", password, new_password);
+        run_cryptsetup_checked(
+            &[
+                "luksAddKey",
+                path,
+                "--key-slot",
+                &keyslot.to_string(),
+                "--pbkdf",
+                &config().luks_pbkdf,
+                "--iter-time",
+                &config().luks_iter_time.to_string(),
+            ],
+            Some(&input),
+        )?;
+        Ok(())
+    }
+
+    fn remove_key(&self, path: &str, password: &str) -> Result<()> {
+        run_cryptsetup_checked(&["luksRemoveKey", path], Some(password))?;
+        Ok(())
+    }
+
+    fn check_key(&self, path: &str, password: &str) -> Result<bool> {
+        let output = run_cryptsetup(&["open", "--test-passphrase", path], Some(password))?;
+        passphrase_result(&output)
+    }
+
+    fn is_luks(&self, path: &str) -> Result<()> {
+        let output = run_cryptsetup(&["isLuks", path], None)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(SecureContainerErr::IsNotLuks(stderr.to_string()));
+        }
+        Ok(())
+    }
+
+    fn uuid(&self, path: &str) -> Result<String> {
+        let output = run_cryptsetup_checked(&["luksUUID", path], None)?;
+        match String::from_utf8(output.stdout) {
+            Ok(stdout) => Ok(stdout.trim().to_string()),
+            Err(err) => Err(SecureContainerErr::ReadingStdoutError(err)),
+        }
+    }
+
+    fn keyslots(&self, path: &str) -> Result<Vec<u8>> {
+        let output = run_cryptsetup_checked(&["luksDump", "--dump-json-metadata", path], None)?;
+        parse_keyslots(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    fn set_token(&self, path: &str, token_json: &str) -> Result<()> {
+        let output = run_cryptsetup_checked(&["luksDump", "--dump-json-metadata", path], None)?;
+        match parse_token_id(&String::from_utf8_lossy(&output.stdout))? {
+            Some(id) => run_cryptsetup_checked(
+                &[
+                    "token",
+                    "import",
+                    "--token-id",
+                    &id.to_string(),
+                    "--token-replace",
+                    path,
+                ],
+                Some(token_json),
+            )?,
+            None => run_cryptsetup_checked(&["token", "import", path], Some(token_json))?,
+        };
+        Ok(())
+    }
+
+    fn get_token(&self, path: &str) -> Result<Option<String>> {
+        let output = run_cryptsetup_checked(&["luksDump", "--dump-json-metadata", path], None)?;
+        let id = match parse_token_id(&String::from_utf8_lossy(&output.stdout))? {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let output = run_cryptsetup_checked(
+            &["token", "export", "--token-id", &id.to_string(), path],
+            None,
+        )?;
+        match String::from_utf8(output.stdout) {
+            Ok(stdout) => Ok(Some(stdout.trim().to_string())),
+            Err(err) => Err(SecureContainerErr::ReadingStdoutError(err)),
+        }
+    }
+
+    fn header_backup(&self, path: &str, out_file: &str) -> Result<()> {
+        run_cryptsetup_checked(
+            &["luksHeaderBackup", path, "--header-backup-file", out_file],
+            None,
+        )?;
+        Ok(())
+    }
+
+    fn header_restore(&self, path: &str, in_file: &str) -> Result<()> {
+        // Without batch mode cryptsetup asks for a confirmation on the terminal.
+        run_cryptsetup_checked(
+            &[
+                "luksHeaderRestore",
+                "--batch-mode",
+                path,
+                "--header-backup-file",
+                in_file,
+            ],
+            None,
+        )?;
+        Ok(())
+    }
+
+    fn dump(&self, path: &str) -> Result<String> {
+        let output = run_cryptsetup_checked(&["luksDump", path], None)?;
+        match String::from_utf8(output.stdout) {
+            Ok(stdout) => Ok(stdout),
+            Err(err) => Err(SecureContainerErr::ReadingStdoutError(err)),
+        }
+    }
+}
+
+/// Parses the used key slots from the JSON metadata of a LUKS2 header.
+/// # Arguments
+/// * `json` - The output of `cryptsetup luksDump --dump-json-metadata`.
+/// # Returns
+/// * `Result<Vec<u8>>` - Returns the sorted indices of the used key slots.
+/// # Errors
+/// * `CryptsetupError` - The metadata is not valid.
+/// # Note
+/// This function is not meant to be called directly.
+fn parse_keyslots(json: &str) -> Result<Vec<u8>> {
+    let metadata: serde_json::Value = match serde_json::from_str(json) {
+        Ok(metadata) => metadata,
+        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
+    };
+    let keyslots = match metadata["keyslots"].as_object() {
+        Some(keyslots) => keyslots,
+        None => {
+            return Err(SecureContainerErr::CryptsetupError(
+                "No keyslots in LUKS2 metadata".to_string(),
+            ))
+        }
+    };
+    let mut used = Vec::new();
+    for index in keyslots.keys() {
+        match index.parse::<u8>() {
+            Ok(index) => used.push(index),
+            Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
+        }
+    }
+    used.sort();
+    Ok(used)
+}
+
+/// Finds the token of the type `TOKEN_TYPE` in the JSON metadata of a LUKS2 header.
+/// # Arguments
+/// * `json` - The output of `cryptsetup luksDump --dump-json-metadata`.
+/// # Returns
+/// * `Result<Option<u32>>` - Returns the id of the token or `None` if there is no such token.
+/// # Errors
+/// * `CryptsetupError` - The metadata is not valid.
+/// # Note
+/// This function is not meant to be called directly.
+fn parse_token_id(json: &str) -> Result<Option<u32>> {
+    let metadata: serde_json::Value = match serde_json::from_str(json) {
+        Ok(metadata) => metadata,
+        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
+    };
+    let tokens = match metadata["tokens"].as_object() {
+        Some(tokens) => tokens,
+        None => {
+            return Err(SecureContainerErr::CryptsetupError(
+                "No tokens in LUKS2 metadata".to_string(),
+            ))
+        }
+    };
+    let mut ids = Vec::new();
+    for (id, token) in tokens {
+        if token["type"] == TOKEN_TYPE {
+            match id.parse::<u32>() {
+                Ok(id) => ids.push(id),
+                Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
+            }
+        }
+    }
+    Ok(ids.into_iter().min())
+}
+
+#[cfg(feature = "libcryptsetup")]
+mod libcryptsetup {
+    use super::{CryptsetupBackend, LibcryptsetupBackend, TOKEN_TYPE};
+    use crate::config::config;
+    use crate::error_handling::{Result, SecureContainerErr};
+
+    use libcryptsetup_rs::consts::flags::{
+        CryptActivate, CryptDeactivate, CryptPbkdf, CryptVolumeKey, CryptWipe,
+    };
+    use libcryptsetup_rs::consts::vals::{
+        CryptKdf, CryptWipePattern, EncryptionFormat, KeyslotInfo,
+    };
+    use libcryptsetup_rs::{
+        CryptDevice, CryptInit, CryptKeyslotHandle, CryptParamsLuks2, CryptParamsLuks2Ref,
+        CryptPbkdfType, CryptTokenInfo, Either, LibcryptErr, TokenInput,
+    };
+    use std::fmt::Write;
+    use std::path::Path;
+
+    /// The size of the volume key in bytes,
+    /// 512 bit for the XTS cipher and 256 bit for the hmac-sha256 integrity tags.
+    /// This matches the defaults of `cryptsetup luksFormat --integrity hmac-sha256`.
+    const VOLUME_KEY_SIZE: usize = 96;
+
+    /// The number of tokens of a LUKS2 header.
+    const LUKS2_TOKENS: u32 = 32;
+
+    /// Converts a libcryptsetup error into a `CryptsetupError`.
+    fn to_err(err: LibcryptErr) -> SecureContainerErr {
+        SecureContainerErr::CryptsetupError(err.to_string())
+    }
+
+    /// Returns the PBKDF of new key slots from the configuration.
+    /// The memory and threads of Argon2 are the defaults of cryptsetup.
+    fn pbkdf() -> CryptPbkdfType {
+        let (type_, max_memory_kb, parallel_threads) = match config().luks_pbkdf.as_str() {
+            "pbkdf2" => (CryptKdf::Pbkdf2, 0, 0),
+            "argon2i" => (CryptKdf::Argon2I, 1024 * 1024, 4),
+            _ => (CryptKdf::Argon2Id, 1024 * 1024, 4),
+        };
+        CryptPbkdfType {
+            type_,
+            hash: "sha256".to_string(),
+            time_ms: config().luks_iter_time,
+            iterations: 0,
+            max_memory_kb,
+            parallel_threads,
+            flags: CryptPbkdf::empty(),
+        }
+    }
+
+    /// Initializes the given container and loads its LUKS2 header.
+    fn load(path: &str) -> std::result::Result<CryptDevice, LibcryptErr> {
+        let mut device = CryptInit::init(Path::new(path))?;
+        device
+            .context_handle()
+            .load::<()>(Some(EncryptionFormat::Luks2), None)?;
+        Ok(device)
+    }
+
+    /// Finds the token of the type `TOKEN_TYPE` in the LUKS2 header of the given container.
+    fn token_id(device: &mut CryptDevice) -> std::result::Result<Option<u32>, LibcryptErr> {
+        for id in 0..LUKS2_TOKENS {
+            match device.token_handle().status(id)? {
+                CryptTokenInfo::Internal(token_type)
+                | CryptTokenInfo::InternalUnknown(token_type)
+                | CryptTokenInfo::External(token_type)
+                | CryptTokenInfo::ExternalUnknown(token_type)
+                    if token_type == TOKEN_TYPE =>
+                {
+                    return Ok(Some(id))
+                }
+                _ => (),
+            }
+        }
+        Ok(None)
+    }
+
+    impl CryptsetupBackend for LibcryptsetupBackend {
+        fn format(&self, device_path: &str, password: &str) -> Result<()> {
+            let mut device = CryptInit::init(Path::new(device_path)).map_err(to_err)?;
+            let params = CryptParamsLuks2 {
+                pbkdf: Some(pbkdf()),
+                integrity: Some("hmac(sha256)".to_string()),
+                integrity_params: None,
+                data_alignment: 0,
+                data_device: None,
+                sector_size: 512,
+                label: None,
+                subsystem: None,
+            };
+            let mut params: CryptParamsLuks2Ref = (&params).try_into().map_err(to_err)?;
+            // libcryptsetup expects the cipher and its mode separately, e.g. "aes" and "xts-plain64".
+            let cipher = config().cipher.split_once('-').ok_or_else(|| {
+                SecureContainerErr::CryptsetupError("Cipher not valid".to_string())
+            })?;
+            device
+                .context_handle()
+                .format(
+                    EncryptionFormat::Luks2,
+                    cipher,
+                    None,
+                    Either::Right(VOLUME_KEY_SIZE),
+                    Some(&mut params),
+                )
+                .map_err(to_err)?;
+            device
+                .keyslot_handle()
+                .add_by_key(None, None, password.as_bytes(), CryptVolumeKey::empty())
+                .map_err(to_err)?;
+
+            // The integrity tags have to be initialized by writing the whole device once,
+            // cryptsetup does the same after formatting a device with integrity protection.
+            let name = format!("secure-container-wipe-{}", std::process::id());
+            device
+                .activate_handle()
+                .activate_by_passphrase(
+                    Some(&name),
+                    None,
+                    password.as_bytes(),
+                    CryptActivate::PRIVATE,
+                )
+                .map_err(to_err)?;
+            let wiped = device.wipe_handle().wipe::<()>(
+                Path::new(&format!("/dev/mapper/{}", name)),
+                CryptWipePattern::Zero,
+                0,
+                0,
+                1024 * 1024,
+                CryptWipe::NO_DIRECT_IO,
+                None,
+                None,
+            );
+            device
+                .activate_handle()
+                .deactivate(&name, CryptDeactivate::empty())
+                .map_err(to_err)?;
+            wiped.map_err(to_err)
+        }
+
+        fn open(
+            &self,
+            path: &str,
+            namespace: &str,
+            password: &str,
+            read_only: bool,
+            keyslot: Option<u8>,
+        ) -> Result<()> {
+            let mut device = load(path).map_err(to_err)?;
+            let flags = if read_only {
+                CryptActivate::READONLY
+            } else {
+                CryptActivate::empty()
+            };
+            device
+                .activate_handle()
+                .activate_by_passphrase(
+                    Some(namespace),
+                    keyslot.map(u32::from),
+                    password.as_bytes(),
+                    flags,
+                )
+                .map_err(to_err)?;
+            Ok(())
+        }
+
+        fn close(&self, namespace: &str) -> Result<()> {
+            let mut device = CryptInit::init_by_name_and_header(namespace, None).map_err(to_err)?;
+            device
+                .activate_handle()
+                .deactivate(namespace, CryptDeactivate::empty())
+                .map_err(to_err)
+        }
+
+        fn change_key(&self, path: &str, old_password: &str, password: &str) -> Result<()> {
+            let mut device = load(path).map_err(to_err)?;
+            device
+                .settings_handle()
+                .set_pbkdf_type(&pbkdf())
+                .map_err(to_err)?;
+            device
+                .keyslot_handle()
+                .change_by_passphrase(None, None, old_password.as_bytes(), password.as_bytes())
+                .map_err(to_err)?;
+            Ok(())
+        }
+
+        fn add_key(
+            &self,
+            path: &str,
+            password: &str,
+            new_password: &str,
+            keyslot: u8,
+        ) -> Result<()> {
+            let mut device = load(path).map_err(to_err)?;
+            device
+                .settings_handle()
+                .set_pbkdf_type(&pbkdf())
+                .map_err(to_err)?;
+            device
+                .keyslot_handle()
+                .add_by_passphrase(
+                    Some(u32::from(keyslot)),
+                    password.as_bytes(),
+                    new_password.as_bytes(),
+                )
+                .map_err(to_err)?;
+            Ok(())
+        }
+
+        fn remove_key(&self, path: &str, password: &str) -> Result<()> {
+            let mut device = load(path).map_err(to_err)?;
+            // Without a name the key slot is only unlocked, this finds the key slot of the password.
+            let keyslot = device
+                .activate_handle()
+                .activate_by_passphrase(None, None, password.as_bytes(), CryptActivate::empty())
+                .map_err(to_err)?;
+            device.keyslot_handle().destroy(keyslot).map_err(to_err)
+        }
+
+        fn check_key(&self, path: &str, password: &str) -> Result<bool> {
+            let mut device = load(path).map_err(to_err)?;
+            // Without a name the key slot is only unlocked and no device is activated.
+            match device.activate_handle().activate_by_passphrase(
+                None,
+                None,
+                password.as_bytes(),
+                CryptActivate::empty(),
+            ) {
+                Ok(_) => Ok(true),
+                // libcryptsetup returns EPERM if no key slot is unlocked by the passphrase.
+                Err(LibcryptErr::IOError(err)) if err.raw_os_error() == Some(1) => Ok(false),
+                Err(err) => Err(to_err(err)),
+            }
+        }
+
+        fn is_luks(&self, path: &str) -> Result<()> {
+            match load(path) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(SecureContainerErr::IsNotLuks(err.to_string())),
+            }
+        }
+
+        fn uuid(&self, path: &str) -> Result<String> {
+            let mut device = load(path).map_err(to_err)?;
+            let uuid = device.status_handle().get_uuid().map_err(to_err)?;
+            Ok(uuid.to_string())
+        }
+
+        fn keyslots(&self, path: &str) -> Result<Vec<u8>> {
+            let mut device = load(path).map_err(to_err)?;
+            let max = CryptKeyslotHandle::max_keyslots(EncryptionFormat::Luks2).map_err(to_err)?;
+            let mut used = Vec::new();
+            for index in 0..max {
+                match device.keyslot_handle().status(index).map_err(to_err)? {
+                    KeyslotInfo::Active | KeyslotInfo::ActiveLast | KeyslotInfo::Unbound => {
+                        used.push(index as u8)
+                    }
+                    KeyslotInfo::Inactive | KeyslotInfo::Invalid => (),
+                }
+            }
+            Ok(used)
+        }
+
+        fn set_token(&self, path: &str, token_json: &str) -> Result<()> {
+            let token: serde_json::Value = serde_json::from_str(token_json)
+                .map_err(|err| SecureContainerErr::CryptsetupError(err.to_string()))?;
+            let mut device = load(path).map_err(to_err)?;
+            let input = match token_id(&mut device).map_err(to_err)? {
+                Some(id) => TokenInput::ReplaceToken(id, &token),
+                None => TokenInput::AddToken(&token),
+            };
+            device.token_handle().json_set(input).map_err(to_err)?;
+            Ok(())
+        }
+
+        fn get_token(&self, path: &str) -> Result<Option<String>> {
+            let mut device = load(path).map_err(to_err)?;
+            match token_id(&mut device).map_err(to_err)? {
+                Some(id) => {
+                    let token = device.token_handle().json_get(id).map_err(to_err)?;
+                    Ok(Some(token.to_string()))
+                }
+                None => Ok(None),
+            }
+        }
+
+        fn header_backup(&self, path: &str, out_file: &str) -> Result<()> {
+            let mut device = load(path).map_err(to_err)?;
+            device
+                .backup_handle()
+                .header_backup(Some(EncryptionFormat::Luks2), Path::new(out_file))
+                .map_err(to_err)
+        }
+
+        fn header_restore(&self, path: &str, in_file: &str) -> Result<()> {
+            // The header of the container may be damaged, so it is not loaded before the restore.
+            let mut device = CryptInit::init(Path::new(path)).map_err(to_err)?;
+            device
+                .backup_handle()
+                .header_restore(Some(EncryptionFormat::Luks2), Path::new(in_file))
+                .map_err(to_err)
+        }
+
+        fn dump(&self, path: &str) -> Result<String> {
+            // crypt_dump only prints to stdout, so the fields of `cryptsetup luksDump` are collected here.
+            let mut device = load(path).map_err(to_err)?;
+            let uuid = device.status_handle().get_uuid().map_err(to_err)?;
+            let cipher = device.status_handle().get_cipher().map_err(to_err)?;
+            let cipher_mode = device.status_handle().get_cipher_mode().map_err(to_err)?;
+            let key_size = device.status_handle().get_volume_key_size() * 8;
+
+            let mut dump = String::new();
+            let _ = writeln!(dump, "Version:        2");
+            let _ = writeln!(dump, "UUID:           {}", uuid);
+            let _ = writeln!(dump, "Cipher:         {}-{}", cipher, cipher_mode);
+            let _ = writeln!(dump, "Cipher key:     {} bits", key_size);
+            if let Ok(integrity) = device.status_handle().get_integrity_info() {
+                let _ = writeln!(dump, "Integrity:      {}", integrity.integrity);
+            }
+            let _ = writeln!(dump, "Keyslots:");
+            let max = CryptKeyslotHandle::max_keyslots(EncryptionFormat::Luks2).map_err(to_err)?;
+            for index in 0..max {
+                match device.keyslot_handle().status(index).map_err(to_err)? {
+                    KeyslotInfo::Active | KeyslotInfo::ActiveLast | KeyslotInfo::Unbound => (),
+                    KeyslotInfo::Inactive | KeyslotInfo::Invalid => continue,
+                }
+                let pbkdf = device.keyslot_handle().get_pbkdf(index).map_err(to_err)?;
+                let kdf = match pbkdf.type_ {
+                    CryptKdf::Pbkdf2 => "pbkdf2",
+                    CryptKdf::Argon2I => "argon2i",
+                    CryptKdf::Argon2Id => "argon2id",
+                };
+                let _ = writeln!(dump, "  {}: luks2", index);
+                let _ = writeln!(dump, "\tPBKDF:      {}", kdf);
+                if pbkdf.type_ == CryptKdf::Pbkdf2 {
+                    let _ = writeln!(dump, "\tHash:       {}", pbkdf.hash);
+                    let _ = writeln!(dump, "\tIterations: {}", pbkdf.iterations);
+                } else {
+                    let _ = writeln!(dump, "\tTime cost:  {}", pbkdf.iterations);
+                    let _ = writeln!(dump, "\tMemory:     {}", pbkdf.max_memory_kb);
+                    let _ = writeln!(dump, "\tThreads:    {}", pbkdf.parallel_threads);
+                }
+            }
+            Ok(dump)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_backend_is_luks() {
+        let path = std::env::current_dir().unwrap().join("Cargo.toml");
+        let result = CliBackend.is_luks(path.to_str().unwrap());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_open_args() {
+        assert_eq!(
+            open_args("/home/Container", "MyContainer", false, None),
+            vec!["luksOpen", "/home/Container", "MyContainer"]
+        );
+        let args = open_args("/home/Container", "MyContainer", true, None);
+        assert!(args.contains(&"--readonly".to_string()));
+        assert_eq!(
+            args,
+            vec!["luksOpen", "--readonly", "/home/Container", "MyContainer"]
+        );
+        assert_eq!(
+            open_args("/home/Container", "MyContainer", false, Some(3)),
+            vec![
+                "luksOpen",
+                "--key-slot",
+                "3",
+                "/home/Container",
+                "MyContainer"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_passphrase_result() {
+        use std::os::unix::process::ExitStatusExt;
+        let output = |code: i32| std::process::Output {
+            status: std::process::ExitStatus::from_raw(code << 8),
+            stdout: Vec::new(),
+            stderr: b"Device /home/Container is not a valid LUKS device.".to_vec(),
+        };
+        assert_eq!(passphrase_result(&output(0)), Ok(true));
+        assert_eq!(
+            passphrase_result(&output(EXIT_CODE_BAD_PASSPHRASE)),
+            Ok(false)
+        );
+        assert_eq!(
+            passphrase_result(&output(1)),
+            Err(SecureContainerErr::CryptsetupError(
+                "Device /home/Container is not a valid LUKS device.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_keyslots() {
+        let json =
+            r#"{"keyslots": {"2": {"type": "luks2"}, "0": {"type": "luks2"}}, "tokens": {}}"#;
+        assert_eq!(parse_keyslots(json), Ok(vec![0, 2]));
+        assert!(parse_keyslots(r#"{"tokens": {}}"#).is_err());
+        assert!(parse_keyslots("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_token_id() {
+        let json = r#"{"keyslots": {}, "tokens": {"0": {"type": "systemd-tpm2"}, "3": {"type": "secure-container"}}}"#;
+        assert_eq!(parse_token_id(json), Ok(Some(3)));
+        assert_eq!(
+            parse_token_id(r#"{"tokens": {"0": {"type": "systemd-tpm2"}}}"#),
+            Ok(None)
+        );
+        assert!(parse_token_id(r#"{"keyslots": {}}"#).is_err());
+        assert!(parse_token_id("not json").is_err());
+    }
+}
@@ -1,75 +1,288 @@
 //! # Cryptsetup Wrapper
-//! This module provides a wrapper for the cryptsetup command line tool.
+//! This module provides a wrapper for cryptsetup.
+//! The LUKS operations are executed by the backend of the `cryptsetup_backend` module.
 //! This module is used to create, open, close, export and import a container.
 //! It also provides functions to change the password of a container,
 //! format a container and check if a file is a LUKS container.
+//! Swap containers are created with a random key that is not stored anywhere,
+//! so their content is lost when they are closed.
 //!
 //!
 
 use crate::error_handling;
-use error_handling::{check_input, Result, SecureContainerErr};
+use error_handling::{Result, SecureContainerErr};
+
+use crate::input_validation::{check_file_system, check_input, check_mount_options, device_name};
 
 use crate::file_system_operations;
 use file_system_operations::{
-    check_container_mounted, check_container_open, check_if_dir_exists, check_if_file_exists,
-    check_lsblk, create_file, create_name_dir, mount, unmount,
+    bind_mount as mount_bind, check_container_mounted, check_container_open, check_if_block_device,
+    check_if_dir_exists, check_if_dir_writable, check_if_file_exists, check_lsblk,
+    check_mount_point_empty, check_mounted_at, check_swap_active, create_file, create_name_dir,
+    get_backing_path, get_device_size, get_mount_point, is_same_dir, list_crypt_devices, make_swap,
+    mount, swap_off, swap_on, unmount,
 };
 
 use crate::file_io_operations;
-use file_io_operations::auto_open_write;
+use file_io_operations::{
+    auto_open_change_id, auto_open_mount_point, auto_open_read, auto_open_write,
+};
 
 use crate::utilities;
-use utilities::{check_integrity, convert_to_base64, get_password};
+use utilities::{
+    check_integrity, convert_to_base64, get_random_password, get_random_salt, get_salted_password,
+    parse_time, TIME_FORMAT,
+};
 
 use crate::utilities::check_functionality_of_integrity;
+
+use crate::config::config;
+use crate::cryptsetup_backend::{backend, INTEGRITY, LUKS2_KEYSLOTS};
+use crate::keyring::{cache_password, cached_password};
+use crate::metadata::{
+    metadata_path, read_metadata, store_metadata, ContainerMetadata, KEY_DERIVATION,
+    LEGACY_KEY_DERIVATION, METADATA_VERSION,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::{DateTime, FixedOffset};
+use ring::digest::{digest, SHA256};
 use ring::pbkdf2::derive;
-use std::io::Write;
+use std::collections::HashMap;
+use std::fs::{DirBuilder, OpenOptions, Permissions};
+use std::io::{ErrorKind, Read};
 use std::num::NonZeroU32;
-use std::process::{Command, Stdio};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The mode of header backups, they contain the key slots of the container.
+const HEADER_BACKUP_MODE: u32 = 0o600;
+
+/// The times at which the containers were opened by the daemon, by namespace.
+/// They are the default cutoff of `verify_integrity`.
+static OPEN_TIMES: Mutex<Option<HashMap<String, DateTime<FixedOffset>>>> = Mutex::new(None);
+
+/// Records or forgets the time at which a container was opened.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `time` - The time the container was opened or `None` if it was closed.
+/// # Note
+/// This function is not meant to be called directly.
+fn set_open_time(namespace: &str, time: Option<DateTime<FixedOffset>>) {
+    let mut open_times = OPEN_TIMES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let open_times = open_times.get_or_insert_with(HashMap::new);
+    match time {
+        Some(time) => open_times.insert(namespace.to_string(), time),
+        None => open_times.remove(namespace),
+    };
+}
+
+/// Returns the time at which a container was opened by the daemon.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Option<DateTime<FixedOffset>>` - The time or `None` if the container was not opened since the daemon started.
+/// # Note
+/// This function is not meant to be called directly.
+fn open_time(namespace: &str) -> Option<DateTime<FixedOffset>> {
+    OPEN_TIMES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .and_then(|open_times| open_times.get(namespace).cloned())
+}
+
+/// The targets of the bind mounts of the containers, by namespace, in the order they were mounted.
+static BIND_MOUNTS: Mutex<Option<HashMap<String, Vec<String>>>> = Mutex::new(None);
+
+/// Runs an operation on the targets of the bind mounts of a container.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `operation` - The operation on the targets.
+/// # Returns
+/// * `T` - The result of the operation.
+/// # Note
+/// This function is not meant to be called directly.
+fn with_bind_mounts<T>(namespace: &str, operation: impl FnOnce(&mut Vec<String>) -> T) -> T {
+    let mut bind_mounts = BIND_MOUNTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let bind_mounts = bind_mounts.get_or_insert_with(HashMap::new);
+    let targets = bind_mounts.entry(namespace.to_string()).or_default();
+    let result = operation(targets);
+    if targets.is_empty() {
+        bind_mounts.remove(namespace);
+    }
+    result
+}
+
+/// Unmounts all bind mounts of a container, the last one first.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if all bind mounts were unmounted otherwise the first error is returned,
+/// the bind mounts that were not unmounted stay tracked.
+/// # Note
+/// This function is not meant to be called directly.
+fn unmount_binds(namespace: &str) -> Result<()> {
+    with_bind_mounts(namespace, |targets| {
+        while let Some(target) = targets.last() {
+            unmount(target)?;
+            targets.pop();
+        }
+        Ok(())
+    })
+}
+
+/// The memory in KiB that Argon2id uses to derive the password of an exported container from the secret.
+pub const ARGON2_MEMORY: u32 = 65_536;
 
-/// The number of iterations the pseudorandom function for the hmac-sha256 algorithm is executed.
-/// This is used for the derivation of the new password for exporting a container.
-const COUNT_PSEUDORANDOM_FUNCTION: u32 = 600000; //count for pseudorandom
+/// The iterations of Argon2id that derive the password of an exported container from the secret.
+pub const ARGON2_ITERATIONS: u32 = 3;
+
+/// The key derivation of secrets of exported containers.
+pub const SECRET_KDF: &str = "argon2id";
+
+/// The key derivation of secrets of containers that were exported before Argon2id was used,
+/// their salt is the namespace.
+pub const LEGACY_SECRET_KDF: &str = "pbkdf2-hmac-sha256";
+
+/// The credential that protects a container while it is transferred to another system.
+/// * `Secret` - A secret phrase, the password is derived from it with Argon2id and a random salt.
+/// * `Keyfile` - A file whose contents are hashed to the password, e.g. random bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferCredential {
+    Secret(String),
+    Keyfile(PathBuf),
+}
+
+impl TransferCredential {
+    /// Returns the key derivation that turns the credential into the password for the transfer.
+    /// # Returns
+    /// * `(&'static str, u32)` - The name of the algorithm and its number of iterations.
+    pub fn kdf(&self) -> (&'static str, u32) {
+        match self {
+            TransferCredential::Secret(_) => (SECRET_KDF, ARGON2_ITERATIONS),
+            TransferCredential::Keyfile(_) => ("sha256", 1),
+        }
+    }
+
+    /// Returns the key derivation of containers that were exported with the credential before Argon2id was used.
+    /// # Returns
+    /// * `Option<(&'static str, u32)>` -
+    /// The name of the algorithm and its number of iterations or `None` if the key derivation did not change.
+    pub fn legacy_kdf(&self) -> Option<(&'static str, u32)> {
+        match self {
+            TransferCredential::Secret(_) => Some((LEGACY_SECRET_KDF, config().pbkdf2_iterations)),
+            TransferCredential::Keyfile(_) => None,
+        }
+    }
+}
+
+/// The details of a container that was created and opened by `create_container`.
+/// * `namespace` - The name of the container.
+/// * `mapper_device` - The device of the opened container in `/dev/mapper`.
+/// * `mount_point` - The path where the container is mounted.
+/// * `usable_bytes` - The size of the opened container in bytes.
+/// * `uuid` - The UUID of the LUKS header of the container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatedContainer {
+    pub namespace: String,
+    pub mapper_device: String,
+    pub mount_point: String,
+    pub usable_bytes: u64,
+    pub uuid: String,
+}
+
+/// The phase in which the backing file of a new container is created.
+pub const PHASE_ALLOCATING: &str = "allocating";
+/// The phase in which the LUKS header of a new container is written.
+pub const PHASE_FORMATTING: &str = "formatting";
+/// The phase in which the file system of a new container is created and mounted.
+pub const PHASE_MAKING_FILESYSTEM: &str = "making filesystem";
+/// The last phase, the container is created.
+pub const PHASE_DONE: &str = "done";
+
+/// The progress of creating a container, it is reported at the start of every phase.
+/// * `phase` - One of the `PHASE_*` constants.
+/// * `percent` - The estimated part of the creation that is finished, from 0 to 100.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CreateProgress {
+    pub phase: &'static str,
+    pub percent: u32,
+}
+
+impl CreateProgress {
+    /// Returns the progress at the start of a phase.
+    /// Formatting takes most of the time, it writes the integrity tags of the whole container.
+    /// # Arguments
+    /// * `phase` - One of the `PHASE_*` constants.
+    /// # Returns
+    /// * `CreateProgress` - The phase with its estimated percentage.
+    pub fn of(phase: &'static str) -> CreateProgress {
+        let percent = match phase {
+            PHASE_ALLOCATING => 0,
+            PHASE_FORMATTING => 10,
+            PHASE_MAKING_FILESYSTEM => 80,
+            _ => 100,
+        };
+        CreateProgress { phase, percent }
+    }
+}
 
 /// Creates and opens a new container.
 /// # Arguments
 /// * `size` - The size of the container in MB (must be at least 16MB).
 /// * `mount_point` - The path to the mount point (must already exist).
-/// * `path` - The path to the directory where the container is stored (must already exist).
+/// * `path` -
+/// The path to the directory where the container is stored (must already exist)
+/// or a block device that is used as container, in this case the size is ignored.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
 /// * `auto_open` -
 /// If true,
 /// the container is added to the autoOpen file
 /// and will be opened automatically when the system starts.
+/// * `fs_type` -
+/// The file system of the container, one of `FILE_SYSTEMS` (see `input_validation`).
+/// It is recorded in the metadata of the container.
 /// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the container was created successfully otherwise an error is returned.
+/// * `Result<CreatedContainer>` -
+/// Returns the details of the new container if it was created successfully otherwise an error is returned.
+/// If the container can not be formatted or opened, the backing file that was created for it is removed again.
+/// The password of the container is derived from the id and a random key salt that is stored in its metadata.
 /// # Errors
-/// * `FileExists` - A file with the given name already exists in this location.
+/// * `FileExists` - A file with the given name already exists in this location or the block device is already a LUKS container.
 /// * `ContainerNameExists` - A container with the given name already exists.
 /// * `PathNotExists` - The provided path is not a dictionary.
+/// * `PathNotWritable` - No file can be created in the provided directory.
 /// * `FileCreationError` - An error occurred while creating a file.
 /// * `StdinError` - An error occurred while reading stdin.
 /// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
 /// * `ReadingStdoutError` - An error occurred while reading stdout.
 /// * `ContainerOpen` - The container is already open.
 /// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
-/// * `LsblkError` - A contaienr with the given name does not exist.
+/// * `LibutaRandomError` - An error occurred while getting the key salt.
+/// * `LsblkError` - An error occurred while executing lsblk.
 /// * `IntegrityError` - The integrity check failed.
 /// * `MkfsError` - An error occurred creation the file system.
 /// * `FileOpenError` - An error occurred while opening a file.
 /// * `FileWriteError` - An error occurred while writing to a file.
 /// * `MountError` - An error occurred while trying to mount the container.
+/// * `FileReadError` - An error occurred while reading the size of the opened container.
 /// ### Errors regarding the input:
 /// * `SizeToSmall` - The given size for the container is too small.
 /// * `MountPointNotExists` - The given mount point does not exist.
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
 /// * `IsNotLuks` - The provided file is not a LUKS container.
+/// * `UnsupportedFilesystem` - The given file system is not supported.
 /// # Example
 /// ```
 /// use secure_container::cryptsetup_wrapper;
@@ -79,8 +292,8 @@ const COUNT_PSEUDORANDOM_FUNCTION: u32 = 600000; //count for pseudorandom
 /// let namespace = "MyContainer";
 /// let id = "myId";
 /// let auto_open = true;
-/// let result = create_container(size, mount_point, path, namespace, id, auto_open);
-/// assert!(result.is_ok());
+/// let result = create_container(size, mount_point, path, namespace, id, auto_open, "xfs");
+/// assert_eq!(result.unwrap().mapper_device, "/dev/mapper/MyContainer");
 /// ```
 ///
 pub fn create_container(
@@ -90,60 +303,361 @@ pub fn create_container(
     namespace: &str,
     id: &str,
     auto_open: bool,
-) -> Result<()> {
-    match check_input(
-        Some(size),
+    fs_type: &str,
+) -> Result<CreatedContainer> {
+    create_container_with_progress(
+        size,
+        mount_point,
+        path,
+        namespace,
+        id,
+        auto_open,
+        fs_type,
+        &|_| (),
+    )
+}
+
+/// Creates and opens a new container like `create_container` and reports the progress.
+/// # Arguments
+/// The arguments of `create_container` and
+/// * `progress` - Called at the start of every phase of the creation and when the container is created.
+/// # Returns
+/// * `Result<CreatedContainer>` - See `create_container`.
+/// # Errors
+/// The same errors as `create_container`.
+/// # Example
+/// ```
+/// let result = create_container_with_progress(200, "/home/MountMe", "/home/Container", "MyContainer", "myId", false, "ext4",
+///     &|progress| println!("{} {}%", progress.phase, progress.percent));
+/// assert!(result.is_ok());
+/// ```
+///
+#[allow(clippy::too_many_arguments)]
+pub fn create_container_with_progress(
+    size: i32,
+    mount_point: &str,
+    path: &str,
+    namespace: &str,
+    id: &str,
+    auto_open: bool,
+    fs_type: &str,
+    progress: &dyn Fn(CreateProgress),
+) -> Result<CreatedContainer> {
+    create(
+        size,
         Some(mount_point),
-        None,
-        Some(namespace),
-        Some(id),
-    ) {
+        fs_type,
+        path,
+        namespace,
+        id,
+        auto_open,
+        progress,
+    )
+}
+
+/// Creates and opens a new container without a file system.
+/// The opened container is a raw mapper device, e.g. for an own file system or as block storage.
+/// # Arguments
+/// * `size` - The size of the container in MB (must be at least 16MB).
+/// * `path` -
+/// The path to the directory where the container is stored (must already exist)
+/// or a block device that is used as container, in this case the size is ignored.
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// # Returns
+/// * `Result<CreatedContainer>` -
+/// Returns the details of the new container, without mount point, if it was created successfully
+/// otherwise an error is returned.
+/// # Errors
+/// The same errors as `create_container` except the ones of the file system and the mount point.
+/// # Example
+/// ```
+/// let result = create_raw_container(200, "/home/Container", "MyContainer", "myId");
+/// assert_eq!(result.unwrap().mapper_device, "/dev/mapper/MyContainer");
+/// ```
+///
+pub fn create_raw_container(
+    size: i32,
+    path: &str,
+    namespace: &str,
+    id: &str,
+) -> Result<CreatedContainer> {
+    create_raw_container_with_progress(size, path, namespace, id, &|_| ())
+}
+
+/// Creates and opens a new container without a file system like `create_raw_container` and reports the progress.
+/// # Arguments
+/// The arguments of `create_raw_container` and
+/// * `progress` - Called at the start of every phase of the creation and when the container is created.
+/// # Returns
+/// * `Result<CreatedContainer>` - See `create_raw_container`.
+/// # Errors
+/// The same errors as `create_raw_container`.
+///
+pub fn create_raw_container_with_progress(
+    size: i32,
+    path: &str,
+    namespace: &str,
+    id: &str,
+    progress: &dyn Fn(CreateProgress),
+) -> Result<CreatedContainer> {
+    create(size, None, "", path, namespace, id, false, progress)
+}
+
+/// Creates and opens a new container, with a file system of the type `fs_type` if a mount point is given.
+/// See `create_container` for the documentation.
+/// # Note
+/// This function is not meant to be called directly.
+#[allow(clippy::too_many_arguments)]
+fn create(
+    size: i32,
+    mount_point: Option<&str>,
+    fs_type: &str,
+    path: &str,
+    namespace: &str,
+    id: &str,
+    auto_open: bool,
+    progress: &dyn Fn(CreateProgress),
+) -> Result<CreatedContainer> {
+    match check_input(Some(size), mount_point, None, Some(namespace), Some(id)) {
         Ok(_) => (),
         Err(err) => return Err(err),
     }
-    if check_if_file_exists(&(path.to_owned() + "/" + namespace)) {
+    if mount_point.is_some() {
+        check_file_system(fs_type)?;
+    }
+    let device = device_name(namespace);
+    let block_device = check_if_block_device(path);
+    let container_path = if block_device {
+        path.to_string()
+    } else {
+        format!("{}/{}", path, device)
+    };
+    if block_device {
+        // Formatting a device that already is a container would destroy the data in it.
+        if check_if_file_is_container(path).is_ok() {
+            return Err(SecureContainerErr::FileExists);
+        }
+    } else if check_if_file_exists(&container_path) {
         return Err(SecureContainerErr::FileExists);
     }
-    if check_lsblk(namespace).unwrap() {
+    if check_lsblk(&device)? {
         return Err(SecureContainerErr::ContainerNameExists);
     }
-    if !check_if_dir_exists(path) {
-        return Err(SecureContainerErr::PathNotExists);
+    // Every container gets its own salt, so the same id does not give the same password for two containers.
+    let key_salt = get_random_salt()?;
+    progress(CreateProgress::of(PHASE_ALLOCATING));
+    if !block_device {
+        if !check_if_dir_exists(path) {
+            return Err(SecureContainerErr::PathNotExists);
+        }
+        if !check_if_dir_writable(path) {
+            return Err(SecureContainerErr::PathNotWritable);
+        }
+        match create_file(size, path, &device) {
+            Ok(_) => (),
+            Err(err) => return Err(err),
+        };
     }
-    match create_file(size, path, namespace) {
+    progress(CreateProgress::of(PHASE_FORMATTING));
+    // The backing file was created above, so it is removed again if the container can not be formatted or opened,
+    // otherwise the next try would fail with `FileExists`. A block device is never removed.
+    match format_container(&container_path, id, &key_salt) {
         Ok(_) => (),
-        Err(err) => return Err(err),
+        Err(err) => return Err(discard_backing_file(&container_path, !block_device, err)),
     };
-    match format_container(&format!("{}/{}", path, namespace), id) {
-        Ok(_) => (),
-        Err(err) => return Err(err),
+    // The metadata holds the key salt, so it is stored before the container is opened with the salted password.
+    let stored = get_container_uuid(&container_path).and_then(|uuid| {
+        store_metadata(
+            &container_path,
+            &ContainerMetadata {
+                version: METADATA_VERSION,
+                namespace: namespace.to_string(),
+                device_name: device.clone(),
+                uuid: uuid.clone(),
+                cipher: config().cipher.clone(),
+                integrity: INTEGRITY.to_string(),
+                pbkdf: config().luks_pbkdf.clone(),
+                iter_time: config().luks_iter_time,
+                created: chrono::Local::now().format(TIME_FORMAT).to_string(),
+                transfer_salt: String::new(),
+                file_system: fs_type.to_string(),
+                key_salt,
+                key_derivation: KEY_DERIVATION,
+            },
+        )
+        .map(|_| uuid)
+    });
+    let uuid = match stored {
+        Ok(uuid) => uuid,
+        Err(err) => return Err(discard_backing_file(&container_path, !block_device, err)),
     };
 
-    match check_functionality_of_integrity() {
-        Ok(_) => (),
-        Err(err) => return Err(err),
-    };
-    if !check_functionality_of_integrity().unwrap() {
+    if !check_functionality_of_integrity(&config().cipher, INTEGRITY)? {
         eprintln!("WARNING: Integrity check not supported by operating system!")
     }
 
-    match open_container(
-        mount_point,
-        &format!("{}/{}", path, namespace),
-        namespace,
-        id,
-    ) {
-        Ok(_) => (),
-        Err(err) => return Err(err),
-    };
-    if auto_open {
-        match auto_open_write(mount_point, path, namespace, id) {
+    if let Err(err) = unlock(&container_path, namespace, id, false, false, None) {
+        return Err(discard_backing_file(&container_path, !block_device, err));
+    }
+    // The file system is only created here, opening the container again must not format it.
+    if let Some(mount_point) = mount_point {
+        progress(CreateProgress::of(PHASE_MAKING_FILESYSTEM));
+        if let Err(err) =
+            create_name_dir(&device, fs_type).and_then(|_| mount(mount_point, &device, &[]))
+        {
+            return Err(discard_opened_container(
+                &container_path,
+                namespace,
+                !block_device,
+                err,
+            ));
+        }
+    }
+    if let (true, Some(mount_point)) = (auto_open, mount_point) {
+        match auto_open_write(mount_point, path, namespace, id, false) {
             Ok(_) => (),
             Err(err) => return Err(err),
         };
     }
 
-    Ok(())
+    let mapper_device = format!("/dev/mapper/{}", device);
+    let usable_bytes = get_device_size(&mapper_device)?;
+    progress(CreateProgress::of(PHASE_DONE));
+    Ok(CreatedContainer {
+        namespace: namespace.to_string(),
+        mapper_device,
+        mount_point: mount_point.unwrap_or_default().to_string(),
+        usable_bytes,
+        uuid,
+    })
+}
+
+/// Removes the backing file of a container that could not be created and its metadata sidecar.
+/// # Arguments
+/// * `container_path` - The path to the backing file.
+/// * `created` - True if the backing file was created for the container, otherwise nothing is removed.
+/// * `err` - The error the creation failed with.
+/// # Returns
+/// * `SecureContainerErr` -
+/// The given error, a backing file that can not be removed only causes a warning.
+/// # Note
+/// This function is not meant to be called directly.
+fn discard_backing_file(
+    container_path: &str,
+    created: bool,
+    err: SecureContainerErr,
+) -> SecureContainerErr {
+    if created {
+        if let Err(remove_err) = std::fs::remove_file(container_path) {
+            eprintln!(
+                "WARNING: Backing file {} could not be removed: {}",
+                container_path, remove_err
+            );
+        }
+        // The sidecar holds the key salt of the removed container, it must not be used for the next try.
+        let sidecar = metadata_path(container_path);
+        if check_if_file_exists(&sidecar) {
+            if let Err(remove_err) = std::fs::remove_file(&sidecar) {
+                eprintln!(
+                    "WARNING: Metadata sidecar {} could not be removed: {}",
+                    sidecar, remove_err
+                );
+            }
+        }
+    }
+    err
+}
+
+/// Closes a container that was opened while it was created and removes it like `discard_backing_file`,
+/// e.g. if its file system could not be created or mounted.
+/// # Arguments
+/// * `container_path` - The path to the backing file.
+/// * `namespace` - The name of the container.
+/// * `created` - True if the backing file was created for the container, otherwise nothing is removed.
+/// * `err` - The error the creation failed with.
+/// # Returns
+/// * `SecureContainerErr` -
+/// The given error, a container that can not be closed only causes a warning.
+/// # Note
+/// This function is not meant to be called directly.
+fn discard_opened_container(
+    container_path: &str,
+    namespace: &str,
+    created: bool,
+    err: SecureContainerErr,
+) -> SecureContainerErr {
+    if let Err(close_err) = backend().close(&device_name(namespace)) {
+        eprintln!(
+            "WARNING: Container {} could not be closed: {}",
+            namespace, close_err
+        );
+    }
+    set_open_time(namespace, None);
+    discard_backing_file(container_path, created, err)
+}
+
+/// Creates an encrypted swap device.
+/// The container is formatted with a random key from libuta that is not stored,
+/// so the swapped pages can not be read after the container is closed or the system is restarted.
+/// The backing file is stored in `swap_dir` (see `config`) and is overwritten on the next call,
+/// e.g. on every boot.
+/// # Arguments
+/// * `size` - The size of the swap device in MB (must be at least 16MB).
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<CreatedContainer>` -
+/// Returns the details of the new container, without mount point, if swapping on it was enabled successfully
+/// otherwise an error is returned.
+/// # Errors
+/// * `ContainerNameExists` - A container with the given name is already open.
+/// * `FileCreationError` - An error occurred while creating the backing file or its directory.
+/// * `FileWriteError` - An error occurred while writing the backing file.
+/// * `LibutaRandomError` - An error occurred while getting the random key.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `MkfsError` - An error occurred while creating the swap area.
+/// * `MountError` - An error occurred while enabling the swap device.
+///
+/// ### Errors regarding the input:
+/// * `SizeToSmall` - The given size is smaller than 16MB.
+/// * `NamespaceNotValid` - The given namespace contains a pipe or control characters.
+/// # Example
+/// ```
+/// let result = create_swap_container(1024, "MySwap");
+/// assert_eq!(result.unwrap().mapper_device, "/dev/mapper/MySwap");
+/// ```
+///
+pub fn create_swap_container(size: i32, namespace: &str) -> Result<CreatedContainer> {
+    check_input(Some(size), None, None, Some(namespace), None)?;
+    let device = device_name(namespace);
+    if check_lsblk(&device)? {
+        return Err(SecureContainerErr::ContainerNameExists);
+    }
+    let swap_dir = &config().swap_dir;
+    if let Err(err) = std::fs::create_dir_all(swap_dir) {
+        return Err(SecureContainerErr::FileCreationError(err.to_string()));
+    }
+    create_file(size, swap_dir, &device)?;
+    let container_path = format!("{}/{}", swap_dir, device);
+
+    let password = get_random_password()?;
+    backend().format(&container_path, &password)?;
+    let opened = chrono::Local::now().fixed_offset();
+    backend().open(&container_path, &device, &password, false, None)?;
+    let mapper_device = format!("/dev/mapper/{}", device);
+    if let Err(err) = make_swap(&mapper_device).and_then(|_| swap_on(&mapper_device)) {
+        backend().close(&device)?;
+        return Err(err);
+    }
+    set_open_time(namespace, Some(opened));
+
+    Ok(CreatedContainer {
+        namespace: namespace.to_string(),
+        usable_bytes: get_device_size(&mapper_device)?,
+        uuid: get_container_uuid(&container_path)?,
+        mapper_device,
+        mount_point: String::new(),
+    })
 }
 
 /// Open an already existing container.
@@ -152,27 +666,44 @@ pub fn create_container(
 /// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
+/// * `keyring` -
+/// If true, the password is looked up in the session keyring first
+/// and a derived password is cached there (see `keyring`).
+/// * `mount_options` -
+/// The options the container is mounted with, each one of `MOUNT_OPTIONS` (see `input_validation`),
+/// e.g. `["ro", "noexec", "nosuid"]` for a container with untrusted data.
+/// * `read_only` -
+/// If true, the dm-crypt mapping is opened read-only (`cryptsetup luksOpen --readonly`)
+/// and the container is mounted with `ro`, so the data can not be changed at all,
+/// e.g. to inspect a container for forensics.
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(()) if the container was opened successfully otherwise an error is returned.
+/// If the container is already open and mounted at the mount point, OK(()) is returned as well,
+/// the mount options are not changed then.
+/// If the container is already open but not mounted, it is only mounted.
+/// The file system is created by `create_container`, it is never created again when the container is opened.
+/// A warning is printed if the metadata of the container names a different namespace.
 /// # Errors
-/// * `ContainerOpen` - The container is already open.
+/// * `MountedElsewhere` - The container is already open and mounted at a different mount point.
+/// * `MountPointNotEmpty` - The mount point contains files or something else is mounted on it.
+/// * `LsblkError` - An error occurred while executing lsblk.
+/// * `FileReadError` - An error occurred while reading the mounted file systems.
 /// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
 /// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
 /// * `ReadingStdoutError` - An error occurred while reading stdout.
 /// * `IntegrityError` - The integrity check failed.
-/// * `LsblkError` - A contaienr with the given name does not exist.
-/// * `MkfsError` - An error occurred creation the file system.
 /// * `MountError` - An error occurred while trying to mount the container.
 ///
 /// ### Errors regarding the input:
 /// * `MountPointNotExists` - The given mount point does not exist.
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
 /// * `IsNotLuks` - The provided file is not a LUKS container.
+/// * `MountOptionNotValid` - A mount option is not one of `MOUNT_OPTIONS`.
 /// # Example
 /// ```
 /// use secure_container::cryptsetup_wrapper;
@@ -180,11 +711,59 @@ pub fn create_container(
 /// let path = "/home/Container";
 /// let namespace = "MyContainer";
 /// let id = "myId";
-/// let result = open_container( mount_point, path, namespace, id);
+/// let result = open_container( mount_point, path, namespace, id, false, &["ro"], false);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn open_container(
+    mount_point: &str,
+    path: &str,
+    namespace: &str,
+    id: &str,
+    keyring: bool,
+    mount_options: &[&str],
+    read_only: bool,
+) -> Result<()> {
+    open_container_with_key_slot(
+        mount_point,
+        path,
+        namespace,
+        id,
+        keyring,
+        mount_options,
+        read_only,
+        None,
+    )
+}
+
+/// Opens a container like `open_container`, but only tries the password of the id on one key slot,
+/// e.g. if recovery secrets were added to the container with `add_key_slot`, they are not tried then.
+/// Every key slot costs a key derivation, so this is faster for a container with several keys.
+/// # Arguments
+/// The arguments of `open_container` and
+/// * `key_slot` - The key slot that is tried, if `None` every key slot is tried like with `open_container`.
+/// # Returns
+/// * `Result<()>` - See `open_container`.
+/// # Errors
+/// The same errors as `open_container`,
+/// `CryptsetupError` if the password of the id does not unlock the key slot.
+/// # Example
+/// ```
+/// let result = open_container_with_key_slot("/home/MountMe", "/home/Container", "MyContainer", "myId", false, &[], false, Some(1));
 /// assert!(result.is_ok());
 /// ```
 ///
-pub fn open_container(mount_point: &str, path: &str, namespace: &str, id: &str) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn open_container_with_key_slot(
+    mount_point: &str,
+    path: &str,
+    namespace: &str,
+    id: &str,
+    keyring: bool,
+    mount_options: &[&str],
+    read_only: bool,
+    key_slot: Option<u8>,
+) -> Result<()> {
     match check_input(
         None,
         Some(mount_point),
@@ -195,130 +774,430 @@ pub fn open_container(mount_point: &str, path: &str, namespace: &str, id: &str)
         Ok(_) => (),
         Err(err) => return Err(err),
     }
-    if check_container_open(namespace).unwrap() {
-        return Err(SecureContainerErr::ContainerOpen);
+    // The options are checked before the container is unlocked, so it is not left open unmounted.
+    check_mount_options(mount_options)?;
+    // A read-only mapping can only be mounted read-only.
+    let mut mount_options = mount_options.to_vec();
+    if read_only && !mount_options.contains(&"ro") {
+        mount_options.push("ro");
     }
-
-    let binding = match get_password(id) {
-        Ok(binding) => binding,
-        Err(err) => return Err(err),
-    };
-    let password = binding.as_str();
-    let mut child = match Command::new("sudo")
-        .args(["cryptsetup", "luksOpen", path, namespace])
-        .stdin(Stdio::piped())
-        .spawn()
-    {
-        Ok(child) => child,
-        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
-    };
-    {
-        let stdin = match child.stdin.as_mut() {
-            Some(stdin) => stdin,
+    let mount_options = mount_options.as_slice();
+    if let Ok(Some(metadata)) = read_metadata(path) {
+        if metadata.namespace != namespace {
+            eprintln!(
+                "WARNING: Container {} was created as {}!",
+                namespace, metadata.namespace
+            )
+        }
+    }
+    let device = device_name(namespace);
+    if check_container_open(&device)? {
+        // Opening is idempotent, an open container only has to be mounted at the requested mount point.
+        return match get_mount_point(&device)? {
+            Some(current) if is_same_dir(&current, mount_point) => Ok(()),
+            Some(_) => Err(SecureContainerErr::MountedElsewhere),
             None => {
-                return Err(SecureContainerErr::CryptsetupError(
-                    "Failed to open stdin".to_string(),
-                ))
+                check_mount_point_empty(mount_point)?;
+                mount(mount_point, &device, mount_options)
             }
         };
-        let _ = stdin.write_all(password.as_bytes());
-    }
-    let lsblk = check_lsblk(namespace);
-
-    let output = child.wait_with_output().unwrap();
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::CryptsetupError(stderr.to_string()));
     }
+    // The mount point is checked before the container is unlocked, so it is not left open unmounted.
+    check_mount_point_empty(mount_point)?;
 
-    let current_time = chrono::Local::now().format("%Y-%m-%dT%H:%M").to_string();
-    let integrity_ok = match check_integrity(&current_time) {
-        Ok(integrity) => integrity,
-        Err(err) => return Err(err),
-    };
-    if !integrity_ok {
-        let output = match Command::new("sudo")
-            .args(["cryptsetup", "luksClose", namespace])
-            .output()
-        {
-            Ok(output) => output,
-            Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
-        };
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(SecureContainerErr::CryptsetupError(stderr.to_string()));
-        }
-        return Err(SecureContainerErr::IntegrityError);
-    }
-    if !lsblk.unwrap() {
-        match create_name_dir(namespace) {
-            Ok(_) => (),
-            Err(err) => return Err(err),
-        };
-    }
+    unlock(path, namespace, id, keyring, read_only, key_slot)?;
 
-    match mount(mount_point, namespace) {
+    match mount(mount_point, &device, mount_options) {
         Ok(_) => (),
         Err(err) => return Err(err),
     };
     Ok(())
 }
 
-/// Close an already existing container that is open.
+/// Open an already existing container without mounting it,
+/// e.g. to take an image of the mapper device or to check its file system.
+/// The integrity check is done like with `open_container`, the file system is neither created nor mounted.
+/// The container can be mounted later with `mount_open`.
 /// # Arguments
-/// * `mount_point` - The path to the mount point (must already exist).
+/// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
-///
+/// * `id` - The id of the container.
 /// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the container was closed successfully otherwise an error is returned.///
+/// * `Result<String>` -
+/// Returns the path of the mapper device if the container was opened successfully otherwise an error is returned.
+/// If the container is already open, the path of its mapper device is returned as well.
 /// # Errors
-/// * `UmountError` - An error occurred while the container was unmounted.
+/// * `LsblkError` - An error occurred executing lsblk.
+/// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
 /// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
-///
+/// * `IntegrityError` - The integrity check failed.
 /// ### Errors regarding the input:
-/// * `MountPointNotExists` - The given mount point does not exist.
-/// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
+/// * `NamespaceNotValid` - The given namespace contains a pipe or control characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
 /// # Example
 /// ```
-/// use secure_container::cryptsetup_wrapper;
-/// let mount_point = "/home/MountMe";
-/// let namespace = "MyContainer";
-/// let result = close_container(mount_point, namespace);
-/// assert!(result.is_ok());
+/// let result = open_unmounted("/home/Container", "MyContainer", "myId");
+/// assert_eq!(result, Ok("/dev/mapper/MyContainer".to_string()));
 /// ```
 ///
-pub fn close_container(mount_point: &str, namespace: &str) -> Result<()> {
-    match check_input(None, Some(mount_point), None, Some(namespace), None) {
-        Ok(_) => (),
-        Err(err) => return Err(err),
-    };
-    match unmount(mount_point) {
-        Ok(_) => (),
-        Err(err) => return Err(err),
-    };
-    let output = match Command::new("sudo")
-        .args(["cryptsetup", "luksClose", namespace])
-        .output()
-    {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
+pub fn open_unmounted(path: &str, namespace: &str, id: &str) -> Result<String> {
+    check_input(None, None, Some(path), Some(namespace), Some(id))?;
+    let device = device_name(namespace);
+    if !check_container_open(&device)? {
+        unlock(path, namespace, id, false, false, None)?;
+    }
+    Ok(format!("/dev/mapper/{}", device))
+}
+
+/// Mount a container that is already open, e.g. after it was opened with `open_unmounted`.
+/// # Arguments
+/// * `mount_point` - The path to the mount point (must already exist).
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the container was mounted successfully otherwise an error is returned.
+/// If the container is already mounted at the mount point, OK(()) is returned as well.
+/// # Errors
+/// * `ContainerNotOpen` - The container is not open.
+/// * `MountedElsewhere` - The container is already mounted at a different mount point.
+/// * `MountPointNotEmpty` - The mount point contains files or something else is mounted on it.
+/// * `LsblkError` - An error occurred executing lsblk.
+/// * `FileReadError` - An error occurred while reading the mounted file systems.
+/// * `MountError` - An error occurred while trying to mount the container.
+/// ### Errors regarding the input:
+/// * `MountPointNotExists` - The given mount point does not exist.
+/// * `NamespaceNotValid` - The given namespace contains a pipe or control characters.
+/// # Example
+/// ```
+/// let result = mount_open("/home/MountMe", "MyContainer");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn mount_open(mount_point: &str, namespace: &str) -> Result<()> {
+    check_input(None, Some(mount_point), None, Some(namespace), None)?;
+    let device = device_name(namespace);
+    if !check_container_open(&device)? {
+        return Err(SecureContainerErr::ContainerNotOpen);
+    }
+    match get_mount_point(&device)? {
+        Some(current) if is_same_dir(&current, mount_point) => Ok(()),
+        Some(_) => Err(SecureContainerErr::MountedElsewhere),
+        None => {
+            check_mount_point_empty(mount_point)?;
+            mount(mount_point, &device, &[])
+        }
+    }
+}
+
+/// Opens the LUKS layer of a container and checks its integrity, the container is not mounted.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// * `keyring` -
+/// If true, the password cached in the keyring is tried first.
+/// If it is missing or does not open the container, the derived password is used and cached.
+/// A password that can not be cached only causes a warning.
+/// * `read_only` - If true, the dm-crypt mapping is opened read-only.
+/// * `key_slot` - The only key slot that is tried, if `None` every key slot is tried.
+/// # Returns
+/// * `Result<()>` - Returns OK(()) if the container was opened successfully otherwise an error is returned.
+/// # Errors
+/// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `ReadingStdoutError` - An error occurred while reading stdout.
+/// * `IntegrityError` - The integrity check failed, the container is closed again.
+/// # Note
+/// This function is not meant to be called directly.
+fn unlock(
+    path: &str,
+    namespace: &str,
+    id: &str,
+    keyring: bool,
+    read_only: bool,
+    key_slot: Option<u8>,
+) -> Result<()> {
+    let device = device_name(namespace);
+    // Only integrity errors that are logged after this point belong to this open.
+    let opened = chrono::Local::now().fixed_offset();
+    let opened_with_cache = keyring
+        && cached_password(namespace, id).is_some_and(|password| {
+            backend()
+                .open(path, &device, &password, read_only, key_slot)
+                .is_ok()
+        });
+    if !opened_with_cache {
+        let binding = container_password(path, id)?;
+        let password = binding.as_str();
+        backend().open(path, &device, password, read_only, key_slot)?;
+        if keyring {
+            if let Err(err) = cache_password(namespace, id, password) {
+                eprintln!("WARNING: Password of {} not cached: {}", namespace, err)
+            }
+        }
+    }
+
+    let integrity_ok = check_integrity(&device, Some(opened))?;
+    if !integrity_ok {
+        backend().close(&device)?;
+        return Err(SecureContainerErr::IntegrityError);
+    }
+    set_open_time(namespace, Some(opened));
+    Ok(())
+}
+
+/// Close an already existing container that is open.
+/// # Arguments
+/// * `mount_point` -
+/// The path to the mount point (must already exist),
+/// empty for a container without file system (see `create_raw_container`) or a swap container.
+/// Swapping on a swap container is disabled before it is closed.
+/// * `namespace` - The name of the container.
+///
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the container was closed successfully otherwise an error is returned.///
+/// # Errors
+/// * `NotMountedAtMountPoint` -
+/// The container is not the file system that is mounted at the given mount point,
+/// nothing is unmounted in this case.
+/// * `FileReadError` - An error occurred while reading the mounted file systems.
+/// * `UmountError` - An error occurred while the container was unmounted.
+/// * `UmountError` - An error occurred while swapping on the container was disabled.
+/// * `FileReadError` - An error occurred while reading the active swap devices.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+///
+/// ### Errors regarding the input:
+/// * `MountPointNotExists` - The given mount point does not exist.
+/// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
+/// # Example
+/// ```
+/// use secure_container::cryptsetup_wrapper;
+/// let mount_point = "/home/MountMe";
+/// let namespace = "MyContainer";
+/// let result = close_container(mount_point, namespace);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn close_container(mount_point: &str, namespace: &str) -> Result<()> {
+    let mount_point = Some(mount_point).filter(|mount_point| !mount_point.is_empty());
+    match check_input(None, mount_point, None, Some(namespace), None) {
+        Ok(_) => (),
+        Err(err) => return Err(err),
     };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::CryptsetupError(stderr.to_string()));
+    let device = device_name(namespace);
+    // A stale mount point must not unmount a different file system.
+    if let Some(mount_point) = mount_point {
+        if !check_mounted_at(&device, mount_point)? {
+            return Err(SecureContainerErr::NotMountedAtMountPoint);
+        }
     }
+    // The bind mounts keep the container busy, so they are removed first.
+    unmount_binds(namespace)?;
+    match mount_point {
+        Some(mount_point) => match unmount(mount_point) {
+            Ok(_) => (),
+            Err(err) => return Err(err),
+        },
+        None => {
+            let mapper_device = format!("/dev/mapper/{}", device);
+            if check_swap_active(&mapper_device)? {
+                swap_off(&mapper_device)?;
+            }
+        }
+    }
+    backend().close(&device)?;
+    set_open_time(namespace, None);
     Ok(())
 }
 
+/// Close a container like `close_container`, but a container that is already closed is not an error,
+/// e.g. for cleanup scripts that do not know if the container is still open.
+/// A container that is open but not mounted is closed without unmounting.
+/// # Arguments
+/// * `mount_point` - The path to the mount point, empty for a container without file system.
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the container was closed successfully or was not open otherwise an error is returned.
+/// # Errors
+/// * `LsblkError` - An error occurred executing lsblk.
+/// * `FileReadError` - An error occurred while reading the mounted file systems.
+/// * For the errors while closing the container see `close_container`.
+/// ### Errors regarding the input:
+/// * `NamespaceNotValid` - The given namespace is not allowed.
+/// # Example
+/// ```
+/// let result = ensure_closed("/home/MountMe", "MyContainer");
+/// assert!(result.is_ok());
+/// assert!(ensure_closed("/home/MountMe", "MyContainer").is_ok());
+/// ```
+///
+pub fn ensure_closed(mount_point: &str, namespace: &str) -> Result<()> {
+    check_input(None, None, None, Some(namespace), None)?;
+    let device = device_name(namespace);
+    if !check_container_open(&device)? {
+        return Ok(());
+    }
+    if !check_container_mounted(&device)? {
+        return close_container("", namespace);
+    }
+    close_container(mount_point, namespace)
+}
+
+/// Close an open container by its name, without giving the mount point.
+/// The mount point is taken from the autoOpen file if the container is registered there,
+/// otherwise from the file systems that are mounted.
+/// A container that is open but not mounted, e.g. a swap container, is closed without unmounting.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the container was closed successfully otherwise an error is returned.
+/// # Errors
+/// * `ContainerNotOpen` - The container is not open.
+/// * `FileReadError` - An error occurred while reading the mounted file systems.
+/// * `LsblkError` - An error occurred executing lsblk.
+/// * For the errors while closing the container see `close_container`.
+/// ### Errors regarding the input:
+/// * `NamespaceNotValid` - The given namespace contains a pipe or control characters.
+/// # Example
+/// ```
+/// let result = close_by_namespace("MyContainer");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn close_by_namespace(namespace: &str) -> Result<()> {
+    check_input(None, None, None, Some(namespace), None)?;
+    // A missing or unreadable autoOpen file only means that the container is not registered.
+    if let Ok(Some(mount_point)) = auto_open_mount_point(namespace) {
+        return close_container(&mount_point, namespace);
+    }
+    let device = device_name(namespace);
+    match get_mount_point(&device)? {
+        Some(mount_point) => close_container(&mount_point, namespace),
+        None if check_container_open(&device)? => close_container("", namespace),
+        None => Err(SecureContainerErr::ContainerNotOpen),
+    }
+}
+
+/// Bind-mounts a directory of an open container to another directory,
+/// e.g. into a chroot or the root file system of a container runtime.
+/// The bind mount is unmounted before the container is closed by `close_container`.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `source` - The directory relative to the mount point of the container, `/` for the whole container.
+/// * `target` - The directory where the source is mounted to (must already exist).
+/// * `read_only` - If true, the target is mounted read-only.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the directory was mounted successfully otherwise an error is returned.
+/// # Errors
+/// * `ContainerNotOpen` - The container is not open or not mounted.
+/// * `FileReadError` - An error occurred while reading the mounted file systems.
+/// * `PathNotExists` - The source does not exist in the container.
+/// * `PathNotValid` - The source is outside of the container.
+/// * `MountError` - An error occurred while trying to mount the directory.
+/// * `MountBusy` - The target is busy (only with the `native-mount` feature).
+/// ### Errors regarding the input:
+/// * `MountPointNotExists` - The given target does not exist.
+/// * `NamespaceNotValid` - The given namespace contains a pipe or control characters.
+/// # Example
+/// ```
+/// let result = bind_mount("MyContainer", "data", "/srv/chroot/data", false);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn bind_mount(namespace: &str, source: &str, target: &str, read_only: bool) -> Result<()> {
+    check_input(None, Some(target), None, Some(namespace), None)?;
+    let mount_point = match get_mount_point(&device_name(namespace))? {
+        Some(mount_point) => mount_point,
+        None => return Err(SecureContainerErr::ContainerNotOpen),
+    };
+    let source = bind_source(&mount_point, source)?;
+    mount_bind(&source, target, read_only)?;
+    with_bind_mounts(namespace, |targets| targets.push(target.to_string()));
+    Ok(())
+}
+
+/// Resolves the source of a bind mount inside of the mount point of a container.
+/// # Arguments
+/// * `mount_point` - The mount point of the container.
+/// * `source` - The directory relative to the mount point.
+/// # Returns
+/// * `Result<String>` - The absolute path of the source.
+/// # Errors
+/// * `PathNotExists` - The source does not exist.
+/// * `PathNotValid` - The source is outside of the mount point, e.g. because of `..` or a symbolic link.
+/// # Note
+/// This function is not meant to be called directly.
+fn bind_source(mount_point: &str, source: &str) -> Result<String> {
+    let root = match std::fs::canonicalize(mount_point) {
+        Ok(root) => root,
+        Err(_) => return Err(SecureContainerErr::PathNotExists),
+    };
+    let source = match std::fs::canonicalize(root.join(source.trim_start_matches('/'))) {
+        Ok(source) => source,
+        Err(_) => return Err(SecureContainerErr::PathNotExists),
+    };
+    if !source.starts_with(&root) {
+        return Err(SecureContainerErr::PathNotValid);
+    }
+    match source.to_str() {
+        Some(source) => Ok(source.to_string()),
+        None => Err(SecureContainerErr::PathNotValid),
+    }
+}
+
+/// Verifies the integrity of an open container on demand.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `since` -
+/// Only integrity errors logged at or after this local time (see `TIME_FORMAT`) are considered.
+/// If `None`, the time the container was opened by the daemon is used,
+/// if the daemon did not open the container, every integrity error in the kernel log is considered.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if no integrity error was found otherwise an error is returned.
+/// # Errors
+/// * `ContainerNotOpen` - The container is not open.
+/// * `IntegrityError` - An integrity error was logged since the cutoff time.
+/// * `LsblkError` - An error occurred while checking the open containers.
+/// * `CryptsetupError` - An error occurred while reading the kernel log.
+/// * `ReadingStdoutError` - An error occurred while reading stdout.
+/// ### Errors regarding the input:
+/// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
+/// * `TimeNotValid` - The given time does not match the format.
+/// # Example
+/// ```
+/// let result = verify_integrity("MyContainer", Some("2024-03-01T12:00"));
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn verify_integrity(namespace: &str, since: Option<&str>) -> Result<()> {
+    check_input(None, None, None, Some(namespace), None)?;
+    let since = since.map(parse_time).transpose()?;
+    let device = device_name(namespace);
+    if !check_container_open(&device)? {
+        return Err(SecureContainerErr::ContainerNotOpen);
+    }
+    if check_integrity(&device, since.or_else(|| open_time(namespace)))? {
+        Ok(())
+    } else {
+        Err(SecureContainerErr::IntegrityError)
+    }
+}
+
 /// Exporting an existing and closed container.
 /// # Arguments
 /// * `mount_point` - The path to the mount point (must already exist).
 /// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
-/// * `secret` - The secret for the container (is needed when container is imported).
+/// * `credential` - The secret or keyfile for the container (is needed when container is imported).
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(()) if the container was exported successfully otherwise an error is returned.
@@ -326,18 +1205,24 @@ pub fn close_container(mount_point: &str, namespace: &str) -> Result<()> {
 /// * `LsblkError` - A contaienr with the given name does not exist.
 /// * `ReadingStdoutError` - An error occurred while reading stdout.
 /// * `ContainerOpen` - The container is already open.
-/// * `LsError` - An error occurred while checking the logical volumes of the system.
+/// * `FileReadError` - An error occurred while reading `/proc/mounts`.
 /// * `ContainerMounted` - The container is still mounted.
 /// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
+/// * `LibutaRandomError` - An error occurred while getting the salt for the secret.
 /// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `FileReadError` - The keyfile could not be read.
 /// ### Errors regarding the input:
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
 /// * `IsNotLuks` - The provided file is not a LUKS container.
-/// * `SecertError` - The secret is empty or contains non-ascii characters.
+/// * `SecertError` - The secret or keyfile is empty or the secret contains non-ascii characters.
+/// # Note
+/// The password of a secret is derived with Argon2id and a random salt that is stored in the metadata of the container,
+/// so it travels with the container.
+/// If the metadata can not be stored, e.g. for LUKS1 block devices, the password is derived with PBKDF2 as before.
 /// # Example
 /// ```
 /// use secure_container::cryptsetup_wrapper;
@@ -345,23 +1230,29 @@ pub fn close_container(mount_point: &str, namespace: &str) -> Result<()> {
 /// let path = "/home/Container";
 /// let namespace = "MyContainer";
 /// let id = "myId";
-/// let secret = "mySecret";
-/// let result = export_container(mount_point, path, namespace, id, secret);
+/// let credential = TransferCredential::Secret("mySecret".to_string());
+/// let result = export_container(path, namespace, id, &credential);
 /// assert!(result.is_ok());
 /// ```
 ///
-pub fn export_container(path: &str, namespace: &str, id: &str, secret: &str) -> Result<()> {
+pub fn export_container(
+    path: &str,
+    namespace: &str,
+    id: &str,
+    credential: &TransferCredential,
+) -> Result<()> {
     match check_input(None, None, Some(path), Some(namespace), Some(id)) {
         Ok(_) => (),
         Err(err) => return Err(err),
     };
-    if secret.is_empty() {
-        return Err(SecureContainerErr::SecertError);
-    }
-    if !secret.is_ascii() {
-        return Err(SecureContainerErr::SecertError);
-    }
-    if match check_container_open(namespace) {
+    let salt = match credential {
+        TransferCredential::Secret(_) => get_random_salt()?,
+        TransferCredential::Keyfile(_) => String::new(),
+    };
+    // The password is derived before anything is changed, so an invalid credential changes nothing.
+    let mut password = transfer_password(credential, namespace, &salt)?;
+    let device = device_name(namespace);
+    if match check_container_open(&device) {
         Ok(true) => true,
         Ok(false) => false,
         Err(err) => return Err(err),
@@ -369,7 +1260,7 @@ pub fn export_container(path: &str, namespace: &str, id: &str, secret: &str) ->
         return Err(SecureContainerErr::ContainerOpen);
     }
 
-    if match check_container_mounted(namespace) {
+    if match check_container_mounted(&device) {
         Ok(true) => true,
         Ok(false) => false,
         Err(err) => return Err(err),
@@ -377,27 +1268,26 @@ pub fn export_container(path: &str, namespace: &str, id: &str, secret: &str) ->
         return Err(SecureContainerErr::ContainerMounted);
     }
 
-    //hash secret
-    let mut out = [0u8; 32];
-    derive(
-        ring::pbkdf2::PBKDF2_HMAC_SHA256,
-        NonZeroU32::new(COUNT_PSEUDORANDOM_FUNCTION).unwrap(),
-        secret.as_bytes(),
-        namespace.as_bytes(),
-        &mut out,
-    );
-
-    let password = convert_to_base64(out.to_vec());
-
-    let old_password = match get_password(id) {
-        Ok(old_password) => old_password,
-        Err(err) => return Err(err),
-    };
+    let old_password = container_password(path, id)?;
 
-    match change_password(path, &old_password, &password) {
-        Ok(_) => (),
-        Err(err) => return Err(err),
-    };
+    // The salt has to be stored before the password is changed, it decides which password is used.
+    let previous_salt = transfer_salt(path, credential);
+    let salt_stored = !salt.is_empty() && store_transfer_salt(path, namespace, &salt).is_ok();
+    if !salt.is_empty() && !salt_stored {
+        password = transfer_password(credential, namespace, "")?;
+    }
+    if let Err(err) = change_password(path, &old_password, &password) {
+        // The container keeps its password, so the metadata must not point to the salt of this export.
+        if salt_stored {
+            if let Err(restore_err) = store_transfer_salt(path, namespace, &previous_salt) {
+                eprintln!(
+                    "WARNING: Transfer salt of {} not restored: {}",
+                    namespace, restore_err
+                )
+            }
+        }
+        return Err(err);
+    }
     Ok(())
 }
 
@@ -407,21 +1297,25 @@ pub fn export_container(path: &str, namespace: &str, id: &str, secret: &str) ->
 /// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
-/// * `secret` - The secret for the container (is needed when container is imported).
+/// * `credential` - The secret or keyfile the container was exported with.
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(()) if the container was imported successfully otherwise an error is returned.
 /// # Errors
 /// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
 /// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `FileReadError` - The keyfile could not be read.
 /// ### Errors regarding the input:
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
 /// * `IsNotLuks` - The provided file is not a LUKS container.
-/// * `SecertError` - The secret is empty or contains non-ascii characters.
+/// * `SecertError` - The secret or keyfile is empty or the secret contains non-ascii characters.
+/// # Note
+/// The salt of a secret is read from the metadata of the container,
+/// a container without salt was exported with PBKDF2 and the namespace as salt.
 /// # Example
 /// ```
 /// use secure_container::cryptsetup_wrapper;
@@ -429,128 +1323,799 @@ pub fn export_container(path: &str, namespace: &str, id: &str, secret: &str) ->
 /// let path = "/home/Container";
 /// let namespace = "MyContainer";
 /// let id = "myId";
-/// let secret = "mySecret";
-/// let result = import_container(mount_point, path, namespace, id, secret);
+/// let credential = TransferCredential::Keyfile(PathBuf::from("/home/transfer.key"));
+/// let result = import_container(path, namespace, id, &credential);
 /// assert!(result.is_ok());
 /// ```
 ///
-pub fn import_container(path: &str, namespace: &str, id: &str, secret: &str) -> Result<()> {
+pub fn import_container(
+    path: &str,
+    namespace: &str,
+    id: &str,
+    credential: &TransferCredential,
+) -> Result<()> {
+    import_container_with_salt(
+        path,
+        namespace,
+        id,
+        credential,
+        &transfer_salt(path, credential),
+    )
+}
+
+/// Importing an existing container that was exported with the given salt, e.g. the salt in the manifest of a bundle.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// * `credential` - The secret or keyfile the container was exported with.
+/// * `salt` - The salt of the secret, empty if the container was exported with PBKDF2.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the container was imported successfully otherwise an error is returned.
+/// # Errors
+/// * All errors of `import_container`.
+/// # Example
+/// ```
+/// let credential = TransferCredential::Secret("mySecret".to_string());
+/// let result = import_container_with_salt("/home/Container", "MyContainer", "myId", &credential, "q0MeF2bY7Zq8hXo1GJ4bXg");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn import_container_with_salt(
+    path: &str,
+    namespace: &str,
+    id: &str,
+    credential: &TransferCredential,
+    salt: &str,
+) -> Result<()> {
     match check_input(None, None, Some(path), Some(namespace), Some(id)) {
         Ok(_) => (),
         Err(err) => return Err(err),
     };
 
-    //hash secret
-    let mut out = [0u8; 32];
-    derive(
-        ring::pbkdf2::PBKDF2_HMAC_SHA256,
-        NonZeroU32::new(COUNT_PSEUDORANDOM_FUNCTION).unwrap(),
-        secret.as_bytes(),
-        namespace.as_bytes(),
-        &mut out,
-    );
-
-    let password = convert_to_base64(out.to_vec());
-    let password_new = match get_password(id) {
-        Ok(old_password) => old_password,
-        Err(err) => return Err(err),
-    };
-    //change password from container
-    match change_password(path, &password, &password_new) {
-        Ok(_) => (),
-        Err(err) => return Err(err),
+    let password = transfer_password(credential, namespace, salt)?;
+    let password_new = container_password(path, id)?;
+    //change password from container
+    match change_password(path, &password, &password_new) {
+        Ok(_) => (),
+        Err(err) => return Err(err),
+    };
+    Ok(())
+}
+
+/// Replaces the password of a container that is derived from the old id with the one derived from the new id.
+/// If the container already accepts the new password, e.g. because an earlier rotation was interrupted,
+/// nothing is changed, so the rotation can be repeated safely.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `old_id` - The id the password of the container is currently derived from.
+/// * `new_id` - The id the new password is derived from.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the container accepts the new password otherwise an error is returned.
+/// # Errors
+/// * `LibutaDeriveKeyError` - An error occurred while deriving a key.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// ### Errors regarding the input:
+/// * `IdNotValid` - One of the ids contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// # Example
+/// ```
+/// let result = rotate_key("/home/Container", "oldId", "newId");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn rotate_key(path: &str, old_id: &str, new_id: &str) -> Result<()> {
+    check_input(None, None, Some(path), None, Some(old_id))?;
+    check_input(None, None, None, None, Some(new_id))?;
+
+    let password = container_password(path, new_id)?;
+    if backend().check_key(path, &password) == Ok(true) {
+        return Ok(());
+    }
+    let old_password = container_password(path, old_id)?;
+    change_password(path, &old_password, &password)
+}
+
+/// Replaces the password of a closed container that is derived from the old id with the one derived from the new id,
+/// e.g. if the old id is compromised. The container stays in place.
+/// Entries of the container in the autoOpen file that use the old id are changed to the new id.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `old_id` - The id the password of the container is currently derived from.
+/// * `new_id` - The id the new password is derived from.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the password was replaced otherwise an error is returned.
+/// # Errors
+/// * `ContainerOpen` - The container is open, under any name.
+/// * `LsblkError` - An error occurred while checking the open containers.
+/// * `LibutaDeriveKeyError` - An error occurred while deriving a key.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command, e.g. the old id is wrong.
+/// * `FileOpenError` - An error occurred while opening the autoOpen file to change an entry.
+/// * `FileWriteError` - An error occurred while writing the autoOpen file.
+/// ### Errors regarding the input:
+/// * `IdNotValid` - One of the ids contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// # Example
+/// ```
+/// let result = rekey_container("/home/Container", "oldId", "newId");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn rekey_container(path: &str, old_id: &str, new_id: &str) -> Result<()> {
+    check_input(None, None, Some(path), None, Some(old_id))?;
+    check_input(None, None, None, None, Some(new_id))?;
+    if path_open(path)? {
+        return Err(SecureContainerErr::ContainerOpen);
+    }
+
+    let old_password = container_password(path, old_id)?;
+    let password = container_password(path, new_id)?;
+    // Without an autoOpen file there is no entry to change.
+    let entries = auto_open_read().unwrap_or_default();
+    change_password(path, &old_password, &password)?;
+
+    for entry in entries {
+        if entry.len() > 3 && is_same_dir(&entry[1], path) && entry[3] == old_id {
+            auto_open_change_id(&entry[2], new_id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks if a container is open under any name, e.g. also if it was opened without the daemon.
+/// # Arguments
+/// * `path` - The path to the container.
+/// # Returns
+/// * `Result<bool>` - Returns true if one of the open crypt devices is stored in the container.
+/// # Errors
+/// * `LsblkError` - An error occurred while listing the open crypt devices.
+/// # Note
+/// This function is not meant to be called directly.
+fn path_open(path: &str) -> Result<bool> {
+    Ok(list_crypt_devices()?.iter().any(|device| {
+        get_backing_path(&device.name).is_ok_and(|backing_path| is_same_dir(&backing_path, path))
+    }))
+}
+
+/// Returns the salt a container was exported with.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `credential` - The secret or keyfile the container was exported with.
+/// # Returns
+/// * `String` -
+/// The salt from the metadata of the container,
+/// empty for keyfiles and for containers that were exported with PBKDF2 or have no metadata.
+/// # Example
+/// ```
+/// let credential = TransferCredential::Secret("mySecret".to_string());
+/// let salt = transfer_salt("/home/Container", &credential);
+/// ```
+///
+pub fn transfer_salt(path: &str, credential: &TransferCredential) -> String {
+    match (credential, read_metadata(path)) {
+        (TransferCredential::Secret(_), Ok(Some(metadata))) => metadata.transfer_salt,
+        _ => String::new(),
+    }
+}
+
+/// Stores the salt of an exported container in its metadata.
+/// A container without metadata gets metadata with the fields that are known.
+/// # Note
+/// This function is not meant to be called directly.
+fn store_transfer_salt(path: &str, namespace: &str, salt: &str) -> Result<()> {
+    let mut metadata = match read_metadata(path)? {
+        Some(metadata) => metadata,
+        None => ContainerMetadata {
+            version: METADATA_VERSION,
+            namespace: namespace.to_string(),
+            device_name: device_name(namespace),
+            uuid: get_container_uuid(path)?,
+            cipher: String::new(),
+            integrity: String::new(),
+            pbkdf: String::new(),
+            iter_time: 0,
+            created: String::new(),
+            transfer_salt: String::new(),
+            file_system: String::new(),
+            key_salt: String::new(),
+            key_derivation: LEGACY_KEY_DERIVATION,
+        },
+    };
+    metadata.transfer_salt = salt.to_string();
+    store_metadata(path, &metadata)
+}
+
+/// Derives the password that protects a container during the transfer.
+/// # Arguments
+/// * `credential` - The secret or keyfile for the transfer.
+/// * `namespace` - The name of the container, it is used as salt for a secret without salt.
+/// * `salt` -
+/// The salt for the secret, the password is derived with Argon2id.
+/// If it is empty the password is derived with PBKDF2 like for containers that were exported before Argon2id was used.
+/// # Returns
+/// * `Result<String>` -
+/// Returns the password for the transfer otherwise an error is returned.
+/// # Errors
+/// * `SecertError` -
+/// The secret or keyfile is empty, the secret contains non-ascii characters or the salt is too short.
+/// * `FileReadError` - The keyfile could not be read.
+/// # Example
+/// ```
+/// use secure_container::cryptsetup_wrapper;
+/// let credential = TransferCredential::Secret("mySecret".to_string());
+/// let result = transfer_password(&credential, "MyContainer", "q0MeF2bY7Zq8hXo1GJ4bXg");
+/// assert!(result.is_ok());
+/// ```
+///
+fn transfer_password(
+    credential: &TransferCredential,
+    namespace: &str,
+    salt: &str,
+) -> Result<String> {
+    match credential {
+        TransferCredential::Secret(secret) => {
+            if secret.is_empty() || !secret.is_ascii() {
+                return Err(SecureContainerErr::SecertError);
+            }
+            //hash secret
+            let mut out = [0u8; 32];
+            if !salt.is_empty() {
+                let params = match Params::new(ARGON2_MEMORY, ARGON2_ITERATIONS, 1, Some(out.len()))
+                {
+                    Ok(params) => params,
+                    Err(_) => return Err(SecureContainerErr::SecertError),
+                };
+                return match Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(secret.as_bytes(), salt.as_bytes(), &mut out)
+                {
+                    Ok(_) => Ok(convert_to_base64(out.to_vec())),
+                    Err(_) => Err(SecureContainerErr::SecertError),
+                };
+            }
+            derive(
+                ring::pbkdf2::PBKDF2_HMAC_SHA256,
+                NonZeroU32::new(config().pbkdf2_iterations).unwrap(),
+                secret.as_bytes(),
+                namespace.as_bytes(),
+                &mut out,
+            );
+            Ok(convert_to_base64(out.to_vec()))
+        }
+        TransferCredential::Keyfile(keyfile) => {
+            let content = match std::fs::read(keyfile) {
+                Ok(content) => content,
+                Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+            };
+            if content.is_empty() {
+                return Err(SecureContainerErr::SecertError);
+            }
+            // The keyfile already has a high entropy, so a single hash is enough.
+            Ok(convert_to_base64(
+                digest(&SHA256, &content).as_ref().to_vec(),
+            ))
+        }
+    }
+}
+
+/// Change the password of an existing container.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `password_old` - The old password of the container.
+/// * `password` - The new password of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the password was changed successfully otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// # Example
+/// ```
+/// use secure_container::cryptsetup_wrapper;
+/// let path = "/home/Container";
+/// let old_password = "myOldPassword";
+/// let new_password = "myNewPassword";
+/// let result = change_password(path, old_password, new_password);
+/// assert!(result.is_ok());
+/// ```
+///
+fn change_password(path: &str, old_password: &str, password: &str) -> Result<()> {
+    backend().change_key(path, old_password, password)
+}
+
+/// Checks if a container would open, without opening or mounting it.
+/// The password is derived from the id and checked against the key slots of the LUKS header,
+/// no device is created, so the check can be run periodically, e.g. for health monitoring.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `id` - The id of the container.
+/// # Returns
+/// * `Result<bool>` -
+/// Returns true if the password of the id unlocks the container, false if it does not,
+/// otherwise an error is returned.
+/// # Errors
+/// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
+/// * `CryptsetupError` - Cryptsetup failed for another reason than a wrong password.
+///
+/// ### Errors regarding the input:
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container or its header is damaged.
+/// # Example
+/// ```
+/// let result = test_open("/home/Container/MyContainer", "myId");
+/// assert_eq!(result, Ok(true));
+/// ```
+///
+pub fn test_open(path: &str, id: &str) -> Result<bool> {
+    check_input(None, None, Some(path), None, Some(id))?;
+    let password = container_password(path, id)?;
+    backend().check_key(path, &password)
+}
+
+/// Adds a secret to a free key slot of a container, the key of the id stays valid,
+/// e.g. to keep a recovery secret out of band with which the container can be opened by cryptsetup
+/// if the id is lost. The secret is used as it is, it is not derived.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `existing_id` - The id of the container, its password unlocks the container to add the secret.
+/// * `new_secret` - The secret that is added.
+/// # Returns
+/// * `Result<u8>` - Returns the key slot the secret was added to otherwise an error is returned.
+/// # Errors
+/// * `SecertError` - The secret is empty or contains non-ascii or control characters.
+/// * `NoFreeKeyslots` - All key slots of the container are used.
+/// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command, e.g. the id is wrong.
+///
+/// ### Errors regarding the input:
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// # Example
+/// ```
+/// let result = add_key_slot("/home/Container", "myId", "myRecoverySecret");
+/// assert_eq!(result, Ok(1));
+/// ```
+///
+pub fn add_key_slot(path: &str, existing_id: &str, new_secret: &str) -> Result<u8> {
+    check_input(None, None, Some(path), None, Some(existing_id))?;
+    check_secret(new_secret)?;
+    let key_slot = first_free_keyslot(path)?;
+    let password = container_password(path, existing_id)?;
+    backend().add_key(path, &password, new_secret, key_slot)?;
+    Ok(key_slot)
+}
+
+/// Removes the key slot of a container that is unlocked by a secret, e.g. a recovery secret that was leaked.
+/// The last key slot is never removed, the container could not be opened anymore.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `secret_to_remove` - The secret of the key slot that is removed.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the key slot was removed otherwise an error is returned.
+/// # Errors
+/// * `SecertError` - The secret is empty, contains non-ascii or control characters or unlocks no key slot.
+/// * `LastKeyslot` - The key slot of the secret is the last key slot of the container.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+///
+/// ### Errors regarding the input:
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// # Example
+/// ```
+/// let result = remove_key_slot("/home/Container", "myRecoverySecret");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn remove_key_slot(path: &str, secret_to_remove: &str) -> Result<()> {
+    check_input(None, None, Some(path), None, None)?;
+    check_secret(secret_to_remove)?;
+    if !backend().check_key(path, secret_to_remove)? {
+        return Err(SecureContainerErr::SecertError);
+    }
+    if backend().keyslots(path)?.len() <= 1 {
+        return Err(SecureContainerErr::LastKeyslot);
+    }
+    backend().remove_key(path, secret_to_remove)
+}
+
+/// Checks a secret that is stored in a key slot as it is.
+/// # Arguments
+/// * `secret` - The secret.
+/// # Returns
+/// * `Result<()>` - Returns OK(()) if the secret is valid otherwise an error is returned.
+/// # Errors
+/// * `SecertError` -
+/// The secret is empty or contains non-ascii or control characters,
+/// cryptsetup reads each passphrase up to a newline.
+/// # Note
+/// This function is not meant to be called directly.
+fn check_secret(secret: &str) -> Result<()> {
+    if secret.is_empty() || !secret.is_ascii() || secret.chars().any(|c| c.is_control()) {
+        return Err(SecureContainerErr::SecertError);
+    }
+    Ok(())
+}
+
+/// Writes a backup of the LUKS header and the key slots of a container to a file,
+/// the backup is only readable and writable by its owner.
+/// With the backup a container whose header was damaged can be opened again.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `out_file` - The path of the backup.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the backup was written otherwise an error is returned.
+/// # Errors
+/// * `FileExists` - A file already exists at the path of the backup.
+/// * `FileCreationError` - The backup or the directory it is written in could not be created.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `FileWriteError` - The permissions of the backup could not be restricted or it could not be moved to its path.
+///
+/// ### Errors regarding the input:
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path or the directory of the backup does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// # Note
+/// The path of the backup is reserved with `HEADER_BACKUP_MODE` before anything is written,
+/// so an existing file is never replaced and the backup is never readable by others.
+/// If the backup can not be written, nothing is left at its path.
+/// # Example
+/// ```
+/// let result = backup_header("/home/Container", "/root/Container.header");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn backup_header(path: &str, out_file: &str) -> Result<()> {
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(HEADER_BACKUP_MODE)
+        .open(out_file)
+    {
+        Ok(_) => (),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            return Err(SecureContainerErr::FileExists)
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(SecureContainerErr::PathNotExists)
+        }
+        Err(err) => return Err(SecureContainerErr::FileCreationError(err.to_string())),
+    };
+    let result = write_header_backup(path, out_file);
+    if result.is_err() {
+        let _ = std::fs::remove_file(out_file);
+    }
+    result
+}
+
+/// Writes the header backup of `backup_header` over the file that was reserved for it.
+/// cryptsetup does not write into an existing file,
+/// so the backup is written into a directory next to it that only the owner can enter
+/// and is moved over the reserved file once its permissions are restricted.
+/// # Note
+/// This function is not meant to be called directly.
+fn write_header_backup(path: &str, out_file: &str) -> Result<()> {
+    check_input(None, None, Some(path), None, None)?;
+    let out_path = Path::new(out_file);
+    let file_name = out_path.file_name().unwrap_or_default().to_string_lossy();
+    let dir = out_path.with_file_name(format!(".{}.tmp", file_name));
+    if let Err(err) = DirBuilder::new().mode(0o700).create(&dir) {
+        return Err(SecureContainerErr::FileCreationError(err.to_string()));
+    }
+    let backup = dir.join("header");
+    let result = backend()
+        .header_backup(path, &backup.to_string_lossy())
+        .and_then(|_| {
+            std::fs::set_permissions(&backup, Permissions::from_mode(HEADER_BACKUP_MODE))
+                .and_then(|_| std::fs::rename(&backup, out_path))
+                .map_err(|err| SecureContainerErr::FileWriteError(err.to_string()))
+        });
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+/// Replaces the LUKS header and the key slots of a container with a backup of `backup_header`.
+/// The container must be closed, after the restore it accepts the passwords of the backup.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `in_file` - The path of the backup.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the header was restored otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command,
+/// e.g. the backup does not belong to the container.
+///
+/// ### Errors regarding the input:
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path or the backup does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// # Example
+/// ```
+/// let result = restore_header("/home/Container", "/root/Container.header");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn restore_header(path: &str, in_file: &str) -> Result<()> {
+    check_input(None, None, Some(path), None, None)?;
+    if !check_if_file_exists(in_file) {
+        return Err(SecureContainerErr::PathNotExists);
+    }
+    backend().header_restore(path, in_file)
+}
+
+/// Describes the LUKS header of a container for an audit,
+/// i.e. its version, UUID, cipher, key size, integrity algorithm and the PBKDF of every key slot.
+/// The description contains no keys or passwords.
+/// # Arguments
+/// * `path` - The path to the container.
+/// # Returns
+/// * `Result<String>` -
+/// Returns the output of `cryptsetup luksDump` otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `ReadingStdoutError` - An error occurred while reading stdout.
+///
+/// ### Errors regarding the input:
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
+/// * `PathNotExists` - The given path does not exist.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// # Example
+/// ```
+/// let info = container_info("/home/Container").unwrap();
+/// assert!(info.contains("Cipher:"));
+/// ```
+///
+pub fn container_info(path: &str) -> Result<String> {
+    check_input(None, None, Some(path), None, None)?;
+    backend().dump(path)
+}
+
+/// Checks if the provided file is a LUKS container.
+/// # Arguments
+/// * `path` - The path to the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the file is a LUKS container, otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `IsNotLuks` - The provided file is not a LUKS container.
+/// # Example
+/// ```
+/// use secure_container::cryptsetup_wrapper;
+/// let path = "/home/Container";
+/// let result = check_if_file_is_container(path);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn check_if_file_is_container(path: &str) -> Result<()> {
+    backend().is_luks(path)
+}
+
+/// Reads the UUID of the LUKS header of a container.
+/// # Arguments
+/// * `path` - The path to the container.
+/// # Returns
+/// * `Result<String>` -
+/// Returns the UUID of the container, otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `ReadingStdoutError` - An error occurred while reading stdout.
+/// # Example
+/// ```
+/// use secure_container::cryptsetup_wrapper;
+/// let path = "/home/Container";
+/// let result = get_container_uuid(path);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn get_container_uuid(path: &str) -> Result<String> {
+    backend().uuid(path)
+}
+
+/// The magic bytes at the start of every LUKS header.
+const LUKS_MAGIC: &[u8] = b"LUKS\xba\xbe";
+
+/// Reads the version of the LUKS header of a container.
+/// # Arguments
+/// * `path` - The path to the container.
+/// # Returns
+/// * `Result<u16>` -
+/// Returns the version of the LUKS header, 1 or 2, otherwise an error is returned.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading the header.
+/// * `IsNotLuks` - The file does not start with a LUKS header.
+/// # Example
+/// ```
+/// let result = luks_version("/home/Container");
+/// assert_eq!(result, Ok(2));
+/// ```
+///
+pub fn luks_version(path: &str) -> Result<u16> {
+    let mut header = [0u8; 8];
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    match file.read_exact(&mut header) {
+        Ok(_) => (),
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    if !header.starts_with(LUKS_MAGIC) {
+        return Err(SecureContainerErr::IsNotLuks(
+            "No LUKS header found".to_string(),
+        ));
+    }
+    // The version follows the magic bytes as big-endian number.
+    Ok(u16::from_be_bytes([header[6], header[7]]))
+}
+
+/// A LUKS container that was found by `scan_directory`.
+/// * `path` - The path to the container.
+/// * `uuid` - The UUID of the LUKS header of the container.
+/// * `version` - The version of the LUKS header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedContainer {
+    pub path: String,
+    pub uuid: String,
+    pub version: u16,
+}
+
+/// Searches a directory and its subdirectories for LUKS containers,
+/// e.g. to add containers that were not created by the daemon to the autoOpen file.
+/// Only regular files are checked, symbolic links and block devices are skipped,
+/// as well as subdirectories that can not be read.
+/// # Arguments
+/// * `directory` - The path to the directory.
+/// # Returns
+/// * `Result<Vec<ScannedContainer>>` -
+/// Returns the containers sorted by path, otherwise an error is returned.
+/// # Errors
+/// * `PathNotExists` - The given directory does not exist.
+/// * `FileReadError` - An error occurred while reading the given directory.
+/// # Example
+/// ```
+/// let result = scan_directory("/home/Containers");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn scan_directory(directory: &str) -> Result<Vec<ScannedContainer>> {
+    if !check_if_dir_exists(directory) {
+        return Err(SecureContainerErr::PathNotExists);
+    }
+    let mut files = Vec::new();
+    collect_files(Path::new(directory), &mut files)?;
+    files.sort();
+    Ok(files
+        .iter()
+        .filter_map(|file| file.to_str())
+        .filter(|file| check_if_file_is_container(file).is_ok())
+        .filter_map(|file| {
+            Some(ScannedContainer {
+                path: file.to_string(),
+                uuid: get_container_uuid(file).ok()?,
+                version: luks_version(file).ok()?,
+            })
+        })
+        .collect())
+}
+
+/// Collects the regular files of a directory and its subdirectories.
+/// # Arguments
+/// * `directory` - The path to the directory.
+/// * `files` - The list the files are added to.
+/// # Returns
+/// * `Result<()>` - Returns OK(()) if the directory was read otherwise an error is returned.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading the directory.
+/// # Note
+/// This function is not meant to be called directly.
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
     };
+    for entry in entries.flatten() {
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+        if file_type.is_dir() {
+            // Subdirectories that can not be read are skipped.
+            let _ = collect_files(&entry.path(), files);
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
     Ok(())
 }
 
-/// Change the password of an existing container.
+/// Lists the key slots of a container that are not used.
 /// # Arguments
 /// * `path` - The path to the container.
-/// * `password_old` - The old password of the container.
-/// * `password` - The new password of the container.
 /// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the password was changed successfully otherwise an error is returned.
+/// * `Result<Vec<u8>>` -
+/// Returns the indices of the free key slots, a LUKS2 container has `LUKS2_KEYSLOTS` key slots.
 /// # Errors
-/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `CryptsetupError` - An error occurred while reading the LUKS header.
 /// # Example
 /// ```
 /// use secure_container::cryptsetup_wrapper;
 /// let path = "/home/Container";
-/// let old_password = "myOldPassword";
-/// let new_password = "myNewPassword";
-/// let result = change_password(path, old_password, new_password);
-/// assert!(result.is_ok());
+/// let result = free_keyslots(path);
+/// assert_eq!(result.unwrap().len(), 31);
 /// ```
 ///
-fn change_password(path: &str, old_password: &str, password: &str) -> Result<()> {
-    let mut output = match Command::new("/usr/sbin/cryptsetup")
-        .args(["luksChangeKey", path])
-        .stdin(Stdio::piped())
-        .spawn()
-    {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
-    };
-
-    let stdin = match output.stdin.as_mut() {
-        Some(stdin) => stdin,
-        None => {
-            return Err(SecureContainerErr::CryptsetupError(
-                "Failed to open stdin".to_string(),
-            ))
-        }
-    };
-
-    let _ = stdin.write_all(old_password.as_bytes());
-    let _ = stdin.write_all(b"\n");
-    let _ = stdin.write_all(password.as_bytes());
-
-    let done = output.wait_with_output().unwrap();
-    if !done.status.success() {
-        let stderr = String::from_utf8_lossy(&done.stderr);
-        return Err(SecureContainerErr::CryptsetupError(stderr.to_string()));
-    }
-    Ok(())
+pub fn free_keyslots(path: &str) -> Result<Vec<u8>> {
+    let used = backend().keyslots(path)?;
+    Ok((0..LUKS2_KEYSLOTS)
+        .filter(|index| !used.contains(index))
+        .collect())
 }
 
-/// Checks if the provided file is a LUKS container.
+/// Selects the key slot for a new key of a container.
 /// # Arguments
 /// * `path` - The path to the container.
 /// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the file is a LUKS container, otherwise an error is returned.
+/// * `Result<u8>` - Returns the index of the first free key slot.
 /// # Errors
-/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
-/// * `IsNotLuks` - The provided file is not a LUKS container.
+/// * `NoFreeKeyslots` - All key slots of the container are used.
+/// * `CryptsetupError` - An error occurred while reading the LUKS header.
 /// # Example
 /// ```
 /// use secure_container::cryptsetup_wrapper;
 /// let path = "/home/Container";
-/// let result = check_if_file_is_container(path);
-/// assert!(result.is_ok());
+/// let result = first_free_keyslot(path);
+/// assert_eq!(result, Ok(1));
 /// ```
 ///
-pub fn check_if_file_is_container(path: &str) -> Result<()> {
-    let output = match Command::new("/usr/sbin/cryptsetup")
-        .args(["isLuks", path])
-        .spawn()
-    {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
-    };
-    let done = output.wait_with_output().unwrap();
-    if !done.status.success() {
-        let stderr = String::from_utf8_lossy(&done.stderr);
-        return Err(SecureContainerErr::IsNotLuks(stderr.to_string()));
+pub fn first_free_keyslot(path: &str) -> Result<u8> {
+    match free_keyslots(path)?.first() {
+        Some(index) => Ok(*index),
+        None => Err(SecureContainerErr::NoFreeKeyslots),
     }
-    Ok(())
+}
+
+/// Returns the password of a container,
+/// derived from the id with the key salt and the key derivation in the metadata of the container.
+/// # Arguments
+/// * `path` - The path to the container.
+/// * `id` - The id of the container.
+/// # Returns
+/// * `Result<String>` - Returns the password otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - The header of the container could not be read, so it is unknown if the container has a key salt.
+/// * `FileReadError` - The metadata sidecar could not be read or is not valid.
+/// * `Base64Error` - The key salt in the metadata is not valid.
+/// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
+/// # Note
+/// This function is not meant to be called directly.
+fn container_password(path: &str, id: &str) -> Result<String> {
+    // Only a container whose header was read and has no metadata is unsalted.
+    let (key_salt, key_derivation) = match read_metadata(path)? {
+        Some(metadata) => (metadata.key_salt, metadata.key_derivation),
+        None => (String::new(), LEGACY_KEY_DERIVATION),
+    };
+    get_salted_password(id, &key_salt, key_derivation)
 }
 
 /// Formats a LUKS container.
 /// # Arguments
 /// * `device_path` - The path to the file that will be the LUKS container.
 /// * `id` - The id of the container.
+/// * `key_salt` - The key salt the password is derived with (see `get_salted_password`).
+/// The id is derived with `KEY_DERIVATION`, which is recorded in the metadata of the container.
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(()) if the container was formatted successfully otherwise an error is returned.
@@ -562,12 +2127,12 @@ pub fn check_if_file_is_container(path: &str) -> Result<()> {
 /// use secure_container::cryptsetup_wrapper;
 /// let device_path = "/home/Container";
 /// let id = "myId";
-/// let result = format_container(size, mount_point, path, namespace, id, auto_open);
+/// let result = format_container(device_path, id, &get_random_salt().unwrap());
 /// assert!(result.is_ok());
 /// ```
 ///
-fn format_container(device_path: &str, id: &str) -> Result<()> {
-    let bind = get_password(id);
+fn format_container(device_path: &str, id: &str, key_salt: &str) -> Result<()> {
+    let bind = get_salted_password(id, key_salt, KEY_DERIVATION);
     if bind.is_err() {
         return Err(SecureContainerErr::StdinError(
             "Error getting password".to_string(),
@@ -576,52 +2141,330 @@ fn format_container(device_path: &str, id: &str) -> Result<()> {
     let bind = bind.unwrap();
     let password = bind.as_str();
 
-    let mut output = match Command::new("/usr/sbin/cryptsetup")
-        .args([
-            "luksFormat",
-            device_path,
-            "--type",
-            "luks2",
-            "--integrity",
-            "hmac-sha256",
-        ])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-    {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
-    };
-    {
-        let stdin = match output.stdin.as_mut() {
-            Some(stdin) => stdin,
-            None => {
-                return Err(SecureContainerErr::CryptsetupError(
-                    "Failed to open stdin".to_string(),
-                ))
-            }
-        };
-        let _ = stdin.write_all(password.as_bytes());
-    }
-
-    let done = match output.wait_with_output() {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
-    };
-    if !done.status.success() {
-        let stderr = String::from_utf8_lossy(&done.stderr);
-        return Err(SecureContainerErr::CryptsetupError(stderr.to_string()));
-    }
-    Ok(())
+    backend().format(device_path, password)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{export_container, SecureContainerErr};
+    use super::{
+        add_key_slot, backup_header, bind_source, check_secret, container_info,
+        create_swap_container, export_container, luks_version, mount_open, open_time,
+        open_unmounted, rekey_container, remove_key_slot, restore_header, scan_directory,
+        set_open_time, test_open, transfer_password, verify_integrity, with_bind_mounts,
+        CreateProgress, SecureContainerErr, TransferCredential, PHASE_ALLOCATING, PHASE_DONE,
+        PHASE_FORMATTING, PHASE_MAKING_FILESYSTEM,
+    };
+    use crate::deadline::with_deadline;
+    use crate::file_system_operations::check_lsblk;
+    use crate::input_validation::{device_name, MAX_ID_LENGTH};
+    use crate::metadata::metadata_path;
     use std::any::Any;
     use std::fs;
     use std::path::Path;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_open_time() {
+        let time = chrono::DateTime::parse_from_rfc3339("2024-03-01T12:00:00+01:00").unwrap();
+        set_open_time("OpenTimeTest", Some(time));
+        assert_eq!(open_time("OpenTimeTest"), Some(time));
+        set_open_time("OpenTimeTest", None);
+        assert_eq!(open_time("OpenTimeTest"), None);
+    }
+
+    #[test]
+    fn test_create_progress() {
+        let percents: Vec<u32> = [
+            PHASE_ALLOCATING,
+            PHASE_FORMATTING,
+            PHASE_MAKING_FILESYSTEM,
+            PHASE_DONE,
+        ]
+        .iter()
+        .map(|phase| CreateProgress::of(phase).percent)
+        .collect();
+        assert_eq!(percents, vec![0, 10, 80, 100]);
+        assert_eq!(CreateProgress::of(PHASE_FORMATTING).phase, "formatting");
+    }
+
+    #[test]
+    fn test_bind_source() {
+        let root = tempfile::tempdir().unwrap();
+        let mount_point = root.path().join("mnt");
+        fs::create_dir_all(mount_point.join("data")).unwrap();
+        let mount_point = mount_point.to_str().unwrap();
+        let data = fs::canonicalize(Path::new(mount_point).join("data")).unwrap();
+
+        assert_eq!(
+            bind_source(mount_point, "data"),
+            Ok(data.to_str().unwrap().to_string())
+        );
+        assert_eq!(
+            bind_source(mount_point, "/data/"),
+            Ok(data.to_str().unwrap().to_string())
+        );
+        assert_eq!(
+            bind_source(mount_point, "missing"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            bind_source(mount_point, "../"),
+            Err(SecureContainerErr::PathNotValid)
+        );
+        std::os::unix::fs::symlink(root.path(), data.join("escape")).unwrap();
+        assert_eq!(
+            bind_source(mount_point, "data/escape"),
+            Err(SecureContainerErr::PathNotValid)
+        );
+    }
+
+    #[test]
+    fn test_with_bind_mounts() {
+        with_bind_mounts("BindTest", |targets| {
+            targets.push("/first".to_string());
+            targets.push("/second".to_string());
+        });
+        assert_eq!(
+            with_bind_mounts("BindTest", |targets| targets.clone()),
+            vec!["/first", "/second"]
+        );
+        with_bind_mounts("BindTest", |targets| targets.clear());
+        assert!(with_bind_mounts("BindTest", |targets| targets.is_empty()));
+    }
+
+    #[test]
+    fn test_verify_integrity_input() {
+        assert_eq!(
+            verify_integrity("My|Container", None),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+        assert_eq!(
+            verify_integrity("MyContainer", Some("yesterday")),
+            Err(SecureContainerErr::TimeNotValid)
+        );
+    }
+
+    #[test]
+    fn test_test_open_input() {
+        assert_eq!(
+            test_open("/not/existing/container", "test"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            test_open("/not/existing/container", "test|"),
+            Err(SecureContainerErr::IdNotValid)
+        );
+    }
+
+    #[test]
+    fn test_key_slot_input() {
+        assert_eq!(
+            add_key_slot("/not/existing/container", "test", "recovery"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            add_key_slot("/not/existing/container", "test|", "recovery"),
+            Err(SecureContainerErr::IdNotValid)
+        );
+        assert_eq!(
+            remove_key_slot("/not/existing/container", "recovery"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let file = file.path().to_str().unwrap();
+        assert_eq!(
+            add_key_slot(file, "test", "recovery"),
+            Err(SecureContainerErr::PathNotLuksContainer)
+        );
+        assert_eq!(
+            remove_key_slot(file, "recovery"),
+            Err(SecureContainerErr::PathNotLuksContainer)
+        );
+    }
+
+    #[test]
+    fn test_check_secret() {
+        assert!(check_secret("recovery").is_ok());
+        assert_eq!(check_secret(""), Err(SecureContainerErr::SecertError));
+        assert_eq!(
+            check_secret("recovery\nsecret"),
+            Err(SecureContainerErr::SecertError)
+        );
+        assert_eq!(
+            check_secret("recovery¢"),
+            Err(SecureContainerErr::SecertError)
+        );
+    }
+
+    #[test]
+    fn test_header_backup_input() {
+        let existing = tempfile::NamedTempFile::new().unwrap();
+        let existing = existing.path().to_str().unwrap();
+        assert_eq!(
+            backup_header("/not/existing/container", existing),
+            Err(SecureContainerErr::FileExists)
+        );
+        assert_eq!(
+            backup_header("/not/existing/container", "/not/existing/backup"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let backup = dir.path().join("backup");
+        assert_eq!(
+            backup_header("/not/existing/container", backup.to_str().unwrap()),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert!(!backup.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert_eq!(
+            restore_header("/not/existing/container", existing),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            restore_header(existing, existing),
+            Err(SecureContainerErr::PathNotLuksContainer)
+        );
+    }
+
+    #[test]
+    fn test_container_info_input() {
+        assert_eq!(
+            container_info("/not/existing/container"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            container_info("/not/valid|container"),
+            Err(SecureContainerErr::PathNotValid)
+        );
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert_eq!(
+            container_info(file.path().to_str().unwrap()),
+            Err(SecureContainerErr::PathNotLuksContainer)
+        );
+    }
+
+    #[test]
+    fn test_rekey_container_input() {
+        assert_eq!(
+            rekey_container("/not/existing/container", "old", "new"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            rekey_container("/not/existing/container", "old|", "new"),
+            Err(SecureContainerErr::IdNotValid)
+        );
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let file = file.path().to_str().unwrap();
+        assert_eq!(
+            rekey_container(file, "old", "new"),
+            Err(SecureContainerErr::PathNotLuksContainer)
+        );
+    }
+
+    #[test]
+    fn test_open_unmounted_input() {
+        assert_eq!(
+            open_unmounted("/not/existing/container", "MyContainer", "test"),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            mount_open("/not/existing/mount_point", "MyContainer"),
+            Err(SecureContainerErr::MountPointNotExists)
+        );
+        assert_eq!(
+            mount_open(std::env::temp_dir().to_str().unwrap(), "My|Container"),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_create_swap_container_input() {
+        assert_eq!(
+            create_swap_container(10, "MySwap").err(),
+            Some(SecureContainerErr::SizeToSmall)
+        );
+        assert_eq!(
+            create_swap_container(100, "My|Swap").err(),
+            Some(SecureContainerErr::NamespaceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_create_container_lsblk_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().to_str().unwrap();
+        // With an expired deadline lsblk is not started, so checking the name of the container fails.
+        let expired = Some(Instant::now() - Duration::from_secs(1));
+        let result = with_deadline(expired, || {
+            super::create_container(16, dir, dir, "LsblkFails", "id", false, "ext4")
+        });
+        assert!(matches!(result, Err(SecureContainerErr::LsblkError(_))));
+        assert!(!Path::new(dir).join("LsblkFails").exists());
+    }
+
+    #[test]
+    fn test_ensure_closed() {
+        // A container that is not open is already closed, only the strict close fails.
+        assert_eq!(super::ensure_closed("", "NeverOpened"), Ok(()));
+        assert!(super::close_container("", "NeverOpened").is_err());
+        assert_eq!(
+            super::ensure_closed("", "test|"),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_create_container_format_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("FormatFails");
+        let dir = dir.path().to_str().unwrap();
+        // The backing file is emptied before it is formatted, so it is too small for the LUKS header.
+        let progress = |progress: CreateProgress| {
+            if progress.phase == PHASE_FORMATTING {
+                fs::write(&file, b"").unwrap();
+            }
+        };
+        let result = super::create_container_with_progress(
+            16,
+            dir,
+            dir,
+            "FormatFails",
+            "id",
+            false,
+            "ext4",
+            &progress,
+        );
+        assert!(result.is_err());
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_create_container_mount_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("MountFails");
+        let mount_point = dir.path().join("mount");
+        fs::create_dir(&mount_point).unwrap();
+        // The mount point is removed after the container is opened, so it can not be mounted.
+        let progress = |progress: CreateProgress| {
+            if progress.phase == PHASE_MAKING_FILESYSTEM {
+                fs::remove_dir(&mount_point).unwrap();
+            }
+        };
+        let result = super::create_container_with_progress(
+            16,
+            mount_point.to_str().unwrap(),
+            dir.path().to_str().unwrap(),
+            "MountFails",
+            "id",
+            false,
+            "ext4",
+            &progress,
+        );
+        assert!(result.is_err());
+        assert!(!file.exists());
+        assert!(!Path::new(&metadata_path(file.to_str().unwrap())).exists());
+        assert_eq!(check_lsblk(&device_name("MountFails")), Ok(false));
+    }
 
     #[test]
     fn test_functionality() {
@@ -679,6 +2522,51 @@ mod tests {
         test_import_container_wrong_secret(path_to_container, namespace, id, secret);
     }
 
+    fn credential(secret: &str) -> TransferCredential {
+        TransferCredential::Secret(secret.to_string())
+    }
+
+    #[test]
+    fn test_transfer_password() {
+        let keyfile = std::env::current_dir().unwrap().join("transfer_test.key");
+        fs::write(&keyfile, [1u8, 2, 3]).unwrap();
+        let from_keyfile =
+            transfer_password(&TransferCredential::Keyfile(keyfile.clone()), "test", "").unwrap();
+        assert_eq!(
+            from_keyfile,
+            transfer_password(&TransferCredential::Keyfile(keyfile.clone()), "other", "").unwrap()
+        );
+        fs::write(&keyfile, []).unwrap();
+        assert_eq!(
+            transfer_password(&TransferCredential::Keyfile(keyfile.clone()), "test", ""),
+            Err(SecureContainerErr::SecertError)
+        );
+        let _ = fs::remove_file(keyfile);
+        let legacy = transfer_password(&credential("123"), "test", "").unwrap();
+        assert_ne!(from_keyfile, legacy);
+        assert_eq!(
+            transfer_password(&credential(""), "test", ""),
+            Err(SecureContainerErr::SecertError)
+        );
+
+        // With a salt the namespace does not change the password, but the salt does.
+        let salted =
+            transfer_password(&credential("123"), "test", "q0MeF2bY7Zq8hXo1GJ4bXg").unwrap();
+        assert_ne!(salted, legacy);
+        assert_eq!(
+            salted,
+            transfer_password(&credential("123"), "other", "q0MeF2bY7Zq8hXo1GJ4bXg").unwrap()
+        );
+        assert_ne!(
+            salted,
+            transfer_password(&credential("123"), "test", "Y3JX9u2nM1aPq7ZrT0vL4w").unwrap()
+        );
+        assert_eq!(
+            transfer_password(&credential("123"), "test", "short"),
+            Err(SecureContainerErr::SecertError)
+        );
+    }
+
     fn print_blogs(message: &str) {
         println!("##############################################################################################################");
         println!("{}", message.to_uppercase());
@@ -693,7 +2581,8 @@ mod tests {
         id: &str,
         auto_open: bool,
     ) {
-        let result_size = super::create_container(15, mount_point, path, namespace, id, auto_open);
+        let result_size =
+            super::create_container(15, mount_point, path, namespace, id, auto_open, "ext4");
         let result_mountpoint = super::create_container(
             size,
             "/wqsedrftgzhuiizurfcgjhg",
@@ -701,6 +2590,7 @@ mod tests {
             namespace,
             id,
             auto_open,
+            "ext4",
         );
         let result_path = super::create_container(
             size,
@@ -709,17 +2599,41 @@ mod tests {
             namespace,
             id,
             auto_open,
+            "ext4",
         );
         let result_namespace =
-            super::create_container(size, mount_point, path, "test|", id, auto_open);
-        let result_namespace_non_ascii =
-            super::create_container(size, mount_point, path, "test¢", id, auto_open);
-        let result_id =
-            super::create_container(size, mount_point, path, namespace, "test|", auto_open);
-        let result_id_non_ascii =
-            super::create_container(size, mount_point, path, namespace, "test¢", auto_open);
-        let result_id_to_long =
-            super::create_container(size, mount_point, path, namespace, "testtest", auto_open);
+            super::create_container(size, mount_point, path, "test|", id, auto_open, "ext4");
+        let result_namespace_control =
+            super::create_container(size, mount_point, path, "test\n", id, auto_open, "ext4");
+        let result_id = super::create_container(
+            size,
+            mount_point,
+            path,
+            namespace,
+            "test|",
+            auto_open,
+            "ext4",
+        );
+        let result_id_non_ascii = super::create_container(
+            size,
+            mount_point,
+            path,
+            namespace,
+            "test¢",
+            auto_open,
+            "ext4",
+        );
+        let result_id_to_long = super::create_container(
+            size,
+            mount_point,
+            path,
+            namespace,
+            &"a".repeat(MAX_ID_LENGTH + 1),
+            auto_open,
+            "ext4",
+        );
+        let result_fs_type =
+            super::create_container(size, mount_point, path, namespace, id, auto_open, "ntfs");
 
         assert_eq!(result_size.err().unwrap(), SecureContainerErr::SizeToSmall);
         assert_eq!(
@@ -735,7 +2649,7 @@ mod tests {
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(
-            result_namespace_non_ascii.err().unwrap(),
+            result_namespace_control.err().unwrap(),
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(result_id.err().unwrap(), SecureContainerErr::IdNotValid);
@@ -747,16 +2661,48 @@ mod tests {
             result_id_to_long.err().unwrap(),
             SecureContainerErr::IdNotValid
         );
+        assert_eq!(
+            result_fs_type.err().unwrap(),
+            SecureContainerErr::UnsupportedFilesystem
+        );
     }
 
     fn test_open_container_wrong_input(mount_point: &str, path: &str, namespace: &str, id: &str) {
-        let result_mountpoint = super::open_container("/home/tian/test12345", path, namespace, id);
-        let result_path = super::open_container(mount_point, "/home/tian/test12345", namespace, id);
-        let result_namespace = super::open_container(mount_point, path, "test|", id);
-        let result_namespace_non_ascii = super::open_container(mount_point, path, "test¢", id);
-        let result_id = super::open_container(mount_point, path, namespace, "test|");
-        let result_id_non_ascii = super::open_container(mount_point, path, namespace, "test¢");
-        let result_id_to_long = super::open_container(mount_point, path, namespace, "testtest");
+        let result_mountpoint = super::open_container(
+            "/home/tian/test12345",
+            path,
+            namespace,
+            id,
+            false,
+            &[],
+            false,
+        );
+        let result_path = super::open_container(
+            mount_point,
+            "/home/tian/test12345",
+            namespace,
+            id,
+            false,
+            &[],
+            false,
+        );
+        let result_namespace =
+            super::open_container(mount_point, path, "test|", id, false, &[], false);
+        let result_namespace_control =
+            super::open_container(mount_point, path, "test\n", id, false, &[], false);
+        let result_id =
+            super::open_container(mount_point, path, namespace, "test|", false, &[], false);
+        let result_id_non_ascii =
+            super::open_container(mount_point, path, namespace, "test¢", false, &[], false);
+        let result_id_to_long = super::open_container(
+            mount_point,
+            path,
+            namespace,
+            &"a".repeat(MAX_ID_LENGTH + 1),
+            false,
+            &[],
+            false,
+        );
         assert_eq!(
             result_mountpoint.err().unwrap(),
             SecureContainerErr::MountPointNotExists
@@ -770,7 +2716,7 @@ mod tests {
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(
-            result_namespace_non_ascii.err().unwrap(),
+            result_namespace_control.err().unwrap(),
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(result_id.err().unwrap(), SecureContainerErr::IdNotValid);
@@ -787,7 +2733,7 @@ mod tests {
     fn test_close_container_wrong_input(container_name: &str, mount_point: &str) {
         let result_mountpoint = super::close_container("/home/tian/test12345", container_name);
         let result_namespace = super::close_container(mount_point, "test|");
-        let result_namespace_non_ascii = super::close_container(mount_point, "test¢");
+        let result_namespace_control = super::close_container(mount_point, "test\n");
         let result_container_not_open = super::close_container(mount_point, "test");
         assert_eq!(
             result_mountpoint.err().unwrap(),
@@ -798,7 +2744,7 @@ mod tests {
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(
-            result_namespace_non_ascii.err().unwrap(),
+            result_namespace_control.err().unwrap(),
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(
@@ -808,15 +2754,21 @@ mod tests {
     }
 
     fn test_export_container_wrong_input(path: &str, namespace: &str, id: &str, secret: &str) {
-        let result_path = export_container("/home/tian/MountME", namespace, id, secret);
-        let result_namespace = export_container(path, "test|", id, secret);
-        let result_namespace_non_ascii = export_container(path, "test¢", id, secret);
-        let result_id = export_container(path, namespace, "test|", secret);
-        let result_id_non_ascii = export_container(path, namespace, "test¢", secret);
-        let result_id_to_long = export_container(path, namespace, "testtest", secret);
-        let result_id_wrong = export_container(path, namespace, "1234", secret);
-        let result_secret_empty = export_container(path, namespace, id, "");
-        let result_secert_non_ascii = export_container(path, namespace, id, "test¢");
+        let result_path =
+            export_container("/home/tian/MountME", namespace, id, &credential(secret));
+        let result_namespace = export_container(path, "test|", id, &credential(secret));
+        let result_namespace_control = export_container(path, "test\n", id, &credential(secret));
+        let result_id = export_container(path, namespace, "test|", &credential(secret));
+        let result_id_non_ascii = export_container(path, namespace, "test¢", &credential(secret));
+        let result_id_to_long = export_container(
+            path,
+            namespace,
+            &"a".repeat(MAX_ID_LENGTH + 1),
+            &credential(secret),
+        );
+        let result_id_wrong = export_container(path, namespace, "1234", &credential(secret));
+        let result_secret_empty = export_container(path, namespace, id, &credential(""));
+        let result_secert_non_ascii = export_container(path, namespace, id, &credential("test¢"));
         assert_eq!(
             result_path.err().unwrap(),
             SecureContainerErr::PathNotExists
@@ -826,7 +2778,7 @@ mod tests {
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(
-            result_namespace_non_ascii.err().unwrap(),
+            result_namespace_control.err().unwrap(),
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(result_id.err().unwrap(), SecureContainerErr::IdNotValid);
@@ -853,15 +2805,24 @@ mod tests {
     }
 
     fn test_import_container_wrong_input(path: &str, namespace: &str, id: &str, secret: &str) {
-        let result_path = super::import_container("/home/tian/MountME", namespace, id, secret);
-        let result_namespace = super::import_container(path, "test|", id, secret);
-        let result_namespace_non_ascii = super::import_container(path, "test¢", id, secret);
-        let result_id = super::import_container(path, namespace, "test|", secret);
-        let result_id_non_ascii = super::import_container(path, namespace, "test¢", secret);
-        let result_id_to_long = super::import_container(path, namespace, "testtest", secret);
-        let result_id_wrong = super::import_container(path, namespace, "1234", secret);
-        let result_secret_empty = super::import_container(path, namespace, id, "");
-        let result_secret_non_ascii = super::import_container(path, namespace, id, "test¢");
+        let result_path =
+            super::import_container("/home/tian/MountME", namespace, id, &credential(secret));
+        let result_namespace = super::import_container(path, "test|", id, &credential(secret));
+        let result_namespace_control =
+            super::import_container(path, "test\n", id, &credential(secret));
+        let result_id = super::import_container(path, namespace, "test|", &credential(secret));
+        let result_id_non_ascii =
+            super::import_container(path, namespace, "test¢", &credential(secret));
+        let result_id_to_long = super::import_container(
+            path,
+            namespace,
+            &"a".repeat(MAX_ID_LENGTH + 1),
+            &credential(secret),
+        );
+        let result_id_wrong = super::import_container(path, namespace, "1234", &credential(secret));
+        let result_secret_empty = super::import_container(path, namespace, id, &credential(""));
+        let result_secret_non_ascii =
+            super::import_container(path, namespace, id, &credential("test¢"));
         assert_eq!(
             result_path.err().unwrap(),
             SecureContainerErr::PathNotExists
@@ -871,7 +2832,7 @@ mod tests {
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(
-            result_namespace_non_ascii.err().unwrap(),
+            result_namespace_control.err().unwrap(),
             SecureContainerErr::NamespaceNotValid
         );
         assert_eq!(result_id.err().unwrap(), SecureContainerErr::IdNotValid);
@@ -897,10 +2858,31 @@ mod tests {
         );
     }
     fn test_import_container_wrong_secret(path: &str, namespace: &str, id: &str, secret: &str) {
-        let result = super::import_container(path, namespace, id, secret);
+        let result = super::import_container(path, namespace, id, &credential(secret));
         assert_eq!(
             result.err().unwrap().type_id(),
             SecureContainerErr::CryptsetupError("".to_string()).type_id()
         );
     }
+
+    #[test]
+    fn test_scan_directory() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("sub")).unwrap();
+        fs::write(root.path().join("sub").join("plain"), "not a container").unwrap();
+        let header = root.path().join("header");
+        fs::write(&header, b"LUKS\xba\xbe\x00\x02").unwrap();
+
+        assert_eq!(luks_version(header.to_str().unwrap()), Ok(2));
+        assert!(luks_version(root.path().join("sub").join("plain").to_str().unwrap()).is_err());
+        // A header without key slots and segments is not a container for cryptsetup.
+        assert_eq!(
+            scan_directory(root.path().to_str().unwrap()),
+            Ok(Vec::new())
+        );
+        assert_eq!(
+            scan_directory(root.path().join("missing").to_str().unwrap()),
+            Err(SecureContainerErr::PathNotExists)
+        );
+    }
 }
@@ -1,10 +1,26 @@
 //! # daemon
 //! This is the daemon that will be running on the system.
-//! It functions as a gRPC server that listens to port 50051 for requests.
-//! On startup, the daemon checks if any containers should be automatically opened and opens them.
+//! It functions as a gRPC server that listens to port 50051 (or `SECURE_CONTAINER_ADDRESS`) for requests.
+//! If `SECURE_CONTAINER_SOCKET` is set, it listens on this Unix socket instead of a TCP port,
+//! the socket can only be used by root.
+//! If `SECURE_CONTAINER_TLS_CERT` and `SECURE_CONTAINER_TLS_KEY` are set, the daemon serves gRPC over TLS,
+//! with `SECURE_CONTAINER_TLS_CLIENT_CA` it also requires a client certificate that is signed by this CA.
+//! Without a certificate the daemon serves plaintext.
+//! Before it serves, the daemon validates its configuration and exits with code 1 if it is not valid.
+//! On startup, the daemon logs a report of its dependencies
+//! and checks if any containers should be automatically opened and opens them.
 //! The daemon is able to create, open, close, export, import containers and add or remove them from the autoOpen file.
+//! Containers can also be exported to and imported from self-describing bundles, see the `bundle` module.
+//! The LUKS header of a container can be backed up to a file and restored from it.
+//! Every request is handled in a span with its operation and namespace,
+//! the start and the end of a request are logged with the error it failed with.
+//! The log is written to stderr with the filter of `SECURE_CONTAINER_LOG` or `RUST_LOG`, e.g. `info`,
+//! without a filter the warnings and errors are logged.
 //! The daemon also shuts down gracefully when a SIGINT or SIGTERM signal is received.
 //! When the daemon shuts down, it checks if containers were opened by the autoOpen process and trys to close them.
+//! If a container could not be closed, the daemon exits with code 1.
+//! When the daemon runs as a `Type=notify` systemd service, it reports when it is ready and when it stops
+//! and pings the watchdog of the service, see the `systemd` module.
 //!
 //! ## Usage
 //! Start the daemon by running the following command (needs to be run as root):
@@ -13,44 +29,383 @@
 //! ```
 //! The daemon is now running and listening for requests.
 //! The daemon can be stopped by sending a SIGINT or SIGTERM signal.
+//! The paths of the external binaries can be set with environment variables, see the `config` module.
+//! When the daemon is built with the `rest` feature, it also serves the operations as JSON over HTTP,
+//! see the `rest_gateway` module.
+//!
+//! ## Platform
+//! The daemon only runs on Linux, it needs cryptsetup, lsblk, dmesg, device-mapper and `/proc`.
+//! Building the daemon for another target fails with a compile error.
+//! The tests can still be built on any Unix system,
+//! so the platform independent logic, e.g. the base64 conversion, `mb_in_bytes` and the error types,
+//! can be tested in CI on other systems as well.
+//! The client library and the CLI only talk to the daemon over gRPC and work on any platform.
 //!
 //! ## Error
 //! If the daemon is not able to start or an error occurs, the generated error message will be printed.
 //!
 //!
+#![allow(clippy::doc_lazy_continuation)]
+
+#[cfg(all(not(target_os = "linux"), not(test)))]
+compile_error!(
+    "secure_container_daemon only runs on Linux, it needs cryptsetup, device-mapper and /proc"
+);
+
 mod cryptsetup_wrapper;
 use cryptsetup_wrapper::{
-    close_container, create_container, export_container, import_container, open_container,
+    add_key_slot, backup_header, bind_mount, close_by_namespace, close_container, container_info,
+    create_container, create_container_with_progress, create_raw_container,
+    create_raw_container_with_progress, create_swap_container, ensure_closed, export_container,
+    import_container, mount_open, open_container_with_key_slot, open_unmounted, rekey_container,
+    remove_key_slot, restore_header, scan_directory, test_open, verify_integrity, CreateProgress,
+    CreatedContainer, TransferCredential, PHASE_DONE,
 };
 mod utilities;
-use utilities::{auto_close, auto_open};
+use utilities::{
+    auto_close, auto_open, check_dependencies, container_state, container_status, diff_state,
+    inventory, rebuild_auto_open, rotate_keys,
+};
 
 mod file_system_operations;
-use file_system_operations::check_if_file_exists;
 
 mod file_io_operations;
-use file_io_operations::{add_to_auto_open, remove_auto_open};
-mod error_handling;
+use file_io_operations::{add_to_auto_open, auto_open_read, remove_auto_open, update_auto_open};
+use secure_container_lib::error_handling;
+mod input_validation;
+use input_validation::{check_input, check_key_slot, DEFAULT_FILE_SYSTEM};
 
-use file_io_operations::PATH_TO_AUTO_OPEN;
+mod config;
+use config::config;
+mod cryptsetup_backend;
+mod deadline;
+use deadline::{request_deadline, with_deadline};
 
-use ctrlc;
+mod bundle;
+use bundle::{export_bundle, import_bundle};
+mod keyring;
+mod metadata;
+use metadata::read_metadata;
+mod operations;
+use operations::{OperationGuard, Operations};
+#[cfg(feature = "rest")]
+mod rest_gateway;
+mod systemd;
 
-use tonic::{transport::Server, Request, Response, Status};
+use std::cell::Cell;
+use std::fs::Permissions;
+use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream, UnixListenerStream};
+use tonic::{
+    transport::{Certificate, Identity, Server, ServerTlsConfig},
+    Request, Response, Status,
+};
+use tracing::{Instrument, Span};
+use tracing_subscriber::EnvFilter;
 
 use secure_container_service::container_server::{Container, ContainerServer};
 
 use crate::error_handling::SecureContainerErr;
 use secure_container_service::{
-    CreateContainerRequest, OpenContainerRequest, SecureContainerResponse,
+    AddKeySlotRequest, AddKeySlotResponse, BackupHeaderRequest, BindMountRequest,
+    CloseByNamespaceRequest, ContainerInfoRequest, ContainerInfoResponse, CreateContainerProgress,
+    CreateContainerRequest, CreateContainerResponse, CreateSwapContainerRequest, DiffStateRequest,
+    DiffStateResponse, DoctorRequest, DoctorResponse, InventoryRequest, InventoryResponse,
+    ListContainersRequest, ListContainersResponse, MountOpenRequest, OpenContainerRequest,
+    OpenUnmountedRequest, OpenUnmountedResponse, RebuildAutoOpenRequest, RebuildAutoOpenResponse,
+    RekeyContainerRequest, RemoveKeySlotRequest, RestoreHeaderRequest, RotateKeysRequest,
+    RotateKeysResponse, ScanDirectoryRequest, ScanDirectoryResponse, SecureContainerResponse,
+    StatusContainerRequest, StatusContainerResponse, TestOpenRequest, TestOpenResponse,
+    UpdateAutoOpenRequest, ValidateInputRequest, ValidateInputResponse, VerifyIntegrityRequest,
 };
 
 pub mod secure_container_service {
     tonic::include_proto!("secure_container_service");
 }
 
+/// The service that handles the requests of the clients.
+/// * `operations` -
+/// The operations that are in progress, only one operation runs at a time for a container.
 #[derive(Debug, Default)]
-pub struct MySecureContainer {}
+pub struct MySecureContainer {
+    operations: Operations,
+}
+
+impl MySecureContainer {
+    /// Starts an operation on a container, it waits until the running operation of the container is finished.
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// * `operation` - The name of the operation that is reported by the status of the container.
+    /// * `deadline` - The deadline of the request, see `request_deadline`.
+    /// # Returns
+    /// * `Result<OperationGuard, Status>` -
+    /// Returns a guard that finishes the operation when it is dropped
+    /// or `Status::deadline_exceeded` if the running operation did not finish before the deadline.
+    #[allow(clippy::result_large_err)]
+    async fn begin(
+        &self,
+        namespace: &str,
+        operation: &'static str,
+        deadline: Option<Instant>,
+    ) -> Result<OperationGuard, Status> {
+        self.operations
+            .begin(namespace, operation, deadline)
+            .await
+            .ok_or_else(|| Status::deadline_exceeded("Deadline exceeded"))
+    }
+
+    /// Starts an operation for a request that only knows the path of a container, e.g. `rekey`, like `begin`.
+    /// The operation is started for the namespace of the container (see `path_namespace`),
+    /// so it also waits for the operations that were started by namespace, e.g. `open` or `close`.
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `operation` - The name of the operation that is reported by the status of the container.
+    /// * `deadline` - The deadline of the request, see `request_deadline`.
+    /// # Returns
+    /// * `Result<OperationGuard, Status>` - See `begin`.
+    #[allow(clippy::result_large_err)]
+    async fn begin_for_path(
+        &self,
+        path: &str,
+        operation: &'static str,
+        deadline: Option<Instant>,
+    ) -> Result<OperationGuard, Status> {
+        self.begin(&path_namespace(path), operation, deadline).await
+    }
+}
+
+/// Returns the namespace the operations on a container are serialized with for a request that only knows its path.
+/// The namespace is taken from the autoOpen file, where the container is registered with the namespace it is opened
+/// with, otherwise from the metadata of the container.
+/// # Arguments
+/// * `path` - The path to the container.
+/// # Returns
+/// * `String` - The namespace of the container, or the path if it has no known namespace, e.g. a new container.
+fn path_namespace(path: &str) -> String {
+    if let Ok(containers) = auto_open_read() {
+        if let Some(container) = containers
+            .iter()
+            .find(|container| container.len() > 2 && Path::new(&container[1]) == Path::new(path))
+        {
+            return container[2].clone();
+        }
+    }
+    match read_metadata(path) {
+        Ok(Some(metadata)) => metadata.namespace,
+        _ => path.to_string(),
+    }
+}
+
+/// Runs the operation of a request within the deadline the client sent with the request.
+/// No external command of the operation is started after the deadline expired,
+/// only the running commands that read, e.g. lsblk, are killed (see the `deadline` module).
+/// # Arguments
+/// * `deadline` - The deadline of the request, see `request_deadline`.
+/// * `operation` - The operation of the request.
+/// # Returns
+/// * `Result<Result<T>, Status>` -
+/// Returns the result of the operation or `Status::deadline_exceeded` if the operation failed after the deadline expired.
+#[allow(clippy::result_large_err)]
+fn within_deadline<T>(
+    deadline: Option<Instant>,
+    operation: impl FnOnce() -> error_handling::Result<T>,
+) -> Result<error_handling::Result<T>, Status> {
+    let result = with_deadline(deadline, operation);
+    if result.is_err() && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        tracing::warn!("request exceeded its deadline");
+        return Err(Status::deadline_exceeded("Deadline exceeded"));
+    }
+    log_result(&result);
+    Ok(result)
+}
+
+/// Creates the span of a request, the events that are logged while handling the request carry its operation and namespace.
+/// The handler of a request runs in the span with `Instrument::instrument`,
+/// so the span is not held across an `.await` on a thread that runs other requests in the meantime.
+/// # Arguments
+/// * `operation` - The name of the operation of the request.
+/// * `namespace` - The name of the container, empty if the request is not for one container.
+/// # Returns
+/// * `Span` - The span of the request.
+fn request_span(operation: &'static str, namespace: &str) -> Span {
+    let span = tracing::info_span!("request", operation, namespace);
+    span.in_scope(|| tracing::info!("request started"));
+    span
+}
+
+/// Logs the end of a request, an error is logged with its variant.
+/// # Arguments
+/// * `result` - The result of the operation of the request.
+fn log_result<T>(result: &error_handling::Result<T>) {
+    match result {
+        Ok(_) => tracing::info!("request finished"),
+        Err(err) => tracing::warn!(error = ?err, "request failed"),
+    }
+}
+
+/// Returns a field of a request if it is set.
+/// # Arguments
+/// * `value` - The value of the field.
+/// # Returns
+/// * `Option<&str>` - The value or `None` if it is empty.
+fn given(value: &str) -> Option<&str> {
+    Some(value).filter(|value| !value.is_empty())
+}
+
+/// Returns the fields that every response reports the result of its operation with.
+/// # Arguments
+/// * `error` - The error of the operation, `None` if it succeeded.
+/// # Returns
+/// * `(bool, String, i32)` - The status, the error message and the exit code of the error (0 for OK).
+fn error_status(error: Option<&SecureContainerErr>) -> (bool, String, i32) {
+    let error = error.unwrap_or(&SecureContainerErr::OK);
+    (
+        *error == SecureContainerErr::OK,
+        error.to_string(),
+        error.exit_code(),
+    )
+}
+
+/// Returns the status, the error message and the exit code of the result of an operation.
+/// # Arguments
+/// * `result` - The result of the operation.
+/// # Returns
+/// * `(bool, String, i32)` - The status, the error message and the exit code of the error (0 for OK).
+fn response_status<T>(result: &error_handling::Result<T>) -> (bool, String, i32) {
+    error_status(result.as_ref().err())
+}
+
+/// Creates the response of a request that only reports the result of its operation.
+/// # Arguments
+/// * `result` - The result of the operation.
+/// # Returns
+/// * `SecureContainerResponse` - The response with the status, the error message and the exit code.
+fn secure_container_response<T>(result: error_handling::Result<T>) -> SecureContainerResponse {
+    let (status, error, code) = response_status(&result);
+    SecureContainerResponse {
+        status,
+        error,
+        code,
+    }
+}
+
+/// Creates the response of a request that creates a container.
+/// # Arguments
+/// * `result` - The result of creating the container.
+/// # Returns
+/// * `CreateContainerResponse` - The response with the details of the container if it was created.
+fn create_container_response(
+    result: error_handling::Result<CreatedContainer>,
+) -> CreateContainerResponse {
+    let (status, error, code) = response_status(&result);
+    let container = result
+        .ok()
+        .map(|created| secure_container_service::CreatedContainer {
+            namespace: created.namespace,
+            mapper_device: created.mapper_device,
+            mount_point: created.mount_point,
+            usable_bytes: created.usable_bytes,
+            uuid: created.uuid,
+        });
+    CreateContainerResponse {
+        status,
+        error,
+        code,
+        container,
+    }
+}
+
+/// The phase of the last message of `create_container_stream` if the container was not created.
+const PHASE_FAILED: &str = "failed";
+
+/// Returns the file system of a request that creates a container.
+/// # Arguments
+/// * `request` - The request.
+/// # Returns
+/// * `&str` - The requested file system or `DEFAULT_FILE_SYSTEM` if none is requested.
+fn requested_file_system(request: &CreateContainerRequest) -> &str {
+    if request.fs_type.is_empty() {
+        DEFAULT_FILE_SYSTEM
+    } else {
+        request.fs_type.as_str()
+    }
+}
+
+/// Creates the container of a unary request, without file system if `format_only` is set.
+/// # Arguments
+/// * `request` - The request.
+/// # Returns
+/// * `error_handling::Result<CreatedContainer>` - The result of creating the container.
+fn create_unary(request: &CreateContainerRequest) -> error_handling::Result<CreatedContainer> {
+    if request.format_only {
+        return create_raw_container(
+            request.size,
+            request.path.as_str(),
+            request.namespace.as_str(),
+            request.id.as_str(),
+        );
+    }
+    create_container(
+        request.size,
+        request.mount_point.as_str(),
+        request.path.as_str(),
+        request.namespace.as_str(),
+        request.id.as_str(),
+        request.auto_open,
+        requested_file_system(request),
+    )
+}
+
+/// Creates the container of a streamed request like `create_unary` and reports the progress.
+/// # Arguments
+/// * `request` - The request.
+/// * `progress` - Called at the start of every phase of the creation.
+/// # Returns
+/// * `error_handling::Result<CreatedContainer>` - The result of creating the container.
+fn create_from_request(
+    request: &CreateContainerRequest,
+    progress: &dyn Fn(CreateProgress),
+) -> error_handling::Result<CreatedContainer> {
+    if request.format_only {
+        return create_raw_container_with_progress(
+            request.size,
+            request.path.as_str(),
+            request.namespace.as_str(),
+            request.id.as_str(),
+            progress,
+        );
+    }
+    create_container_with_progress(
+        request.size,
+        request.mount_point.as_str(),
+        request.path.as_str(),
+        request.namespace.as_str(),
+        request.id.as_str(),
+        request.auto_open,
+        requested_file_system(request),
+        progress,
+    )
+}
+
+/// Selects the credential for exporting or importing a container.
+/// # Arguments
+/// * `secret` - The secret of the request.
+/// * `keyfile` - The path to the keyfile of the request, it is used if it is not empty.
+/// # Returns
+/// * `TransferCredential` - The keyfile if one was given, otherwise the secret.
+fn transfer_credential(secret: String, keyfile: String) -> TransferCredential {
+    if keyfile.is_empty() {
+        TransferCredential::Secret(secret)
+    } else {
+        TransferCredential::Keyfile(keyfile.into())
+    }
+}
 
 /// Implementation of the Container trait for the MySecureContainer struct.
 /// This implementation allows the daemon to handle the client requests and return the right responses.
@@ -59,191 +414,1051 @@ impl Container for MySecureContainer {
     async fn create_container(
         &self,
         request: Request<CreateContainerRequest>,
-    ) -> Result<Response<SecureContainerResponse>, Status> {
+    ) -> Result<Response<CreateContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
         let request = request.into_inner();
+        let span = request_span("create", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "create", deadline).await?;
 
-        let result = create_container(
-            request.size,
-            request.mount_point.as_str(),
-            request.path.as_str(),
-            request.namespace.as_str(),
-            request.id.as_str(),
-            request.auto_open,
-        );
-        let binding = result.err().unwrap_or(SecureContainerErr::OK).to_string();
-        let err = binding.as_str();
-        let mut status = false;
-        if err == "OK" {
-            status = true;
+            let result = within_deadline(deadline, || create_unary(&request))?;
+            Ok(Response::new(create_container_response(result)))
         }
-        let response = secure_container_service::SecureContainerResponse {
-            status,
-            error: err.into(),
-        };
+        .instrument(span)
+        .await
+    }
+    type CreateContainerStreamStream = ReceiverStream<Result<CreateContainerProgress, Status>>;
+    async fn create_container_stream(
+        &self,
+        request: Request<CreateContainerRequest>,
+    ) -> Result<Response<Self::CreateContainerStreamStream>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("create", &request.namespace);
+        // The operation is started before the blocking thread, so waiting for it does not occupy the thread.
+        let operation = self
+            .begin(&request.namespace, "create", deadline)
+            .instrument(span.clone())
+            .await?;
+        let (sender, receiver) = mpsc::channel(4);
 
-        Ok(Response::new(response))
+        // The container is created on a blocking thread, so the progress is sent while it is running.
+        tokio::task::spawn_blocking(move || {
+            let _span = span.entered();
+            let _operation = operation;
+            let percent = Cell::new(0);
+            let progress = |progress: CreateProgress| {
+                percent.set(progress.percent);
+                // The last message is sent with the result below.
+                if progress.phase != PHASE_DONE {
+                    // A client that is gone does not stop the creation.
+                    let _ = sender.blocking_send(Ok(CreateContainerProgress {
+                        phase: progress.phase.to_string(),
+                        percent: progress.percent,
+                        result: None,
+                    }));
+                }
+            };
+            let last = within_deadline(deadline, || create_from_request(&request, &progress)).map(
+                |result| {
+                    let phase = if result.is_ok() {
+                        PHASE_DONE
+                    } else {
+                        PHASE_FAILED
+                    };
+                    CreateContainerProgress {
+                        phase: phase.to_string(),
+                        percent: percent.get(),
+                        result: Some(create_container_response(result)),
+                    }
+                },
+            );
+            let _ = sender.blocking_send(last);
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
     }
     async fn open_container(
         &self,
         request: Request<OpenContainerRequest>,
     ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
         let request = request.into_inner();
+        let span = request_span("open", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "open", deadline).await?;
 
-        let result = open_container(
-            request.mount_point.as_str(),
-            request.path.as_str(),
-            request.namespace.as_str(),
-            request.id.as_str(),
-        );
-        let binding = result.err().unwrap_or(SecureContainerErr::OK).to_string();
-        let err = binding.as_str();
-        let mut status = false;
-        if err == "OK" {
-            status = true;
-        }
-        let response = secure_container_service::SecureContainerResponse {
-            status,
-            error: err.into(),
-        };
+            let mount_options: Vec<&str> =
+                request.mount_options.iter().map(String::as_str).collect();
+            let key_slot = request.key_slot.map(check_key_slot).transpose();
+            let result = within_deadline(deadline, || {
+                open_container_with_key_slot(
+                    request.mount_point.as_str(),
+                    request.path.as_str(),
+                    request.namespace.as_str(),
+                    request.id.as_str(),
+                    request.keyring,
+                    &mount_options,
+                    request.read_only,
+                    key_slot?,
+                )
+            })?;
+            let response = secure_container_response(result);
 
-        Ok(Response::new(response))
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
     }
     async fn close_container(
         &self,
         request: Request<secure_container_service::CloseContainerRequest>,
     ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
         let request = request.into_inner();
+        let span = request_span("close", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "close", deadline).await?;
 
-        let result = close_container(request.mount_point.as_str(), request.namespace.as_str());
-        let binding = result.err().unwrap_or(SecureContainerErr::OK).to_string();
-        let err = binding.as_str();
-        let mut status = false;
-        if err == "OK" {
-            status = true;
-        }
-        let response = secure_container_service::SecureContainerResponse {
-            status,
-            error: err.into(),
-        };
+            let result = within_deadline(deadline, || {
+                if request.if_open {
+                    ensure_closed(request.mount_point.as_str(), request.namespace.as_str())
+                } else {
+                    close_container(request.mount_point.as_str(), request.namespace.as_str())
+                }
+            })?;
+            let response = secure_container_response(result);
 
-        Ok(Response::new(response))
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
     }
     async fn export_container(
         &self,
         request: Request<secure_container_service::ExportContainerRequest>,
     ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
         let request = request.into_inner();
+        let span = request_span("export", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "export", deadline).await?;
 
-        let result = export_container(
-            request.path.as_str(),
-            request.namespace.as_str(),
-            request.id.as_str(),
-            request.secret.as_str(),
-        );
-        let binding = result.err().unwrap_or(SecureContainerErr::OK).to_string();
-        let err = binding.as_str();
-        let mut status = false;
-        if err == "OK" {
-            status = true;
-        }
-        let response = secure_container_service::SecureContainerResponse {
-            status,
-            error: err.into(),
-        };
+            let credential = transfer_credential(request.secret, request.keyfile);
+            let result = within_deadline(deadline, || {
+                export_container(
+                    request.path.as_str(),
+                    request.namespace.as_str(),
+                    request.id.as_str(),
+                    &credential,
+                )
+            })?;
+            let response = secure_container_response(result);
 
-        Ok(Response::new(response))
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
     }
     async fn import_container(
         &self,
         request: Request<secure_container_service::ImportContainerRequest>,
     ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
         let request = request.into_inner();
+        let span = request_span("import", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "import", deadline).await?;
 
-        let result = import_container(
-            request.path.as_str(),
-            request.namespace.as_str(),
-            request.id.as_str(),
-            request.secret.as_str(),
-        );
-        let binding = result.err().unwrap_or(SecureContainerErr::OK).to_string();
-        let err = binding.as_str();
-        let mut status = false;
-        if err == "OK" {
-            status = true;
-        }
-        let response = secure_container_service::SecureContainerResponse {
-            status,
-            error: err.into(),
-        };
+            let credential = transfer_credential(request.secret, request.keyfile);
+            let result = within_deadline(deadline, || {
+                import_container(
+                    request.path.as_str(),
+                    request.namespace.as_str(),
+                    request.id.as_str(),
+                    &credential,
+                )
+            })?;
+            let response = secure_container_response(result);
 
-        Ok(Response::new(response))
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
     }
     async fn add_to_auto_open(
         &self,
         request: Request<secure_container_service::AddToAutoOpenRequest>,
     ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
         let request = request.into_inner();
+        let span = request_span("add-auto-open", &request.namespace);
+        async {
+            let _operation = self
+                .begin(&request.namespace, "add-auto-open", deadline)
+                .await?;
 
-        let result = add_to_auto_open(
-            request.mount_point.as_str(),
-            request.path.as_str(),
-            request.namespace.as_str(),
-            request.id.as_str(),
-        );
-        let binding = result.err().unwrap_or(SecureContainerErr::OK).to_string();
-        let err = binding.as_str();
-        let mut status = false;
-        if err == "OK" {
-            status = true;
-        }
-        let response = secure_container_service::SecureContainerResponse {
-            status,
-            error: err.into(),
-        };
+            let result = within_deadline(deadline, || {
+                add_to_auto_open(
+                    request.mount_point.as_str(),
+                    request.path.as_str(),
+                    request.namespace.as_str(),
+                    request.id.as_str(),
+                    request.wait_for_path,
+                )
+            })?;
+            let response = secure_container_response(result);
 
-        Ok(Response::new(response))
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
     }
 
     async fn remove_from_auto_open(
         &self,
         request: Request<secure_container_service::RemoveFromAutoOpenRequest>,
     ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
         let request = request.into_inner();
+        let span = request_span("remove-auto-open", &request.namespace);
+        async {
+            let _operation = self
+                .begin(&request.namespace, "remove-auto-open", deadline)
+                .await?;
 
-        let result = remove_auto_open(
-            request.mount_point.as_str(),
-            request.path.as_str(),
-            request.namespace.as_str(),
-            request.id.as_str(),
-        );
-        let binding = result.err().unwrap_or(SecureContainerErr::OK).to_string();
-        let err = binding.as_str();
-        let mut status = false;
-        if err == "OK" {
-            status = true;
+            let result = within_deadline(deadline, || {
+                remove_auto_open(
+                    request.mount_point.as_str(),
+                    request.path.as_str(),
+                    request.namespace.as_str(),
+                    request.id.as_str(),
+                )
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn doctor(
+        &self,
+        _request: Request<DoctorRequest>,
+    ) -> Result<Response<DoctorResponse>, Status> {
+        let span = request_span("doctor", "");
+        async {
+            let report = check_dependencies();
+            let result = if report
+                .iter()
+                .all(|check| check.available || !check.essential)
+            {
+                Ok(())
+            } else {
+                Err(SecureContainerErr::DependencyMissing)
+            };
+            log_result(&result);
+            let (status, error, code) = response_status(&result);
+            let checks = report
+                .into_iter()
+                .map(|check| secure_container_service::DependencyCheck {
+                    name: check.name,
+                    essential: check.essential,
+                    available: check.available,
+                    details: check.details,
+                })
+                .collect();
+            let response = DoctorResponse {
+                status,
+                error,
+                code,
+                checks,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn list_containers(
+        &self,
+        request: Request<ListContainersRequest>,
+    ) -> Result<Response<ListContainersResponse>, Status> {
+        let request = request.into_inner();
+        let span = request_span("list", &request.namespace);
+        async {
+            let namespace =
+                Some(request.namespace.as_str()).filter(|namespace| !namespace.is_empty());
+            let result = container_status(namespace, request.all);
+            log_result(&result);
+            let (status, error, code) = response_status(&result);
+            let containers = result
+                .map(|containers| {
+                    containers
+                        .into_iter()
+                        .map(|container| secure_container_service::ContainerStatus {
+                            busy: self.operations.busy(&container.namespace),
+                            namespace: container.namespace,
+                            mount_point: container.mount_point,
+                            path: container.path,
+                            open: container.open,
+                            mounted: container.mounted,
+                            source: container.source.to_string(),
+                            used_keyslots: container.used_keyslots,
+                            free_keyslots: container.free_keyslots,
+                            cipher: container.cipher,
+                            integrity: container.integrity,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let response = ListContainersResponse {
+                status,
+                error,
+                code,
+                containers,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn export_bundle(
+        &self,
+        request: Request<secure_container_service::ExportBundleRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("export-bundle", &request.namespace);
+        async {
+            let _operation = self
+                .begin(&request.namespace, "export-bundle", deadline)
+                .await?;
+
+            let credential = transfer_credential(request.secret, request.keyfile);
+            let result = within_deadline(deadline, || {
+                export_bundle(
+                    request.path.as_str(),
+                    request.namespace.as_str(),
+                    request.id.as_str(),
+                    &credential,
+                    request.bundle_path.as_str(),
+                )
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn import_bundle(
+        &self,
+        request: Request<secure_container_service::ImportBundleRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let span = request_span("import-bundle", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self
+                .begin_for_path(&request.path, "import-bundle", deadline)
+                .await?;
+
+            let credential = transfer_credential(request.secret, request.keyfile);
+            let result = within_deadline(deadline, || {
+                import_bundle(
+                    request.bundle_path.as_str(),
+                    request.path.as_str(),
+                    request.id.as_str(),
+                    &credential,
+                )
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn rotate_keys(
+        &self,
+        request: Request<RotateKeysRequest>,
+    ) -> Result<Response<RotateKeysResponse>, Status> {
+        let span = request_span("rotate-keys", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+
+            let result = with_deadline(deadline, || rotate_keys(request.new_id.as_str()));
+            log_result(&result);
+            let (status, error, code) = match &result {
+                Ok(results) => error_status(
+                    results
+                        .iter()
+                        .find_map(|container| container.result.as_ref().err()),
+                ),
+                Err(err) => error_status(Some(err)),
+            };
+            let containers = result
+                .map(|results| {
+                    results
+                        .into_iter()
+                        .map(|container| {
+                            let (status, error, code) = response_status(&container.result);
+                            secure_container_service::RotatedContainer {
+                                namespace: container.namespace,
+                                status,
+                                error,
+                                code,
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let response = RotateKeysResponse {
+                status,
+                error,
+                code,
+                containers,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn verify_integrity(
+        &self,
+        request: Request<VerifyIntegrityRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("verify", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "verify", deadline).await?;
+
+            let since = Some(request.since.as_str()).filter(|since| !since.is_empty());
+            let result = within_deadline(deadline, || {
+                verify_integrity(request.namespace.as_str(), since)
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn validate_input(
+        &self,
+        request: Request<ValidateInputRequest>,
+    ) -> Result<Response<ValidateInputResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("validate", &request.namespace);
+        async {
+            let result = within_deadline(deadline, || {
+                check_input(
+                    Some(request.size).filter(|size| *size != 0),
+                    given(&request.mount_point),
+                    given(&request.path),
+                    given(&request.namespace),
+                    given(&request.id),
+                )
+            })?;
+            let (status, error, code) = response_status(&result);
+            let response = ValidateInputResponse {
+                status,
+                error_kind: error_handling::error_kind(&error).to_string(),
+                field: result
+                    .err()
+                    .and_then(|err| err.field())
+                    .unwrap_or_default()
+                    .to_string(),
+                error,
+                code,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn bind_mount(
+        &self,
+        request: Request<BindMountRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("bind-mount", &request.namespace);
+        async {
+            let _operation = self
+                .begin(&request.namespace, "bind-mount", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                bind_mount(
+                    request.namespace.as_str(),
+                    request.source.as_str(),
+                    request.target.as_str(),
+                    request.read_only,
+                )
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
         }
-        let response = secure_container_service::SecureContainerResponse {
-            status,
-            error: err.into(),
-        };
+        .instrument(span)
+        .await
+    }
+    async fn create_swap_container(
+        &self,
+        request: Request<CreateSwapContainerRequest>,
+    ) -> Result<Response<CreateContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("create-swap", &request.namespace);
+        async {
+            let _operation = self
+                .begin(&request.namespace, "create-swap", deadline)
+                .await?;
 
-        Ok(Response::new(response))
+            let result = within_deadline(deadline, || {
+                create_swap_container(request.size, request.namespace.as_str())
+            })?;
+            Ok(Response::new(create_container_response(result)))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn test_open(
+        &self,
+        request: Request<TestOpenRequest>,
+    ) -> Result<Response<TestOpenResponse>, Status> {
+        let span = request_span("test-open", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self
+                .begin_for_path(&request.path, "test-open", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                test_open(request.path.as_str(), request.id.as_str())
+            })?;
+            let (status, error, code) = response_status(&result);
+            let response = TestOpenResponse {
+                status,
+                error,
+                code,
+                opens: result.unwrap_or_default(),
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn rebuild_auto_open(
+        &self,
+        request: Request<RebuildAutoOpenRequest>,
+    ) -> Result<Response<RebuildAutoOpenResponse>, Status> {
+        let span = request_span("rebuild-auto-open", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+
+            let result = within_deadline(deadline, || {
+                rebuild_auto_open(&request.ids, given(&request.default_id))
+            })?;
+            let (status, error, code) = match &result {
+                Ok(results) => error_status(
+                    results
+                        .iter()
+                        .find_map(|container| container.result.as_ref().err()),
+                ),
+                Err(err) => error_status(Some(err)),
+            };
+            let containers = result
+                .map(|results| {
+                    results
+                        .into_iter()
+                        .map(|container| {
+                            let (status, error, code) = response_status(&container.result);
+                            secure_container_service::RebuiltContainer {
+                                namespace: container.namespace,
+                                mount_point: container.mount_point,
+                                path: container.path,
+                                status,
+                                error,
+                                code,
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let response = RebuildAutoOpenResponse {
+                status,
+                error,
+                code,
+                containers,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn update_auto_open(
+        &self,
+        request: Request<UpdateAutoOpenRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("update-auto-open", &request.namespace);
+        async {
+            let _operation = self
+                .begin(&request.namespace, "update-auto-open", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                update_auto_open(request.namespace.as_str(), request.mount_point.as_str())
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn close_by_namespace(
+        &self,
+        request: Request<CloseByNamespaceRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("close", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "close", deadline).await?;
+
+            let result =
+                within_deadline(deadline, || close_by_namespace(request.namespace.as_str()))?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn scan_directory(
+        &self,
+        request: Request<ScanDirectoryRequest>,
+    ) -> Result<Response<ScanDirectoryResponse>, Status> {
+        let span = request_span("scan", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+
+            let result = within_deadline(deadline, || scan_directory(request.directory.as_str()))?;
+            let (status, error, code) = response_status(&result);
+            let containers = result
+                .map(|containers| {
+                    containers
+                        .into_iter()
+                        .map(|container| secure_container_service::ScannedContainer {
+                            path: container.path,
+                            uuid: container.uuid,
+                            version: container.version as u32,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let response = ScanDirectoryResponse {
+                status,
+                error,
+                code,
+                containers,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn diff_state(
+        &self,
+        request: Request<DiffStateRequest>,
+    ) -> Result<Response<DiffStateResponse>, Status> {
+        let span = request_span("diff-state", "");
+        async {
+            let deadline = request_deadline(&request);
+
+            let result = within_deadline(deadline, diff_state)?;
+            let (status, error, code) = response_status(&result);
+            let diff = result.unwrap_or_default();
+            let response = DiffStateResponse {
+                status,
+                error,
+                code,
+                open: diff.open,
+                closed: diff.closed,
+                missing: diff.missing,
+                unregistered: diff.unregistered,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn open_unmounted(
+        &self,
+        request: Request<OpenUnmountedRequest>,
+    ) -> Result<Response<OpenUnmountedResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("open-unmounted", &request.namespace);
+        async {
+            let _operation = self
+                .begin(&request.namespace, "open-unmounted", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                open_unmounted(
+                    request.path.as_str(),
+                    request.namespace.as_str(),
+                    request.id.as_str(),
+                )
+            })?;
+            let (status, error, code) = response_status(&result);
+            let response = OpenUnmountedResponse {
+                status,
+                error,
+                code,
+                mapper_device: result.unwrap_or_default(),
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn mount_open(
+        &self,
+        request: Request<MountOpenRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("mount", &request.namespace);
+        async {
+            let _operation = self.begin(&request.namespace, "mount", deadline).await?;
+
+            let result = within_deadline(deadline, || {
+                mount_open(request.mount_point.as_str(), request.namespace.as_str())
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn inventory(
+        &self,
+        request: Request<InventoryRequest>,
+    ) -> Result<Response<InventoryResponse>, Status> {
+        let span = request_span("inventory", "");
+        async {
+            let deadline = request_deadline(&request);
+
+            let result = within_deadline(deadline, inventory)?;
+            let (status, error, code) = response_status(&result);
+            let containers = result
+                .map(|items| {
+                    items
+                        .into_iter()
+                        .map(|item| secure_container_service::ContainerInventoryItem {
+                            namespace: item.namespace,
+                            path: item.path,
+                            mount_point: item.mount_point,
+                            uuid: item.uuid,
+                            cipher: item.cipher,
+                            integrity: item.integrity,
+                            created: item.created,
+                            open: item.open,
+                            mounted: item.mounted,
+                            source: item.source.to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let response = InventoryResponse {
+                status,
+                error,
+                code,
+                containers,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn status_container(
+        &self,
+        request: Request<StatusContainerRequest>,
+    ) -> Result<Response<StatusContainerResponse>, Status> {
+        let deadline = request_deadline(&request);
+        let request = request.into_inner();
+        let span = request_span("status", &request.namespace);
+        async {
+            // The state is only read, so it does not wait for an operation on the container.
+            let result = within_deadline(deadline, || container_state(request.namespace.as_str()))?;
+            let (status, error, code) = response_status(&result);
+            let state = result
+                .ok()
+                .map(|state| secure_container_service::ContainerState {
+                    open: state.open,
+                    mounted: state.mounted,
+                    mount_point: state.mount_point,
+                });
+            let response = StatusContainerResponse {
+                status,
+                error,
+                code,
+                state,
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn backup_header(
+        &self,
+        request: Request<BackupHeaderRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let span = request_span("backup-header", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self
+                .begin_for_path(&request.path, "backup-header", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                backup_header(request.path.as_str(), request.out_file.as_str())
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn restore_header(
+        &self,
+        request: Request<RestoreHeaderRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let span = request_span("restore-header", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self
+                .begin_for_path(&request.path, "restore-header", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                restore_header(request.path.as_str(), request.in_file.as_str())
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn container_info(
+        &self,
+        request: Request<ContainerInfoRequest>,
+    ) -> Result<Response<ContainerInfoResponse>, Status> {
+        let span = request_span("info", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self.begin_for_path(&request.path, "info", deadline).await?;
+
+            let result = within_deadline(deadline, || container_info(request.path.as_str()))?;
+            let (status, error, code) = response_status(&result);
+            let response = ContainerInfoResponse {
+                status,
+                error,
+                code,
+                info: result.unwrap_or_default(),
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn rekey_container(
+        &self,
+        request: Request<RekeyContainerRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let span = request_span("rekey", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self
+                .begin_for_path(&request.path, "rekey", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                rekey_container(
+                    request.path.as_str(),
+                    request.old_id.as_str(),
+                    request.new_id.as_str(),
+                )
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn add_key_slot(
+        &self,
+        request: Request<AddKeySlotRequest>,
+    ) -> Result<Response<AddKeySlotResponse>, Status> {
+        let span = request_span("add-key-slot", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self
+                .begin_for_path(&request.path, "add-key-slot", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                add_key_slot(
+                    request.path.as_str(),
+                    request.id.as_str(),
+                    request.secret.as_str(),
+                )
+            })?;
+            let (status, error, code) = response_status(&result);
+            let response = AddKeySlotResponse {
+                status,
+                error,
+                code,
+                key_slot: result.map(u32::from).unwrap_or_default(),
+            };
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
+    }
+    async fn remove_key_slot(
+        &self,
+        request: Request<RemoveKeySlotRequest>,
+    ) -> Result<Response<SecureContainerResponse>, Status> {
+        let span = request_span("remove-key-slot", "");
+        async {
+            let deadline = request_deadline(&request);
+            let request = request.into_inner();
+            let _operation = self
+                .begin_for_path(&request.path, "remove-key-slot", deadline)
+                .await?;
+
+            let result = within_deadline(deadline, || {
+                remove_key_slot(request.path.as_str(), request.secret.as_str())
+            })?;
+            let response = secure_container_response(result);
+
+            Ok(Response::new(response))
+        }
+        .instrument(span)
+        .await
     }
 }
 
 /// This is the main function of the daemon.
-/// It starts the daemon and listens to port 50051 for requests.
+/// It validates the configuration, starts the daemon and listens to the configured address for requests.
 /// It also handles the SIGINT and SIGTERM signals to initialize the graceful shutdown.
 /// # Return
 /// `Result<(), Box<dyn std::error::Error>>`: Returns an error if the daemon is not able to start.
 ///
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "[::1]:50051".parse().unwrap();
-    let secure_container = MySecureContainer::default();
+    let problems = config().validate();
+    if !problems.is_empty() {
+        println!("Invalid configuration:");
+        for problem in &problems {
+            println!("  {}", problem);
+        }
+        std::process::exit(1);
+    }
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(&config().log_filter))
+        .with_writer(std::io::stderr)
+        .init();
+    let addr: SocketAddr = config().listen_address.parse()?;
+    let secure_container = Arc::new(MySecureContainer::default());
+    for check in check_dependencies() {
+        match (check.available, check.essential) {
+            (true, _) => tracing::info!(dependency = %check.name, "{}", check),
+            (false, true) => tracing::error!(dependency = %check.name, "{}", check),
+            (false, false) => tracing::warn!(dependency = %check.name, "{}", check),
+        }
+    }
     match auto_open() {
-        Ok(_) => (),
-        Err(err) => println!("Error while Auto Open: {:?}", err),
+        Ok(results) => {
+            for container in &results {
+                match &container.result {
+                    Ok(_) => tracing::info!(namespace = %container.namespace, "Opened container"),
+                    Err(err) => tracing::warn!(
+                        namespace = %container.namespace,
+                        error = %err,
+                        "Error opening container"
+                    ),
+                }
+            }
+            let failed = results
+                .iter()
+                .filter(|container| container.result.is_err())
+                .count();
+            let opened = results.len() - failed;
+            if failed > 0 {
+                tracing::warn!(
+                    opened,
+                    failed,
+                    "Opened {} of {} containers",
+                    opened,
+                    results.len()
+                );
+            } else {
+                tracing::info!(opened, "Opened {} of {} containers", opened, results.len());
+            }
+            let timed_out: Vec<&str> = results
+                .iter()
+                .filter(|container| container.result == Err(SecureContainerErr::PathWaitTimeout))
+                .map(|container| container.namespace.as_str())
+                .collect();
+            if !timed_out.is_empty() {
+                tracing::warn!(
+                    "Timed out waiting for the path of {} containers: {}",
+                    timed_out.len(),
+                    timed_out.join(", ")
+                );
+            }
+        }
+        Err(err) => tracing::error!(error = ?err, "Error while Auto Open"),
     };
 
     //Channel to signal shutdown
@@ -257,30 +1472,248 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     })
     .expect("Error setting Ctrl-C handler");
 
-    match Server::builder()
-        .add_service(ContainerServer::new(secure_container))
-        .serve(addr)
-        .await
-    {
+    #[cfg(feature = "rest")]
+    if config().rest_enabled() {
+        let rest_container = secure_container.clone();
+        tokio::spawn(async move {
+            match rest_gateway::serve(rest_container).await {
+                Ok(_) => (),
+                Err(err) => tracing::error!(error = ?err, "Error in REST gateway"),
+            };
+        });
+    }
+
+    let mut server = Server::builder();
+    if let Some(tls) = server_tls_config()? {
+        server = server.tls_config(tls)?;
+    }
+    let server = server.add_service(ContainerServer::from_arc(secure_container));
+    // The listener is bound before the server runs, so systemd is told that the daemon is ready once it accepts connections.
+    let served = if config().socket_path.is_empty() {
+        let listener = TcpListener::bind(addr).await?;
+        notify_ready();
+        server
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+    } else {
+        let listener = bind_socket(&config().socket_path)?;
+        notify_ready();
+        server
+            .serve_with_incoming(UnixListenerStream::new(listener))
+            .await
+    };
+    match served {
         Ok(_) => (),
-        Err(err) => println!("{:?}", err),
+        Err(err) => tracing::error!(error = ?err, "Error serving requests"),
     };
     Ok(())
 }
 
+/// Creates the TLS configuration of the gRPC server from the certificate, key and client CA of the configuration.
+/// # Return
+/// `std::io::Result<Option<ServerTlsConfig>>`:
+/// Returns `None` if no certificate is configured, so the server serves plaintext,
+/// or an error if a file can not be read.
+///
+fn server_tls_config() -> std::io::Result<Option<ServerTlsConfig>> {
+    if config().tls_cert_path.is_empty() {
+        return Ok(None);
+    }
+    let identity = Identity::from_pem(
+        std::fs::read(&config().tls_cert_path)?,
+        std::fs::read(&config().tls_key_path)?,
+    );
+    let mut tls = ServerTlsConfig::new().identity(identity);
+    if !config().tls_client_ca_path.is_empty() {
+        tls = tls.client_ca_root(Certificate::from_pem(std::fs::read(
+            &config().tls_client_ca_path,
+        )?));
+    }
+    Ok(Some(tls))
+}
+
+/// Tells systemd that the daemon is ready and starts pinging its watchdog if the service has one.
+fn notify_ready() {
+    systemd::ready();
+    if let Some(interval) = systemd::watchdog_interval() {
+        tokio::spawn(systemd::watchdog(interval));
+    }
+}
+
+/// Binds the Unix socket the daemon listens on, a socket that is left from a previous run is replaced.
+/// The permissions of the socket are set to 0600, so only root can connect to it.
+/// # Arguments
+/// * `path` - The path of the socket.
+/// # Return
+/// `std::io::Result<UnixListener>`: Returns an error if the socket can not be bound or its permissions can not be set.
+///
+fn bind_socket(path: &str) -> std::io::Result<UnixListener> {
+    if Path::new(path).exists() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
 /// This function is called when a SIGINT or SIGTERM signal is received.
-/// This function checks if a container was open by the autoOpen process and tries to close it.
-/// When the containers are closed successfully, the daemon exits with code 0.
+/// This function closes the containers that were opened by the autoOpen process.
+/// The result of every container and a summary are logged.
+/// When all containers are closed successfully, the daemon exits with code 0, otherwise with code 1.
 fn graceful_shutdown() {
-    let bind: &str;
-    unsafe {
-        bind = PATH_TO_AUTO_OPEN;
+    systemd::stopping();
+    let mut exit_code = 0;
+    let results = auto_close();
+    for container in &results {
+        match &container.result {
+            Ok(_) => tracing::info!(namespace = %container.namespace, "Closed container"),
+            Err(err) => tracing::warn!(
+                mount_point = %container.mount_point,
+                namespace = %container.namespace,
+                error = %err,
+                "Giving up closing container"
+            ),
+        }
+    }
+    let failed = results
+        .iter()
+        .filter(|container| container.result.is_err())
+        .count();
+    let closed = results.len() - failed;
+    if failed > 0 {
+        tracing::warn!(
+            closed,
+            failed,
+            "Closed {} of {} containers",
+            closed,
+            results.len()
+        );
+        exit_code = 1;
+    } else {
+        tracing::info!(closed, "Closed {} of {} containers", closed, results.len());
+    }
+    std::process::exit(exit_code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_response_status() {
+        assert_eq!(response_status(&Ok(())), (true, "OK".to_string(), 0));
+        let response = secure_container_response::<()>(Err(SecureContainerErr::NotInAutoOpen));
+        assert!(!response.status);
+        assert_eq!(response.error, "Container not in auto open");
+        assert_eq!(response.code, 42);
+        assert_eq!(
+            error_status(Some(&SecureContainerErr::LastKeyslot)),
+            (false, "Last keyslot".to_string(), 48)
+        );
+    }
+
+    /// Creates a request whose deadline expires shortly.
+    fn with_timeout<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.set_timeout(Duration::from_millis(50));
+        request
+    }
+
+    #[test]
+    fn test_path_namespace() {
+        // A container without a known namespace is serialized by its path.
+        assert_eq!(
+            path_namespace("/not/existing/container"),
+            "/not/existing/container"
+        );
     }
-    if check_if_file_exists(bind) {
-        match auto_close() {
-            Ok(_) => (),
-            Err(err) => println!("{:?}", err),
-        };
+
+    #[tokio::test]
+    async fn test_path_requests_wait_for_operation() {
+        let container = MySecureContainer::default();
+        let path = "/not/existing/container".to_string();
+        let guard = container
+            .operations
+            .begin(&path, "restore-header", None)
+            .await
+            .unwrap();
+        // Every request that only knows the path of a container waits for the operation on the path.
+        let results = [
+            container
+                .rekey_container(with_timeout(RekeyContainerRequest {
+                    path: path.clone(),
+                    ..Default::default()
+                }))
+                .await
+                .err(),
+            container
+                .add_key_slot(with_timeout(AddKeySlotRequest {
+                    path: path.clone(),
+                    ..Default::default()
+                }))
+                .await
+                .err(),
+            container
+                .remove_key_slot(with_timeout(RemoveKeySlotRequest {
+                    path: path.clone(),
+                    ..Default::default()
+                }))
+                .await
+                .err(),
+            container
+                .backup_header(with_timeout(BackupHeaderRequest {
+                    path: path.clone(),
+                    ..Default::default()
+                }))
+                .await
+                .err(),
+            container
+                .restore_header(with_timeout(RestoreHeaderRequest {
+                    path: path.clone(),
+                    ..Default::default()
+                }))
+                .await
+                .err(),
+            container
+                .test_open(with_timeout(TestOpenRequest {
+                    path: path.clone(),
+                    ..Default::default()
+                }))
+                .await
+                .err(),
+            container
+                .import_bundle(with_timeout(
+                    secure_container_service::ImportBundleRequest {
+                        path: path.clone(),
+                        ..Default::default()
+                    },
+                ))
+                .await
+                .err(),
+            container
+                .container_info(with_timeout(ContainerInfoRequest { path: path.clone() }))
+                .await
+                .err(),
+        ];
+        for result in results {
+            assert_eq!(
+                result.map(|status| status.code()),
+                Some(tonic::Code::DeadlineExceeded)
+            );
+        }
+        assert_eq!(
+            container.operations.busy(&path),
+            Some("restore-header".to_string())
+        );
+
+        drop(guard);
+        let response = container
+            .container_info(with_timeout(ContainerInfoRequest { path: path.clone() }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.status);
+        assert_eq!(container.operations.busy(&path), None);
     }
-    std::process::exit(0);
 }
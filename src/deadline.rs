@@ -0,0 +1,233 @@
+//! # Deadline
+//! This module bounds how long the external commands of a request may run.
+//! A gRPC client can send a deadline with its request (the `grpc-timeout` header).
+//! The daemon sets it for the thread that handles the request with `with_deadline`,
+//! no command is started with `output`, `output_or_kill` or by cryptsetup afterwards once the deadline expired.
+//! A command that is already running is only killed if it only reads, e.g. lsblk, with `output_or_kill`.
+//! A command that changes a container, e.g. luksFormat or mkfs, is never killed,
+//! because a header or file system that is written halfway would destroy the container.
+//! The operations of the `libcryptsetup` and `native-mount` features do not execute commands,
+//! so they are not interrupted.
+//!
+
+use std::cell::Cell;
+use std::io::{self, Read};
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The interval in which a running command is checked against the deadline.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+thread_local! {
+    /// The deadline of the request that is handled by the current thread.
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Parses the value of a `grpc-timeout` header.
+/// # Arguments
+/// * `value` - The value of the header, at most 8 digits followed by a unit (`H`, `M`, `S`, `m`, `u` or `n`).
+/// # Returns
+/// * `Option<Duration>` - The timeout or `None` if the value is not valid.
+/// # Example
+/// ```
+/// assert_eq!(parse_grpc_timeout("150m"), Some(Duration::from_millis(150)));
+/// ```
+///
+pub fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 || !value.is_ascii() {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+/// Returns the deadline of a gRPC request.
+/// # Arguments
+/// * `request` - The gRPC request.
+/// # Returns
+/// * `Option<Instant>` - The deadline or `None` if the client did not send a valid timeout.
+pub fn request_deadline<T>(request: &tonic::Request<T>) -> Option<Instant> {
+    let timeout = request.metadata().get("grpc-timeout")?.to_str().ok()?;
+    parse_grpc_timeout(timeout).map(|timeout| Instant::now() + timeout)
+}
+
+/// Runs an operation with the given deadline for all commands it executes.
+/// # Arguments
+/// * `deadline` - The deadline or `None` to run the commands without limit.
+/// * `operation` - The operation.
+/// # Returns
+/// * `T` - The result of the operation.
+pub fn with_deadline<T>(deadline: Option<Instant>, operation: impl FnOnce() -> T) -> T {
+    let previous = DEADLINE.with(|cell| cell.replace(deadline));
+    let result = operation();
+    DEADLINE.with(|cell| cell.set(previous));
+    result
+}
+
+/// Checks if the deadline of the current thread has expired.
+/// # Returns
+/// * `bool` - `true` if a deadline is set and has expired.
+pub fn expired() -> bool {
+    DEADLINE
+        .with(Cell::get)
+        .is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// The error that is returned when a command is aborted because of the deadline.
+fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "Deadline exceeded")
+}
+
+/// Executes a command like `Command::output` but does not start it after the deadline of the current thread.
+/// A command that was started runs until it is finished, so this is used for every command that changes something.
+/// # Arguments
+/// * `command` - The command.
+/// # Returns
+/// * `io::Result<Output>` - The output of the command.
+/// # Errors
+/// * `TimedOut` - The deadline expired before the command was started.
+/// * Every error of `Command::output`.
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    spawn(command)?.wait_with_output()
+}
+
+/// Executes a command like `output`, but the command is also killed when the deadline expires while it runs.
+/// This is only used for commands that only read, e.g. lsblk or dmesg.
+/// # Arguments
+/// * `command` - The command.
+/// # Returns
+/// * `io::Result<Output>` - The output of the command.
+/// # Errors
+/// * `TimedOut` - The deadline expired before or while the command was running.
+/// * Every error of `Command::output`.
+pub fn output_or_kill(command: &mut Command) -> io::Result<Output> {
+    wait_or_kill(spawn(command)?)
+}
+
+/// Spawns a command with its output piped, if the deadline of the current thread has not expired.
+/// # Note
+/// This function is not meant to be called directly.
+fn spawn(command: &mut Command) -> io::Result<Child> {
+    if expired() {
+        return Err(timed_out());
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+/// Waits for a spawned command like `Child::wait_with_output`, the command is killed when the deadline expires.
+/// # Note
+/// This function is not meant to be called directly.
+fn wait_or_kill(mut child: Child) -> io::Result<Output> {
+    let deadline = match DEADLINE.with(Cell::get) {
+        Some(deadline) => deadline,
+        None => return child.wait_with_output(),
+    };
+    // The pipes are drained while waiting, so a command with a lot of output does not block.
+    let stdout = child
+        .stdout
+        .take()
+        .map(|pipe| thread::spawn(|| read_all(pipe)));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| thread::spawn(|| read_all(pipe)));
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Err(timed_out());
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.map(join).unwrap_or_default(),
+        stderr: stderr.map(join).unwrap_or_default(),
+    })
+}
+
+/// Reads a pipe until it is closed.
+fn read_all(mut pipe: impl Read) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let _ = pipe.read_to_end(&mut buffer);
+    buffer
+}
+
+/// Returns the contents of a pipe that was read by `read_all`.
+fn join(reader: thread::JoinHandle<Vec<u8>>) -> Vec<u8> {
+    reader.join().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grpc_timeout() {
+        assert_eq!(parse_grpc_timeout("2H"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_grpc_timeout("3M"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_grpc_timeout("10S"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_grpc_timeout("150m"), Some(Duration::from_millis(150)));
+        assert_eq!(
+            parse_grpc_timeout("99999999u"),
+            Some(Duration::from_micros(99999999))
+        );
+        assert_eq!(parse_grpc_timeout("5n"), Some(Duration::from_nanos(5)));
+        assert_eq!(parse_grpc_timeout("123456789S"), None);
+        assert_eq!(parse_grpc_timeout("10x"), None);
+        assert_eq!(parse_grpc_timeout("S"), None);
+        assert_eq!(parse_grpc_timeout("-1S"), None);
+    }
+
+    #[test]
+    fn test_output_with_deadline() {
+        let echo = with_deadline(Some(Instant::now() + Duration::from_secs(10)), || {
+            output(Command::new("echo").arg("test"))
+        })
+        .unwrap();
+        assert!(echo.status.success());
+        assert_eq!(echo.stdout, b"test\n");
+
+        // A command that was started is not killed, it may change a container.
+        let result = with_deadline(Some(Instant::now() + Duration::from_millis(50)), || {
+            output(Command::new("sleep").arg("0.2"))
+        });
+        assert!(result.unwrap().status.success());
+
+        let result = with_deadline(Some(Instant::now()), || output(&mut Command::new("true")));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert!(!expired());
+    }
+
+    #[test]
+    fn test_output_or_kill_with_deadline() {
+        let start = Instant::now();
+        let result = with_deadline(Some(Instant::now() + Duration::from_millis(100)), || {
+            output_or_kill(Command::new("sleep").arg("10"))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let result = with_deadline(Some(Instant::now()), || {
+            output_or_kill(&mut Command::new("true"))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -1,14 +1,20 @@
 //! # Error handling
-//! This module contains all the error definitions and input validation for the whole project.
+//! This module contains all the error definitions for the whole project.
 //! Here is the custom error type `SecureContainerErr` and a custom result type `Result<E>` defined.
+//! It also contains the exit codes of the errors, which are used by the CLI.
+//! The module is part of the library, so the exit codes can be used by every client of the daemon.
 //!
-use crate::file_system_operations;
-use file_system_operations::{check_if_dir_exists, check_if_file_exists};
+use std::{fmt, string};
 
-use crate::cryptsetup_wrapper;
-use cryptsetup_wrapper::check_if_file_is_container;
+/// The exit code for an error that is not known.
+pub const EXIT_CODE_UNKNOWN: i32 = 28;
+
+/// The exit code if the client stopped waiting for the daemon, the message of this error is `Deadline exceeded`.
+pub const EXIT_CODE_DEADLINE_EXCEEDED: i32 = 34;
+
+/// The kind of `IsNotLuks` as it is spelled by older daemons, it is still restored as `IsNotLuks`.
+pub const LEGACY_IS_NOT_LUKS: &str = "Path is not a luks divice";
 
-use std::{fmt, string};
 /// The `Result<E>` type is used to return the custom error type from functions.
 pub type Result<E> = std::result::Result<E, SecureContainerErr>;
 /// The `SecureContainerErr`
@@ -42,12 +48,33 @@ pub enum SecureContainerErr {
     PathNotLuksContainer,
     PathNotValid,
     IsNotLuks(String),
+    DependencyMissing,
+    #[cfg_attr(not(feature = "native-mount"), allow(dead_code))]
+    MountBusy,
+    PathNotWritable,
+    MountedElsewhere,
+    NoFreeKeyslots,
+    BundleNotValid,
+    TimeNotValid,
+    ContainerNotOpen,
+    LibutaRandomError(String),
+    MountPointConflictsWithPath,
+    NotMountedAtMountPoint,
+    KeyringError(String),
+    NotInAutoOpen,
+    PathWaitTimeout,
+    UnsupportedFilesystem,
+    Base64Error(String),
+    MountOptionNotValid,
+    MountPointNotEmpty,
+    LastKeyslot,
+    KeySlotNotValid,
     OK,
 }
 /// Here the `Display` trait for the costem `SecureContainerErr` type is implemented.
 /// # Example
 /// ```
-/// use secure_container::error_handling::{SecureContainerErr};
+/// use secure_container_lib::error_handling::{SecureContainerErr};
 /// let err = SecureContainerErr::SizeToSmall;
 /// println!("{}", err);
 /// ```
@@ -84,220 +111,283 @@ impl fmt::Display for SecureContainerErr {
             SecureContainerErr::SecertError => write!(f, "Secret not valid"),
             SecureContainerErr::PathNotLuksContainer => write!(f, "Path is not a luks container"),
             SecureContainerErr::PathNotValid => write!(f, "Path not valid"),
-            SecureContainerErr::IsNotLuks(err) => write!(f, "Path is not a luks divice: {}", err),
+            SecureContainerErr::IsNotLuks(err) => write!(f, "Path is not a luks device: {}", err),
+            SecureContainerErr::DependencyMissing => write!(f, "Essential dependency missing"),
+            SecureContainerErr::MountBusy => write!(f, "Mount point busy"),
+            SecureContainerErr::PathNotWritable => write!(f, "Path not writable"),
+            SecureContainerErr::MountedElsewhere => write!(f, "Container mounted elsewhere"),
+            SecureContainerErr::NoFreeKeyslots => write!(f, "No free keyslots"),
+            SecureContainerErr::BundleNotValid => write!(f, "Bundle not valid"),
+            SecureContainerErr::TimeNotValid => write!(f, "Time not valid"),
+            SecureContainerErr::ContainerNotOpen => write!(f, "Container not open"),
+            SecureContainerErr::LibutaRandomError(err) => write!(f, "Libuta random error: {}", err),
+            SecureContainerErr::MountPointConflictsWithPath => {
+                write!(f, "Mount point conflicts with path")
+            }
+            SecureContainerErr::NotMountedAtMountPoint => {
+                write!(f, "Container not mounted at mount point")
+            }
+            SecureContainerErr::KeyringError(err) => write!(f, "Keyring error: {}", err),
+            SecureContainerErr::NotInAutoOpen => write!(f, "Container not in auto open"),
+            SecureContainerErr::PathWaitTimeout => write!(f, "Timed out waiting for path"),
+            SecureContainerErr::UnsupportedFilesystem => write!(f, "File system not supported"),
+            SecureContainerErr::Base64Error(err) => write!(f, "Base64 error: {}", err),
+            SecureContainerErr::MountOptionNotValid => write!(f, "Mount option not valid"),
+            SecureContainerErr::MountPointNotEmpty => write!(f, "Mount point not empty"),
+            SecureContainerErr::LastKeyslot => write!(f, "Last keyslot"),
+            SecureContainerErr::KeySlotNotValid => write!(f, "Key slot not valid"),
             SecureContainerErr::OK => write!(f, "OK"),
         }
     }
 }
 
-/// Checks the given input if they are valid and can be used further by different functions.
-/// # Arguments
-/// * `size` - The size of the container in MB (must be at least 16MB).
-/// * `mount_point` - The path to the mount point (must already exist).
-/// * `path` - The path to the container.
-/// * `namespace` - The name of the container.
-/// * `id` - The id of the container.
-/// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the provided inputs are valid otherwise an error is returned.
-/// # Errors
-/// * `SizeToSmall` - The given size for the container is too small.
-/// * `MountPointNotExists` - The given mount point does not exist.
-/// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
-/// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
-/// * `PathNotExists` - The given path does not exist.
-/// * `PathNotLuksContainer` - The given path is not a LUKS container.
-/// * `IsNotLuks` - The provided file is not a LUKS container.
-/// # Example
-/// ```
-/// use secure_container::error_handling::{check_input};
-/// let size = 12;
-/// let mount_point = "/home/MountMe";
-/// let path = "/home/Container";
-/// let namespace = "MyContainer";
-/// let id = "myId";
-/// let result = check_input(Some(size), Some(mount_point), Some(path), Some(namespace), Some(id));
-/// assert_eq!(result, Err(SecureContainerErr::SizeToSmall));
-/// ```
-///
-
-pub fn check_input(
-    size: Option<i32>,
-    mount_point: Option<&str>,
-    path: Option<&str>,
-    namespace: Option<&str>,
-    id: Option<&str>,
-) -> Result<()> {
-    if size.is_some() && size.unwrap() < 16 {
-        return Err(SecureContainerErr::SizeToSmall);
+impl SecureContainerErr {
+    /// Returns the exit code of the error.
+    /// # Returns
+    /// * `i32` - The exit code, `0` for `OK`.
+    /// # Example
+    /// ```
+    /// use secure_container_lib::error_handling::SecureContainerErr;
+    /// assert_eq!(SecureContainerErr::SizeToSmall.exit_code(), 1);
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SecureContainerErr::OK => 0,
+            SecureContainerErr::SizeToSmall => 1,
+            SecureContainerErr::MountPointNotExists => 2,
+            SecureContainerErr::PathNotExists => 3,
+            SecureContainerErr::NamespaceNotValid => 4,
+            SecureContainerErr::IdNotValid => 5,
+            SecureContainerErr::LsblkError(_) => 6,
+            SecureContainerErr::ReadingStdoutError(_) => 7,
+            SecureContainerErr::UmountError(_) => 8,
+            SecureContainerErr::MountError(_) => 9,
+            SecureContainerErr::MkfsError(_) => 10,
+            SecureContainerErr::LsError(_) => 11,
+            SecureContainerErr::CryptsetupError(_) => 12,
+            SecureContainerErr::StdinError(_) => 13,
+            SecureContainerErr::FileCreationError(_) => 14,
+            SecureContainerErr::FileWriteError(_) => 15,
+            SecureContainerErr::LibutaDeriveKeyError(_) => 16,
+            SecureContainerErr::FileReadError(_) => 17,
+            SecureContainerErr::FileOpenError(_) => 18,
+            SecureContainerErr::IntegrityError => 19,
+            SecureContainerErr::ContainerMounted => 20,
+            SecureContainerErr::ContainerOpen => 21,
+            SecureContainerErr::ContainerNameExists => 22,
+            SecureContainerErr::FileExists => 23,
+            SecureContainerErr::SecertError => 24,
+            SecureContainerErr::PathNotLuksContainer => 25,
+            SecureContainerErr::PathNotValid => 26,
+            SecureContainerErr::IsNotLuks(_) => 27,
+            SecureContainerErr::DependencyMissing => 29,
+            SecureContainerErr::MountBusy => 30,
+            SecureContainerErr::PathNotWritable => 31,
+            SecureContainerErr::MountedElsewhere => 32,
+            SecureContainerErr::NoFreeKeyslots => 33,
+            SecureContainerErr::BundleNotValid => 35,
+            SecureContainerErr::TimeNotValid => 36,
+            SecureContainerErr::ContainerNotOpen => 37,
+            SecureContainerErr::LibutaRandomError(_) => 38,
+            SecureContainerErr::MountPointConflictsWithPath => 39,
+            SecureContainerErr::NotMountedAtMountPoint => 40,
+            SecureContainerErr::KeyringError(_) => 41,
+            SecureContainerErr::NotInAutoOpen => 42,
+            SecureContainerErr::PathWaitTimeout => 43,
+            SecureContainerErr::UnsupportedFilesystem => 44,
+            SecureContainerErr::Base64Error(_) => 45,
+            SecureContainerErr::MountOptionNotValid => 46,
+            SecureContainerErr::MountPointNotEmpty => 47,
+            SecureContainerErr::LastKeyslot => 48,
+            SecureContainerErr::KeySlotNotValid => 49,
+        }
     }
 
-    if mount_point.is_some() && !check_if_dir_exists(mount_point.unwrap()) {
-        return Err(SecureContainerErr::MountPointNotExists);
+    /// Returns the input field that caused the error.
+    /// # Returns
+    /// * `Option<&'static str>` -
+    /// The name of the field as it is used in the requests of the daemon,
+    /// or `None` if the error is not caused by a single input.
+    /// # Example
+    /// ```
+    /// use secure_container_lib::error_handling::SecureContainerErr;
+    /// assert_eq!(SecureContainerErr::IdNotValid.field(), Some("id"));
+    /// ```
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            SecureContainerErr::SizeToSmall => Some("size"),
+            SecureContainerErr::MountPointNotExists => Some("mountPoint"),
+            SecureContainerErr::PathNotExists
+            | SecureContainerErr::PathNotValid
+            | SecureContainerErr::PathNotLuksContainer
+            | SecureContainerErr::IsNotLuks(_) => Some("path"),
+            SecureContainerErr::NamespaceNotValid => Some("namespace"),
+            SecureContainerErr::IdNotValid => Some("id"),
+            SecureContainerErr::SecertError => Some("secret"),
+            SecureContainerErr::TimeNotValid => Some("since"),
+            SecureContainerErr::UnsupportedFilesystem => Some("fsType"),
+            SecureContainerErr::MountOptionNotValid => Some("mountOptions"),
+            SecureContainerErr::MountPointNotEmpty => Some("mountPoint"),
+            SecureContainerErr::KeySlotNotValid => Some("keySlot"),
+            _ => None,
+        }
     }
 
-    if namespace.is_some() && (!namespace.unwrap().is_ascii() || namespace.unwrap().contains('|')) {
-        return Err(SecureContainerErr::NamespaceNotValid);
+    /// Restores the error from its message as it is returned by the daemon,
+    /// so the client can match on the variant instead of the message.
+    /// # Arguments
+    /// * `message` - The error message, e.g. `Cryptsetup error: No key available with this passphrase.`.
+    /// # Returns
+    /// * `Option<SecureContainerErr>` -
+    /// The error with the message of the daemon, or `None` if the kind of the message is not known.
+    /// The message of `ReadingStdoutError` can not be restored, it is replaced by a placeholder.
+    /// # Example
+    /// ```
+    /// use secure_container_lib::error_handling::SecureContainerErr;
+    /// let err = SecureContainerErr::from_daemon_string("Cryptsetup error: failed");
+    /// assert_eq!(err, Some(SecureContainerErr::CryptsetupError("failed".to_string())));
+    /// ```
+    pub fn from_daemon_string(message: &str) -> Option<SecureContainerErr> {
+        let kind = match error_kind(message) {
+            LEGACY_IS_NOT_LUKS => "Path is not a luks device",
+            kind => kind,
+        };
+        let detail = message
+            .split_once(':')
+            .map_or("", |(_, detail)| detail.strip_prefix(' ').unwrap_or(detail));
+        SecureContainerErr::all()
+            .into_iter()
+            .find(|err| error_kind(&err.to_string()) == kind)
+            .map(|err| err.with_detail(detail))
     }
 
-    if id.is_some()
-        && (id.unwrap().contains('|') || !id.unwrap().is_ascii() || id.unwrap().len() >= 8)
-    {
-        return Err(SecureContainerErr::IdNotValid);
+    /// Replaces the message of an error that carries one, other errors are returned unchanged.
+    /// # Note
+    /// This function is not meant to be called directly.
+    fn with_detail(self, detail: &str) -> SecureContainerErr {
+        let detail = detail.to_string();
+        match self {
+            SecureContainerErr::LsblkError(_) => SecureContainerErr::LsblkError(detail),
+            SecureContainerErr::UmountError(_) => SecureContainerErr::UmountError(detail),
+            SecureContainerErr::MountError(_) => SecureContainerErr::MountError(detail),
+            SecureContainerErr::MkfsError(_) => SecureContainerErr::MkfsError(detail),
+            SecureContainerErr::LsError(_) => SecureContainerErr::LsError(detail),
+            SecureContainerErr::CryptsetupError(_) => SecureContainerErr::CryptsetupError(detail),
+            SecureContainerErr::StdinError(_) => SecureContainerErr::StdinError(detail),
+            SecureContainerErr::FileCreationError(_) => {
+                SecureContainerErr::FileCreationError(detail)
+            }
+            SecureContainerErr::FileWriteError(_) => SecureContainerErr::FileWriteError(detail),
+            SecureContainerErr::LibutaDeriveKeyError(_) => {
+                SecureContainerErr::LibutaDeriveKeyError(detail)
+            }
+            SecureContainerErr::FileReadError(_) => SecureContainerErr::FileReadError(detail),
+            SecureContainerErr::FileOpenError(_) => SecureContainerErr::FileOpenError(detail),
+            SecureContainerErr::IsNotLuks(_) => SecureContainerErr::IsNotLuks(detail),
+            SecureContainerErr::LibutaRandomError(_) => {
+                SecureContainerErr::LibutaRandomError(detail)
+            }
+            SecureContainerErr::KeyringError(_) => SecureContainerErr::KeyringError(detail),
+            SecureContainerErr::Base64Error(_) => SecureContainerErr::Base64Error(detail),
+            other => other,
+        }
     }
 
-    if path.is_some() && (!path.unwrap().is_ascii() || path.unwrap().contains('|')) {
-        return Err(SecureContainerErr::PathNotValid);
+    /// Returns one error of every kind, the errors with a message have an empty one.
+    /// # Note
+    /// This function is not meant to be called directly.
+    fn all() -> Vec<SecureContainerErr> {
+        let not_utf8 = String::from_utf8(vec![0, 159]).unwrap_err();
+        vec![
+            SecureContainerErr::OK,
+            SecureContainerErr::SizeToSmall,
+            SecureContainerErr::MountPointNotExists,
+            SecureContainerErr::PathNotExists,
+            SecureContainerErr::NamespaceNotValid,
+            SecureContainerErr::IdNotValid,
+            SecureContainerErr::LsblkError(String::new()),
+            SecureContainerErr::ReadingStdoutError(not_utf8),
+            SecureContainerErr::UmountError(String::new()),
+            SecureContainerErr::MountError(String::new()),
+            SecureContainerErr::MkfsError(String::new()),
+            SecureContainerErr::LsError(String::new()),
+            SecureContainerErr::CryptsetupError(String::new()),
+            SecureContainerErr::StdinError(String::new()),
+            SecureContainerErr::FileCreationError(String::new()),
+            SecureContainerErr::FileWriteError(String::new()),
+            SecureContainerErr::LibutaDeriveKeyError(String::new()),
+            SecureContainerErr::FileReadError(String::new()),
+            SecureContainerErr::FileOpenError(String::new()),
+            SecureContainerErr::IntegrityError,
+            SecureContainerErr::ContainerMounted,
+            SecureContainerErr::ContainerOpen,
+            SecureContainerErr::ContainerNameExists,
+            SecureContainerErr::FileExists,
+            SecureContainerErr::SecertError,
+            SecureContainerErr::PathNotLuksContainer,
+            SecureContainerErr::PathNotValid,
+            SecureContainerErr::IsNotLuks(String::new()),
+            SecureContainerErr::DependencyMissing,
+            SecureContainerErr::MountBusy,
+            SecureContainerErr::PathNotWritable,
+            SecureContainerErr::MountedElsewhere,
+            SecureContainerErr::NoFreeKeyslots,
+            SecureContainerErr::BundleNotValid,
+            SecureContainerErr::TimeNotValid,
+            SecureContainerErr::ContainerNotOpen,
+            SecureContainerErr::LibutaRandomError(String::new()),
+            SecureContainerErr::MountPointConflictsWithPath,
+            SecureContainerErr::NotMountedAtMountPoint,
+            SecureContainerErr::KeyringError(String::new()),
+            SecureContainerErr::NotInAutoOpen,
+            SecureContainerErr::PathWaitTimeout,
+            SecureContainerErr::UnsupportedFilesystem,
+            SecureContainerErr::Base64Error(String::new()),
+            SecureContainerErr::MountOptionNotValid,
+            SecureContainerErr::MountPointNotEmpty,
+            SecureContainerErr::LastKeyslot,
+            SecureContainerErr::KeySlotNotValid,
+        ]
     }
+}
 
-    if path.is_some() && !check_if_file_exists(path.unwrap()) {
-        return Err(SecureContainerErr::PathNotExists);
-    }
-    if path.is_some() && check_if_file_is_container(path.unwrap()).is_err() {
-        return Err(SecureContainerErr::PathNotLuksContainer);
-    }
+/// Returns the kind of an error message, which is the part before the first colon.
+/// # Arguments
+/// * `message` - The error message, e.g. `Cryptsetup error: No key available with this passphrase.`.
+/// # Returns
+/// * `&str` - The kind of the error, e.g. `Cryptsetup error`.
+/// # Example
+/// ```
+/// use secure_container_lib::error_handling::error_kind;
+/// assert_eq!(error_kind("Cryptsetup error: failed"), "Cryptsetup error");
+/// ```
+pub fn error_kind(message: &str) -> &str {
+    message.split(':').next().unwrap_or_default().trim()
+}
 
-    Ok(())
+/// Returns the exit code of an error message as it is returned by the daemon or the library.
+/// # Arguments
+/// * `message` - The error message, e.g. `Cryptsetup error: No key available with this passphrase.`.
+/// # Returns
+/// * `i32` - The exit code of the error, `EXIT_CODE_UNKNOWN` if the message is not known.
+/// # Example
+/// ```
+/// use secure_container_lib::error_handling::exit_code_from_message;
+/// assert_eq!(exit_code_from_message("Cryptsetup error: failed"), 12);
+/// ```
+pub fn exit_code_from_message(message: &str) -> i32 {
+    if error_kind(message) == "Deadline exceeded" {
+        return EXIT_CODE_DEADLINE_EXCEEDED;
+    }
+    SecureContainerErr::from_daemon_string(message)
+        .map_or(EXIT_CODE_UNKNOWN, |err| err.exit_code())
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error_handling::SecureContainerErr::CryptsetupError;
-    use std::fs::File;
 
-    #[test]
-    fn test_check_input() {
-        let path = std::env::current_dir().unwrap();
-        let _file = File::create(path.join("test.txt"));
-        let mount_point = path.to_str().unwrap();
-        let path = path.join("test.txt");
-        let path = path.to_str().unwrap();
-        let namespace = "test";
-        let id = "test";
-        let size = 16;
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some("not_exists"),
-                Some(namespace),
-                Some(id)
-            ),
-            Err(SecureContainerErr::PathNotExists)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some("not_ascii€"),
-                Some(namespace),
-                Some(id)
-            ),
-            Err(SecureContainerErr::PathNotValid)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some("contains|"),
-                Some(namespace),
-                Some(id)
-            ),
-            Err(SecureContainerErr::PathNotValid)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some(path),
-                Some(namespace),
-                Some(id)
-            ),
-            Err(SecureContainerErr::PathNotLuksContainer)
-        );
-        assert_eq!(
-            check_input(
-                Some(15),
-                Some(mount_point),
-                Some(path),
-                Some(namespace),
-                Some(id)
-            ),
-            Err(SecureContainerErr::SizeToSmall)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some("not_exists"),
-                Some(path),
-                Some(namespace),
-                Some(id)
-            ),
-            Err(SecureContainerErr::MountPointNotExists)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some(path),
-                Some("test|"),
-                Some(id)
-            ),
-            Err(SecureContainerErr::NamespaceNotValid)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some(path),
-                Some("not_ascii€"),
-                Some(id)
-            ),
-            Err(SecureContainerErr::NamespaceNotValid)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some(path),
-                Some(namespace),
-                Some("test€")
-            ),
-            Err(SecureContainerErr::IdNotValid)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some(path),
-                Some(namespace),
-                Some("test|")
-            ),
-            Err(SecureContainerErr::IdNotValid)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                Some(path),
-                Some(namespace),
-                Some("testtest")
-            ),
-            Err(SecureContainerErr::IdNotValid)
-        );
-        assert_eq!(
-            check_input(
-                Some(size),
-                Some(mount_point),
-                None,
-                Some(namespace),
-                Some("test")
-            ),
-            Ok(())
-        );
-        let _ = std::fs::remove_file(path);
-    }
     #[test]
     fn test_fmt() {
         let bytes = vec![0, 159];
@@ -335,9 +425,106 @@ mod tests {
             SecureContainerErr::SecertError,
             SecureContainerErr::PathNotLuksContainer,
             SecureContainerErr::PathNotValid,
+            SecureContainerErr::DependencyMissing,
+            SecureContainerErr::MountBusy,
+            SecureContainerErr::PathNotWritable,
+            SecureContainerErr::MountedElsewhere,
+            SecureContainerErr::NoFreeKeyslots,
+            SecureContainerErr::BundleNotValid,
+            SecureContainerErr::TimeNotValid,
+            SecureContainerErr::ContainerNotOpen,
+            SecureContainerErr::LibutaRandomError("test".to_string()),
+            SecureContainerErr::MountPointConflictsWithPath,
+            SecureContainerErr::NotMountedAtMountPoint,
+            SecureContainerErr::KeyringError("test".to_string()),
+            SecureContainerErr::NotInAutoOpen,
+            SecureContainerErr::PathWaitTimeout,
+            SecureContainerErr::UnsupportedFilesystem,
+            SecureContainerErr::Base64Error("test".to_string()),
+            SecureContainerErr::MountOptionNotValid,
+            SecureContainerErr::MountPointNotEmpty,
+            SecureContainerErr::LastKeyslot,
+            SecureContainerErr::KeySlotNotValid,
         ];
         for error in error_list.iter() {
             println!("{}", error);
         }
     }
+
+    #[test]
+    fn test_exit_code() {
+        let errors = SecureContainerErr::all();
+        let mut codes: Vec<i32> = errors.iter().map(SecureContainerErr::exit_code).collect();
+        for error in &errors {
+            assert_eq!(exit_code_from_message(&error.to_string()), error.exit_code());
+        }
+        assert_eq!(SecureContainerErr::OK.exit_code(), 0);
+        assert_eq!(
+            exit_code_from_message("Path is not a luks device: not a LUKS device"),
+            27
+        );
+        assert_eq!(
+            exit_code_from_message(&SecureContainerErr::IsNotLuks("boom".into()).to_string()),
+            27
+        );
+        assert_eq!(
+            exit_code_from_message("Path is not a luks divice: not a LUKS device"),
+            27
+        );
+        assert_eq!(exit_code_from_message("Deadline exceeded"), 34);
+        assert_eq!(exit_code_from_message("Something else"), EXIT_CODE_UNKNOWN);
+
+        for error in SecureContainerErr::all() {
+            assert_eq!(
+                SecureContainerErr::from_daemon_string(&error.to_string()),
+                Some(error)
+            );
+        }
+        assert_eq!(
+            SecureContainerErr::from_daemon_string("Cryptsetup error: No key: slot 0"),
+            Some(CryptsetupError("No key: slot 0".to_string()))
+        );
+        assert_eq!(
+            SecureContainerErr::from_daemon_string("Path is not a luks divice: boom"),
+            Some(SecureContainerErr::IsNotLuks("boom".to_string()))
+        );
+        assert_eq!(SecureContainerErr::from_daemon_string("Deadline exceeded"), None);
+
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&EXIT_CODE_UNKNOWN));
+        assert!(!codes.contains(&EXIT_CODE_DEADLINE_EXCEEDED));
+    }
+
+    #[test]
+    fn test_field() {
+        assert_eq!(SecureContainerErr::SizeToSmall.field(), Some("size"));
+        assert_eq!(
+            SecureContainerErr::MountPointNotExists.field(),
+            Some("mountPoint")
+        );
+        assert_eq!(
+            SecureContainerErr::IsNotLuks("test".to_string()).field(),
+            Some("path")
+        );
+        assert_eq!(
+            SecureContainerErr::NamespaceNotValid.field(),
+            Some("namespace")
+        );
+        assert_eq!(
+            SecureContainerErr::UnsupportedFilesystem.field(),
+            Some("fsType")
+        );
+        assert_eq!(
+            SecureContainerErr::MountOptionNotValid.field(),
+            Some("mountOptions")
+        );
+        assert_eq!(
+            SecureContainerErr::MountPointNotEmpty.field(),
+            Some("mountPoint")
+        );
+        assert_eq!(SecureContainerErr::OK.field(), None);
+        assert_eq!(SecureContainerErr::IntegrityError.field(), None);
+    }
 }
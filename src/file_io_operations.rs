@@ -3,19 +3,240 @@
 //! This module is responsible for creating, reading,
 //! adding and removing containers from the autoOpen file.
 //! The autoOpen file is used for automatically opening containers on startup.
+//! The file is accessed through the `AutoOpenStore` trait, so the logic can be used with other storages.
+//!
+//! Every line of the autoOpen file is one entry, a JSON array with the mount point, path, namespace and id
+//! of a container and optionally `WAIT_FOR_PATH`, e.g. `["/home/MountMe","/home/Container","MyContainer","myId"]`.
+//! Files that were written before separate the fields with commas, their lines are still read
+//! and written as JSON arrays when the file is changed the next time.
+//!
+//! The daemon can change the autoOpen file from several requests at the same time,
+//! so every read and every read-modify-write holds an advisory lock (see `AutoOpenStore::lock`).
 //!
 
 use crate::error_handling;
-use error_handling::{check_input, Result, SecureContainerErr};
+use error_handling::{Result, SecureContainerErr};
+
+use crate::config::config;
+use crate::input_validation::{check_input, overlaps};
 
-use crate::file_system_operations::check_if_file_exists;
+use fs2::FileExt;
 use std::fs::File;
 use std::fs::OpenOptions;
+use std::fs::Permissions;
 use std::io::Read;
 use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// The mode of the autoOpen file.
+/// The file reveals where the containers are stored and mounted, so only the owner may read it.
+pub const AUTO_OPEN_FILE_MODE: u32 = 0o600;
+
+/// The optional fifth field of an autoOpen entry that makes auto open wait for the path of the container,
+/// e.g. because it is on a network file system that is mounted after the daemon has started.
+pub const WAIT_FOR_PATH: &str = "wait";
+
+/// The storage that holds the content of the autoOpen file.
+pub trait AutoOpenStore {
+    /// Reads the whole content of the storage.
+    /// # Returns
+    /// * `Result<String>` - Returns the content otherwise an error is returned.
+    /// # Errors
+    /// * `FileOpenError` - An error occurred while opening the storage.
+    /// * `FileReadError` - An error occurred while reading the storage.
+    fn read(&self) -> Result<String>;
+
+    /// Appends data to the storage, the storage is created if it does not exist.
+    /// # Arguments
+    /// * `data` - The data that is appended.
+    /// # Returns
+    /// * `Result<()>` - Returns OK(()) if the data was appended otherwise an error is returned.
+    /// # Errors
+    /// * `FileOpenError` - An error occurred while opening the storage.
+    /// * `FileWriteError` - An error occurred while writing to the storage.
+    fn append(&self, data: &str) -> Result<()>;
+
+    /// Replaces the whole content of the storage.
+    /// # Arguments
+    /// * `data` - The new content.
+    /// # Returns
+    /// * `Result<()>` - Returns OK(()) if the content was replaced otherwise an error is returned.
+    /// # Errors
+    /// * `FileOpenError` - An error occurred while opening the storage.
+    /// * `FileWriteError` - An error occurred while writing to the storage.
+    fn replace(&self, data: &str) -> Result<()>;
+
+    /// Locks the storage exclusively, the lock is held until the returned `AutoOpenLock` is dropped.
+    /// The storage is not locked by default, e.g. if it is only used by one thread.
+    /// # Returns
+    /// * `Result<AutoOpenLock>` - Returns the lock otherwise an error is returned.
+    /// # Errors
+    /// * `FileOpenError` - An error occurred while opening or locking the lock file.
+    fn lock(&self) -> Result<AutoOpenLock> {
+        Ok(AutoOpenLock { _file: None })
+    }
+}
+
+/// The lock of an autoOpen storage, the lock is released when it is dropped.
+/// * `_file` - The locked file, `None` if the storage is not locked.
+pub struct AutoOpenLock {
+    _file: Option<File>,
+}
+
+/// The autoOpen file on the file system, it is created with the mode `AUTO_OPEN_FILE_MODE`.
+/// Its content is replaced by writing a temporary file next to it that is renamed over it,
+/// so the file is never left truncated or half written.
+/// * `path` - The path to the autoOpen file.
+pub struct AutoOpenFile {
+    path: PathBuf,
+}
+
+impl AutoOpenFile {
+    /// Creates the store for the autoOpen file at the given path.
+    /// # Arguments
+    /// * `path` - The path to the autoOpen file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        AutoOpenFile { path: path.into() }
+    }
+
+    /// Returns the path of the temporary file that replaces the autoOpen file, it is in the same directory,
+    /// so it can be renamed over the autoOpen file.
+    fn temp_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".tmp");
+        PathBuf::from(path)
+    }
+
+    /// Flushes the directory of the autoOpen file, so the rename that replaced the file survives a power loss.
+    /// # Errors
+    /// * `FileWriteError` - The directory could not be opened or flushed.
+    fn sync_dir(&self) -> Result<()> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        match File::open(dir).and_then(|dir| dir.sync_all()) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(SecureContainerErr::FileWriteError(err.to_string())),
+        }
+    }
+
+    /// Returns the path of the lock file next to the autoOpen file.
+    /// The autoOpen file itself is replaced by renaming, so a lock on it would not be seen by the next writer.
+    fn lock_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        PathBuf::from(path)
+    }
+}
+
+impl AutoOpenStore for AutoOpenFile {
+    fn read(&self) -> Result<String> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) => return Err(SecureContainerErr::FileOpenError(err.to_string())),
+        };
+        let mut contents = String::new();
+        match file.read_to_string(&mut contents) {
+            Ok(_) => (),
+            Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+        };
+        Ok(contents)
+    }
+
+    fn append(&self, data: &str) -> Result<()> {
+        let file = open_auto_open_file(&self.path, false)?;
+        write_auto_open_file(file, data)
+    }
 
-/// The path to the autoOpen file.
-pub static mut PATH_TO_AUTO_OPEN: &str = "/usr/bin/auto_open";
+    fn replace(&self, data: &str) -> Result<()> {
+        let temp_path = self.temp_path();
+        let result = open_auto_open_file(&temp_path, true)
+            .and_then(|file| write_auto_open_file(file, data))
+            .and_then(|_| match std::fs::rename(&temp_path, &self.path) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(SecureContainerErr::FileWriteError(err.to_string())),
+            })
+            .and_then(|_| self.sync_dir());
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    fn lock(&self) -> Result<AutoOpenLock> {
+        let file = open_auto_open_file(&self.lock_path(), false)?;
+        match file.lock_exclusive() {
+            Ok(_) => Ok(AutoOpenLock { _file: Some(file) }),
+            Err(err) => Err(SecureContainerErr::FileOpenError(err.to_string())),
+        }
+    }
+}
+
+/// Returns the store of the autoOpen file of the daemon.
+/// # Returns
+/// * `AutoOpenFile` - The autoOpen file at `auto_open_path` of the configuration (see `config`).
+/// # Example
+/// ```
+/// let containers = reading_auto_open(&auto_open_file());
+/// ```
+///
+pub fn auto_open_file() -> AutoOpenFile {
+    AutoOpenFile::new(&config().auto_open_path)
+}
+
+/// Opens the autoOpen file for writing and makes sure it has the mode `AUTO_OPEN_FILE_MODE`.
+/// # Arguments
+/// * `path_to_auto_open` - The path to the autoOpen file.
+/// * `truncate` - If true, the content of the file is removed, otherwise new data is appended.
+/// # Returns
+/// * `Result<File>` - Returns the opened file, if the file does not exist it is created.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening the file or setting its mode.
+/// # Note
+/// This function is not meant to be called directly.
+fn open_auto_open_file(path_to_auto_open: &Path, truncate: bool) -> Result<File> {
+    let file = match OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(!truncate)
+        .truncate(truncate)
+        .mode(AUTO_OPEN_FILE_MODE)
+        .open(path_to_auto_open)
+    {
+        Ok(file) => file,
+        Err(err) => return Err(SecureContainerErr::FileOpenError(err.to_string())),
+    };
+    // The mode is only applied on creation, so files that already exist are restricted here.
+    match file.set_permissions(Permissions::from_mode(AUTO_OPEN_FILE_MODE)) {
+        Ok(_) => (),
+        Err(err) => return Err(SecureContainerErr::FileOpenError(err.to_string())),
+    };
+    Ok(file)
+}
+
+/// Writes data to the opened autoOpen file and flushes it to the disk,
+/// so an entry is not lost on a crash after the operation reported success.
+/// # Arguments
+/// * `file` - The opened autoOpen file.
+/// * `data` - The data that is written.
+/// # Returns
+/// * `Result<()>` - Returns OK(()) if the data was written and synced otherwise an error is returned.
+/// # Errors
+/// * `FileWriteError` - An error occurred while writing to or syncing the file.
+/// # Note
+/// This function is not meant to be called directly.
+fn write_auto_open_file(mut file: File, data: &str) -> Result<()> {
+    match file.write_all(data.as_bytes()) {
+        Ok(_) => (),
+        Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+    };
+    match file.sync_all() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(SecureContainerErr::FileWriteError(err.to_string())),
+    }
+}
 
 /// The function that is called to write a new container to the autoOpen file.
 /// # Arguments
@@ -23,12 +244,12 @@ pub static mut PATH_TO_AUTO_OPEN: &str = "/usr/bin/auto_open";
 /// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
+/// * `wait_for_path` - Whether auto open waits for the path of the container to appear.
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(())
 /// if the container was added successfully to the auto open file otherwise an error is returned.
 /// # Errors
-/// * `FileCreationError` - An error occurred while creating a file.
 /// * `FileOpenError` - An error occurred while opening a file.
 /// * `FileWriteError` - An error occurred while writing to a file.
 /// # Example
@@ -37,14 +258,19 @@ pub static mut PATH_TO_AUTO_OPEN: &str = "/usr/bin/auto_open";
 /// let path = "/home/Container";
 /// let namespace = "MyContainer";
 /// let id = "myId";
-/// let result = auto_open_write(mount_point, path, namespace, id);
-/// assert_eq!(result.is_ok(), true);
+/// let result = auto_open_write(mount_point, path, namespace, id, false);
+/// assert!(result.is_ok());
 /// ```
 ///
-pub fn auto_open_write(mount_point: &str, path: &str, namespace: &str, id: &str) -> Result<()> {
-    let path_to_auto_open = unsafe { PATH_TO_AUTO_OPEN };
-
-    match writing_to_auto_open(mount_point, path, namespace, id, path_to_auto_open) {
+pub fn auto_open_write(
+    mount_point: &str,
+    path: &str,
+    namespace: &str,
+    id: &str,
+    wait_for_path: bool,
+) -> Result<()> {
+    let store = auto_open_file();
+    match writing_to_auto_open(mount_point, path, namespace, id, wait_for_path, &store) {
         Ok(_) => (),
         Err(err) => return Err(err),
     };
@@ -62,35 +288,74 @@ pub fn auto_open_write(mount_point: &str, path: &str, namespace: &str, id: &str)
 /// * `FileReadError` - An error occurred while reading a file.
 /// # Example
 /// ```
-/// let sample_data = ["/home/MountMe,/home/Container,MyContainer,myId\n"];
+/// let sample_data = ["[\"/home/MountMe\",\"/home/Container\",\"MyContainer\",\"myId\"]\n"];
 /// let data=[sample_data];
 /// let result = auto_open_read();
-/// assert_eq!(result.is_ok(), true);
+/// assert!(result.is_ok());
 /// ```
 ///
 pub fn auto_open_read() -> Result<Vec<Vec<String>>> {
-    let path_to_auto_open = unsafe { PATH_TO_AUTO_OPEN };
+    reading_auto_open(&auto_open_file())
+}
 
-    match reading_auto_open(path_to_auto_open) {
-        Ok(containers) => Ok(containers),
-        Err(err) => Err(err),
-    }
+/// The function that is called to get the mount point of a container from the autoOpen file.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<Option<String>>` -
+/// Returns the mount point or `None` if the container is not in the autoOpen file.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
+/// # Example
+/// ```
+/// let result = auto_open_mount_point("MyContainer");
+/// assert_eq!(result, Ok(Some("/home/MountMe".to_string())));
+/// ```
+///
+pub fn auto_open_mount_point(namespace: &str) -> Result<Option<String>> {
+    finding_mount_point_in_auto_open(namespace, &auto_open_file())
+}
+
+/// The internal function that is called to get the mount point of a container from the autoOpen file.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `store` - The storage of the autoOpen file.
+/// # Returns
+/// * `Result<Option<String>>` -
+/// Returns the mount point or `None` if the container is not in the autoOpen file.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
+/// # Note
+/// This function is not meant to be called directly.
+pub fn finding_mount_point_in_auto_open(
+    namespace: &str,
+    store: &dyn AutoOpenStore,
+) -> Result<Option<String>> {
+    Ok(reading_auto_open(store)?
+        .into_iter()
+        .find(|container| container.len() > 2 && container[2] == namespace)
+        .map(|mut container| container.swap_remove(0)))
 }
 
 /// The internal function that is called to write a new container to the autoOpen file.
+/// If an entry with the same mount point, path, namespace and id already exists, nothing is written,
+/// so adding a container again does not make auto open open it twice.
 /// # Arguments
 /// * `mount_point` - The path to the mount point (must already exist).
 /// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
-/// * `path_to_auto_open` - The path to the autoOpen file.
+/// * `wait_for_path` - Whether auto open waits for the path of the container to appear.
+/// * `store` - The storage of the autoOpen file.
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(())
-/// if the container was added successfully to the auto open file otherwise an error is returned.
+/// if the container was added successfully to the auto open file or is already in it, otherwise an error is returned.
 /// # Errors
-/// * `FileCreationError` - An error occurred while creating a file.
 /// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
 /// * `FileWriteError` - An error occurred while writing to a file.
 /// # Note
 /// This function is not meant to be called directly.
@@ -99,31 +364,35 @@ pub fn writing_to_auto_open(
     path: &str,
     namespace: &str,
     id: &str,
-    path_to_auto_open: &str,
+    wait_for_path: bool,
+    store: &dyn AutoOpenStore,
 ) -> Result<()> {
-    let data = format!("{},{},{},{}\n", mount_point, path, namespace, id);
-    if !check_if_file_exists(path_to_auto_open) {
-        let file = File::create(path_to_auto_open);
-        if file.is_err() {
-            return Err(SecureContainerErr::FileCreationError(
-                file.err().unwrap().to_string(),
-            ));
-        }
-    }
-    let mut file = match OpenOptions::new().append(true).open(path_to_auto_open) {
-        Ok(file) => file,
-        Err(err) => return Err(SecureContainerErr::FileOpenError(err.to_string())),
-    };
-    match file.write_all(data.as_bytes()) {
-        Ok(_) => (),
-        Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+    let _lock = store.lock()?;
+    let containers = match read_entries(store) {
+        Ok(containers) => containers,
+        // An autoOpen file that does not exist yet has no entries, it is created by the append.
+        Err(SecureContainerErr::FileOpenError(_)) => Vec::new(),
+        Err(err) => return Err(err),
     };
-    Ok(())
+    if containers.iter().any(|container| {
+        container.len() > 3
+            && container[0] == mount_point
+            && container[1] == path
+            && container[2] == namespace
+            && container[3] == id
+    }) {
+        return Ok(());
+    }
+    let mut fields = vec![mount_point, path, namespace, id];
+    if wait_for_path {
+        fields.push(WAIT_FOR_PATH);
+    }
+    store.append(&format_entry(&fields))
 }
 
 /// The function that is called to read containers from the autoOpen file.
 /// # Arguments
-/// * `path_to_auto_open` - The path to the autoOpen file.
+/// * `store` - The storage of the autoOpen file.
 /// # Returns
 /// * `Result<Vec<Vec<String>>>` -
 /// Returns `Vec<Vec<String>>` with all the data that is needed from all containers that should be opened on startup.
@@ -134,44 +403,111 @@ pub fn writing_to_auto_open(
 /// # Note
 /// This function is not meant to be called directly.
 ///
-pub fn reading_auto_open(path_to_auto_open: &str) -> Result<Vec<Vec<String>>> {
-    let mut file = match File::open(path_to_auto_open) {
-        Ok(file) => file,
-        Err(err) => return Err(SecureContainerErr::FileOpenError(err.to_string())),
-    };
-    let mut contents = String::new();
-    match file.read_to_string(&mut contents) {
-        Ok(_) => (),
-        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
-    };
-    let containers: Vec<String> = contents.split('\n').map(|s| s.to_string()).collect();
+pub fn reading_auto_open(store: &dyn AutoOpenStore) -> Result<Vec<Vec<String>>> {
+    let _lock = store.lock()?;
+    read_entries(store)
+}
+
+/// Reads the entries of the autoOpen file without locking it,
+/// the caller holds the lock for the whole read-modify-write.
+/// # Arguments
+/// * `store` - The storage of the autoOpen file.
+/// # Returns
+/// * `Result<Vec<Vec<String>>>` - Returns the entries otherwise an error is returned.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
+/// # Note
+/// This function is not meant to be called directly.
+fn read_entries(store: &dyn AutoOpenStore) -> Result<Vec<Vec<String>>> {
+    let contents = store.read()?;
     let mut elements: Vec<Vec<String>> = Vec::new();
-    for container in containers {
-        let element: Vec<String> = container.split(',').map(|s| s.to_string()).collect();
-        if element.len() > 1 {
+    for container in contents.split('\n') {
+        if let Some(element) = parse_entry(container)? {
             elements.push(element);
         }
     }
     Ok(elements)
 }
+
+/// Formats an entry of the autoOpen file as a JSON array on one line,
+/// so fields with commas or other special characters are kept as they are.
+/// # Arguments
+/// * `fields` - The fields of the entry.
+/// # Returns
+/// * `String` - The line of the entry including the newline.
+/// # Note
+/// This function is not meant to be called directly.
+fn format_entry<S: AsRef<str>>(fields: &[S]) -> String {
+    let fields: Vec<&str> = fields.iter().map(AsRef::as_ref).collect();
+    format!("{}\n", serde_json::Value::from(fields))
+}
+
+/// The number of fields every entry of the autoOpen file has: mount point, path, namespace and id.
+/// An entry may have further fields, e.g. `WAIT_FOR_PATH`.
+const ENTRY_FIELDS: usize = 4;
+
+/// Parses a line of the autoOpen file.
+/// A line that is not a JSON array is an entry of the old format that separates the fields with commas,
+/// the mount point of an entry is an absolute path, so it never starts with `[`.
+/// # Arguments
+/// * `line` - The line of the autoOpen file.
+/// # Returns
+/// * `Result<Option<Vec<String>>>` -
+/// Returns the fields of the entry, there are at least `ENTRY_FIELDS` of them,
+/// or `None` if the line is empty, otherwise an error is returned.
+/// # Errors
+/// * `FileReadError` -
+/// The line starts like a JSON array but is not a valid one or the entry has less than `ENTRY_FIELDS` fields.
+/// # Note
+/// This function is not meant to be called directly.
+fn parse_entry(line: &str) -> Result<Option<Vec<String>>> {
+    let fields: Vec<String> = if line.starts_with('[') {
+        match serde_json::from_str(line) {
+            Ok(fields) => fields,
+            Err(err) => {
+                return Err(SecureContainerErr::FileReadError(format!(
+                    "Entry of the autoOpen file not valid: {}",
+                    err
+                )))
+            }
+        }
+    } else if line.is_empty() {
+        return Ok(None);
+    } else {
+        line.split(',').map(|s| s.to_string()).collect()
+    };
+    // The callers index the first fields, so a short entry is rejected here instead of panicking there.
+    if fields.len() < ENTRY_FIELDS {
+        return Err(SecureContainerErr::FileReadError(format!(
+            "Entry of the autoOpen file not valid: {} fields instead of at least {}",
+            fields.len(),
+            ENTRY_FIELDS
+        )));
+    }
+    Ok(Some(fields))
+}
 /// The function that is called by the daemon to add a new container to the autoOpen file.
 /// # Arguments
 /// * `mount_point` - The path to the mount point (must already exist).
 /// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
+/// * `wait_for_path` -
+/// Whether auto open waits up to `auto_open_wait_timeout` seconds (see `config`) for the path of the container to appear.
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(())
 /// if the container was added successfully to the auto open file otherwise an error is returned.
+/// If the same entry is already in the autoOpen file, OK(()) is returned and it is not added again.
 /// # Errors
-/// * `FileCreationError` - An error occurred while creating a file.
 /// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
 /// * `FileWriteError` - An error occurred while writing to a file.
 /// ### Errors regarding the input:
 /// * `MountPointNotExists` - The given mount point does not exist.
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
@@ -182,11 +518,17 @@ pub fn reading_auto_open(path_to_auto_open: &str) -> Result<Vec<Vec<String>>> {
 /// let path = "/home/Container";
 /// let namespace = "MyContainer";
 /// let id = "myId";
-/// let result = auto_open_write(mount_point, path, namespace, id);
-/// assert_eq!(result.is_ok(), true);
+/// let result = add_to_auto_open(mount_point, path, namespace, id, true);
+/// assert!(result.is_ok());
 /// ```
 ///
-pub fn add_to_auto_open(mount_point: &str, path: &str, namespace: &str, id: &str) -> Result<()> {
+pub fn add_to_auto_open(
+    mount_point: &str,
+    path: &str,
+    namespace: &str,
+    id: &str,
+    wait_for_path: bool,
+) -> Result<()> {
     match check_input(
         None,
         Some(mount_point),
@@ -198,7 +540,7 @@ pub fn add_to_auto_open(mount_point: &str, path: &str, namespace: &str, id: &str
         Err(err) => return Err(err),
     };
 
-    match auto_open_write(mount_point, path, namespace, id) {
+    match auto_open_write(mount_point, path, namespace, id, wait_for_path) {
         Ok(_) => (),
         Err(err) => return Err(err),
     };
@@ -218,13 +560,12 @@ pub fn add_to_auto_open(mount_point: &str, path: &str, namespace: &str, id: &str
 /// # Errors
 /// * `FileOpenError` - An error occurred while opening a file.
 /// * `FileReadError` - An error occurred while reading a file.
-/// * `FileCreationError` - An error occurred while creating a file.
 /// * `FileWriteError` - An error occurred while writing to a file.
 ///
 /// ### Errors regarding the input:
 /// * `MountPointNotExists` - The given mount point does not exist.
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
@@ -236,16 +577,11 @@ pub fn add_to_auto_open(mount_point: &str, path: &str, namespace: &str, id: &str
 /// let namespace = "MyContainer";
 /// let id = "myId";
 /// let result = remove_auto_open(mount_point, path, namespace, id);
-/// assert_eq!(result.is_ok(), true);
+/// assert!(result.is_ok());
 /// ```
 ///
 pub fn remove_auto_open(mount_point: &str, path: &str, namespace: &str, id: &str) -> Result<()> {
-    let path_to_auto_open = unsafe { PATH_TO_AUTO_OPEN };
-    match remove_from_auto_open(mount_point, path, namespace, id, path_to_auto_open) {
-        Ok(_) => (),
-        Err(err) => panic!("Error removing from auto open: {}", err),
-    }
-    Ok(())
+    remove_from_auto_open(mount_point, path, namespace, id, &auto_open_file())
 }
 
 /// The function that is called to remove a container from the autoOpen file.
@@ -254,7 +590,7 @@ pub fn remove_auto_open(mount_point: &str, path: &str, namespace: &str, id: &str
 /// * `path` - The path to the container.
 /// * `namespace` - The name of the container.
 /// * `id` - The id of the container.
-/// * `path_to_auto_open` - The path to the autoOpen file.
+/// * `store` - The storage of the autoOpen file.
 /// # Returns
 /// * `Result<()>` -
 /// Returns OK(())
@@ -262,13 +598,12 @@ pub fn remove_auto_open(mount_point: &str, path: &str, namespace: &str, id: &str
 /// # Errors
 /// * `FileOpenError` - An error occurred while opening a file.
 /// * `FileReadError` - An error occurred while reading a file.
-/// * `FileCreationError` - An error occurred while creating a file.
 /// * `FileWriteError` - An error occurred while writing to a file.
 ///
 /// ### Errors regarding the input:
 /// * `MountPointNotExists` - The given mount point does not exist.
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
@@ -280,124 +615,564 @@ pub fn remove_from_auto_open(
     path: &str,
     namespace: &str,
     id: &str,
-    path_to_auto_open: &str,
+    store: &dyn AutoOpenStore,
 ) -> Result<()> {
-    let containers = match reading_auto_open(path_to_auto_open) {
-        Ok(containers) => containers,
-        Err(err) => return Err(err),
-    };
+    let _lock = store.lock()?;
+    let containers = read_entries(store)?;
     let mut new_containers: Vec<Vec<String>> = Vec::new();
     for container in containers {
-        if container[0] != mount_point
-            && container[1] != path
-            && container[2] != namespace
-            && container[3] != id
+        // Only the entry that matches in all four fields is removed, the others are kept.
+        if !(container[0] == mount_point
+            && container[1] == path
+            && container[2] == namespace
+            && container[3] == id)
         {
             new_containers.push(container);
         }
     }
-    let mut file = match File::create(path_to_auto_open) {
-        Ok(file) => file,
-        Err(err) => return Err(SecureContainerErr::FileCreationError(err.to_string())),
+    let data: String = new_containers
+        .iter()
+        .map(|container| format_entry(container))
+        .collect();
+    store.replace(&data)
+}
+
+/// The function that is called to change the id of a container in the autoOpen file.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `id` - The new id of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the id was changed successfully otherwise an error is returned.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
+/// * `FileWriteError` - An error occurred while writing to a file.
+/// # Example
+/// ```
+/// let result = auto_open_change_id("MyContainer", "newId");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn auto_open_change_id(namespace: &str, id: &str) -> Result<()> {
+    changing_id_in_auto_open(namespace, id, &auto_open_file())
+}
+
+/// The internal function that is called to change the id of a container in the autoOpen file.
+/// All other entries are written back unchanged.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `id` - The new id of the container.
+/// * `store` - The storage of the autoOpen file.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the id was changed successfully otherwise an error is returned.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
+/// * `FileWriteError` - An error occurred while writing to a file.
+/// # Note
+/// This function is not meant to be called directly.
+pub fn changing_id_in_auto_open(
+    namespace: &str,
+    id: &str,
+    store: &dyn AutoOpenStore,
+) -> Result<()> {
+    let _lock = store.lock()?;
+    let containers = read_entries(store)?;
+    let mut data = String::new();
+    for mut container in containers {
+        if container[2] == namespace {
+            container[3] = id.to_string();
+        }
+        data.push_str(&format_entry(&container));
+    }
+    store.replace(&data)
+}
+
+/// The function that is called by the daemon to change the mount point of a container in the autoOpen file.
+/// Only the mount point of the entry is changed, the entry is not removed and added again.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `mount_point` - The new path to the mount point (must already exist).
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the mount point was changed successfully otherwise an error is returned.
+/// # Errors
+/// * `NotInAutoOpen` - The container is not in the autoOpen file.
+/// * `MountPointConflictsWithPath` - The new mount point is the path of the container or one of its parents.
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
+/// * `FileWriteError` - An error occurred while writing to a file.
+/// ### Errors regarding the input:
+/// * `MountPointNotExists` - The given mount point does not exist.
+/// * `NamespaceNotValid` - The given namespace contains a pipe or control characters.
+/// # Example
+/// ```
+/// let result = update_auto_open("MyContainer", "/home/NewMountPoint");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn update_auto_open(namespace: &str, mount_point: &str) -> Result<()> {
+    check_input(None, Some(mount_point), None, Some(namespace), None)?;
+    changing_mount_point_in_auto_open(namespace, mount_point, &auto_open_file())
+}
+
+/// The internal function that is called to change the mount point of a container in the autoOpen file.
+/// All other entries are written back unchanged.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `mount_point` - The new path to the mount point.
+/// * `store` - The storage of the autoOpen file.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the mount point was changed successfully otherwise an error is returned.
+/// # Errors
+/// * `NotInAutoOpen` - The container is not in the autoOpen file.
+/// * `MountPointConflictsWithPath` - The new mount point is the path of the container or one of its parents.
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileReadError` - An error occurred while reading a file.
+/// * `FileWriteError` - An error occurred while writing to a file.
+/// # Note
+/// This function is not meant to be called directly.
+pub fn changing_mount_point_in_auto_open(
+    namespace: &str,
+    mount_point: &str,
+    store: &dyn AutoOpenStore,
+) -> Result<()> {
+    let _lock = store.lock()?;
+    let mut containers = read_entries(store)?;
+    let container = match containers
+        .iter_mut()
+        .find(|container| container.len() > 3 && container[2] == namespace)
+    {
+        Some(container) => container,
+        None => return Err(SecureContainerErr::NotInAutoOpen),
     };
-    for container in new_containers {
-        let data = format!(
-            "{},{},{},{}\n",
-            container[0], container[1], container[2], container[3]
-        );
-        match file.write_all(data.as_bytes()) {
-            Ok(_) => (),
-            Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
-        };
+    if overlaps(mount_point, &container[1]) {
+        return Err(SecureContainerErr::MountPointConflictsWithPath);
     }
-    Ok(())
+    container[0] = mount_point.to_string();
+    let data: String = containers
+        .iter()
+        .map(|container| format_entry(container))
+        .collect();
+    store.replace(&data)
+}
+
+/// The function that is called to replace all entries of the autoOpen file,
+/// e.g. when it is rebuilt after it was lost.
+/// # Arguments
+/// * `containers` - The entries, each with the mount point, path, namespace and id of a container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the entries were written successfully otherwise an error is returned.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileWriteError` - An error occurred while writing to a file.
+/// # Example
+/// ```
+/// let containers = vec![["/home/MountMe", "/home/Container", "MyContainer", "myId"].map(String::from)];
+/// let result = auto_open_replace(&containers);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn auto_open_replace(containers: &[[String; 4]]) -> Result<()> {
+    replacing_auto_open(containers, &auto_open_file())
+}
+
+/// The internal function that is called to replace all entries of the autoOpen file.
+/// # Arguments
+/// * `containers` - The entries, each with the mount point, path, namespace and id of a container.
+/// * `store` - The storage of the autoOpen file.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the entries were written successfully otherwise an error is returned.
+/// # Errors
+/// * `FileOpenError` - An error occurred while opening a file.
+/// * `FileWriteError` - An error occurred while writing to a file.
+/// # Note
+/// This function is not meant to be called directly.
+pub fn replacing_auto_open(containers: &[[String; 4]], store: &dyn AutoOpenStore) -> Result<()> {
+    let _lock = store.lock()?;
+    let data: String = containers
+        .iter()
+        .map(|container| format_entry(container))
+        .collect();
+    store.replace(&data)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::RefCell;
     use std::fs;
-    use std::fs::File;
-    use std::io::Read;
-    use std::io::Write;
+
+    /// An autoOpen store that keeps its content in memory.
+    #[derive(Default)]
+    struct MemoryStore {
+        contents: RefCell<Option<String>>,
+    }
+
+    impl AutoOpenStore for MemoryStore {
+        fn read(&self) -> Result<String> {
+            match self.contents.borrow().as_ref() {
+                Some(contents) => Ok(contents.clone()),
+                None => Err(SecureContainerErr::FileOpenError("Not found".to_string())),
+            }
+        }
+
+        fn append(&self, data: &str) -> Result<()> {
+            self.contents
+                .borrow_mut()
+                .get_or_insert_with(String::new)
+                .push_str(data);
+            Ok(())
+        }
+
+        fn replace(&self, data: &str) -> Result<()> {
+            *self.contents.borrow_mut() = Some(data.to_string());
+            Ok(())
+        }
+    }
 
     #[test]
     fn test_auto_open_write() {
-        let testing_path = "/tmp/auto_open";
+        let store = MemoryStore::default();
         let mount_point = "/mnt";
         let path = "/path";
         let namespace = "namespace";
         let id = "id";
-        let data = format!("{},{},{},{}\n", mount_point, path, namespace, id);
-        let result = writing_to_auto_open(mount_point, path, namespace, id, testing_path);
-        assert_eq!(result.is_ok(), true);
-        let mut file = match File::open(testing_path) {
-            Ok(file) => file,
-            Err(err) => panic!("Error opening file: {}", err),
-        };
-        let mut contents = String::new();
-        match file.read_to_string(&mut contents) {
-            Ok(_) => (),
-            Err(err) => panic!("Error reading file: {}", err),
-        };
-        assert_eq!(contents, data);
-        fs::remove_file(testing_path).unwrap();
+        let data = "[\"/mnt\",\"/path\",\"namespace\",\"id\"]\n";
+        let result = writing_to_auto_open(mount_point, path, namespace, id, false, &store);
+        assert!(result.is_ok());
+        assert_eq!(store.read(), Ok(data.to_string()));
+
+        let result = writing_to_auto_open("/net", "/nfs/path", "network", id, true, &store);
+        assert!(result.is_ok());
+        assert_eq!(
+            store.read(),
+            Ok(format!(
+                "{}[\"/net\",\"/nfs/path\",\"network\",\"id\",\"wait\"]\n",
+                data
+            ))
+        );
+        assert!(changing_id_in_auto_open("network", "newId", &store).is_ok());
+        assert_eq!(
+            store.read(),
+            Ok(format!(
+                "{}[\"/net\",\"/nfs/path\",\"network\",\"newId\",\"wait\"]\n",
+                data
+            ))
+        );
+    }
+
+    #[test]
+    fn test_writing_to_auto_open_twice() {
+        let store = MemoryStore::default();
+        for _ in 0..2 {
+            let result = writing_to_auto_open("/mnt", "/path", "namespace", "id", false, &store);
+            assert!(result.is_ok());
+        }
+        assert_eq!(
+            store.read(),
+            Ok("[\"/mnt\",\"/path\",\"namespace\",\"id\"]\n".to_string())
+        );
+
+        // An entry that differs in one field is added.
+        let result = writing_to_auto_open("/mnt", "/path", "namespace", "other", false, &store);
+        assert!(result.is_ok());
+        assert_eq!(reading_auto_open(&store).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_replacing_auto_open() {
+        let store = MemoryStore::default();
+        store.replace("/old,/old,old,id\n").unwrap();
+        let containers = [
+            ["/mnt/a", "/srv/a", "a", "id"].map(String::from),
+            ["/mnt/b", "/srv/b", "b", "other"].map(String::from),
+        ];
+        assert!(replacing_auto_open(&containers, &store).is_ok());
+        assert_eq!(
+            store.read(),
+            Ok(
+                "[\"/mnt/a\",\"/srv/a\",\"a\",\"id\"]\n[\"/mnt/b\",\"/srv/b\",\"b\",\"other\"]\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_auto_open_path_with_comma() {
+        let store = MemoryStore::default();
+        let path = "/srv/a,b/Container";
+        let result = writing_to_auto_open("/mnt/a,b", path, "namespace", "id", true, &store);
+        assert!(result.is_ok());
+        assert_eq!(
+            reading_auto_open(&store),
+            Ok(vec![vec![
+                "/mnt/a,b".to_string(),
+                path.to_string(),
+                "namespace".to_string(),
+                "id".to_string(),
+                WAIT_FOR_PATH.to_string(),
+            ]])
+        );
+        let result = remove_from_auto_open("/mnt/a,b", path, "namespace", "id", &store);
+        assert!(result.is_ok());
+        assert_eq!(store.read(), Ok(String::new()));
+    }
+
+    #[test]
+    fn test_auto_open_legacy_format() {
+        let store = MemoryStore::default();
+        store
+            .replace("/mnt,/path,namespace,id\n[\"/mnt2\",\"/path2\",\"other\",\"id\"]\n")
+            .unwrap();
+        assert_eq!(
+            reading_auto_open(&store).unwrap(),
+            vec![
+                vec!["/mnt", "/path", "namespace", "id"],
+                vec!["/mnt2", "/path2", "other", "id"],
+            ]
+        );
+        // Changing the file writes the old entries in the new format.
+        assert!(changing_id_in_auto_open("other", "newId", &store).is_ok());
+        assert_eq!(
+            store.read(),
+            Ok(
+                "[\"/mnt\",\"/path\",\"namespace\",\"id\"]\n[\"/mnt2\",\"/path2\",\"other\",\"newId\"]\n"
+                    .to_string()
+            )
+        );
+
+        store.replace("[\"/mnt\",\"/path\"\n").unwrap();
+        assert!(matches!(
+            reading_auto_open(&store),
+            Err(SecureContainerErr::FileReadError(_))
+        ));
+    }
+
+    #[test]
+    fn test_auto_open_short_entry() {
+        let store = MemoryStore::default();
+        for short in ["/mnt,/path,namespace\n", "[\"/mnt\",\"/path\"]\n", "/mnt\n"] {
+            store
+                .replace(&format!("/mnt2,/path2,other,id\n{}", short))
+                .unwrap();
+            assert!(matches!(
+                reading_auto_open(&store),
+                Err(SecureContainerErr::FileReadError(_))
+            ));
+            // A short entry is reported instead of panicking while the file is changed.
+            assert!(matches!(
+                remove_from_auto_open("/mnt2", "/path2", "other", "id", &store),
+                Err(SecureContainerErr::FileReadError(_))
+            ));
+            assert!(matches!(
+                changing_id_in_auto_open("other", "newId", &store),
+                Err(SecureContainerErr::FileReadError(_))
+            ));
+        }
+        store.replace("\n/mnt,/path,namespace,id\n\n").unwrap();
+        assert_eq!(
+            reading_auto_open(&store).unwrap(),
+            vec![vec!["/mnt", "/path", "namespace", "id"]]
+        );
     }
 
     #[test]
     fn test_auto_open_read() {
-        let testing_path = "/tmp/auto_open2";
+        let store = MemoryStore::default();
         let mount_point = "/mnt";
         let path = "/path";
         let namespace = "namespace";
         let id = "id";
+        assert!(matches!(
+            reading_auto_open(&store),
+            Err(SecureContainerErr::FileOpenError(_))
+        ));
         let data = format!("{},{},{},{}\n", mount_point, path, namespace, id);
-        let mut file = match File::create(testing_path) {
-            Ok(file) => file,
-            Err(err) => panic!("Error creating file: {}", err),
-        };
-        match file.write_all(data.as_bytes()) {
-            Ok(_) => (),
-            Err(err) => panic!("Error writing to file: {}", err),
-        };
-        let result = reading_auto_open(testing_path);
-        assert_eq!(result.is_ok(), true);
+        store.replace(&data).unwrap();
+        let result = reading_auto_open(&store);
+        assert!(result.is_ok());
         let result = result.unwrap();
         assert_eq!(result[0][0], mount_point);
         assert_eq!(result[0][1], path);
         assert_eq!(result[0][2], namespace);
         assert_eq!(result[0][3], id);
-        fs::remove_file(testing_path).unwrap();
     }
 
     #[test]
     fn test_remove_from_auto_open() {
-        let testing_path = "/tmp/auto_open3";
+        let store = MemoryStore::default();
         let mount_point = "/mnt";
         let path = "/path";
         let namespace = "namespace";
         let id = "id";
         let data = format!("{},{},{},{}\n", mount_point, path, namespace, id);
-        let mut file = match File::create(testing_path) {
-            Ok(file) => file,
-            Err(err) => panic!("Error creating file: {}", err),
-        };
-        match file.write_all(data.as_bytes()) {
-            Ok(_) => (),
-            Err(err) => panic!("Error writing to file: {}", err),
-        };
-        let result = remove_from_auto_open(mount_point, path, namespace, id, testing_path);
-        assert_eq!(result.is_ok(), true);
-        let mut file = match File::open(testing_path) {
-            Ok(file) => file,
-            Err(err) => panic!("Error opening file: {}", err),
-        };
-        let mut contents = String::new();
-        match file.read_to_string(&mut contents) {
-            Ok(_) => (),
-            Err(err) => panic!("Error reading file: {}", err),
-        };
-        assert_eq!(contents, "");
-        fs::remove_file(testing_path).unwrap();
+        store.replace(&data).unwrap();
+        let result = remove_from_auto_open(mount_point, path, namespace, id, &store);
+        assert!(result.is_ok());
+        assert_eq!(store.read(), Ok(String::new()));
+
+        // Entries that share a mount point but not the namespace are kept.
+        store
+            .replace(
+                "/mnt,/path,namespace,id
+/mnt,/path2,other,id,wait
+",
+            )
+            .unwrap();
+        let result = remove_from_auto_open(mount_point, path, namespace, id, &store);
+        assert!(result.is_ok());
+        assert_eq!(
+            store.read(),
+            Ok("[\"/mnt\",\"/path2\",\"other\",\"id\",\"wait\"]\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_changing_id_in_auto_open() {
+        let store = MemoryStore::default();
+        store
+            .replace("/mnt,/path,namespace,id\n/mnt2,/path2,other,id\n")
+            .unwrap();
+        let result = changing_id_in_auto_open("namespace", "newId", &store);
+        assert!(result.is_ok());
+        assert_eq!(
+            store.read(),
+            Ok(
+                "[\"/mnt\",\"/path\",\"namespace\",\"newId\"]\n[\"/mnt2\",\"/path2\",\"other\",\"id\"]\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_finding_mount_point_in_auto_open() {
+        let store = MemoryStore::default();
+        assert!(finding_mount_point_in_auto_open("namespace", &store).is_err());
+        store
+            .replace("/mnt,/path,namespace,id\n/mnt2,/path2,other,id\n")
+            .unwrap();
+        assert_eq!(
+            finding_mount_point_in_auto_open("other", &store),
+            Ok(Some("/mnt2".to_string()))
+        );
+        assert_eq!(
+            finding_mount_point_in_auto_open("missing", &store),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_changing_mount_point_in_auto_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Container");
+        fs::write(&path, "").unwrap();
+        let store = MemoryStore::default();
+        store
+            .replace(&format!(
+                "/mnt,{},namespace,id\n/mnt2,/path2,other,id\n",
+                path.display()
+            ))
+            .unwrap();
+        let result = changing_mount_point_in_auto_open("namespace", "/mnt/new", &store);
+        assert!(result.is_ok());
+        assert_eq!(
+            store.read(),
+            Ok(format!(
+                "[\"/mnt/new\",\"{}\",\"namespace\",\"id\"]\n[\"/mnt2\",\"/path2\",\"other\",\"id\"]\n",
+                path.display()
+            ))
+        );
+        assert_eq!(
+            changing_mount_point_in_auto_open("missing", "/mnt/new", &store),
+            Err(SecureContainerErr::NotInAutoOpen)
+        );
+        assert_eq!(
+            changing_mount_point_in_auto_open("namespace", dir.path().to_str().unwrap(), &store),
+            Err(SecureContainerErr::MountPointConflictsWithPath)
+        );
+    }
+
+    #[test]
+    fn test_auto_open_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = AutoOpenFile::new(dir.path().join("auto_open"));
+        let result = writing_to_auto_open("/mnt", "/path", "namespace", "id", false, &store);
+        assert!(result.is_ok());
+        assert_eq!(
+            reading_auto_open(&store).unwrap(),
+            vec![vec!["/mnt", "/path", "namespace", "id"]]
+        );
+        let mode = fs::metadata(&store.path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, AUTO_OPEN_FILE_MODE);
+
+        fs::set_permissions(&store.path, Permissions::from_mode(0o644)).unwrap();
+        let result = remove_from_auto_open("/mnt", "/path", "namespace", "id", &store);
+        assert!(result.is_ok());
+        assert_eq!(fs::read_to_string(&store.path).unwrap(), "");
+        let mode = fs::metadata(&store.path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, AUTO_OPEN_FILE_MODE);
+        assert!(!store.temp_path().exists());
+    }
+
+    #[test]
+    fn test_auto_open_file_write_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = AutoOpenFile::new(dir.path().join("auto_open"));
+        let data = "/mnt,/path,namespace,id\n/mnt2,/path2,other,id\n";
+        fs::write(&store.path, data).unwrap();
+        // A directory in place of the temporary file makes writing it fail.
+        fs::create_dir(store.temp_path()).unwrap();
+        let result = remove_from_auto_open("/mnt", "/path", "namespace", "id", &store);
+        assert!(matches!(result, Err(SecureContainerErr::FileOpenError(_))));
+        assert_eq!(fs::read_to_string(&store.path).unwrap(), data);
+    }
+
+    #[test]
+    fn test_auto_open_file_concurrent_writers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auto_open");
+        let writers: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let store = AutoOpenFile::new(path);
+                    for i in 0..20 {
+                        let namespace = format!("{}{}", writer, i);
+                        let mount_point = format!("/mnt/{}", namespace);
+                        let result = writing_to_auto_open(
+                            &mount_point,
+                            "/path",
+                            &namespace,
+                            "id",
+                            false,
+                            &store,
+                        );
+                        assert!(result.is_ok());
+                        // Replacing the file must not drop an entry the other thread added meanwhile.
+                        assert!(changing_id_in_auto_open(&namespace, "newId", &store).is_ok());
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let containers = reading_auto_open(&AutoOpenFile::new(&path)).unwrap();
+        assert_eq!(containers.len(), 40);
+        for writer in ["a", "b"] {
+            for i in 0..20 {
+                let namespace = format!("{}{}", writer, i);
+                assert!(containers
+                    .iter()
+                    .any(|container| container[2] == namespace && container[3] == "newId"));
+            }
+        }
     }
 }
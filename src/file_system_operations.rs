@@ -1,313 +1,1472 @@
-//! # File System Operations
-//! This module provides all function
-//! needed to interact with the file system for the entire project.
-//! It provides functions for checking file or directory,
-//! creating files and directories, checking if a container is mounted,
-//! creating a directory for the container,
-//! mounting and unmounting the container, and checking if the container is open.
-//!
-
-use crate::error_handling;
-use error_handling::{Result, SecureContainerErr};
-
-use crate::utilities;
-use utilities::mb_in_bytes;
-
-use std::fs::File;
-use std::io::Write;
-
-use std::path::Path;
-use std::process::Command;
-
-/// Check if a file exists
-/// # Arguments
-/// * `path` - The path to a file.
-/// # Returns
-/// * `bool` - True if the provided path is a file otherwise false.
-/// In case of an error, this error is returned.
-/// # Example
-/// ```
-/// let path = "/usr/bin/auto_open";
-/// let result = check_if_file_exists(path);
-/// assert_eq!(result, true);
-/// ```
-///
-pub fn check_if_file_exists(path: &str) -> bool {
-    let path = Path::new(path);
-    path.is_file()
-}
-
-/// Check if a directory exists
-/// # Arguments
-/// * `path` - The path to a directory.
-/// # Returns
-/// * `bool` - True if the provided path is a directory otherwise false.
-/// In case of an error, this error is returned.
-/// # Example
-/// ```
-/// let path = "/usr/bin";
-/// let result = check_if_file_exists(path);
-/// assert_eq!(result, true);
-/// ```
-///
-pub fn check_if_dir_exists(path: &str) -> bool {
-    let path = Path::new(path);
-    path.is_dir()
-}
-
-/// Create a file
-/// # Arguments
-/// * `size` - Filesize in MB.
-/// * `path` - The path to where the file should be created.
-/// * `namespace` - The name of the file.
-/// # Returns
-/// * `Result<()>` -
-/// Returns OK(())
-/// if the file was created successfully otherwise an error is returned.
-/// # Errors
-/// * `FileCreationError` - An error occurred while creating a file.
-/// * `FileWriteError` - An error occurred while writing to a file.
-/// # Example
-/// ```
-/// let size = 10;
-/// let path = "/usr/bin";
-/// let namespace = "test.txt";
-/// let result = create_file(size, path, namespace);
-/// assert!(result.is_ok());
-/// ```
-///
-pub fn create_file(size: i32, path: &str, namespace: &str) -> Result<()> {
-    let complete_path = Path::new(path).join(namespace);
-    let file_size_in_bytes = mb_in_bytes(size);
-    let mut file = match File::create(complete_path) {
-        Ok(file) => file,
-        Err(err) => return Err(SecureContainerErr::FileCreationError(err.to_string())),
-    };
-
-    let mut bytes_written = 0;
-    while bytes_written < file_size_in_bytes {
-        let bytes_to_write = std::cmp::min(1024, file_size_in_bytes - bytes_written) as usize;
-        let data = vec![0u8; bytes_to_write];
-        match file.write_all(&data) {
-            Ok(_) => bytes_written += bytes_to_write as u64,
-            Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
-        };
-    }
-
-    Ok(())
-}
-
-/// Check connected block devices using lsblk
-/// # Arguments
-/// * `name` - The name of the block device.
-/// # Returns
-/// * `Result<bool>` -
-/// Returns true if the block device is connected otherwise false.
-/// In case of an error, this error is returned.
-/// # Errors
-/// * `LsblkError` - An error occurred executing lsblk.
-/// * `ReadingStdoutError` - An error occurred while reading stdout.
-/// # Example
-/// ```
-/// let name = "myBlockDevice";
-/// let result = check_lsblk(name);
-/// assert_eq!(result.unwrap(), true);
-/// ```
-///
-pub fn check_lsblk(name: &str) -> Result<bool> {
-    let output = match Command::new("lsblk").output() {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::LsblkError(err.to_string())),
-    };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::LsblkError(stderr.to_string()));
-    }
-    let stdout = match String::from_utf8(output.stdout) {
-        Ok(stdout) => stdout,
-        Err(err) => return Err(SecureContainerErr::ReadingStdoutError(err)),
-    };
-    let lines: Vec<&str> = stdout.split(' ').collect();
-    for line in lines {
-        let mut line = line.replace('\n', "");
-        line = line.replace("└─", "");
-        if line == name {
-            return Ok(true);
-        }
-    }
-
-    Ok(false)
-}
-
-/// Check if a container is mounted
-/// # Arguments
-/// * `namespace` - The name of the container.
-/// # Returns
-/// * `Result<bool>` -
-/// Returns true if the container is mounted otherwise false.
-/// In case of an error, this error is returned.
-/// # Errors
-/// * `LsError` - An error occurred while checking the logical volumes of the system.
-/// * `ReadingStdoutError` - An error occurred while reading stdout.
-/// # Example
-/// ```
-/// let namespace = "myContainer";
-/// let result = check_container_mounted(namespace);
-/// assert_eq!(result.unwrap(), true);
-/// ```
-///
-pub fn check_container_mounted(namespace: &str) -> Result<bool> {
-    let output = match Command::new("ls").args(["-l", "/dev/mapper"]).output() {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::LsError(err.to_string())),
-    };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::LsError(stderr.to_string()));
-    }
-    let stdout = match String::from_utf8(output.stdout) {
-        Ok(stdout) => stdout,
-        Err(err) => return Err(SecureContainerErr::ReadingStdoutError(err)),
-    };
-    let lines: Vec<&str> = stdout.split('\n').collect();
-    for line in lines {
-        if line.contains(&format!("{} ", namespace)) {
-            return Ok(true);
-        }
-    }
-    Ok(false)
-}
-
-/// Create a directory for the container in /dev/mapper
-/// # Arguments
-/// * `namespace` - The name of the container.
-/// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the directory was created successfully otherwise an error is returned.
-/// # Errors
-/// * `MkfsError` - An error occurred creation the file system.
-/// # Example
-/// ```
-/// let namespace = "myContainer";
-/// let result = create_name_dir(namespace);
-/// assert!(result.is_ok());
-/// ```
-///
-pub fn create_name_dir(namespace: &str) -> Result<()> {
-    let path = Path::new("/dev/mapper");
-    let file_path = path.join(namespace);
-
-    let output = match Command::new("/sbin/mkfs.ext4").args(&[file_path]).output() {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::MkfsError(err.to_string())),
-    };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::MkfsError(stderr.to_string()));
-    }
-
-    Ok(())
-}
-
-/// Mount a device to a directory
-/// # Arguments
-/// * `mount_point` - The directory where the device should be mounted to.
-/// * `device` - The name of the device to be mounted.
-/// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the device was mounted successfully otherwise an error is returned.
-/// # Errors
-/// * `MountError` - An error occurred while trying to mount the container.
-/// # Example
-/// ```
-/// let mount_point = "/home/MountMe";
-/// let device = "myContainer";
-/// let result = mount(mount_point, device);
-/// assert!(result.is_ok());
-/// ```
-///
-pub fn mount(mount_point: &str, device: &str) -> Result<()> {
-    let binding = "/dev/mapper/".to_owned() + device;
-    let device = binding.as_str();
-    let output = match Command::new("mount").args([device, mount_point]).output() {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::MountError(err.to_string())),
-    };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::MountError(stderr.to_string()));
-    }
-
-    Ok(())
-}
-
-/// Unmount a device from a directory
-/// # Arguments
-/// * `mount_point` - The directory where the device is mounted to.
-/// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if the device was unmounted successfully otherwise an error is returned.
-/// # Errors
-/// * `UmountError` - An error occurred while the device was unmounted.
-/// # Example
-/// ```
-/// let mount_point = "/home/MountMe";
-/// let result = unmount(mount_point);
-/// assert!(result.is_ok());
-/// ```
-///
-pub fn unmount(mount_point: &str) -> Result<()> {
-    let output = match Command::new("umount").args([mount_point]).output() {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::UmountError(err.to_string())),
-    };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::UmountError(stderr.to_string()));
-    }
-    Ok(())
-}
-
-/// Check if a container is open
-/// # Arguments
-/// * `namespace` - The name of the container.
-/// # Returns
-/// * `Result<bool>` -
-/// Returns true if the container is open otherwise false.
-/// In case of an error, this error is returned.
-/// # Errors
-/// * `LsblkError` - An error occurred executing lsblk.
-/// * `ReadingStdoutError` - An error occurred while reading stdout.
-/// # Example
-/// ```
-/// let namespace = "myContainer";
-/// let result = check_container_open(namespace);
-/// assert_eq!(result.unwrap(), false);
-/// ```
-///
-
-pub fn check_container_open(namespace: &str) -> Result<bool> {
-    let output = match Command::new("lsblk")
-        .args(["-o", "NAME,TYPE,MOUNTPOINT"])
-        .output()
-    {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::LsblkError(err.to_string())),
-    };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::LsblkError(stderr.to_string()));
-    }
-
-    let stdout = match String::from_utf8(output.stdout) {
-        Ok(stdout) => stdout,
-        Err(err) => return Err(SecureContainerErr::ReadingStdoutError(err)),
-    };
-    let lines: Vec<&str> = stdout.split('\n').collect();
-    for line in lines {
-        if line.contains(&format!("{} ", namespace)) && line.contains("crypt") {
-            return Ok(true);
-        }
-    }
-    Ok(false)
-}
+//! # File System Operations
+//! This module provides all function
+//! needed to interact with the file system for the entire project.
+//! It provides functions for checking file or directory,
+//! creating files and directories, checking if a container is mounted,
+//! creating a directory for the container,
+//! mounting and unmounting the container, and checking if the container is open.
+//! It also provides the functions to use a container as swap device.
+//!
+
+use crate::error_handling;
+use error_handling::{Result, SecureContainerErr};
+
+use crate::utilities;
+use utilities::mb_in_bytes;
+
+use crate::config::config;
+use crate::deadline;
+use crate::input_validation::check_mount_options;
+#[cfg(feature = "native-mount")]
+use crate::input_validation::FILE_SYSTEMS;
+
+use serde::Deserialize;
+
+use nix::errno::Errno;
+use nix::fcntl::{fallocate, FallocateFlags};
+use nix::libc::off_t;
+
+use std::fs::File;
+use std::io::Write;
+
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::process::Command;
+
+/// A block device as it is reported by `lsblk --json`.
+/// * `name` - The name of the device, for crypt devices this is the name in `/dev/mapper`.
+/// * `device_type` - The type of the device, e.g. `disk`, `loop` or `crypt`.
+/// * `mountpoint` - The path where the device is mounted, if it is mounted.
+/// * `children` - The devices that are stacked on top of this device.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlockDevice {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub device_type: String,
+    pub mountpoint: Option<String>,
+    #[serde(default)]
+    pub children: Vec<BlockDevice>,
+}
+
+/// The top level object of the output of `lsblk --json`.
+#[derive(Debug, Deserialize)]
+struct LsblkOutput {
+    blockdevices: Vec<BlockDevice>,
+}
+
+/// Check if a file exists
+/// # Arguments
+/// * `path` - The path to a file.
+/// # Returns
+/// * `bool` - True if the provided path is a file otherwise false.
+/// In case of an error, this error is returned.
+/// # Example
+/// ```
+/// let path = "/usr/bin/auto_open";
+/// let result = check_if_file_exists(path);
+/// assert_eq!(result, true);
+/// ```
+///
+pub fn check_if_file_exists(path: &str) -> bool {
+    let path = Path::new(path);
+    path.is_file()
+}
+
+/// Check if a path is a block device
+/// # Arguments
+/// * `path` - The path to a block device, e.g. `/dev/sdb1`.
+/// # Returns
+/// * `bool` - True if the provided path is a block device otherwise false.
+/// # Example
+/// ```
+/// let path = "/dev/sdb1";
+/// let result = check_if_block_device(path);
+/// assert_eq!(result, true);
+/// ```
+///
+pub fn check_if_block_device(path: &str) -> bool {
+    match std::fs::metadata(path) {
+        Ok(metadata) => metadata.file_type().is_block_device(),
+        Err(_) => false,
+    }
+}
+
+/// Check if a directory exists
+/// # Arguments
+/// * `path` - The path to a directory.
+/// # Returns
+/// * `bool` - True if the provided path is a directory otherwise false.
+/// In case of an error, this error is returned.
+/// # Example
+/// ```
+/// let path = "/usr/bin";
+/// let result = check_if_file_exists(path);
+/// assert_eq!(result, true);
+/// ```
+///
+pub fn check_if_dir_exists(path: &str) -> bool {
+    let path = Path::new(path);
+    path.is_dir()
+}
+
+/// Check if a directory is writable
+/// # Arguments
+/// * `path` - The path to a directory.
+/// # Returns
+/// * `bool` - True if a file can be created in the provided directory otherwise false.
+/// # Note
+/// The check creates and removes a temporary file,
+/// so it also detects read-only file systems and missing permissions of root.
+/// # Example
+/// ```
+/// let path = "/tmp";
+/// let result = check_if_dir_writable(path);
+/// assert_eq!(result, true);
+/// ```
+///
+pub fn check_if_dir_writable(path: &str) -> bool {
+    let test_file = Path::new(path).join(format!(".secure_container_{}", std::process::id()));
+    match File::create(&test_file) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(test_file);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Create a file
+/// The blocks of the file are allocated with `fallocate`, so even large containers are created instantly
+/// and a file system without enough free space fails here instead of when the container is written.
+/// If the file system does not support `fallocate`, the zeros are written instead.
+/// # Arguments
+/// * `size` - Filesize in MB.
+/// * `path` - The path to where the file should be created.
+/// * `namespace` - The name of the file.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(())
+/// if the file was created successfully otherwise an error is returned.
+/// # Errors
+/// * `FileCreationError` - An error occurred while creating a file.
+/// * `FileWriteError` -
+/// An error occurred while writing to or syncing the file or the file is shorter than requested.
+/// # Example
+/// ```
+/// let size = 10;
+/// let path = "/usr/bin";
+/// let namespace = "test.txt";
+/// let result = create_file(size, path, namespace);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn create_file(size: i32, path: &str, namespace: &str) -> Result<()> {
+    let complete_path = Path::new(path).join(namespace);
+    let file_size_in_bytes = mb_in_bytes(size);
+    let mut file = match File::create(complete_path) {
+        Ok(file) => file,
+        Err(err) => return Err(SecureContainerErr::FileCreationError(err.to_string())),
+    };
+
+    match fallocate(
+        file.as_raw_fd(),
+        FallocateFlags::empty(),
+        0,
+        file_size_in_bytes as off_t,
+    ) {
+        Ok(_) => (),
+        Err(Errno::EOPNOTSUPP) => write_zeros(&mut file, file_size_in_bytes)?,
+        Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+    };
+
+    // The size must be durable before the container is formatted on the file.
+    match file.sync_all() {
+        Ok(_) => (),
+        Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+    };
+    verify_file_size(&file, file_size_in_bytes)
+}
+
+/// Writes zeros to a file from its current position, it is the fallback of `create_file`.
+/// # Arguments
+/// * `file` - The file.
+/// * `size` - The number of bytes to write.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the zeros were written successfully otherwise an error is returned.
+/// # Errors
+/// * `FileWriteError` - An error occurred while writing to the file.
+/// # Note
+/// This function is not meant to be called directly.
+fn write_zeros(file: &mut File, size: u64) -> Result<()> {
+    let mut bytes_written = 0;
+    while bytes_written < size {
+        let bytes_to_write = std::cmp::min(1024, size - bytes_written) as usize;
+        let data = vec![0u8; bytes_to_write];
+        match file.write_all(&data) {
+            Ok(_) => bytes_written += bytes_to_write as u64,
+            Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+        };
+    }
+    Ok(())
+}
+
+/// Checks that a written file has the expected size, so a short write is not formatted as container.
+/// # Arguments
+/// * `file` - The written file.
+/// * `size` - The expected size in bytes.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the file has the expected size otherwise an error is returned.
+/// # Errors
+/// * `FileWriteError` - The size of the file could not be read or differs from the expected size.
+/// # Note
+/// This function is not meant to be called directly.
+fn verify_file_size(file: &File, size: u64) -> Result<()> {
+    let length = match file.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+    };
+    if length != size {
+        return Err(SecureContainerErr::FileWriteError(format!(
+            "File has {} bytes instead of {} bytes",
+            length, size
+        )));
+    }
+    Ok(())
+}
+
+/// Check connected block devices using lsblk
+/// # Arguments
+/// * `name` - The name of the block device.
+/// # Returns
+/// * `Result<bool>` -
+/// Returns true if the block device is connected otherwise false.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `LsblkError` - An error occurred executing lsblk.
+/// * `ReadingStdoutError` - An error occurred while reading stdout.
+/// # Example
+/// ```
+/// let name = "myBlockDevice";
+/// let result = check_lsblk(name);
+/// assert_eq!(result.unwrap(), true);
+/// ```
+///
+pub fn check_lsblk(name: &str) -> Result<bool> {
+    let output = match deadline::output_or_kill(Command::new(&config().lsblk_path).args([
+        "--noheadings",
+        "--output",
+        "NAME",
+        "--raw",
+    ])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::LsblkError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::LsblkError(stderr.to_string()));
+    }
+    let stdout = match String::from_utf8(output.stdout) {
+        Ok(stdout) => stdout,
+        Err(err) => return Err(SecureContainerErr::ReadingStdoutError(err)),
+    };
+
+    Ok(parse_raw_names(&stdout).iter().any(|device| device == name))
+}
+
+/// Parses the output of `lsblk --noheadings --output NAME --raw`.
+/// The raw output has one device per line without tree glyphs,
+/// spaces and other unsafe characters in a name are escaped as `\xNN`.
+/// # Arguments
+/// * `raw` - The output of lsblk.
+/// # Returns
+/// * `Vec<String>` - The names of all devices, the nested devices included.
+/// # Note
+/// This function is not meant to be called directly.
+fn parse_raw_names(raw: &str) -> Vec<String> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(unescape_raw)
+        .collect()
+}
+
+/// Replaces the `\xNN` escapes of the raw output of lsblk by the bytes they stand for.
+/// # Arguments
+/// * `name` - An escaped name.
+/// # Returns
+/// * `String` - The name, an escape that is not valid is kept as it is.
+/// # Note
+/// This function is not meant to be called directly.
+fn unescape_raw(name: &str) -> String {
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .strip_prefix(b"x")
+            .filter(|_| byte == b'\\')
+            .and_then(|hex| hex.get(..2))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(value) => {
+                bytes.push(value);
+                rest = &tail[3..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Check if a container is mounted
+/// The mapper device of the container must exist and be listed in `/proc/mounts`,
+/// by its mapper name or the `/dev/dm-N` device it links to.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<bool>` -
+/// Returns true if the container is mounted otherwise false.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading `/proc/mounts`.
+/// # Example
+/// ```
+/// let namespace = "myContainer";
+/// let result = check_container_mounted(namespace);
+/// assert_eq!(result.unwrap(), true);
+/// ```
+///
+pub fn check_container_mounted(namespace: &str) -> Result<bool> {
+    let mapper_device = Path::new(MAPPER_DIR).join(namespace);
+    if !mapper_device.exists() {
+        return Ok(false);
+    }
+    let mounts = match std::fs::read_to_string(PROC_MOUNTS) {
+        Ok(mounts) => mounts,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    Ok(find_mount_point(&mounts, &linked_devices(&mapper_device)).is_some())
+}
+
+/// Create the file system of the container in /dev/mapper
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `fs_type` - The file system, one of `FILE_SYSTEMS` (see `input_validation`).
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the file system was created successfully otherwise an error is returned.
+/// # Errors
+/// * `UnsupportedFilesystem` - The given file system is not supported.
+/// * `MkfsError` - An error occurred creation the file system.
+/// # Example
+/// ```
+/// let namespace = "myContainer";
+/// let result = create_name_dir(namespace, "xfs");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn create_name_dir(namespace: &str, fs_type: &str) -> Result<()> {
+    let path = Path::new("/dev/mapper");
+    let file_path = path.join(namespace);
+    let mkfs = match fs_type {
+        "ext4" => &config().mkfs_ext4_path,
+        "xfs" => &config().mkfs_xfs_path,
+        "btrfs" => &config().mkfs_btrfs_path,
+        _ => return Err(SecureContainerErr::UnsupportedFilesystem),
+    };
+
+    let output = match deadline::output(Command::new(mkfs).args(&[file_path])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::MkfsError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::MkfsError(stderr.to_string()));
+    }
+
+    Ok(())
+}
+
+/// The flags the containers are mounted with when the `native-mount` feature is enabled and no options are given.
+/// The containers are mounted with the same defaults as the mount binary uses.
+#[cfg(feature = "native-mount")]
+const MOUNT_FLAGS: nix::mount::MsFlags = nix::mount::MsFlags::empty();
+
+/// Mount a device to a directory
+/// # Arguments
+/// * `mount_point` - The directory where the device should be mounted to.
+/// * `device` - The name of the device to be mounted.
+/// * `options` - The mount options, each one of `MOUNT_OPTIONS` (see `input_validation`), e.g. `["ro", "noexec"]`.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the device was mounted successfully otherwise an error is returned.
+/// # Errors
+/// * `MountOptionNotValid` - An option is not one of `MOUNT_OPTIONS`.
+/// * `MountError` - An error occurred while trying to mount the container.
+/// * `MountBusy` - The device or the mount point is busy (only with the `native-mount` feature).
+/// # Example
+/// ```
+/// let mount_point = "/home/MountMe";
+/// let device = "myContainer";
+/// let result = mount(mount_point, device, &["ro", "nosuid"]);
+/// assert!(result.is_ok());
+/// ```
+///
+#[cfg(not(feature = "native-mount"))]
+pub fn mount(mount_point: &str, device: &str, options: &[&str]) -> Result<()> {
+    let args = mount_args(mount_point, device, options)?;
+    let output = match deadline::output(Command::new(&config().mount_path).args(args)) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::MountError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::MountError(stderr.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Returns the arguments of the mount binary for mounting a device.
+/// The options are checked first, so nothing but the allowed options is passed with `-o`.
+/// # Arguments
+/// * `mount_point` - The directory where the device should be mounted to.
+/// * `device` - The name of the device to be mounted.
+/// * `options` - The mount options.
+/// # Returns
+/// * `Result<Vec<String>>` -
+/// Returns the arguments, `-o` and the comma separated options come first if options are given.
+/// # Errors
+/// * `MountOptionNotValid` - An option is not one of `MOUNT_OPTIONS`.
+/// # Note
+/// This function is not meant to be called directly.
+#[cfg(not(feature = "native-mount"))]
+fn mount_args(mount_point: &str, device: &str, options: &[&str]) -> Result<Vec<String>> {
+    check_mount_options(options)?;
+    let mut args = Vec::new();
+    if !options.is_empty() {
+        args.push("-o".to_string());
+        args.push(options.join(","));
+    }
+    args.push(format!("/dev/mapper/{}", device));
+    args.push(mount_point.to_string());
+    Ok(args)
+}
+
+/// Mount a device to a directory using the mount(2) system call.
+/// The file systems of `FILE_SYSTEMS` are tried one after another, like the mount binary does,
+/// because the kernel rejects a file system type that does not match the device with `EINVAL`.
+/// See the command based `mount` for the documentation.
+#[cfg(feature = "native-mount")]
+pub fn mount(mount_point: &str, device: &str, options: &[&str]) -> Result<()> {
+    let flags = mount_flags(options)?;
+    let device = "/dev/mapper/".to_owned() + device;
+    let mut result = Err(Errno::EINVAL);
+    for fs_type in FILE_SYSTEMS {
+        result = nix::mount::mount(
+            Some(device.as_str()),
+            mount_point,
+            Some(fs_type),
+            flags,
+            None::<&str>,
+        );
+        if result != Err(Errno::EINVAL) {
+            break;
+        }
+    }
+    match result {
+        Ok(()) => Ok(()),
+        Err(Errno::EBUSY) => Err(SecureContainerErr::MountBusy),
+        Err(errno) => Err(SecureContainerErr::MountError(errno.desc().to_string())),
+    }
+}
+
+/// Returns the flags of the mount(2) system call for the mount options.
+/// # Arguments
+/// * `options` - The mount options.
+/// # Returns
+/// * `Result<nix::mount::MsFlags>` - Returns `MOUNT_FLAGS` with the flag of every option.
+/// # Errors
+/// * `MountOptionNotValid` - An option is not one of `MOUNT_OPTIONS`.
+/// # Note
+/// This function is not meant to be called directly.
+#[cfg(feature = "native-mount")]
+fn mount_flags(options: &[&str]) -> Result<nix::mount::MsFlags> {
+    use nix::mount::MsFlags;
+
+    check_mount_options(options)?;
+    Ok(options
+        .iter()
+        .fold(MOUNT_FLAGS, |flags, option| match *option {
+            "ro" => flags | MsFlags::MS_RDONLY,
+            "noexec" => flags | MsFlags::MS_NOEXEC,
+            "nosuid" => flags | MsFlags::MS_NOSUID,
+            "nodev" => flags | MsFlags::MS_NODEV,
+            "noatime" => flags | MsFlags::MS_NOATIME,
+            _ => flags,
+        }))
+}
+
+/// Bind-mount a directory to another directory
+/// # Arguments
+/// * `source` - The directory that is mounted.
+/// * `target` - The directory where the source should be mounted to.
+/// * `read_only` - If true, the target is mounted read-only.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the directory was mounted successfully otherwise an error is returned.
+/// # Errors
+/// * `MountError` - An error occurred while trying to mount the directory.
+/// * `MountBusy` - The target is busy (only with the `native-mount` feature).
+/// # Example
+/// ```
+/// let result = bind_mount("/home/MountMe/data", "/srv/chroot/data", false);
+/// assert!(result.is_ok());
+/// ```
+///
+#[cfg(not(feature = "native-mount"))]
+pub fn bind_mount(source: &str, target: &str, read_only: bool) -> Result<()> {
+    let options = if read_only { "bind,ro" } else { "bind" };
+    let output = match deadline::output(
+        Command::new(&config().mount_path).args(["-o", options, source, target]),
+    ) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::MountError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::MountError(stderr.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Bind-mount a directory to another directory using the mount(2) system call.
+/// See the command based `bind_mount` for the documentation.
+#[cfg(feature = "native-mount")]
+pub fn bind_mount(source: &str, target: &str, read_only: bool) -> Result<()> {
+    use nix::mount::MsFlags;
+
+    let to_err = |errno: Errno| match errno {
+        Errno::EBUSY => SecureContainerErr::MountBusy,
+        errno => SecureContainerErr::MountError(errno.desc().to_string()),
+    };
+    nix::mount::mount(
+        Some(source),
+        target,
+        None::<&str>,
+        MsFlags::MS_BIND,
+        None::<&str>,
+    )
+    .map_err(to_err)?;
+    if read_only {
+        // The read-only flag of a bind mount can only be set by remounting it.
+        let flags = MsFlags::MS_BIND | MsFlags::MS_REMOUNT | MsFlags::MS_RDONLY;
+        if let Err(errno) =
+            nix::mount::mount(None::<&str>, target, None::<&str>, flags, None::<&str>)
+        {
+            let _ = nix::mount::umount2(target, nix::mount::MntFlags::empty());
+            return Err(to_err(errno));
+        }
+    }
+    Ok(())
+}
+
+/// Unmount a device from a directory
+/// # Arguments
+/// * `mount_point` - The directory where the device is mounted to.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the device was unmounted successfully otherwise an error is returned.
+/// # Errors
+/// * `UmountError` - An error occurred while the device was unmounted.
+/// * `MountBusy` - The mount point is still in use (only with the `native-mount` feature).
+/// # Example
+/// ```
+/// let mount_point = "/home/MountMe";
+/// let result = unmount(mount_point);
+/// assert!(result.is_ok());
+/// ```
+///
+#[cfg(not(feature = "native-mount"))]
+pub fn unmount(mount_point: &str) -> Result<()> {
+    let output = match deadline::output(Command::new(&config().umount_path).args([mount_point])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::UmountError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::UmountError(stderr.to_string()));
+    }
+    Ok(())
+}
+
+/// Unmount a device from a directory using the umount2(2) system call.
+/// See the command based `unmount` for the documentation.
+#[cfg(feature = "native-mount")]
+pub fn unmount(mount_point: &str) -> Result<()> {
+    match nix::mount::umount2(mount_point, nix::mount::MntFlags::empty()) {
+        Ok(()) => Ok(()),
+        Err(Errno::EBUSY) => Err(SecureContainerErr::MountBusy),
+        Err(errno) => Err(SecureContainerErr::UmountError(errno.desc().to_string())),
+    }
+}
+
+/// Create a swap area on a device using mkswap
+/// # Arguments
+/// * `device` - The path to the device, e.g. `/dev/mapper/mySwap`.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the swap area was created successfully otherwise an error is returned.
+/// # Errors
+/// * `MkfsError` - An error occurred creating the swap area.
+/// # Example
+/// ```
+/// let result = make_swap("/dev/mapper/mySwap");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn make_swap(device: &str) -> Result<()> {
+    let output = match deadline::output(Command::new(&config().mkswap_path).args([device])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::MkfsError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::MkfsError(stderr.to_string()));
+    }
+    Ok(())
+}
+
+/// Enable a device for swapping using swapon
+/// # Arguments
+/// * `device` - The path to the device, e.g. `/dev/mapper/mySwap`.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if swapping on the device was enabled successfully otherwise an error is returned.
+/// # Errors
+/// * `MountError` - An error occurred enabling the device.
+/// # Example
+/// ```
+/// let result = swap_on("/dev/mapper/mySwap");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn swap_on(device: &str) -> Result<()> {
+    let output = match deadline::output(Command::new(&config().swapon_path).args([device])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::MountError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::MountError(stderr.to_string()));
+    }
+    Ok(())
+}
+
+/// Disable a device for swapping using swapoff
+/// # Arguments
+/// * `device` - The path to the device, e.g. `/dev/mapper/mySwap`.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if swapping on the device was disabled successfully otherwise an error is returned.
+/// # Errors
+/// * `UmountError` - An error occurred disabling the device, e.g. there is not enough memory for the swapped pages.
+/// # Example
+/// ```
+/// let result = swap_off("/dev/mapper/mySwap");
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn swap_off(device: &str) -> Result<()> {
+    let output = match deadline::output(Command::new(&config().swapoff_path).args([device])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::UmountError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::UmountError(stderr.to_string()));
+    }
+    Ok(())
+}
+
+/// Get the file or block device an open container is stored in
+/// # Arguments
+/// * `namespace` - The name of the container in `/dev/mapper`.
+/// # Returns
+/// * `Result<String>` -
+/// Returns the backing file of the loop device the container is opened on,
+/// or the block device if the container is a block device.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `FileReadError` - The device or its underlying devices in `/sys/class/block` could not be read.
+/// # Example
+/// ```
+/// let result = get_backing_path("myContainer");
+/// assert_eq!(result, Ok("/home/Container/myContainer".to_string()));
+/// ```
+///
+pub fn get_backing_path(namespace: &str) -> Result<String> {
+    let device = match std::fs::canonicalize(format!("/dev/mapper/{}", namespace)) {
+        Ok(device) => device,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    let mut name = match device.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => {
+            return Err(SecureContainerErr::FileReadError(
+                "Not a device".to_string(),
+            ))
+        }
+    };
+    // A container with integrity protection is stacked on a second device-mapper device.
+    while name.starts_with("dm-") {
+        let slaves = match std::fs::read_dir(format!("/sys/class/block/{}/slaves", name)) {
+            Ok(slaves) => slaves,
+            Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+        };
+        name = match slaves.flatten().next() {
+            Some(slave) => slave.file_name().to_string_lossy().to_string(),
+            None => {
+                return Err(SecureContainerErr::FileReadError(format!(
+                    "No device below {}",
+                    name
+                )))
+            }
+        };
+    }
+    match std::fs::read_to_string(format!("/sys/class/block/{}/loop/backing_file", name)) {
+        Ok(backing_file) => Ok(backing_file.trim_end().to_string()),
+        Err(_) => Ok(format!("/dev/{}", name)),
+    }
+}
+
+/// The file in which the kernel lists all active swap devices.
+const PROC_SWAPS: &str = "/proc/swaps";
+
+/// Check if a device is used for swapping
+/// # Arguments
+/// * `device` - The path to the device, e.g. `/dev/mapper/mySwap`.
+/// # Returns
+/// * `Result<bool>` -
+/// Returns true if swapping on the device is enabled otherwise false.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading `/proc/swaps`.
+/// # Example
+/// ```
+/// let result = check_swap_active("/dev/mapper/mySwap");
+/// assert_eq!(result, Ok(true));
+/// ```
+///
+pub fn check_swap_active(device: &str) -> Result<bool> {
+    let swaps = match std::fs::read_to_string(PROC_SWAPS) {
+        Ok(swaps) => swaps,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    // The kernel lists the device as /dev/dm-N, independent of the path swapon was called with.
+    let mut devices = vec![device.to_string()];
+    if let Ok(resolved) = std::fs::canonicalize(device) {
+        devices.push(resolved.to_string_lossy().to_string());
+    }
+    Ok(find_swap(&swaps, &devices))
+}
+
+/// Searches the content of `/proc/swaps` for a device.
+/// # Arguments
+/// * `swaps` - The content of `/proc/swaps`.
+/// * `devices` - The names under which the device can be listed.
+/// # Returns
+/// * `bool` - Returns true if the device is listed.
+/// # Note
+/// This function is not meant to be called directly.
+fn find_swap(swaps: &str, devices: &[String]) -> bool {
+    swaps
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .any(|name| devices.contains(&unescape_mount_field(name)))
+}
+
+/// List all open crypt devices using lsblk
+/// # Arguments
+/// # Returns
+/// * `Result<Vec<BlockDevice>>` -
+/// Returns all devices of the type `crypt`, independent of how they were opened.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `LsblkError` - An error occurred executing lsblk or parsing its output.
+/// # Example
+/// ```
+/// let result = list_crypt_devices();
+/// for device in result.unwrap() {
+///     println!("{} {:?}", device.name, device.mountpoint);
+/// }
+/// ```
+///
+pub fn list_crypt_devices() -> Result<Vec<BlockDevice>> {
+    let output = match deadline::output_or_kill(Command::new(&config().lsblk_path).args([
+        "--json",
+        "-o",
+        "NAME,TYPE,MOUNTPOINT",
+    ])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::LsblkError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::LsblkError(stderr.to_string()));
+    }
+    parse_crypt_devices(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parses the output of `lsblk --json` and collects all crypt devices.
+/// # Arguments
+/// * `json` - The output of `lsblk --json -o NAME,TYPE,MOUNTPOINT`.
+/// # Returns
+/// * `Result<Vec<BlockDevice>>` -
+/// Returns all devices of the type `crypt`, the nested devices are searched as well.
+/// Crypt devices that only serve as layer for another crypt device,
+/// e.g. the integrity device of a container, are skipped.
+/// # Errors
+/// * `LsblkError` - The output is not valid.
+/// # Note
+/// This function is not meant to be called directly.
+fn parse_crypt_devices(json: &str) -> Result<Vec<BlockDevice>> {
+    let output: LsblkOutput = match serde_json::from_str(json) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::LsblkError(err.to_string())),
+    };
+    let mut crypt_devices = Vec::new();
+    let mut devices = output.blockdevices;
+    while let Some(mut device) = devices.pop() {
+        let is_layer = device
+            .children
+            .iter()
+            .any(|child| child.device_type == "crypt");
+        devices.append(&mut device.children);
+        if device.device_type == "crypt" && !is_layer {
+            crypt_devices.push(device);
+        }
+    }
+    crypt_devices.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(crypt_devices)
+}
+
+/// Check if a container is open
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<bool>` -
+/// Returns true if the container is open otherwise false.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `LsblkError` - An error occurred executing lsblk or parsing its output.
+/// * `ReadingStdoutError` - An error occurred while reading stdout.
+/// # Example
+/// ```
+/// let namespace = "myContainer";
+/// let result = check_container_open(namespace);
+/// assert_eq!(result.unwrap(), false);
+/// ```
+///
+pub fn check_container_open(namespace: &str) -> Result<bool> {
+    let output = match deadline::output_or_kill(Command::new(&config().lsblk_path).args([
+        "--json",
+        "-o",
+        "NAME,TYPE,MOUNTPOINT",
+    ])) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::LsblkError(err.to_string())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecureContainerErr::LsblkError(stderr.to_string()));
+    }
+
+    let stdout = match String::from_utf8(output.stdout) {
+        Ok(stdout) => stdout,
+        Err(err) => return Err(SecureContainerErr::ReadingStdoutError(err)),
+    };
+    let output: LsblkOutput = match serde_json::from_str(&stdout) {
+        Ok(output) => output,
+        Err(err) => return Err(SecureContainerErr::LsblkError(err.to_string())),
+    };
+    Ok(contains_crypt_device(&output.blockdevices, namespace))
+}
+
+/// Searches the device tree of lsblk for a crypt device.
+/// # Arguments
+/// * `devices` - The devices as they are reported by `lsblk --json`.
+/// * `name` - The exact name of the crypt device.
+/// # Returns
+/// * `bool` - True if one of the devices or their nested devices is a crypt device with the name.
+/// # Note
+/// This function is not meant to be called directly.
+fn contains_crypt_device(devices: &[BlockDevice], name: &str) -> bool {
+    devices.iter().any(|device| {
+        (device.device_type == "crypt" && device.name == name)
+            || contains_crypt_device(&device.children, name)
+    })
+}
+
+/// Get the size of a block device
+/// # Arguments
+/// * `device` - The path to the block device, e.g. `/dev/mapper/myContainer`.
+/// # Returns
+/// * `Result<u64>` -
+/// Returns the size of the block device in bytes.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `FileReadError` - The block device or its size in `/sys/class/block` could not be read.
+/// # Example
+/// ```
+/// let device = "/dev/mapper/myContainer";
+/// let result = get_device_size(device);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn get_device_size(device: &str) -> Result<u64> {
+    let device = match std::fs::canonicalize(device) {
+        Ok(device) => device,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    let name = match device.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => {
+            return Err(SecureContainerErr::FileReadError(
+                "Not a device".to_string(),
+            ))
+        }
+    };
+    let sectors = match std::fs::read_to_string(format!("/sys/class/block/{}/size", name)) {
+        Ok(sectors) => sectors,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    // The kernel reports the size in 512 byte sectors, independent of the sector size of the device.
+    match sectors.trim().parse::<u64>() {
+        Ok(sectors) => Ok(sectors * 512),
+        Err(err) => Err(SecureContainerErr::FileReadError(err.to_string())),
+    }
+}
+
+/// Check if two paths point to the same directory
+/// # Arguments
+/// * `first` - The first path.
+/// * `second` - The second path.
+/// # Returns
+/// * `bool` - Returns true if both paths resolve to the same directory.
+/// # Example
+/// ```
+/// assert!(is_same_dir("/tmp", "/tmp/../tmp"));
+/// ```
+///
+pub fn is_same_dir(first: &str, second: &str) -> bool {
+    match (std::fs::canonicalize(first), std::fs::canonicalize(second)) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => first == second,
+    }
+}
+
+/// The file in which the kernel lists all mounted file systems.
+const PROC_MOUNTS: &str = "/proc/mounts";
+
+/// The directory of the device-mapper devices, the opened containers are listed there by their name.
+const MAPPER_DIR: &str = "/dev/mapper";
+
+/// Get the mount point of an open container
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<Option<String>>` -
+/// Returns the path where the container is mounted or `None` if it is not mounted.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading `/proc/mounts`.
+/// # Example
+/// ```
+/// let namespace = "myContainer";
+/// let result = get_mount_point(namespace);
+/// assert_eq!(result, Ok(Some("/home/MountMe".to_string())));
+/// ```
+///
+pub fn get_mount_point(namespace: &str) -> Result<Option<String>> {
+    let mounts = match std::fs::read_to_string(PROC_MOUNTS) {
+        Ok(mounts) => mounts,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    Ok(find_mount_point(&mounts, &mapper_devices(namespace)))
+}
+
+/// Check if an open container is the file system that is mounted at a mount point
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `mount_point` - The path to the mount point.
+/// # Returns
+/// * `Result<bool>` -
+/// Returns true if the container is mounted at the mount point,
+/// false if nothing or a different file system is mounted there.
+/// If several file systems are mounted at the mount point, the one mounted last is checked,
+/// as it is the one that is unmounted first.
+/// In case of an error, this error is returned.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading `/proc/mounts`.
+/// # Example
+/// ```
+/// let result = check_mounted_at("myContainer", "/home/MountMe");
+/// assert_eq!(result, Ok(true));
+/// ```
+///
+pub fn check_mounted_at(namespace: &str, mount_point: &str) -> Result<bool> {
+    let mounts = match std::fs::read_to_string(PROC_MOUNTS) {
+        Ok(mounts) => mounts,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    // The kernel lists the resolved path of the mount point.
+    let mount_point = match std::fs::canonicalize(mount_point) {
+        Ok(mount_point) => mount_point.to_string_lossy().to_string(),
+        Err(_) => mount_point.to_string(),
+    };
+    Ok(find_mounted_device(&mounts, &mount_point)
+        .is_some_and(|device| mapper_devices(namespace).contains(&device)))
+}
+
+/// Check if a mount point is an empty directory that nothing is mounted on,
+/// so mounting a container there neither hides files nor stacks on another mount.
+/// # Arguments
+/// * `mount_point` - The path to the mount point.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the mount point is empty and not mounted otherwise an error is returned.
+/// # Errors
+/// * `MountPointNotEmpty` - The mount point contains files or something is mounted on it.
+/// * `FileReadError` - An error occurred while reading the mount point or `/proc/mounts`.
+/// # Example
+/// ```
+/// let result = check_mount_point_empty("/home/MountMe");
+/// assert_eq!(result, Ok(()));
+/// ```
+///
+pub fn check_mount_point_empty(mount_point: &str) -> Result<()> {
+    let mounts = match std::fs::read_to_string(PROC_MOUNTS) {
+        Ok(mounts) => mounts,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    // The kernel lists the resolved path of the mount point.
+    let resolved = match std::fs::canonicalize(mount_point) {
+        Ok(resolved) => resolved.to_string_lossy().to_string(),
+        Err(_) => mount_point.to_string(),
+    };
+    if find_mounted_device(&mounts, &resolved).is_some() {
+        return Err(SecureContainerErr::MountPointNotEmpty);
+    }
+    match std::fs::read_dir(mount_point) {
+        Ok(mut entries) => {
+            if entries.next().is_none() {
+                Ok(())
+            } else {
+                Err(SecureContainerErr::MountPointNotEmpty)
+            }
+        }
+        Err(err) => Err(SecureContainerErr::FileReadError(err.to_string())),
+    }
+}
+
+/// Returns the names under which the mapper device of a container is listed in `/proc/mounts`.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Vec<String>` - The mapper device and, if it exists, the `/dev/dm-N` device it links to.
+/// # Note
+/// This function is not meant to be called directly.
+fn mapper_devices(namespace: &str) -> Vec<String> {
+    linked_devices(&Path::new(MAPPER_DIR).join(namespace))
+}
+
+/// Returns the names under which a device is listed in `/proc/mounts`.
+/// # Arguments
+/// * `device` - The path to the device, e.g. `/dev/mapper/myContainer`.
+/// # Returns
+/// * `Vec<String>` - The path and, if it exists, the device it links to.
+/// # Note
+/// This function is not meant to be called directly.
+fn linked_devices(device: &Path) -> Vec<String> {
+    // Depending on how it was mounted, the device is listed by its mapper name or as /dev/dm-N.
+    let mut devices = vec![device.to_string_lossy().to_string()];
+    if let Ok(linked) = std::fs::canonicalize(device) {
+        devices.push(linked.to_string_lossy().to_string());
+    }
+    devices
+}
+
+/// Searches the content of `/proc/mounts` for the mount point of a device.
+/// # Arguments
+/// * `mounts` - The content of `/proc/mounts`.
+/// * `devices` - The names under which the device can be listed.
+/// # Returns
+/// * `Option<String>` - Returns the first mount point of the device or `None` if it is not mounted.
+/// # Note
+/// This function is not meant to be called directly.
+fn find_mount_point(mounts: &str, devices: &[String]) -> Option<String> {
+    for line in mounts.lines() {
+        let fields: Vec<&str> = line.split(' ').collect();
+        if fields.len() < 2 {
+            continue;
+        }
+        if devices.contains(&unescape_mount_field(fields[0])) {
+            return Some(unescape_mount_field(fields[1]));
+        }
+    }
+    None
+}
+
+/// Searches the content of `/proc/mounts` for the device that is mounted at a mount point.
+/// # Arguments
+/// * `mounts` - The content of `/proc/mounts`.
+/// * `mount_point` - The path to the mount point.
+/// # Returns
+/// * `Option<String>` -
+/// Returns the device that was mounted last at the mount point or `None` if nothing is mounted there.
+/// # Note
+/// This function is not meant to be called directly.
+fn find_mounted_device(mounts: &str, mount_point: &str) -> Option<String> {
+    let mut device = None;
+    for line in mounts.lines() {
+        let fields: Vec<&str> = line.split(' ').collect();
+        if fields.len() < 2 {
+            continue;
+        }
+        if unescape_mount_field(fields[1]) == mount_point {
+            device = Some(unescape_mount_field(fields[0]));
+        }
+    }
+    device
+}
+
+/// Replaces the octal escapes of `/proc/mounts`, e.g. `\040` for a space, with the original characters.
+/// # Arguments
+/// * `field` - A field of a line in `/proc/mounts`.
+/// # Returns
+/// * `String` - The field without escapes.
+/// # Note
+/// This function is not meant to be called directly.
+fn unescape_mount_field(field: &str) -> String {
+    let mut result = String::new();
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        let escape = rest.get(index + 1..index + 4).unwrap_or("");
+        match u8::from_str_radix(escape, 8) {
+            Ok(byte) if escape.len() == 3 => {
+                result.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            _ => {
+                result.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+
+    #[test]
+    #[cfg(not(feature = "native-mount"))]
+    fn test_mount_args() {
+        assert_eq!(
+            mount_args("/home/MountMe", "myContainer", &[]).unwrap(),
+            vec!["/dev/mapper/myContainer", "/home/MountMe"]
+        );
+        let args = mount_args("/home/MountMe", "myContainer", &["ro"]).unwrap();
+        assert_eq!(args[..2], ["-o", "ro"]);
+        assert_eq!(
+            mount_args("/home/MountMe", "myContainer", &["ro", "noexec", "nosuid"]).unwrap(),
+            vec![
+                "-o",
+                "ro,noexec,nosuid",
+                "/dev/mapper/myContainer",
+                "/home/MountMe"
+            ]
+        );
+        assert_eq!(
+            mount_args("/home/MountMe", "myContainer", &["ro,exec"]),
+            Err(SecureContainerErr::MountOptionNotValid)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "native-mount")]
+    fn test_mount_flags() {
+        use nix::mount::MsFlags;
+
+        assert_eq!(mount_flags(&[]), Ok(MOUNT_FLAGS));
+        assert_eq!(
+            mount_flags(&["ro", "nosuid"]),
+            Ok(MsFlags::MS_RDONLY | MsFlags::MS_NOSUID)
+        );
+        assert_eq!(
+            mount_flags(&["exec"]),
+            Err(SecureContainerErr::MountOptionNotValid)
+        );
+    }
+
+    #[test]
+    fn test_check_if_dir_writable() {
+        let path = std::env::current_dir().unwrap();
+        assert!(check_if_dir_writable(path.to_str().unwrap()));
+        assert!(!check_if_dir_writable("/proc"));
+        assert!(!check_if_dir_writable("/not/existing/dir"));
+    }
+
+    #[test]
+    fn test_verify_file_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = File::create(dir.path().join("container")).unwrap();
+        file.set_len(2048).unwrap();
+        assert_eq!(verify_file_size(&file, 2048), Ok(()));
+        // A truncated file is detected.
+        file.set_len(1024).unwrap();
+        assert!(matches!(
+            verify_file_size(&file, 2048),
+            Err(SecureContainerErr::FileWriteError(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            create_file(16, dir.path().to_str().unwrap(), "container"),
+            Ok(())
+        );
+        let length = std::fs::metadata(dir.path().join("container"))
+            .unwrap()
+            .len();
+        assert_eq!(length, 16 * 1024 * 1024);
+        // The blocks are allocated, the file is not sparse.
+        let blocks = std::fs::metadata(dir.path().join("container"))
+            .unwrap()
+            .blocks();
+        assert!(blocks * 512 >= length);
+
+        let mut file = File::create(dir.path().join("zeros")).unwrap();
+        assert_eq!(write_zeros(&mut file, 3000), Ok(()));
+        assert_eq!(verify_file_size(&file, 3000), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_raw_names() {
+        // Captured from `lsblk --noheadings --output NAME --raw` with an open container on a loop device.
+        let raw = "loop0\nMyContainer_dif\nMyContainer\nsda\nsda1\nsda2\nMy\\x20Space\n";
+        let names = parse_raw_names(raw);
+        assert_eq!(
+            names,
+            vec![
+                "loop0",
+                "MyContainer_dif",
+                "MyContainer",
+                "sda",
+                "sda1",
+                "sda2",
+                "My Space"
+            ]
+        );
+        assert!(names.iter().any(|name| name == "MyContainer"));
+        assert!(!names.iter().any(|name| name == "MyContain"));
+        assert_eq!(parse_raw_names(""), Vec::<String>::new());
+        assert_eq!(unescape_raw("a\\x2"), "a\\x2");
+        assert_eq!(unescape_raw("a\\xzz"), "a\\xzz");
+    }
+
+    #[test]
+    fn test_contains_crypt_device() {
+        let json = r#"{"blockdevices": [
+            {"name": "sda", "type": "disk", "mountpoint": null, "children": [
+                {"name": "sda1", "type": "part", "mountpoint": "/"}
+            ]},
+            {"name": "loop0", "type": "loop", "mountpoint": null, "children": [
+                {"name": "data2_dif", "type": "crypt", "mountpoint": null, "children": [
+                    {"name": "data2", "type": "crypt", "mountpoint": "/mnt/data2"}
+                ]}
+            ]},
+            {"name": "loop1", "type": "loop", "mountpoint": null}
+        ]}"#;
+        let output: LsblkOutput = serde_json::from_str(json).unwrap();
+        let devices = output.blockdevices;
+        // The nested child of the integrity device is found.
+        assert!(contains_crypt_device(&devices, "data2"));
+        assert!(contains_crypt_device(&devices, "data2_dif"));
+        // A namespace that is a prefix of another one is not open.
+        assert!(!contains_crypt_device(&devices, "data"));
+        assert!(!contains_crypt_device(&devices, "data22"));
+        // Only crypt devices count.
+        assert!(!contains_crypt_device(&devices, "loop1"));
+        assert!(!contains_crypt_device(&[], "data2"));
+    }
+
+    #[test]
+    fn test_parse_crypt_devices() {
+        let json = r#"{"blockdevices": [
+            {"name": "sda", "type": "disk", "mountpoint": null, "children": [
+                {"name": "sda1", "type": "part", "mountpoint": "/"}
+            ]},
+            {"name": "loop0", "type": "loop", "mountpoint": null, "children": [
+                {"name": "second", "type": "crypt", "mountpoint": null}
+            ]},
+            {"name": "loop1", "type": "loop", "mountpoint": null, "children": [
+                {"name": "first_dif", "type": "crypt", "mountpoint": null, "children": [
+                    {"name": "first", "type": "crypt", "mountpoint": "/mnt"}
+                ]}
+            ]}
+        ]}"#;
+        let devices = parse_crypt_devices(json).unwrap();
+        let names: Vec<&str> = devices.iter().map(|device| device.name.as_str()).collect();
+        assert_eq!(names, ["first", "second"]);
+        assert_eq!(devices[0].mountpoint, Some("/mnt".to_string()));
+        assert!(parse_crypt_devices("not json").is_err());
+    }
+
+    #[test]
+    fn test_check_if_block_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let device = dir.path().join("device");
+        let device = device.to_str().unwrap();
+        // A device node of the first loop device, only root is allowed to create it.
+        let created = Command::new("mknod")
+            .args([device, "b", "7", "0"])
+            .status()
+            .is_ok_and(|status| status.success());
+        if created {
+            assert!(check_if_block_device(device));
+            assert!(!check_if_file_exists(device));
+        }
+        let file = dir.path().join("file");
+        File::create(&file).unwrap();
+        assert!(!check_if_block_device(file.to_str().unwrap()));
+        assert!(!check_if_block_device("/not/existing/device"));
+    }
+
+    #[test]
+    fn test_find_swap() {
+        let swaps = "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n\
+                     /dev/dm-3                               partition\t102396\t\t0\t\t-2\n\
+                     /swap\\040file                           file\t\t1024\t\t0\t\t-3\n";
+        assert!(find_swap(swaps, &["/dev/dm-3".to_string()]));
+        assert!(find_swap(swaps, &["/swap file".to_string()]));
+        assert!(!find_swap(swaps, &["/dev/dm-4".to_string()]));
+        assert!(!find_swap(swaps, &["Filename".to_string()]));
+    }
+
+    #[test]
+    fn test_is_same_dir() {
+        assert!(is_same_dir("/tmp", "/tmp/../tmp"));
+        assert!(!is_same_dir("/tmp", "/proc"));
+    }
+
+    #[test]
+    fn test_linked_devices_prefix() {
+        // Two mapper-like names, one is a prefix of the other, link to different devices.
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("dm-0"), b"").unwrap();
+        std::fs::write(dir.path().join("dm-1"), b"").unwrap();
+        let mapper = dir.path().join("mapper");
+        std::fs::create_dir(&mapper).unwrap();
+        std::os::unix::fs::symlink("../dm-0", mapper.join("data")).unwrap();
+        std::os::unix::fs::symlink("../dm-1", mapper.join("data2")).unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let mounts = format!("{}/dm-1 /mnt/data2 ext4 rw 0 0\n", root.display());
+
+        let data2 = linked_devices(&mapper.join("data2"));
+        assert_eq!(data2[1], format!("{}/dm-1", root.display()));
+        assert_eq!(
+            find_mount_point(&mounts, &data2),
+            Some("/mnt/data2".to_string())
+        );
+        assert_eq!(
+            find_mount_point(&mounts, &linked_devices(&mapper.join("data"))),
+            None
+        );
+        let by_name = format!(
+            "{} /mnt/data2 ext4 rw 0 0\n",
+            mapper.join("data2").display()
+        );
+        assert_eq!(
+            find_mount_point(&by_name, &linked_devices(&mapper.join("data"))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_mount_point() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+            /dev/mapper/first /mnt/first ext4 rw 0 0\n\
+            /dev/dm-3 /mnt/with\\040space ext4 rw 0 0\n";
+        assert_eq!(
+            find_mount_point(mounts, &["/dev/mapper/first".to_string()]),
+            Some("/mnt/first".to_string())
+        );
+        assert_eq!(
+            find_mount_point(
+                mounts,
+                &["/dev/mapper/second".to_string(), "/dev/dm-3".to_string()]
+            ),
+            Some("/mnt/with space".to_string())
+        );
+        assert_eq!(
+            find_mount_point(mounts, &["/dev/mapper/third".to_string()]),
+            None
+        );
+        assert_eq!(unescape_mount_field("a\\134b\\x"), "a\\b\\x");
+    }
+
+    #[test]
+    fn test_find_mounted_device() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+            /dev/mapper/first /mnt/first ext4 rw 0 0\n\
+            /dev/sdb1 /mnt/stacked ext4 rw 0 0\n\
+            /dev/mapper/second /mnt/stacked ext4 rw 0 0\n\
+            /dev/dm-3 /mnt/with\\040space ext4 rw 0 0\n";
+        assert_eq!(
+            find_mounted_device(mounts, "/mnt/first"),
+            Some("/dev/mapper/first".to_string())
+        );
+        assert_eq!(
+            find_mounted_device(mounts, "/mnt/stacked"),
+            Some("/dev/mapper/second".to_string())
+        );
+        assert_eq!(
+            find_mounted_device(mounts, "/mnt/with space"),
+            Some("/dev/dm-3".to_string())
+        );
+        assert_eq!(find_mounted_device(mounts, "/mnt"), None);
+        assert_eq!(check_mounted_at("not_existing", "/"), Ok(false));
+    }
+
+    #[test]
+    fn test_check_mount_point_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let mount_point = dir.path().to_str().unwrap();
+        assert_eq!(check_mount_point_empty(mount_point), Ok(()));
+        std::fs::write(dir.path().join("data"), b"data").unwrap();
+        assert_eq!(
+            check_mount_point_empty(mount_point),
+            Err(SecureContainerErr::MountPointNotEmpty)
+        );
+        assert_eq!(
+            check_mount_point_empty("/proc"),
+            Err(SecureContainerErr::MountPointNotEmpty)
+        );
+    }
+}
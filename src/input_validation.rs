@@ -0,0 +1,603 @@
+//! # Input validation
+//! This module contains the validation of the inputs of the container operations.
+//!
+use crate::error_handling;
+use error_handling::{Result, SecureContainerErr};
+
+use crate::file_system_operations;
+use file_system_operations::{check_if_block_device, check_if_dir_exists, check_if_file_exists};
+
+use crate::cryptsetup_wrapper;
+use cryptsetup_wrapper::check_if_file_is_container;
+
+use libuta_rs::MAX_DERIVATION_STRING_LENGTH;
+
+use crate::cryptsetup_backend::LUKS2_KEYSLOTS;
+
+/// The maximum length of the device name of a namespace (see `device_name`).
+/// The device name is used as device-mapper name, which the kernel limits to 127 characters.
+/// The integrity device of a container gets an additional suffix,
+/// so the limit is set well below the kernel limit.
+pub const MAX_NAMESPACE_LENGTH: usize = 64;
+
+/// The maximum length of an id, it is limited to what libuta accepts as derivation string.
+/// Only containers with `metadata::KEY_DERIVATION` derive their password from the whole id,
+/// older containers only use its first 8 bytes.
+pub const MAX_ID_LENGTH: usize = MAX_DERIVATION_STRING_LENGTH;
+
+/// The minimum size of a container in MB, smaller containers can not hold the LUKS2 header.
+pub const MIN_CONTAINER_SIZE: i32 = 16;
+
+/// The file systems a container can be created with, `create_name_dir` runs the matching `mkfs` binary.
+pub const FILE_SYSTEMS: [&str; 3] = ["ext4", "xfs", "btrfs"];
+
+/// The file system of a container if none is requested.
+pub const DEFAULT_FILE_SYSTEM: &str = "ext4";
+
+/// The options a container can be mounted with, they only restrict what is allowed on the file system.
+pub const MOUNT_OPTIONS: [&str; 5] = ["ro", "noexec", "nosuid", "nodev", "noatime"];
+
+/// Returns the name under which a container is known to the system,
+/// it is used as device-mapper name and as file name of the container.
+/// Every byte of the namespace that is not ASCII or is a `%` is percent-encoded,
+/// so ASCII namespaces are used unchanged.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `String` - The device name.
+/// # Example
+/// ```
+/// assert_eq!(device_name("MyContainer"), "MyContainer");
+/// assert_eq!(device_name("Überweisungen"), "%C3%9Cberweisungen");
+/// ```
+///
+pub fn device_name(namespace: &str) -> String {
+    let mut name = String::new();
+    for byte in namespace.bytes() {
+        if byte.is_ascii() && byte != b'%' {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    name
+}
+
+/// Returns the namespace of a device name, this reverses `device_name`.
+/// # Arguments
+/// * `device_name` - The name of the device.
+/// # Returns
+/// * `String` -
+/// The namespace or the device name itself if it is not a valid encoding,
+/// e.g. for devices that were not opened by the secure container service.
+/// # Example
+/// ```
+/// assert_eq!(namespace_of_device("%C3%9Cberweisungen"), "Überweisungen");
+/// ```
+///
+pub fn namespace_of_device(device_name: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = device_name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let value = tail
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match value {
+                Some(value) => {
+                    bytes.push(value);
+                    rest = &tail[2..];
+                    continue;
+                }
+                None => return device_name.to_string(),
+            }
+        }
+        bytes.push(byte);
+        rest = tail;
+    }
+    String::from_utf8(bytes).unwrap_or_else(|_| device_name.to_string())
+}
+
+/// Checks the given input if they are valid and can be used further by different functions.
+/// # Arguments
+/// * `size` - The size of the container in MB (must be at least 16MB).
+/// * `mount_point` - The path to the mount point (must already exist).
+/// * `path` - The path to the container, either a file or a block device.
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the provided inputs are valid otherwise an error is returned.
+/// # Errors
+/// * `SizeToSmall` -
+/// The given size for the container is zero, negative or smaller than `MIN_CONTAINER_SIZE`.
+/// * `MountPointNotExists` - The given mount point does not exist.
+/// * `NamespaceNotValid` - The given namespace is not allowed, see `namespace_allowed`.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `PathNotValid` - The given path contains non-ascii characters or a pipe or starts with a dash.
+/// * `PathNotExists` - The given path does not exist.
+/// * `MountPointConflictsWithPath` -
+/// The given mount point is the given path or one of its parent directories,
+/// so mounting the container would hide its own file.
+/// * `PathNotLuksContainer` - The given path is not a LUKS container.
+/// * `IsNotLuks` - The provided file is not a LUKS container.
+/// # Example
+/// ```
+/// use secure_container::input_validation::{check_input};
+/// let size = 12;
+/// let mount_point = "/home/MountMe";
+/// let path = "/home/Container";
+/// let namespace = "MyContainer";
+/// let id = "myId";
+/// let result = check_input(Some(size), Some(mount_point), Some(path), Some(namespace), Some(id));
+/// assert_eq!(result, Err(SecureContainerErr::SizeToSmall));
+/// ```
+///
+pub fn check_input(
+    size: Option<i32>,
+    mount_point: Option<&str>,
+    path: Option<&str>,
+    namespace: Option<&str>,
+    id: Option<&str>,
+) -> Result<()> {
+    if let Some(size) = size {
+        // A zero or negative size would wrap around when it is converted to bytes.
+        if size <= 0 || size < MIN_CONTAINER_SIZE {
+            return Err(SecureContainerErr::SizeToSmall);
+        }
+    }
+
+    if mount_point.is_some() && !check_if_dir_exists(mount_point.unwrap()) {
+        return Err(SecureContainerErr::MountPointNotExists);
+    }
+
+    if namespace.is_some() && !namespace_allowed(namespace.unwrap()) {
+        return Err(SecureContainerErr::NamespaceNotValid);
+    }
+
+    if id.is_some()
+        && (id.unwrap().contains('|')
+            || !id.unwrap().is_ascii()
+            || id.unwrap().len() > MAX_ID_LENGTH)
+    {
+        return Err(SecureContainerErr::IdNotValid);
+    }
+
+    // A path that starts with a dash would be read as an option by cryptsetup and losetup.
+    if path.is_some()
+        && (!path.unwrap().is_ascii()
+            || path.unwrap().contains('|')
+            || path.unwrap().starts_with('-'))
+    {
+        return Err(SecureContainerErr::PathNotValid);
+    }
+
+    if path.is_some()
+        && !check_if_file_exists(path.unwrap())
+        && !check_if_block_device(path.unwrap())
+    {
+        return Err(SecureContainerErr::PathNotExists);
+    }
+
+    if mount_point.is_some() && path.is_some() && overlaps(mount_point.unwrap(), path.unwrap()) {
+        return Err(SecureContainerErr::MountPointConflictsWithPath);
+    }
+
+    if path.is_some() && check_if_file_is_container(path.unwrap()).is_err() {
+        return Err(SecureContainerErr::PathNotLuksContainer);
+    }
+
+    Ok(())
+}
+
+/// Checks if a namespace can be used as name of a container.
+/// The ASCII characters of the namespace must be letters, digits, `_` or `-`,
+/// so its device name is a single path component that is not read as an option.
+/// Other characters are allowed, they are percent-encoded in the device name (see `device_name`).
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `bool` -
+/// Returns true if the namespace is not empty, does not start with a dash,
+/// contains no other ASCII characters and its device name is at most `MAX_NAMESPACE_LENGTH` characters long.
+/// # Example
+/// ```
+/// assert!(namespace_allowed("My_Container-1"));
+/// assert!(namespace_allowed("Überweisungen"));
+/// assert!(!namespace_allowed("../etc"));
+/// assert!(!namespace_allowed("--help"));
+/// ```
+///
+pub fn namespace_allowed(namespace: &str) -> bool {
+    !namespace.is_empty()
+        && !namespace.starts_with('-')
+        && namespace.chars().all(|c| {
+            !c.is_ascii() && !c.is_control() || c.is_ascii_alphanumeric() || c == '_' || c == '-'
+        })
+        && device_name(namespace).len() <= MAX_NAMESPACE_LENGTH
+}
+
+/// Checks if a mount point and the path of a container overlap,
+/// i.e. one of them is the other or lies within it.
+/// Both paths are canonicalized, so symbolic links and relative paths are resolved.
+/// # Arguments
+/// * `mount_point` - The path to the mount point.
+/// * `path` - The path to the container.
+/// # Returns
+/// * `bool` - Returns true if the paths overlap, false if they don't or cannot be resolved.
+/// # Example
+/// ```
+/// assert!(overlaps("/home", "/home/Container"));
+/// assert!(!overlaps("/home/MountMe", "/home/Container"));
+/// ```
+///
+pub fn overlaps(mount_point: &str, path: &str) -> bool {
+    match (
+        std::fs::canonicalize(mount_point),
+        std::fs::canonicalize(path),
+    ) {
+        (Ok(mount_point), Ok(path)) => {
+            path.starts_with(&mount_point) || mount_point.starts_with(&path)
+        }
+        _ => false,
+    }
+}
+
+/// Checks if a container can be created with a file system.
+/// # Arguments
+/// * `fs_type` - The file system, e.g. `ext4`.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the file system is one of `FILE_SYSTEMS` otherwise an error is returned.
+/// # Errors
+/// * `UnsupportedFilesystem` - The given file system is not one of `FILE_SYSTEMS`.
+/// # Example
+/// ```
+/// assert!(check_file_system("xfs").is_ok());
+/// assert_eq!(check_file_system("ntfs"), Err(SecureContainerErr::UnsupportedFilesystem));
+/// ```
+///
+pub fn check_file_system(fs_type: &str) -> Result<()> {
+    if FILE_SYSTEMS.contains(&fs_type) {
+        Ok(())
+    } else {
+        Err(SecureContainerErr::UnsupportedFilesystem)
+    }
+}
+
+/// Checks if a container can be mounted with the given options.
+/// # Arguments
+/// * `options` - The mount options, e.g. `["ro", "noexec"]`.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if every option is one of `MOUNT_OPTIONS` otherwise an error is returned.
+/// # Errors
+/// * `MountOptionNotValid` - An option is not one of `MOUNT_OPTIONS`.
+/// # Example
+/// ```
+/// assert!(check_mount_options(&["ro", "nosuid"]).is_ok());
+/// assert_eq!(check_mount_options(&["exec"]), Err(SecureContainerErr::MountOptionNotValid));
+/// ```
+///
+pub fn check_mount_options(options: &[&str]) -> Result<()> {
+    if options.iter().all(|option| MOUNT_OPTIONS.contains(option)) {
+        Ok(())
+    } else {
+        Err(SecureContainerErr::MountOptionNotValid)
+    }
+}
+
+/// Checks if a key slot is one of the key slots of a LUKS2 container.
+/// # Arguments
+/// * `key_slot` - The key slot as it is requested, e.g. `1`.
+/// # Returns
+/// * `Result<u8>` -
+/// Returns the key slot if it is below `LUKS2_KEYSLOTS` otherwise an error is returned.
+/// # Errors
+/// * `KeySlotNotValid` - The key slot is not below `LUKS2_KEYSLOTS`.
+/// # Example
+/// ```
+/// assert_eq!(check_key_slot(1), Ok(1));
+/// assert_eq!(check_key_slot(256), Err(SecureContainerErr::KeySlotNotValid));
+/// ```
+///
+pub fn check_key_slot(key_slot: u32) -> Result<u8> {
+    u8::try_from(key_slot)
+        .ok()
+        .filter(|key_slot| *key_slot < LUKS2_KEYSLOTS)
+        .ok_or(SecureContainerErr::KeySlotNotValid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_check_input() {
+        let path = std::env::current_dir().unwrap();
+        let _file = File::create(path.join("test.txt"));
+        let mount_point = path.to_str().unwrap();
+        let path = path.join("test.txt");
+        let path = path.to_str().unwrap();
+        let namespace = "test";
+        let id = "test";
+        let size = 16;
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some("not_exists"),
+                Some(namespace),
+                Some(id)
+            ),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some("not_ascii€"),
+                Some(namespace),
+                Some(id)
+            ),
+            Err(SecureContainerErr::PathNotValid)
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some("contains|"),
+                Some(namespace),
+                Some(id)
+            ),
+            Err(SecureContainerErr::PathNotValid)
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some(path),
+                Some(namespace),
+                Some(id)
+            ),
+            Err(SecureContainerErr::MountPointConflictsWithPath)
+        );
+        let other_mount_point = std::env::temp_dir();
+        assert_eq!(
+            check_input(
+                Some(size),
+                other_mount_point.to_str(),
+                Some(path),
+                Some(namespace),
+                Some(id)
+            ),
+            Err(SecureContainerErr::PathNotLuksContainer)
+        );
+        assert_eq!(
+            check_input(
+                Some(15),
+                Some(mount_point),
+                Some(path),
+                Some(namespace),
+                Some(id)
+            ),
+            Err(SecureContainerErr::SizeToSmall)
+        );
+        assert_eq!(
+            check_input(Some(0), None, None, None, None),
+            Err(SecureContainerErr::SizeToSmall)
+        );
+        assert_eq!(
+            check_input(Some(-100), None, None, None, None),
+            Err(SecureContainerErr::SizeToSmall)
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some("not_exists"),
+                Some(path),
+                Some(namespace),
+                Some(id)
+            ),
+            Err(SecureContainerErr::MountPointNotExists)
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some(path),
+                Some("test|"),
+                Some(id)
+            ),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+        assert_eq!(
+            check_input(None, None, None, Some("Überweisungen"), None),
+            Ok(())
+        );
+        assert_eq!(
+            check_input(None, None, None, Some("test\n"), None),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+        for namespace in [
+            "",
+            "my container",
+            "a/b",
+            "../etc",
+            "-v",
+            "--help",
+            "a.b",
+            "a%b",
+            "a$b",
+        ] {
+            assert_eq!(
+                check_input(None, None, None, Some(namespace), None),
+                Err(SecureContainerErr::NamespaceNotValid),
+                "{}",
+                namespace
+            );
+        }
+        assert_eq!(
+            check_input(None, None, None, Some("My_Container-1"), None),
+            Ok(())
+        );
+        assert_eq!(
+            check_input(None, None, Some("--key-file=/tmp/key"), None, None),
+            Err(SecureContainerErr::PathNotValid)
+        );
+        assert_eq!(
+            check_input(None, None, None, Some(&"ü".repeat(11)), None),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+        assert_eq!(
+            check_input(
+                None,
+                None,
+                None,
+                Some(&"a".repeat(MAX_NAMESPACE_LENGTH + 1)),
+                None
+            ),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+        assert_eq!(
+            check_input(
+                None,
+                None,
+                None,
+                Some(&"a".repeat(MAX_NAMESPACE_LENGTH)),
+                None
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some(path),
+                Some(namespace),
+                Some("test€")
+            ),
+            Err(SecureContainerErr::IdNotValid)
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some(path),
+                Some(namespace),
+                Some("test|")
+            ),
+            Err(SecureContainerErr::IdNotValid)
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                Some(path),
+                Some(namespace),
+                Some(&"a".repeat(MAX_ID_LENGTH + 1))
+            ),
+            Err(SecureContainerErr::IdNotValid)
+        );
+        assert_eq!(
+            check_input(None, None, None, None, Some("testtest")),
+            Ok(())
+        );
+        assert_eq!(
+            check_input(None, None, None, None, Some(&"a".repeat(32))),
+            Ok(())
+        );
+        assert_eq!(
+            check_input(
+                Some(size),
+                Some(mount_point),
+                None,
+                Some(namespace),
+                Some("test")
+            ),
+            Ok(())
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_check_key_slot() {
+        assert_eq!(check_key_slot(0), Ok(0));
+        assert_eq!(check_key_slot(31), Ok(31));
+        for key_slot in [32, 255, 256, u32::MAX] {
+            assert_eq!(
+                check_key_slot(key_slot),
+                Err(SecureContainerErr::KeySlotNotValid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_mount_options() {
+        assert_eq!(check_mount_options(&[]), Ok(()));
+        assert_eq!(check_mount_options(&MOUNT_OPTIONS), Ok(()));
+        for options in [
+            &["exec"][..],
+            &["ro,exec"],
+            &["ro", "suid"],
+            &["ro -o exec"],
+            &[""],
+        ] {
+            assert_eq!(
+                check_mount_options(options),
+                Err(SecureContainerErr::MountOptionNotValid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_file_system() {
+        for fs_type in FILE_SYSTEMS {
+            assert_eq!(check_file_system(fs_type), Ok(()));
+        }
+        assert!(FILE_SYSTEMS.contains(&DEFAULT_FILE_SYSTEM));
+        for fs_type in ["ntfs", "", "EXT4", "ext4 "] {
+            assert_eq!(
+                check_file_system(fs_type),
+                Err(SecureContainerErr::UnsupportedFilesystem)
+            );
+        }
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let dir = std::env::temp_dir().join("test_overlaps");
+        let _ = std::fs::create_dir_all(dir.join("MountMe"));
+        let _file = File::create(dir.join("Container"));
+        let dir_str = dir.to_str().unwrap();
+        let container = dir.join("Container");
+        let container = container.to_str().unwrap();
+        assert!(overlaps(dir_str, container));
+        assert!(overlaps(&format!("{}/MountMe/..", dir_str), container));
+        assert!(overlaps("/", container));
+        assert!(overlaps(dir_str, dir_str));
+        assert!(overlaps(container, container));
+        assert!(!overlaps(dir.join("MountMe").to_str().unwrap(), container));
+        assert!(!overlaps(dir_str, "not_exists"));
+        assert_eq!(
+            check_input(None, Some(dir_str), Some(container), None, None),
+            Err(SecureContainerErr::MountPointConflictsWithPath)
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_device_name() {
+        assert_eq!(device_name("MyContainer"), "MyContainer");
+        assert_eq!(device_name("Überweisungen"), "%C3%9Cberweisungen");
+        assert_eq!(device_name("100%"), "100%25");
+        assert_eq!(device_name("日記"), "%E6%97%A5%E8%A8%98");
+        for namespace in ["MyContainer", "Überweisungen", "100%", "日記"] {
+            assert_eq!(namespace_of_device(&device_name(namespace)), namespace);
+        }
+        assert_eq!(namespace_of_device("luks-1%"), "luks-1%");
+        assert_eq!(namespace_of_device("%FF"), "%FF");
+    }
+}
@@ -0,0 +1,129 @@
+//! # Keyring
+//! This module caches the derived passwords of containers in the session keyring of the daemon,
+//! so opening a container again within a session does not derive the key with libuta again.
+//! Every password is stored under a description per container and id and expires after
+//! `keyring_timeout` seconds (see `config`).
+//! The keyring is only used if the project is built with the `keyring` feature,
+//! otherwise no password is cached and every password is derived with libuta.
+//!
+//! ## Usage
+//! ```bash
+//! cargo build --release --features keyring
+//! ```
+//!
+
+use crate::error_handling;
+use error_handling::{Result, SecureContainerErr};
+
+use crate::input_validation::device_name;
+
+#[cfg(feature = "keyring")]
+use crate::config::config;
+#[cfg(feature = "keyring")]
+use linux_keyutils::{KeyRing, KeyRingIdentifier};
+
+/// Returns the description under which the password of a container is stored in the keyring.
+/// The id is part of the description, so a cached password is only used for the id it was derived from.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// # Returns
+/// * `String` - The description of the key.
+/// # Example
+/// ```
+/// assert_eq!(key_description("MyContainer", "myId"), "secure_container:MyContainer:myId");
+/// ```
+///
+#[cfg_attr(not(feature = "keyring"), allow(dead_code))]
+pub fn key_description(namespace: &str, id: &str) -> String {
+    format!("secure_container:{}:{}", device_name(namespace), id)
+}
+
+/// Reads the cached password of a container from the session keyring.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// # Returns
+/// * `Option<String>` -
+/// Returns the password or `None` if it is not cached, has expired or the keyring can not be read.
+/// # Example
+/// ```
+/// let password = cached_password("MyContainer", "myId");
+/// assert!(password.is_none());
+/// ```
+///
+#[cfg(feature = "keyring")]
+pub fn cached_password(namespace: &str, id: &str) -> Option<String> {
+    let keyring = KeyRing::from_special_id(KeyRingIdentifier::Session, false).ok()?;
+    let key = keyring.search(&key_description(namespace, id)).ok()?;
+    String::from_utf8(key.read_to_vec().ok()?).ok()
+}
+
+/// Without the `keyring` feature no password is cached.
+#[cfg(not(feature = "keyring"))]
+pub fn cached_password(_namespace: &str, _id: &str) -> Option<String> {
+    None
+}
+
+/// Stores the password of a container in the session keyring, a cached password is replaced.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// * `password` - The password that was derived for the id.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the password was cached otherwise an error is returned.
+/// # Errors
+/// * `KeyringError` -
+/// An error occurred while accessing the keyring or the project is built without the `keyring` feature.
+/// # Example
+/// ```
+/// let result = cache_password("MyContainer", "myId", "password");
+/// assert!(result.is_ok());
+/// ```
+///
+#[cfg(feature = "keyring")]
+pub fn cache_password(namespace: &str, id: &str, password: &str) -> Result<()> {
+    let keyring = match KeyRing::from_special_id(KeyRingIdentifier::Session, true) {
+        Ok(keyring) => keyring,
+        Err(err) => return Err(SecureContainerErr::KeyringError(err.to_string())),
+    };
+    let key = match keyring.add_key(&key_description(namespace, id), password) {
+        Ok(key) => key,
+        Err(err) => return Err(SecureContainerErr::KeyringError(err.to_string())),
+    };
+    match key.set_timeout(config().keyring_timeout as usize) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            // A password without expiry must not stay in the keyring.
+            let _ = key.invalidate();
+            Err(SecureContainerErr::KeyringError(err.to_string()))
+        }
+    }
+}
+
+/// Without the `keyring` feature no password can be cached.
+#[cfg(not(feature = "keyring"))]
+pub fn cache_password(_namespace: &str, _id: &str, _password: &str) -> Result<()> {
+    Err(SecureContainerErr::KeyringError(
+        "built without the keyring feature".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_description() {
+        assert_eq!(
+            key_description("MyContainer", "myId"),
+            "secure_container:MyContainer:myId"
+        );
+        assert_eq!(
+            key_description("Überweisungen", "myId"),
+            "secure_container:%C3%9Cberweisungen:myId"
+        );
+        assert_eq!(cached_password("not_cached", "test"), None);
+    }
+}
@@ -34,20 +34,407 @@
 //!         "Path is not a luks container",
 //!         "Path not valid",
 //!         "Path is not a luks device",
+//!         "Essential dependency missing",
+//!         "Mount point busy",
+//!         "Path not writable",
+//!         "Container mounted elsewhere",
+//!         "No free keyslots",
+//!         "Bundle not valid",
+//!         "Time not valid",
+//!         "Container not open",
+//!         "Libuta random error",
+//!         "Mount point conflicts with path",
+//!         "Container not mounted at mount point",
+//!         "Keyring error",
+//!         "Container not in auto open",
+//!         "Timed out waiting for path",
+//!         "File system not supported",
+//!         "Base64 error",
+//!         "Mount option not valid",
+//!         "Mount point not empty",
+//!         "Last keyslot",
+//!         "Key slot not valid",
 //!         "OK"
-use tonic::{transport::{Channel}, Request, Status};
+//!
+//! The daemon is reached at `http://[::1]:50051`,
+//! another URL can be set with `set_server_url` or the environment variable `SECURE_CONTAINER_URL`.
+//! A daemon that listens on a Unix socket is reached with the URL `unix:<path>`, e.g. `unix:/run/secure_container.sock`.
+//!
+//! If the daemon serves TLS, the client uses an `https://` URL and reads the TLS files from the environment:
+//! ```bash
+//! SECURE_CONTAINER_TLS_CA           PEM CA that signed the certificate of the daemon, enables TLS
+//! SECURE_CONTAINER_TLS_CLIENT_CERT  PEM certificate of the client, if the daemon requires one
+//! SECURE_CONTAINER_TLS_CLIENT_KEY   PEM private key of the client certificate
+//! SECURE_CONTAINER_TLS_DOMAIN       Name in the certificate of the daemon, if it differs from the host of the URL
+//! ```
+//! If the certificates do not match, the connection fails with "Error connecting to server" and the reason.
+//!
+//! If a timeout is set with `set_timeout` and an operation does not finish in time, "Deadline exceeded" is returned.
+//! The timeout is also sent to the daemon, which then aborts the operation.
+//! Without `set_timeout`, the timeout in seconds is taken from the environment variable `SECURE_CONTAINER_TIMEOUT` if it is set.
+//!
+//! The client waits up to `SECURE_CONTAINER_CONNECT_TIMEOUT` seconds (default 5) for the connection to the daemon.
+//! If the daemon can not be reached, e.g. because it is restarting, the client tries again with a backoff
+//! of 200 ms, 400 ms, ... up to `SECURE_CONTAINER_CONNECT_ATTEMPTS` attempts (default 3).
+//! Scripts that want to fail fast set `SECURE_CONTAINER_CONNECT_ATTEMPTS=1`.
+//!
+//! Every operation is available as an asynchronous function, e.g. `open_container`,
+//! that can be awaited by callers that already run a tokio runtime.
+//! The synchronous wrappers, e.g. `open_container_sync`, are meant for callers without a runtime like the CLI,
+//! they share one runtime that is created on first use.
+//! Called from within a runtime they return "Synchronous wrapper called from within a runtime ..." instead of panicking.
+//! `set_timeout` only limits the synchronous wrappers, the asynchronous functions send it to the daemon
+//! and callers wrap them with `tokio::time::timeout` themselves.
+//!
+//! The errors are defined in the `error_handling` module,
+//! `error_handling::exit_code_from_message` returns the exit code of an error message as it is used by the CLI.
+//! The operations that only report success, e.g. `open_container_sync`, return a `DaemonError` instead,
+//! it carries the exit code that is sent by the daemon next to the message, so callers do not need to parse the message.
+#![allow(clippy::doc_lazy_continuation)]
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use std::error::Error;
+use tonic::{transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity}, Request, Status};
+#[cfg(unix)]
+use tonic::transport::Uri;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+#[cfg(unix)]
+use tower::service_fn;
 use secure_container_service::container_client::ContainerClient;
 use secure_container_service::{
-    AddToAutoOpenRequest, CloseContainerRequest, CreateContainerRequest, ExportContainerRequest,
-    ImportContainerRequest, OpenContainerRequest, RemoveFromAutoOpenRequest,
+    AddKeySlotRequest, AddToAutoOpenRequest, BackupHeaderRequest, BindMountRequest, CloseByNamespaceRequest, ContainerInfoRequest, CloseContainerRequest, CreateContainerRequest, CreateSwapContainerRequest, CreatedContainer, DependencyCheck, DiffStateRequest, DiffStateResponse,
+    ContainerInventoryItem, ContainerState, ContainerStatus, DoctorRequest, ExportBundleRequest, ExportContainerRequest, ImportBundleRequest,
+    ImportContainerRequest, InventoryRequest, ListContainersRequest, MountOpenRequest, OpenContainerRequest, OpenUnmountedRequest, RebuildAutoOpenRequest, RebuiltContainer, RekeyContainerRequest, RemoveFromAutoOpenRequest, RemoveKeySlotRequest,
+    RestoreHeaderRequest, RotateKeysRequest, RotatedContainer, ScanDirectoryRequest, ScannedContainer, StatusContainerRequest, TestOpenRequest, UpdateAutoOpenRequest, ValidateInputRequest, ValidateInputResponse, VerifyIntegrityRequest,
 };
 
 pub mod secure_container_service {
     tonic::include_proto!("secure_container_service");
 }
 
-    /// Server URL
-    const SERVER_URL: &'static str = "http://[::1]:50051";
+pub mod error_handling;
+
+/// The credential that protects a container while it is transferred to another system.
+/// * `Secret` - A secret phrase.
+/// * `Keyfile` - The path to a keyfile on the system of the daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferCredential {
+    Secret(String),
+    Keyfile(String),
+}
+
+impl TransferCredential {
+    /// Splits the credential into the secret and keyfile fields of the gRPC requests.
+    fn into_fields(self) -> (String, String) {
+        match self {
+            TransferCredential::Secret(secret) => (secret, String::new()),
+            TransferCredential::Keyfile(keyfile) => (String::new(), keyfile),
+        }
+    }
+}
+
+/// The error of an operation of the daemon.
+/// * `code` - The exit code of the error (see `error_handling`), it does not depend on the wording of the message.
+/// * `message` - The error message for humans.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaemonError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl DaemonError {
+    /// Creates the error from the code and the message of a response of the daemon.
+    /// Daemons that do not send a code yet send 0, then the code is derived from the message.
+    pub fn from_response(code: i32, message: String) -> DaemonError {
+        match code {
+            0 => DaemonError::from(message),
+            code => DaemonError { code, message },
+        }
+    }
+}
+
+/// Errors of the client, e.g. if the daemon can not be reached, only have a message, the code is derived from it.
+impl From<String> for DaemonError {
+    fn from(message: String) -> DaemonError {
+        DaemonError {
+            code: error_handling::exit_code_from_message(&message),
+            message,
+        }
+    }
+}
+
+/// The message of the error, so it can be used wherever an error message is expected.
+impl From<DaemonError> for String {
+    fn from(error: DaemonError) -> String {
+        error.message
+    }
+}
+
+impl std::fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A check of the doctor is shown like the report of the daemon:
+/// essential dependencies that are not available as `FAIL`, optional ones as `WARN`.
+impl std::fmt::Display for DependencyCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let state = match (self.available, self.essential) {
+            (true, _) => "PASS",
+            (false, true) => "FAIL",
+            (false, false) => "WARN",
+        };
+        write!(f, "[{}] {}: {}", state, self.name, self.details)
+    }
+}
+
+/// The description of a container that is created by `provision_container_sync`.
+/// * `size` - The size of the container in MB (must be at least 16MB).
+/// * `mount_point` - The path to the mount point.
+/// * `path` - The path to the directory where the container is stored (must already exist).
+/// * `namespace` - The name of the container.
+/// * `id` - The id of the container.
+/// * `auto_open` - If true, the container is added to the autoOpen file.
+/// * `fs_type` - The file system of the container: `ext4`, `xfs` or `btrfs`.
+/// * `create_mount_point` -
+/// If true, the mount point and its parents are created by the client if they do not exist,
+/// this requires that the client runs on the system of the daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerSpec {
+    pub size: i32,
+    pub mount_point: String,
+    pub path: String,
+    pub namespace: String,
+    pub id: String,
+    pub auto_open: bool,
+    pub fs_type: String,
+    pub create_mount_point: bool,
+}
+
+    /// The URL of the daemon if neither `set_server_url` nor `SECURE_CONTAINER_URL` sets another one.
+    const DEFAULT_SERVER_URL: &str = "http://[::1]:50051";
+
+    /// The URL of the daemon that was set with `set_server_url`.
+    static SERVER_URL: Mutex<Option<String>> = Mutex::new(None);
+
+    /// The runtime that is shared by all synchronous wrappers, it is created on first use.
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    /// The error message that is returned if an operation did not finish within the timeout.
+    pub const TIMEOUT_ERROR: &str = "Deadline exceeded";
+
+    /// The error message that is returned if a synchronous wrapper is called from within a tokio runtime.
+    pub const RUNTIME_ERROR: &str = "Synchronous wrapper called from within a runtime, await the asynchronous function instead";
+
+    /// The timeout of the synchronous wrappers in milliseconds, 0 means that there is no timeout.
+    static TIMEOUT_MS: AtomicU64 = AtomicU64::new(0);
+
+    /// The time the client waits for the connection to the daemon if `SECURE_CONTAINER_CONNECT_TIMEOUT` does not set another one.
+    const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// The number of connection attempts if `SECURE_CONTAINER_CONNECT_ATTEMPTS` does not set another number.
+    const DEFAULT_CONNECT_ATTEMPTS: u32 = 3;
+
+    /// The time the client waits after the first failed connection attempt, it is doubled after every further attempt.
+    const CONNECT_BACKOFF: Duration = Duration::from_millis(200);
+
+    /// Sets the time after which the synchronous wrappers give up waiting for the daemon.
+    /// # Arguments
+    /// * `timeout` -
+    /// The maximum duration of an operation or `None` to use `SECURE_CONTAINER_TIMEOUT` or, if it is not set, wait without limit.
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// secure_container_lib::set_timeout(Some(Duration::from_secs(60)));
+    /// ```
+    pub fn set_timeout(timeout: Option<Duration>) {
+        // A timeout of 0 ms would disable the timeout, so it is rounded up to 1 ms.
+        let millis = timeout.map(|timeout| (timeout.as_millis() as u64).max(1)).unwrap_or(0);
+        TIMEOUT_MS.store(millis, Ordering::Relaxed);
+    }
+
+    /// Sets the URL of the daemon, e.g. if the daemon listens on another address with `SECURE_CONTAINER_ADDRESS`.
+    /// # Arguments
+    /// * `url` -
+    /// The URL of the daemon or `None` to use the environment variable `SECURE_CONTAINER_URL`
+    /// or, if it is not set, `http://[::1]:50051`.
+    /// A URL `unix:<path>` connects to the Unix socket at the path.
+    /// # Examples
+    /// ```
+    /// secure_container_lib::set_server_url(Some("http://127.0.0.1:50051".to_string()));
+    /// ```
+    pub fn set_server_url(url: Option<String>) {
+        *SERVER_URL.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = url;
+    }
+
+    /// Returns the URL of the daemon.
+    /// # Returns
+    /// * `String` with the URL that was set with `set_server_url`, the value of `SECURE_CONTAINER_URL` or the default.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn server_url() -> String {
+        if let Some(url) = SERVER_URL.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone() {
+            return url;
+        }
+        env_value("SECURE_CONTAINER_URL").unwrap_or_else(|| DEFAULT_SERVER_URL.to_string())
+    }
+
+    /// Returns the timeout of an operation.
+    /// # Returns
+    /// * `u64` -
+    /// The timeout in milliseconds that was set with `set_timeout`,
+    /// otherwise the seconds of `SECURE_CONTAINER_TIMEOUT` in milliseconds or 0 if there is no timeout.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn timeout_ms() -> u64 {
+        match TIMEOUT_MS.load(Ordering::Relaxed) {
+            0 => env_value("SECURE_CONTAINER_TIMEOUT").and_then(|seconds| seconds.parse::<u64>().ok()).map(|seconds| seconds.saturating_mul(1000)).unwrap_or(0),
+            millis => millis,
+        }
+    }
+
+    /// Returns the time the client waits for the connection to the daemon.
+    /// # Returns
+    /// * `Duration` with the seconds of `SECURE_CONTAINER_CONNECT_TIMEOUT` or the default of 5 seconds.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn connect_timeout() -> Duration {
+        env_value("SECURE_CONTAINER_CONNECT_TIMEOUT").and_then(|seconds| seconds.parse().ok()).filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs).unwrap_or(DEFAULT_CONNECT_TIMEOUT)
+    }
+
+    /// Returns how often the client tries to connect to the daemon.
+    /// # Returns
+    /// * `u32` with the number of `SECURE_CONTAINER_CONNECT_ATTEMPTS` or the default of 3, 1 fails on the first error.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn connect_attempts() -> u32 {
+        env_value("SECURE_CONTAINER_CONNECT_ATTEMPTS").and_then(|attempts| attempts.parse().ok()).filter(|attempts| *attempts > 0)
+            .unwrap_or(DEFAULT_CONNECT_ATTEMPTS)
+    }
+
+    /// Returns the time the client waits after a failed connection attempt.
+    /// # Arguments
+    /// * `attempt` - The number of the failed attempt, starting with 1.
+    /// # Returns
+    /// * `Duration` - `CONNECT_BACKOFF` after the first attempt and twice the previous wait after every further attempt.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn connect_backoff(attempt: u32) -> Duration {
+        CONNECT_BACKOFF.saturating_mul(1 << attempt.saturating_sub(1).min(16))
+    }
+
+    /// Returns the value of an environment variable.
+    /// # Arguments
+    /// * `name` - The name of the environment variable.
+    /// # Returns
+    /// * `Option<String>` with the value or `None` if the variable is not set or empty.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn env_value(name: &str) -> Option<String> {
+        std::env::var(name).ok().filter(|value| !value.is_empty())
+    }
+
+    /// Creates the TLS configuration of the client from the environment variables `SECURE_CONTAINER_TLS_*`.
+    /// # Returns
+    /// * `Result<Option<ClientTlsConfig>, String>` -
+    /// Returns `None` if `SECURE_CONTAINER_TLS_CA` is not set, so the client connects without TLS,
+    /// or an error if a file can not be read or only one of the client certificate and key is set.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn client_tls_config() -> Result<Option<ClientTlsConfig>, String> {
+        let ca = match env_value("SECURE_CONTAINER_TLS_CA") {
+            Some(ca) => ca,
+            None => return Ok(None),
+        };
+        let mut tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(read_tls_file(&ca)?));
+        match (env_value("SECURE_CONTAINER_TLS_CLIENT_CERT"), env_value("SECURE_CONTAINER_TLS_CLIENT_KEY")) {
+            (Some(cert), Some(key)) => tls = tls.identity(Identity::from_pem(read_tls_file(&cert)?, read_tls_file(&key)?)),
+            (None, None) => (),
+            _ => return Err("SECURE_CONTAINER_TLS_CLIENT_CERT and SECURE_CONTAINER_TLS_CLIENT_KEY must be set together".to_string()),
+        }
+        if let Some(domain) = env_value("SECURE_CONTAINER_TLS_DOMAIN") {
+            tls = tls.domain_name(domain);
+        }
+        Ok(Some(tls))
+    }
+
+    /// Reads a PEM file of the TLS configuration.
+    /// # Arguments
+    /// * `path` - The path of the file.
+    /// # Returns
+    /// * `Result<Vec<u8>, String>` - Returns the content of the file or an error if it can not be read.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn read_tls_file(path: &str) -> Result<Vec<u8>, String> {
+        std::fs::read(path).map_err(|err| format!("TLS file '{}' can not be read: {}", path, err))
+    }
+
+    /// Formats an error with all of its sources, e.g. the certificate error behind a transport error.
+    /// # Arguments
+    /// * `err` - The error.
+    /// # Returns
+    /// * `String` with the messages of the error and its sources separated by ": ".
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn error_chain(err: &dyn Error) -> String {
+        let mut message = err.to_string();
+        let mut source = err.source();
+        while let Some(err) = source {
+            message = format!("{}: {}", message, err);
+            source = err.source();
+        }
+        message
+    }
+
+    /// Runs an operation on the shared runtime and aborts it when the timeout expires.
+    /// # Arguments
+    /// * `future` - The operation.
+    /// # Returns
+    /// * `Ok(T)` with the result of the operation.
+    /// * `Err(E)` with the error of the operation or `TIMEOUT_ERROR` if the timeout expired,
+    /// `RUNTIME_ERROR` if it is called from within a tokio runtime.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn block_on<T, E: From<String>>(future: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        // Blocking a thread of a runtime panics, callers with a runtime await the asynchronous functions instead.
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Err(E::from(RUNTIME_ERROR.to_string()));
+        }
+        let runtime = runtime().map_err(E::from)?;
+        match timeout_ms() {
+            0 => runtime.block_on(future),
+            // Dropping the future closes the request, so the daemon sees that the client is gone.
+            millis => runtime.block_on(async {
+                tokio::time::timeout(Duration::from_millis(millis), future).await
+                    .map_err(|_| E::from(TIMEOUT_ERROR.to_string()))?
+            }),
+        }
+    }
+
+    /// Returns the runtime that is shared by all synchronous wrappers.
+    /// # Arguments
+    /// * `None`
+    /// # Returns
+    /// * `Ok(&'static Runtime)` with the shared runtime.
+    /// * `Err(String)` with the error message if the runtime could not be created.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn runtime() -> Result<&'static Runtime, String> {
+        if let Some(runtime) = RUNTIME.get() {
+            return Ok(runtime);
+        }
+        let runtime = Runtime::new().map_err(|err| format!("Error creating runtime: {}", err))?;
+        // If another thread was faster, its runtime is used and this one is dropped.
+        Ok(RUNTIME.get_or_init(|| runtime))
+    }
 
     /// Synchronous wrapper for creating a container
     /// # Arguments
@@ -60,14 +447,82 @@ pub mod secure_container_service {
     /// If true,
     /// the container is added to the autoOpen file
     /// and will be opened automatically when the system starts.
+    /// * `fs_type` - The file system of the container: `ext4`, `xfs` or `btrfs`.
+    /// # Returns
+    /// * `Ok(CreatedContainer)` with the mapper device, mount point, size and UUID if the container was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn create_container_sync(size: i32, mount_point: String, path: String, namespace: String, id: String, auto_open: bool, fs_type: String) -> Result<CreatedContainer, DaemonError> {
+        block_on(async {
+            create_container(size, mount_point, path, namespace, id, auto_open, fs_type).await
+        })
+    }
+
+    /// Synchronous wrapper for creating a container that reports the progress of the creation
+    /// # Arguments
+    /// The arguments of `create_container_sync` and
+    /// * `progress` -
+    /// Called with the phase and the estimated percentage at the start of every phase of the creation,
+    /// the phases are `allocating`, `formatting` and `making filesystem`.
+    /// # Returns
+    /// * `Ok(CreatedContainer)` with the mapper device, mount point, size and UUID if the container was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
+    /// # Examples
+    /// ```no_run
+    /// let created = secure_container_lib::create_container_with_progress_sync(64, "/home/MountMe".to_string(),
+    ///     "/home".to_string(), "MyContainer".to_string(), "myId".to_string(), false, "ext4".to_string(),
+    ///     |phase, percent| println!("{} {}%", phase, percent));
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_container_with_progress_sync(size: i32, mount_point: String, path: String, namespace: String, id: String, auto_open: bool, fs_type: String, progress: impl FnMut(&str, u32)) -> Result<CreatedContainer, DaemonError> {
+        block_on(async {
+            create_container_stream(size, mount_point, path, namespace, id, auto_open, fs_type, progress).await
+        })
+    }
+
+    /// Creates the mount point of a container if requested and then creates and opens the container
+    /// # Arguments
+    /// * `spec` - The description of the container.
+    /// # Returns
+    /// * `Ok(CreatedContainer)` with the mapper device, mount point, size and UUID if the container was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the mount point or the container was not created successfully.
+    /// # Examples
+    /// ```no_run
+    /// let spec = secure_container_lib::ContainerSpec {
+    ///     size: 64,
+    ///     mount_point: "/home/MountMe".to_string(),
+    ///     path: "/home".to_string(),
+    ///     namespace: "MyContainer".to_string(),
+    ///     id: "myId".to_string(),
+    ///     auto_open: false,
+    ///     fs_type: "ext4".to_string(),
+    ///     create_mount_point: true,
+    /// };
+    /// let created = secure_container_lib::provision_container_sync(spec);
+    /// ```
+    pub fn provision_container_sync(spec: ContainerSpec) -> Result<CreatedContainer, DaemonError> {
+        if spec.create_mount_point {
+            std::fs::create_dir_all(&spec.mount_point)
+                .map_err(|err| format!("Error creating mount point: {}", err))?;
+        }
+        create_container_sync(spec.size, spec.mount_point, spec.path, spec.namespace, spec.id, spec.auto_open, spec.fs_type)
+    }
+
+    /// Synchronous wrapper for creating a container without file system
+    /// # Arguments
+    /// * `size` - The size of the container in MB (must be at least 16MB).
+    /// * `path` - The path to the directory where the container is stored (must already exist).
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
     /// # Returns
-    /// * `Ok(())` if the container was created successfully.
-    /// * `Err(String)` with the error message if the container was not created successfully.
+    /// * `Ok(CreatedContainer)` with the mapper device, size and UUID if the container was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
     /// # Examples
     /// For example usage see cli.rs.
-    pub fn create_container_sync(size: i32, mount_point: String, path: String, namespace: String, id: String, auto_open: bool) -> Result<(), String> {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            create_container(size, mount_point, path, namespace, id, auto_open).await
+    pub fn create_raw_container_sync(size: i32, path: String, namespace: String, id: String) -> Result<CreatedContainer, DaemonError> {
+        block_on(async {
+            create_raw_container(size, path, namespace, id).await
         })
     }
 
@@ -79,342 +534,1853 @@ pub mod secure_container_service {
     /// * `id` - The id of the container.
     /// # Returns
     /// * `Ok(())` if the container was opened successfully.
-    /// * `Err(String)` with the error message if the container was not opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn open_container_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), DaemonError> {
+        block_on(async {
+            open_container(mount_point, path, namespace, id, false, Vec::new(), false, None).await
+        })
+    }
+
+    /// Synchronous wrapper for opening a container with the password cached in the session keyring of the daemon.
+    /// If the password is not cached, it is derived and cached until it expires.
+    /// The daemon only caches passwords if it is built with the `keyring` feature.
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn open_container_with_keyring_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), DaemonError> {
+        block_on(async {
+            open_container(mount_point, path, namespace, id, true, Vec::new(), false, None).await
+        })
+    }
+
+    /// Synchronous wrapper for opening a container with mount options, e.g. read-only for a container with untrusted data.
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `keyring` - If true, the password cached in the session keyring of the daemon is used and cached there.
+    /// * `mount_options` - The options the container is mounted with: `ro`, `noexec`, `nosuid`, `nodev` or `noatime`.
+    /// # Returns
+    /// * `Ok(())` if the container was opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully,
+    /// "Mount option not valid" if an option is not allowed.
+    /// # Examples
+    /// ```no_run
+    /// let result = secure_container_lib::open_container_with_options_sync("/home/MountMe".to_string(),
+    ///     "/home/Container".to_string(), "MyContainer".to_string(), "myId".to_string(), false,
+    ///     vec!["ro".to_string(), "noexec".to_string(), "nosuid".to_string()]);
+    /// ```
+    pub fn open_container_with_options_sync(mount_point: String, path: String, namespace: String, id: String, keyring: bool, mount_options: Vec<String>) -> Result<(), DaemonError> {
+        block_on(async {
+            open_container(mount_point, path, namespace, id, keyring, mount_options, false, None).await
+        })
+    }
+
+    /// Synchronous wrapper for opening a container read-only, e.g. to inspect it for forensics.
+    /// The dm-crypt mapping is opened read-only and the container is mounted with `ro`,
+    /// so neither the file system nor the container can be changed.
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `keyring` - If true, the password cached in the session keyring of the daemon is used and cached there.
+    /// # Returns
+    /// * `Ok(())` if the container was opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully.
+    /// If the container is already open and mounted at the mount point, it is not opened again read-only.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn open_container_read_only_sync(mount_point: String, path: String, namespace: String, id: String, keyring: bool) -> Result<(), DaemonError> {
+        block_on(async {
+            open_container(mount_point, path, namespace, id, keyring, Vec::new(), true, None).await
+        })
+    }
+
+    /// Synchronous wrapper for opening a container with all options.
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `keyring` - If true, the password cached in the session keyring of the daemon is used and cached there.
+    /// * `mount_options` - The options the container is mounted with: `ro`, `noexec`, `nosuid`, `nodev` or `noatime`.
+    /// * `read_only` - If true, the dm-crypt mapping is opened read-only and the container is mounted with `ro`.
+    /// * `key_slot` - The only key slot the key of the id is tried on, every key slot is tried if it is `None`.
+    /// # Returns
+    /// * `Ok(())` if the container was opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully.
+    /// # Examples
+    /// ```no_run
+    /// let result = secure_container_lib::open_container_with_key_slot_sync("/home/MountMe".to_string(),
+    ///     "/home/Container".to_string(), "MyContainer".to_string(), "myId".to_string(), false, Vec::new(), false, Some(0));
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_container_with_key_slot_sync(mount_point: String, path: String, namespace: String, id: String, keyring: bool, mount_options: Vec<String>, read_only: bool, key_slot: Option<u32>) -> Result<(), DaemonError> {
+        block_on(async {
+            open_container(mount_point, path, namespace, id, keyring, mount_options, read_only, key_slot).await
+        })
+    }
+
+    /// Synchronous wrapper for closing a container
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was closed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not closed successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn close_container_sync(mount_point: String, namespace: String) -> Result<(), DaemonError> {
+        block_on(async {
+            close_container(mount_point, namespace, false).await
+        })
+    }
+
+    /// Synchronous wrapper for closing a container that may already be closed, e.g. in cleanup scripts.
+    /// A container that is open but not mounted is closed without unmounting.
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was closed successfully or was not open.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not closed successfully.
+    /// # Examples
+    /// ```no_run
+    /// let result = secure_container_lib::close_container_if_open_sync("/home/MountMe".to_string(), "MyContainer".to_string());
+    /// ```
+    pub fn close_container_if_open_sync(mount_point: String, namespace: String) -> Result<(), DaemonError> {
+        block_on(async {
+            close_container(mount_point, namespace, true).await
+        })
+    }
+
+    /// Synchronous wrapper for closing a container by its name
+    /// # Arguments
+    /// * `namespace` - The name of the container, the mount point is looked up by the daemon.
+    /// # Returns
+    /// * `Ok(())` if the container was closed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not closed successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn close_container_by_namespace_sync(namespace: String) -> Result<(), DaemonError> {
+        block_on(async {
+            close_container_by_namespace(namespace).await
+        })
+    }
+
+    /// Synchronous wrapper for opening a container without mounting it
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(String)` with the path of the mapper device if the container was opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn open_unmounted_sync(path: String, namespace: String, id: String) -> Result<String, DaemonError> {
+        block_on(async {
+            open_unmounted(path, namespace, id).await
+        })
+    }
+
+    /// Synchronous wrapper for mounting a container that is already open
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was mounted successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not mounted successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn mount_open_sync(mount_point: String, namespace: String) -> Result<(), DaemonError> {
+        block_on(async {
+            mount_open(mount_point, namespace).await
+        })
+    }
+
+    /// Synchronous wrapper for exporting a container
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile for the container (is needed when container is imported).
+    /// # Returns
+    /// * `Ok(())` if the container was exported successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not exported successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn export_container_sync(path: String, namespace: String, id: String, credential: TransferCredential) -> Result<(), DaemonError> {
+        block_on(async {
+            export_container(path, namespace, id, credential).await
+        })
+    }
+
+    /// Synchronous wrapper for importing a container
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile for the container (is needed when container is imported).
+    /// # Returns
+    /// * `Ok(())` if the container was imported successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not imported successfully.
     /// # Examples
     /// For example usage see cli.rs.
-    pub fn open_container_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), String> {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            open_container(mount_point, path, namespace, id).await
+    pub fn import_container_sync(path: String, namespace: String, id: String, credential: TransferCredential) -> Result<(), DaemonError> {
+        block_on(async {
+            import_container(path, namespace, id, credential).await
         })
     }
 
-    /// Synchronous wrapper for closing a container
+    /// Synchronous wrapper for exporting a container into a bundle
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile for the bundle (is needed when the bundle is imported).
+    /// * `bundle_path` - The path of the bundle that is created.
+    /// # Returns
+    /// * `Ok(())` if the bundle was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the bundle was not created successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn export_bundle_sync(path: String, namespace: String, id: String, credential: TransferCredential, bundle_path: String) -> Result<(), DaemonError> {
+        block_on(async {
+            export_bundle(path, namespace, id, credential, bundle_path).await
+        })
+    }
+
+    /// Synchronous wrapper for importing a container from a bundle
+    /// # Arguments
+    /// * `bundle_path` - The path to the bundle.
+    /// * `path` - The path where the container is placed (must not exist).
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile the bundle was exported with.
+    /// # Returns
+    /// * `Ok(())` if the container was imported successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not imported successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn import_bundle_sync(bundle_path: String, path: String, id: String, credential: TransferCredential) -> Result<(), DaemonError> {
+        block_on(async {
+            import_bundle(bundle_path, path, id, credential).await
+        })
+    }
+
+    /// Synchronous wrapper for adding container to auto open file
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was added to auto open file successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not added to auto open file successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn add_container_to_auto_open_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), DaemonError> {
+        block_on(async {
+            add_container_to_auto_open(mount_point, path, namespace, id, false).await
+        })
+    }
+
+    /// Synchronous wrapper for adding container to auto open file that the daemon waits for on startup.
+    /// The daemon waits up to `SECURE_CONTAINER_AUTO_OPEN_WAIT_TIMEOUT` seconds for the path of the container to appear,
+    /// e.g. because it is on a network file system that is mounted after the daemon has started.
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was added to auto open file successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not added to auto open file successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn add_container_to_auto_open_with_wait_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), DaemonError> {
+        block_on(async {
+            add_container_to_auto_open(mount_point, path, namespace, id, true).await
+        })
+    }
+
+    /// Synchronous wrapper for removing container from auto open file
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was removed from auto open file successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not removed from auto open file successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn remove_container_from_auto_open_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), DaemonError> {
+        block_on(async {
+            remove_container_from_auto_open(mount_point, path, namespace, id).await
+        })
+    }
+
+    /// Synchronous wrapper for checking the dependencies of the daemon
+    /// # Arguments
+    /// * `None`
+    /// # Returns
+    /// * `Ok(Vec<DependencyCheck>)` with one entry for every dependency that was checked.
+    /// * `Err(String)` with the error message if the daemon could not be reached.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn doctor_sync() -> Result<Vec<DependencyCheck>, String> {
+        block_on(async {
+            doctor().await
+        })
+    }
+
+    /// Synchronous wrapper for listing the status of the containers
+    /// # Arguments
+    /// * `namespace` - If set, only the container with this name is listed.
+    /// * `all` - If true, open crypt devices that are not in the autoOpen file are listed as well.
+    /// # Returns
+    /// * `Ok(Vec<ContainerStatus>)` with one entry for every container.
+    /// * `Err(DaemonError)` with the code and the message of the error if the containers could not be listed.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn list_containers_sync(namespace: Option<String>, all: bool) -> Result<Vec<ContainerStatus>, DaemonError> {
+        block_on(async {
+            list_containers(namespace, all).await
+        })
+    }
+
+    /// Synchronous wrapper for rotating the keys of all containers in the auto open file
+    /// # Arguments
+    /// * `new_id` - The id the new passwords of the containers are derived from.
+    /// # Returns
+    /// * `Ok(Vec<RotatedContainer>)` with the result of every container, also if some could not be rotated.
+    /// * `Err(DaemonError)` with the code and the message of the error if the rotation could not be started.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn rotate_keys_sync(new_id: String) -> Result<Vec<RotatedContainer>, DaemonError> {
+        block_on(async {
+            rotate_keys(new_id).await
+        })
+    }
+
+    /// Synchronous wrapper for rebuilding the auto open file from the containers that are open and mounted
+    /// # Arguments
+    /// * `ids` - The id of every container, by namespace.
+    /// * `default_id` - The id of the containers that are not listed in `ids`, empty to skip them.
+    /// # Returns
+    /// * `Ok(Vec<RebuiltContainer>)` with the result of every open container, also if some were skipped.
+    /// * `Err(DaemonError)` with the code and the message of the error if the auto open file could not be rebuilt.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn rebuild_auto_open_sync(ids: HashMap<String, String>, default_id: String) -> Result<Vec<RebuiltContainer>, DaemonError> {
+        block_on(async {
+            rebuild_auto_open(ids, default_id).await
+        })
+    }
+
+    /// Synchronous wrapper for changing the mount point of a container in the auto open file
+    /// # Arguments
+    /// * `mount_point` - The path to the new mount point (must already exist).
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(())` if the mount point was changed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the mount point was not changed successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn update_container_in_auto_open_sync(mount_point: String, namespace: String) -> Result<(), DaemonError> {
+        block_on(async {
+            update_container_in_auto_open(mount_point, namespace).await
+        })
+    }
+
+    /// Synchronous wrapper for verifying the integrity of an open container
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// * `since` -
+    /// If set, only integrity errors since this time (e.g. `2024-03-01T12:00`) are reported,
+    /// otherwise the time the container was opened by the daemon is used.
+    /// # Returns
+    /// * `Ok(())` if no integrity error was found.
+    /// * `Err(DaemonError)` with the code and the message of the error if an integrity error was found or the check failed.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn verify_integrity_sync(namespace: String, since: Option<String>) -> Result<(), DaemonError> {
+        block_on(async {
+            verify_integrity(namespace, since).await
+        })
+    }
+
+    /// Synchronous wrapper for bind-mounting a directory of an open container to another directory
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// * `source` - The directory relative to the mount point of the container.
+    /// * `target` - The directory where the source is mounted to (must already exist).
+    /// * `read_only` - If true, the target is mounted read-only.
+    /// # Returns
+    /// * `Ok(())` if the directory was mounted successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the directory was not mounted successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn bind_mount_sync(namespace: String, source: String, target: String, read_only: bool) -> Result<(), DaemonError> {
+        block_on(async {
+            bind_mount(namespace, source, target, read_only).await
+        })
+    }
+
+    /// Synchronous wrapper for creating an encrypted swap device with a random key
+    /// # Arguments
+    /// * `size` - The size of the swap device in MB (must be at least 16MB).
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(CreatedContainer)` with the mapper device, size and UUID if swapping on the container was enabled successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn create_swap_container_sync(size: i32, namespace: String) -> Result<CreatedContainer, DaemonError> {
+        block_on(async {
+            create_swap_container(size, namespace).await
+        })
+    }
+
+    /// Synchronous wrapper for checking if a container would open, without opening or mounting it
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(bool)` with true if the key of the id unlocks the container and false if it does not.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container could not be checked.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn test_open_sync(path: String, id: String) -> Result<bool, DaemonError> {
+        block_on(async {
+            test_open(path, id).await
+        })
+    }
+
+    /// Synchronous wrapper for backing up the LUKS header of a container
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `out_file` - The path of the backup, it must not exist yet.
+    /// # Returns
+    /// * `Ok(())` if the backup was written successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the backup was not written.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn backup_header_sync(path: String, out_file: String) -> Result<(), DaemonError> {
+        block_on(async {
+            backup_header(path, out_file).await
+        })
+    }
+
+    /// Synchronous wrapper for restoring the LUKS header of a container from a backup
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `in_file` - The path of the backup.
+    /// # Returns
+    /// * `Ok(())` if the header was restored successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the header was not restored.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn restore_header_sync(path: String, in_file: String) -> Result<(), DaemonError> {
+        block_on(async {
+            restore_header(path, in_file).await
+        })
+    }
+
+    /// Synchronous wrapper for describing the LUKS header of a container
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// # Returns
+    /// * `Ok(String)` with the output of `cryptsetup luksDump`, it contains no keys.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container could not be described.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn container_info_sync(path: String) -> Result<String, DaemonError> {
+        block_on(async {
+            container_info(path).await
+        })
+    }
+
+    /// Synchronous wrapper for replacing the password of a closed container with the one of a new id
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `old_id` - The id the password of the container is currently derived from.
+    /// * `new_id` - The id the new password is derived from.
+    /// # Returns
+    /// * `Ok(())` if the password was replaced successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the password was not replaced.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn rekey_container_sync(path: String, old_id: String, new_id: String) -> Result<(), DaemonError> {
+        block_on(async {
+            rekey_container(path, old_id, new_id).await
+        })
+    }
+
+    /// Synchronous wrapper for adding a secret to a free key slot of a container, e.g. a recovery secret
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `existing_id` - The id of the container, its key unlocks the container to add the secret.
+    /// * `new_secret` - The secret that is added as it is, it is not derived.
+    /// # Returns
+    /// * `Ok(u32)` with the key slot the secret was added to.
+    /// * `Err(DaemonError)` with the code and the message of the error if the secret was not added,
+    /// "No free keyslots" if all key slots are used.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn add_key_slot_sync(path: String, existing_id: String, new_secret: String) -> Result<u32, DaemonError> {
+        block_on(async {
+            add_key_slot(path, existing_id, new_secret).await
+        })
+    }
+
+    /// Synchronous wrapper for removing the key slot of a container that is unlocked by a secret
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `secret_to_remove` - The secret of the key slot that is removed.
+    /// # Returns
+    /// * `Ok(())` if the key slot was removed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the key slot was not removed,
+    /// "Last keyslot" if it is the last key slot of the container.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn remove_key_slot_sync(path: String, secret_to_remove: String) -> Result<(), DaemonError> {
+        block_on(async {
+            remove_key_slot(path, secret_to_remove).await
+        })
+    }
+
+    /// Synchronous wrapper for searching a directory for LUKS containers
+    /// # Arguments
+    /// * `directory` - The path to the directory, its subdirectories are searched as well.
+    /// # Returns
+    /// * `Ok(Vec<ScannedContainer>)` with the path, UUID and LUKS version of every container that was found.
+    /// * `Err(DaemonError)` with the code and the message of the error if the directory could not be searched.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn scan_directory_sync(directory: String) -> Result<Vec<ScannedContainer>, DaemonError> {
+        block_on(async {
+            scan_directory(directory).await
+        })
+    }
+
+    /// Synchronous wrapper for comparing the auto open file with the containers that are open
+    /// # Returns
+    /// * `Ok(DiffStateResponse)` with the namespaces of the registered containers by state and of the open containers that are not registered.
+    /// * `Err(DaemonError)` with the code and the message of the error if the state could not be compared.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn diff_state_sync() -> Result<DiffStateResponse, DaemonError> {
+        block_on(async {
+            diff_state().await
+        })
+    }
+
+    /// Synchronous wrapper for collecting the public metadata and the state of every container
+    /// # Returns
+    /// * `Ok(Vec<ContainerInventoryItem>)` with the namespace, path, mount point, UUID, cipher, integrity, creation time and state of every container.
+    /// * `Err(DaemonError)` with the code and the message of the error if the inventory could not be collected.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn inventory_sync() -> Result<Vec<ContainerInventoryItem>, DaemonError> {
+        block_on(async {
+            inventory().await
+        })
+    }
+
+    /// Synchronous wrapper for checking if a container is open and mounted without changing anything
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(ContainerState)` with the state and the mount point of the container, a container that does not exist is closed.
+    /// * `Err(DaemonError)` with the code and the message of the error if the state could not be checked.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn status_container_sync(namespace: String) -> Result<ContainerState, DaemonError> {
+        block_on(async {
+            status_container(namespace).await
+        })
+    }
+
+    /// Synchronous wrapper for validating inputs without creating or changing anything
+    /// # Arguments
+    /// * `size` - The size of the container in MB.
+    /// * `mount_point` - The path to the mount point.
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// Only the inputs that are set are validated.
+    /// # Returns
+    /// * `Ok(ValidateInputResponse)` with the first error and the field that caused it, or `OK` if all inputs are valid.
+    /// * `Err(String)` with the error message if the daemon could not be reached.
+    /// # Examples
+    /// For example usage see cli.rs.
+    pub fn validate_input_sync(size: Option<i32>, mount_point: Option<String>, path: Option<String>, namespace: Option<String>, id: Option<String>) -> Result<ValidateInputResponse, String> {
+        block_on(async {
+            validate_input(size, mount_point, path, namespace, id).await
+        })
+    }
+
+    /// Asynchronously creates a container
+    /// # Arguments
+    /// * `size` - The size of the container in MB (must be at least 16MB).
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the directory where the container is stored (must already exist).
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `auto_open` -
+    /// If true,
+    /// the container is added to the autoOpen file
+    /// and will be opened automatically when the system starts.
+    /// * `fs_type` - The file system of the container: `ext4`, `xfs` or `btrfs`.
+    /// # Returns
+    /// * `Ok(CreatedContainer)` with the details of the container if the container was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn create_container(size: i32, mount_point: String, path: String, namespace: String, id: String, auto_open: bool, fs_type: String) -> Result<CreatedContainer, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(CreateContainerRequest {
+            size,
+            mount_point,
+            path,
+            namespace,
+            id,
+            auto_open,
+            format_only: false,
+            fs_type,
+        });
+
+        let response = client.create_container(request).await
+            .map_err(|err| format!("Error creating container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner.container.unwrap_or_default())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously creates a container and streams the progress of the creation
+    /// # Arguments
+    /// The arguments of `create_container` and
+    /// * `progress` - Called with the phase and the estimated percentage of every progress message.
+    /// # Returns
+    /// * `Ok(CreatedContainer)` with the details of the container if the container was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_container_stream(size: i32, mount_point: String, path: String, namespace: String, id: String, auto_open: bool, fs_type: String, mut progress: impl FnMut(&str, u32)) -> Result<CreatedContainer, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(CreateContainerRequest {
+            size,
+            mount_point,
+            path,
+            namespace,
+            id,
+            auto_open,
+            format_only: false,
+            fs_type,
+        });
+
+        let mut stream = client.create_container_stream(request).await
+            .map_err(|err| format!("Error creating container: {}", err))?
+            .into_inner();
+
+        // Only the last message carries the result.
+        while let Some(message) = stream.message().await.map_err(|err| format!("Error creating container: {}", err))? {
+            match message.result {
+                Some(inner) if inner.status => return Ok(inner.container.unwrap_or_default()),
+                Some(inner) => return Err(DaemonError::from_response(inner.code, inner.error)),
+                None => progress(&message.phase, message.percent),
+            }
+        }
+        Err("Error creating container: the daemon sent no result".to_string().into())
+    }
+
+    /// Asynchronously creates a container without file system
+    /// # Arguments
+    /// * `size` - The size of the container in MB (must be at least 16MB).
+    /// * `path` - The path to the directory where the container is stored (must already exist).
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(CreatedContainer)` with the details of the container if the container was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn create_raw_container(size: i32, path: String, namespace: String, id: String) -> Result<CreatedContainer, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(CreateContainerRequest {
+            size,
+            mount_point: String::new(),
+            path,
+            namespace,
+            id,
+            auto_open: false,
+            format_only: true,
+            fs_type: String::new(),
+        });
+
+        let response = client.create_container(request).await
+            .map_err(|err| format!("Error creating container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner.container.unwrap_or_default())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously opens a container
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `keyring` - If true, the daemon looks up the password in its session keyring first and caches it there.
+    /// * `mount_options` - The options the container is mounted with, the daemon checks them against its allowlist.
+    /// * `read_only` - If true, the dm-crypt mapping is opened read-only and the container is mounted with `ro`.
+    /// * `key_slot` - The only key slot the daemon tries the key of the id on, every key slot is tried if it is `None`.
+    /// # Returns
+    /// * `Ok(())` if the container was opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn open_container(mount_point: String, path: String, namespace: String, id: String, keyring: bool, mount_options: Vec<String>, read_only: bool, key_slot: Option<u32>) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(OpenContainerRequest {
+            mount_point,
+            path,
+            namespace,
+            id,
+            keyring,
+            mount_options,
+            read_only,
+            key_slot,
+        });
+
+        let response = client.open_container(request).await
+            .map_err(|err| format!("Error opening container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously closes a container
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `namespace` - The name of the container.
+    /// * `if_open` - If true, a container that is already closed is not an error.
+    /// # Returns
+    /// * `Ok(())` if the container was closed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not closed successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn close_container(mount_point: String, namespace: String, if_open: bool) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(CloseContainerRequest {
+            mount_point,
+            namespace,
+            if_open,
+        });
+
+        let response = client.close_container(request).await
+            .map_err(|err| format!("Error closing container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously closes a container by its name
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was closed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not closed successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn close_container_by_namespace(namespace: String) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(CloseByNamespaceRequest {
+            namespace,
+        });
+
+        let response = client.close_by_namespace(request).await
+            .map_err(|err| format!("Error closing container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously opens a container without mounting it
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(String)` with the path of the mapper device if the container was opened successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not opened successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn open_unmounted(path: String, namespace: String, id: String) -> Result<String, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(OpenUnmountedRequest {
+            path,
+            namespace,
+            id,
+        });
+
+        let response = client.open_unmounted(request).await
+            .map_err(|err| format!("Error opening container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner.mapper_device)
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously mounts a container that is already open
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was mounted successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not mounted successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn mount_open(mount_point: String, namespace: String) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(MountOpenRequest {
+            mount_point,
+            namespace,
+        });
+
+        let response = client.mount_open(request).await
+            .map_err(|err| format!("Error mounting container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously exports a container
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile for the container (is needed when container is imported).
+    /// # Returns
+    /// * `Ok(())` if the container was exported successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not exported successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn export_container(path: String, namespace: String, id: String, credential: TransferCredential) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let (secret, keyfile) = credential.into_fields();
+        let request = request(ExportContainerRequest {
+            path,
+            namespace,
+            id,
+            secret,
+            keyfile,
+        });
+
+        let response = client.export_container(request).await
+            .map_err(|err| format!("Error exporting container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously imports a container
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile for the container (is needed when container is imported).
+    /// # Returns
+    /// * `Ok(())` if the container was imported successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not imported successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn import_container(path: String, namespace: String, id: String, credential: TransferCredential) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let (secret, keyfile) = credential.into_fields();
+        let request = request(ImportContainerRequest {
+            path,
+            namespace,
+            id,
+            secret,
+            keyfile,
+        });
+
+        let response = client.import_container(request).await
+            .map_err(|err| format!("Error importing container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously exports a container into a bundle
+    /// # Arguments
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile for the bundle (is needed when the bundle is imported).
+    /// * `bundle_path` - The path of the bundle that is created.
+    /// # Returns
+    /// * `Ok(())` if the bundle was created successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the bundle was not created successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn export_bundle(path: String, namespace: String, id: String, credential: TransferCredential, bundle_path: String) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let (secret, keyfile) = credential.into_fields();
+        let request = request(ExportBundleRequest {
+            path,
+            namespace,
+            id,
+            secret,
+            keyfile,
+            bundle_path,
+        });
+
+        let response = client.export_bundle(request).await
+            .map_err(|err| format!("Error exporting bundle: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously imports a container from a bundle
+    /// # Arguments
+    /// * `bundle_path` - The path to the bundle.
+    /// * `path` - The path where the container is placed (must not exist).
+    /// * `id` - The id of the container.
+    /// * `credential` - The secret or keyfile the bundle was exported with.
+    /// # Returns
+    /// * `Ok(())` if the container was imported successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not imported successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn import_bundle(bundle_path: String, path: String, id: String, credential: TransferCredential) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let (secret, keyfile) = credential.into_fields();
+        let request = request(ImportBundleRequest {
+            bundle_path,
+            path,
+            id,
+            secret,
+            keyfile,
+        });
+
+        let response = client.import_bundle(request).await
+            .map_err(|err| format!("Error importing bundle: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously Add container to auto open file
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was added to auto open file successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not added to auto open file successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn add_container_to_auto_open(mount_point: String, path: String, namespace: String, id: String, wait_for_path: bool) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(AddToAutoOpenRequest {
+            mount_point,
+            path,
+            namespace,
+            id,
+            wait_for_path,
+        });
+
+        let response = client.add_to_auto_open(request).await
+            .map_err(|err| format!("Error adding container to auto open: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously Remove container from auto open file
+    /// # Arguments
+    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `path` - The path to the container.
+    /// * `namespace` - The name of the container.
+    /// * `id` - The id of the container.
+    /// # Returns
+    /// * `Ok(())` if the container was removed from auto open file successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not removed from auto open file successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn remove_container_from_auto_open(mount_point: String, path: String, namespace: String, id: String) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(RemoveFromAutoOpenRequest {
+            mount_point,
+            path,
+            namespace,
+            id,
+        });
+
+        let response = client.remove_from_auto_open(request).await
+            .map_err(|err| format!("Error removing container from auto open: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously checks the dependencies of the daemon
+    /// # Arguments
+    /// * `None`
+    /// # Returns
+    /// * `Ok(Vec<DependencyCheck>)` with one entry for every dependency that was checked.
+    /// * `Err(String)` with the error message if the daemon could not be reached.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn doctor() -> Result<Vec<DependencyCheck>, String> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(DoctorRequest {});
+
+        let response = client.doctor(request).await
+            .map_err(|err| format!("Error checking dependencies: {}", err))?;
+
+        Ok(response.into_inner().checks)
+    }
+
+    /// Asynchronously lists the status of the containers
+    /// # Arguments
+    /// * `namespace` - If set, only the container with this name is listed.
+    /// * `all` - If true, open crypt devices that are not in the autoOpen file are listed as well.
+    /// # Returns
+    /// * `Ok(Vec<ContainerStatus>)` with one entry for every container.
+    /// * `Err(DaemonError)` with the code and the message of the error if the containers could not be listed.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn list_containers(namespace: Option<String>, all: bool) -> Result<Vec<ContainerStatus>, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(ListContainersRequest {
+            namespace: namespace.unwrap_or_default(),
+            all,
+        });
+
+        let response = client.list_containers(request).await
+            .map_err(|err| format!("Error listing containers: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner.containers)
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously rotates the keys of all containers in the auto open file
+    /// # Arguments
+    /// * `new_id` - The id the new passwords of the containers are derived from.
+    /// # Returns
+    /// * `Ok(Vec<RotatedContainer>)` with the result of every container, also if some could not be rotated.
+    /// * `Err(DaemonError)` with the code and the message of the error if the rotation could not be started.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn rotate_keys(new_id: String) -> Result<Vec<RotatedContainer>, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(RotateKeysRequest {
+            new_id,
+        });
+
+        let response = client.rotate_keys(request).await
+            .map_err(|err| format!("Error rotating keys: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status || !inner.containers.is_empty() {
+            Ok(inner.containers)
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously rebuilds the auto open file from the containers that are open and mounted
+    /// # Arguments
+    /// * `ids` - The id of every container, by namespace.
+    /// * `default_id` - The id of the containers that are not listed in `ids`, empty to skip them.
+    /// # Returns
+    /// * `Ok(Vec<RebuiltContainer>)` with the result of every open container, also if some were skipped.
+    /// * `Err(DaemonError)` with the code and the message of the error if the auto open file could not be rebuilt.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn rebuild_auto_open(ids: HashMap<String, String>, default_id: String) -> Result<Vec<RebuiltContainer>, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(RebuildAutoOpenRequest {
+            ids,
+            default_id,
+        });
+
+        let response = client.rebuild_auto_open(request).await
+            .map_err(|err| format!("Error rebuilding auto open file: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status || !inner.containers.is_empty() {
+            Ok(inner.containers)
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously changes the mount point of a container in the auto open file
+    /// # Arguments
+    /// * `mount_point` - The path to the new mount point (must already exist).
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Ok(())` if the mount point was changed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the mount point was not changed successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn update_container_in_auto_open(mount_point: String, namespace: String) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(UpdateAutoOpenRequest {
+            mount_point,
+            namespace,
+        });
+
+        let response = client.update_auto_open(request).await
+            .map_err(|err| format!("Error updating auto open file: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously verifies the integrity of an open container
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// * `since` - If set, only integrity errors since this time are reported.
+    /// # Returns
+    /// * `Ok(())` if no integrity error was found.
+    /// * `Err(DaemonError)` with the code and the message of the error if an integrity error was found or the check failed.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn verify_integrity(namespace: String, since: Option<String>) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(VerifyIntegrityRequest {
+            namespace,
+            since: since.unwrap_or_default(),
+        });
+
+        let response = client.verify_integrity(request).await
+            .map_err(|err| format!("Error verifying integrity: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously bind-mounts a directory of an open container to another directory
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
     /// * `namespace` - The name of the container.
+    /// * `source` - The directory relative to the mount point of the container.
+    /// * `target` - The directory where the source is mounted to (must already exist).
+    /// * `read_only` - If true, the target is mounted read-only.
     /// # Returns
-    /// * `Ok(())` if the container was closed successfully.
-    /// * `Err(String)` with the error message if the container was not closed successfully.
-    /// # Examples
-    /// For example usage see cli.rs.
-    pub fn close_container_sync(mount_point: String, namespace: String) -> Result<(), String> {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            close_container(mount_point, namespace).await
-        })
+    /// * `Ok(())` if the directory was mounted successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the directory was not mounted successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn bind_mount(namespace: String, source: String, target: String, read_only: bool) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(BindMountRequest {
+            namespace,
+            source,
+            target,
+            read_only,
+        });
+
+        let response = client.bind_mount(request).await
+            .map_err(|err| format!("Error bind-mounting container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
     }
 
-    /// Synchronous wrapper for exporting a container
+    /// Asynchronously creates an encrypted swap device with a random key
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
-    /// * `path` - The path to the container.
+    /// * `size` - The size of the swap device in MB (must be at least 16MB).
     /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
-    /// * `secret` - The secret for the container (is needed when container is imported).
     /// # Returns
-    /// * `Ok(())` if the container was exported successfully.
-    /// * `Err(String)` with the error message if the container was not exported successfully.
-    /// # Examples
-    /// For example usage see cli.rs.
-    pub fn export_container_sync(path: String, namespace: String, id: String, secret: String) -> Result<(), String> {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            export_container(path, namespace, id, secret).await
-        })
+    /// * `Ok(CreatedContainer)` with the details of the container if swapping on the container was enabled successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container was not created successfully.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn create_swap_container(size: i32, namespace: String) -> Result<CreatedContainer, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(CreateSwapContainerRequest {
+            size,
+            namespace,
+        });
+
+        let response = client.create_swap_container(request).await
+            .map_err(|err| format!("Error creating swap container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner.container.unwrap_or_default())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
     }
 
-    /// Synchronous wrapper for importing a container
+    /// Asynchronously checks if a container would open, without opening or mounting it
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
     /// * `path` - The path to the container.
-    /// * `namespace` - The name of the container.
     /// * `id` - The id of the container.
-    /// * `secret` - The secret for the container (is needed when container is imported).
     /// # Returns
-    /// * `Ok(())` if the container was imported successfully.
-    /// * `Err(String)` with the error message if the container was not imported successfully.
-    /// # Examples
-    /// For example usage see cli.rs.
-    pub fn import_container_sync(path: String, namespace: String, id: String, secret: String) -> Result<(), String> {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            import_container(path, namespace, id, secret).await
-        })
+    /// * `Ok(bool)` with true if the key of the id unlocks the container and false if it does not.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container could not be checked.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn test_open(path: String, id: String) -> Result<bool, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(TestOpenRequest {
+            path,
+            id,
+        });
+
+        let response = client.test_open(request).await
+            .map_err(|err| format!("Error testing container: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner.opens)
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
     }
 
-    /// Synchronous wrapper for adding container to auto open file
+    /// Asynchronously backs up the LUKS header of a container
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
     /// * `path` - The path to the container.
-    /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
+    /// * `out_file` - The path of the backup, it must not exist yet.
     /// # Returns
-    /// * `Ok(())` if the container was added to auto open file successfully.
-    /// * `Err(String)` with the error message if the container was not added to auto open file successfully.
-    /// # Examples
-    /// For example usage see cli.rs.
+    /// * `Ok(())` if the backup was written successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the backup was not written.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn backup_header(path: String, out_file: String) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
 
-    pub fn add_container_to_auto_open_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), String> {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            add_container_to_auto_open(mount_point, path, namespace, id).await
-        })
+        let request = request(BackupHeaderRequest {
+            path,
+            out_file,
+        });
+
+        let response = client.backup_header(request).await
+            .map_err(|err| format!("Error backing up header: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
     }
 
-    /// Synchronous wrapper for removing container from auto open file
+    /// Asynchronously restores the LUKS header of a container from a backup
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
     /// * `path` - The path to the container.
-    /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
+    /// * `in_file` - The path of the backup.
     /// # Returns
-    /// * `Ok(())` if the container was removed from auto open file successfully.
-    /// * `Err(String)` with the error message if the container was not removed from auto open file successfully.
-    /// # Examples
-    /// For example usage see cli.rs.
-    pub fn remove_container_from_auto_open_sync(mount_point: String, path: String, namespace: String, id: String) -> Result<(), String> {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            remove_container_from_auto_open(mount_point, path, namespace, id).await
-        })
+    /// * `Ok(())` if the header was restored successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the header was not restored.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn restore_header(path: String, in_file: String) -> Result<(), DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let request = request(RestoreHeaderRequest {
+            path,
+            in_file,
+        });
+
+        let response = client.restore_header(request).await
+            .map_err(|err| format!("Error restoring header: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(())
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
     }
 
-    /// Asynchronously creates a container
+    /// Asynchronously describes the LUKS header of a container
     /// # Arguments
-    /// * `size` - The size of the container in MB (must be at least 16MB).
-    /// * `mount_point` - The path to the mount point (must already exist).
-    /// * `path` - The path to the directory where the container is stored (must already exist).
-    /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
-    /// * `auto_open` -
-    /// If true,
-    /// the container is added to the autoOpen file
-    /// and will be opened automatically when the system starts.
+    /// * `path` - The path to the container.
     /// # Returns
-    /// * `Ok(())` if the container was created successfully.
-    /// * `Err(String)` with the error message if the container was not created successfully.
+    /// * `Ok(String)` with the output of `cryptsetup luksDump`, it contains no keys.
+    /// * `Err(DaemonError)` with the code and the message of the error if the container could not be described.
     /// # Note
-    /// This function is asynchronous and is not mend to be called directly.
-    async fn create_container(size: i32, mount_point: String, path: String, namespace: String, id: String, auto_open: bool) -> Result<(), String> {
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn container_info(path: String) -> Result<String, DaemonError> {
         let mut client = connect().await.map_err(|e| e.to_string())?;
 
-        let request = Request::new(CreateContainerRequest {
-            size,
-            mount_point,
+        let request = request(ContainerInfoRequest {
             path,
-            namespace,
-            id,
-            auto_open,
         });
 
-        let response = client.create_container(request).await
-            .map_err(|err| format!("Error creating container: {}", err))?;
+        let response = client.container_info(request).await
+            .map_err(|err| format!("Error reading container info: {}", err))?;
 
         let inner = response.into_inner();
         if inner.status {
-            Ok(())
+            Ok(inner.info)
         } else {
-            Err(inner.error)
+            Err(DaemonError::from_response(inner.code, inner.error))
         }
     }
 
-    /// Asynchronously opens a container
+    /// Asynchronously replaces the password of a closed container with the one of a new id
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
     /// * `path` - The path to the container.
-    /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
+    /// * `old_id` - The id the password of the container is currently derived from.
+    /// * `new_id` - The id the new password is derived from.
     /// # Returns
-    /// * `Ok(())` if the container was opened successfully.
-    /// * `Err(String)` with the error message if the container was not opened successfully.
+    /// * `Ok(())` if the password was replaced successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the password was not replaced.
     /// # Note
-    /// This function is asynchronous and is not mend to be called directly.
-    async fn open_container(mount_point: String, path: String, namespace: String, id: String) -> Result<(), String> {
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn rekey_container(path: String, old_id: String, new_id: String) -> Result<(), DaemonError> {
         let mut client = connect().await.map_err(|e| e.to_string())?;
 
-        let request = Request::new(OpenContainerRequest {
-            mount_point,
+        let request = request(RekeyContainerRequest {
             path,
-            namespace,
-            id,
+            old_id,
+            new_id,
         });
 
-        let response = client.open_container(request).await
-            .map_err(|err| format!("Error opening container: {}", err))?;
+        let response = client.rekey_container(request).await
+            .map_err(|err| format!("Error rekeying container: {}", err))?;
 
         let inner = response.into_inner();
         if inner.status {
             Ok(())
         } else {
-            Err(inner.error)
+            Err(DaemonError::from_response(inner.code, inner.error))
         }
     }
 
-    /// Asynchronously closes a container
+    /// Asynchronously adds a secret to a free key slot of a container
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
-    /// * `namespace` - The name of the container.
+    /// * `path` - The path to the container.
+    /// * `existing_id` - The id of the container, its key unlocks the container to add the secret.
+    /// * `new_secret` - The secret that is added.
     /// # Returns
-    /// * `Ok(())` if the container was closed successfully.
-    /// * `Err(String)` with the error message if the container was not closed successfully.
+    /// * `Ok(u32)` with the key slot the secret was added to.
+    /// * `Err(DaemonError)` with the code and the message of the error if the secret was not added.
     /// # Note
-    /// This function is asynchronous and is not mend to be called directly.
-    async fn close_container(mount_point: String, namespace: String) -> Result<(), String> {
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn add_key_slot(path: String, existing_id: String, new_secret: String) -> Result<u32, DaemonError> {
         let mut client = connect().await.map_err(|e| e.to_string())?;
 
-        let request = Request::new(CloseContainerRequest {
-            mount_point,
-            namespace,
+        let request = request(AddKeySlotRequest {
+            path,
+            id: existing_id,
+            secret: new_secret,
         });
 
-        let response = client.close_container(request).await
-            .map_err(|err| format!("Error closing container: {}", err))?;
+        let response = client.add_key_slot(request).await
+            .map_err(|err| format!("Error adding key slot: {}", err))?;
 
         let inner = response.into_inner();
         if inner.status {
-            Ok(())
+            Ok(inner.key_slot)
         } else {
-            Err(inner.error)
+            Err(DaemonError::from_response(inner.code, inner.error))
         }
     }
 
-    /// Asynchronously exports a container
+    /// Asynchronously removes the key slot of a container that is unlocked by a secret
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
     /// * `path` - The path to the container.
-    /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
-    /// * `secret` - The secret for the container (is needed when container is imported).
+    /// * `secret_to_remove` - The secret of the key slot that is removed.
     /// # Returns
-    /// * `Ok(())` if the container was exported successfully.
-    /// * `Err(String)` with the error message if the container was not exported successfully.
+    /// * `Ok(())` if the key slot was removed successfully.
+    /// * `Err(DaemonError)` with the code and the message of the error if the key slot was not removed.
     /// # Note
-    /// This function is asynchronous and is not mend to be called directly.
-    async fn export_container(path: String, namespace: String, id: String, secret: String) -> Result<(), String> {
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn remove_key_slot(path: String, secret_to_remove: String) -> Result<(), DaemonError> {
         let mut client = connect().await.map_err(|e| e.to_string())?;
 
-        let request = Request::new(ExportContainerRequest {
+        let request = request(RemoveKeySlotRequest {
             path,
-            namespace,
-            id,
-            secret,
+            secret: secret_to_remove,
         });
 
-        let response = client.export_container(request).await
-            .map_err(|err| format!("Error exporting container: {}", err))?;
+        let response = client.remove_key_slot(request).await
+            .map_err(|err| format!("Error removing key slot: {}", err))?;
 
         let inner = response.into_inner();
         if inner.status {
             Ok(())
         } else {
-            Err(inner.error)
+            Err(DaemonError::from_response(inner.code, inner.error))
         }
     }
 
-    /// Asynchronously imports a container
+    /// Asynchronously searches a directory for LUKS containers
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
-    /// * `path` - The path to the container.
-    /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
-    /// * `secret` - The secret for the container (is needed when container is imported).
+    /// * `directory` - The path to the directory.
     /// # Returns
-    /// * `Ok(())` if the container was imported successfully.
-    /// * `Err(String)` with the error message if the container was not imported successfully.
+    /// * `Ok(Vec<ScannedContainer>)` with the path, UUID and LUKS version of every container that was found.
+    /// * `Err(DaemonError)` with the code and the message of the error if the directory could not be searched.
     /// # Note
-    /// This function is asynchronous and is not mend to be called directly.
-    async fn import_container(path: String, namespace: String, id: String, secret: String) -> Result<(), String> {
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn scan_directory(directory: String) -> Result<Vec<ScannedContainer>, DaemonError> {
         let mut client = connect().await.map_err(|e| e.to_string())?;
 
-        let request = Request::new(ImportContainerRequest {
-            path,
-            namespace,
-            id,
-            secret,
+        let request = request(ScanDirectoryRequest {
+            directory,
         });
 
-        let response = client.import_container(request).await
-            .map_err(|err| format!("Error importing container: {}", err))?;
+        let response = client.scan_directory(request).await
+            .map_err(|err| format!("Error scanning directory: {}", err))?;
 
         let inner = response.into_inner();
         if inner.status {
-            Ok(())
+            Ok(inner.containers)
         } else {
-            Err(inner.error)
+            Err(DaemonError::from_response(inner.code, inner.error))
         }
     }
 
-    /// Asynchronously Add container to auto open file
+    /// Asynchronously compares the auto open file with the containers that are open
+    /// # Returns
+    /// * `Ok(DiffStateResponse)` with the namespaces of the containers by state.
+    /// * `Err(DaemonError)` with the code and the message of the error if the state could not be compared.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn diff_state() -> Result<DiffStateResponse, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let response = client.diff_state(request(DiffStateRequest {})).await
+            .map_err(|err| format!("Error comparing state: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner)
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously collects the public metadata and the state of every container
+    /// # Returns
+    /// * `Ok(Vec<ContainerInventoryItem>)` with the public metadata and the state of every container.
+    /// * `Err(DaemonError)` with the code and the message of the error if the inventory could not be collected.
+    /// # Note
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn inventory() -> Result<Vec<ContainerInventoryItem>, DaemonError> {
+        let mut client = connect().await.map_err(|e| e.to_string())?;
+
+        let response = client.inventory(request(InventoryRequest {})).await
+            .map_err(|err| format!("Error collecting inventory: {}", err))?;
+
+        let inner = response.into_inner();
+        if inner.status {
+            Ok(inner.containers)
+        } else {
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
+    }
+
+    /// Asynchronously checks if a container is open and mounted without changing anything
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
-    /// * `path` - The path to the container.
     /// * `namespace` - The name of the container.
-    /// * `id` - The id of the container.
     /// # Returns
-    /// * `Ok(())` if the container was added to auto open file successfully.
-    /// * `Err(String)` with the error message if the container was not added to auto open file successfully.
+    /// * `Ok(ContainerState)` with the state and the mount point of the container, a container that does not exist is closed.
+    /// * `Err(DaemonError)` with the code and the message of the error if the state could not be checked.
     /// # Note
-    /// This function is asynchronous and is not mend to be called directly.
-    async fn add_container_to_auto_open(mount_point: String, path: String, namespace: String, id: String) -> Result<(), String> {
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn status_container(namespace: String) -> Result<ContainerState, DaemonError> {
         let mut client = connect().await.map_err(|e| e.to_string())?;
 
-        let request = Request::new(AddToAutoOpenRequest {
-            mount_point,
-            path,
+        let request = request(StatusContainerRequest {
             namespace,
-            id,
         });
 
-        let response = client.add_to_auto_open(request).await
-            .map_err(|err| format!("Error adding container to auto open: {}", err))?;
+        let response = client.status_container(request).await
+            .map_err(|err| format!("Error checking the state of the container: {}", err))?;
 
         let inner = response.into_inner();
         if inner.status {
-            Ok(())
+            Ok(inner.state.unwrap_or_default())
         } else {
-            Err(inner.error)        }
+            Err(DaemonError::from_response(inner.code, inner.error))
+        }
     }
 
-    /// Asynchronously Remove container from auto open file
+    /// Asynchronously validates inputs without creating or changing anything
     /// # Arguments
-    /// * `mount_point` - The path to the mount point (must already exist).
+    /// * `size` - The size of the container in MB.
+    /// * `mount_point` - The path to the mount point.
     /// * `path` - The path to the container.
     /// * `namespace` - The name of the container.
     /// * `id` - The id of the container.
     /// # Returns
-    /// * `Ok(())` if the container was removed from auto open file successfully.
-    /// * `Err(String)` with the error message if the container was not removed from auto open file successfully.
+    /// * `Ok(ValidateInputResponse)` with the first error and the field that caused it, or `OK` if all inputs are valid.
+    /// * `Err(String)` with the error message if the daemon could not be reached.
     /// # Note
-    /// This function is asynchronous and is not mend to be called directly.
-    async fn remove_container_from_auto_open(mount_point: String, path: String, namespace: String, id: String) -> Result<(), String> {
+    /// This function is asynchronous, it can be awaited in the tokio runtime of the caller.
+    /// Without a runtime the synchronous wrapper is used instead.
+    pub async fn validate_input(size: Option<i32>, mount_point: Option<String>, path: Option<String>, namespace: Option<String>, id: Option<String>) -> Result<ValidateInputResponse, String> {
         let mut client = connect().await.map_err(|e| e.to_string())?;
 
-        let request = Request::new(RemoveFromAutoOpenRequest {
-            mount_point,
-            path,
-            namespace,
-            id,
+        let request = request(ValidateInputRequest {
+            size: size.unwrap_or_default(),
+            mount_point: mount_point.unwrap_or_default(),
+            path: path.unwrap_or_default(),
+            namespace: namespace.unwrap_or_default(),
+            id: id.unwrap_or_default(),
         });
 
-        let response = client.remove_from_auto_open(request).await
-            .map_err(|err| format!("Error removing container from auto open: {}", err))?;
+        let response = client.validate_input(request).await
+            .map_err(|err| format!("Error validating input: {}", err))?;
 
-        let inner = response.into_inner();
-        if inner.status {
-            Ok(())
-        } else {
-            Err(inner.error)
+        Ok(response.into_inner())
+    }
+
+    /// Wraps a message into a gRPC request.
+    /// If a timeout is set with `set_timeout`, it is sent to the daemon as deadline of the request,
+    /// so the daemon aborts the operation when the client stops waiting.
+    /// # Arguments
+    /// * `message` - The message of the request.
+    /// # Returns
+    /// * `Request<T>` with the message and the deadline.
+    /// # Note
+    /// This function is not mend to be called directly.
+    fn request<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        match timeout_ms() {
+            0 => (),
+            millis => request.set_timeout(Duration::from_millis(millis)),
         }
+        request
     }
 
     /// Asynchronously connects to the gRPC server using the server URL.
+    /// If the daemon can not be reached, the connection is tried again after a backoff,
+    /// up to `SECURE_CONTAINER_CONNECT_ATTEMPTS` times (default 3).
     /// # Arguments
     /// * `None`
     /// # Returns
     /// * `Ok(ContainerClient<Channel>)` if the connection was successful.
-    /// * `Err(Status)` with the error message if the connection was not successful.
+    /// * `Err(Status)` with the error message if the connection was not successful,
+    /// `Unavailable` with the number of attempts if the daemon could not be reached by any of them.
     /// # Note
     /// This function is asynchronous and is not mend to be called directly.
     async fn connect() -> Result<ContainerClient<Channel>, Status> {
-        ContainerClient::connect(SERVER_URL).await.map_err(|err| Status::new(tonic::Code::Unavailable, format!("Error connecting to server: {}", err)))
+        let attempts = connect_attempts();
+        let mut attempt = 1;
+        loop {
+            match connect_once().await {
+                // Only a daemon that can not be reached may come up in the meantime, other errors are returned right away.
+                Err(status) if status.code() == tonic::Code::Unavailable && attempt < attempts => {
+                    tokio::time::sleep(connect_backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(status) if status.code() == tonic::Code::Unavailable && attempts > 1 => {
+                    return Err(Status::new(tonic::Code::Unavailable, format!("{} (gave up after {} attempts)", status.message(), attempts)));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Connects to the gRPC server using the server URL once.
+    /// # Arguments
+    /// * `None`
+    /// # Returns
+    /// * `Ok(ContainerClient<Channel>)` if the connection was successful.
+    /// * `Err(Status)` with the error message if the connection was not successful or did not succeed within the connect timeout.
+    /// # Note
+    /// This function is asynchronous and is not mend to be called directly.
+    async fn connect_once() -> Result<ContainerClient<Channel>, Status> {
+        let url = server_url();
+        if let Some(path) = url.strip_prefix("unix:") {
+            return connect_unix(path.to_string()).await.map(ContainerClient::new);
+        }
+        let mut endpoint = Endpoint::from_shared(url.clone())
+            .map_err(|err| Status::new(tonic::Code::InvalidArgument, format!("Server URL '{}' is not valid: {}", url, err)))?;
+        if let Some(tls) = client_tls_config().map_err(|err| Status::new(tonic::Code::InvalidArgument, err))? {
+            if !url.starts_with("https://") {
+                return Err(Status::new(tonic::Code::InvalidArgument, format!("Server URL '{}' must start with https:// to use TLS", url)));
+            }
+            endpoint = endpoint.tls_config(tls)
+                .map_err(|err| Status::new(tonic::Code::InvalidArgument, format!("TLS configuration is not valid: {}", error_chain(&err))))?;
+        }
+        ContainerClient::connect(endpoint.connect_timeout(connect_timeout())).await.map_err(|err| Status::new(tonic::Code::Unavailable, format!("Error connecting to server: {}", error_chain(&err))))
+    }
+
+    /// Connects to the daemon over the Unix socket at the given path.
+    /// The URL of the endpoint is not used, the connector always connects to the socket.
+    /// # Arguments
+    /// * `path` - The path of the socket.
+    /// # Returns
+    /// * `Result<Channel, Status>` - Returns the channel or an error if the socket can not be connected.
+    /// # Note
+    /// This function is asynchronous and is not mend to be called directly.
+    #[cfg(unix)]
+    async fn connect_unix(path: String) -> Result<Channel, Status> {
+        Endpoint::from_static(DEFAULT_SERVER_URL)
+            .connect_timeout(connect_timeout())
+            .connect_with_connector(service_fn(move |_: Uri| UnixStream::connect(path.clone())))
+            .await
+            .map_err(|err| Status::new(tonic::Code::Unavailable, format!("Error connecting to server: {}", error_chain(&err))))
+    }
+
+    /// Unix sockets are only supported on Unix systems.
+    #[cfg(not(unix))]
+    async fn connect_unix(path: String) -> Result<Channel, Status> {
+        Err(Status::new(tonic::Code::InvalidArgument, format!("Server URL 'unix:{}' is not valid: Unix sockets are not supported on this platform", path)))
+    }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependency_check_display() {
+        let mut check = DependencyCheck {
+            name: "cryptsetup".to_string(),
+            essential: true,
+            available: true,
+            details: "cryptsetup 2.6.1".to_string(),
+        };
+        assert_eq!(check.to_string(), "[PASS] cryptsetup: cryptsetup 2.6.1");
+        check.available = false;
+        assert_eq!(check.to_string(), "[FAIL] cryptsetup: cryptsetup 2.6.1");
+        check.essential = false;
+        assert_eq!(check.to_string(), "[WARN] cryptsetup: cryptsetup 2.6.1");
+    }
+
+    #[test]
+    fn test_runtime_is_reused() {
+        // The calls fail fast instead of trying to connect again after a backoff.
+        std::env::set_var("SECURE_CONTAINER_CONNECT_ATTEMPTS", "1");
+        let first = runtime().unwrap() as *const Runtime;
+        for _ in 0..200 {
+            // No daemon is listening in the tests, so every call fails to connect.
+            assert!(doctor_sync().is_err());
+        }
+        assert_eq!(first, runtime().unwrap() as *const Runtime);
+    }
+
+    #[test]
+    fn test_block_on_within_runtime() {
+        let result = Runtime::new().unwrap().block_on(async {
+            block_on(async { Ok::<_, String>(1) })
+        });
+        assert_eq!(result, Err(RUNTIME_ERROR.to_string()));
     }
 
+    #[test]
+    fn test_timeout() {
+        set_timeout(Some(Duration::from_millis(10)));
+        let result = block_on(std::future::pending::<Result<(), String>>());
+        set_timeout(None);
+        assert_eq!(result, Err(TIMEOUT_ERROR.to_string()));
+        assert_eq!(block_on(async { Ok::<_, String>(1) }), Ok(1));
+    }
+
+    #[test]
+    fn test_connect_backoff() {
+        assert_eq!(connect_backoff(1), Duration::from_millis(200));
+        assert_eq!(connect_backoff(2), Duration::from_millis(400));
+        assert_eq!(connect_backoff(3), Duration::from_millis(800));
+        assert_eq!(DEFAULT_CONNECT_ATTEMPTS, 3);
+        assert_eq!(DEFAULT_CONNECT_TIMEOUT, Duration::from_secs(5));
+    }
 
+    #[test]
+    fn test_server_url() {
+        set_server_url(Some("not a url".to_string()));
+        let result = doctor_sync();
+        set_server_url(Some("unix:/not/existing/secure_container.sock".to_string()));
+        let unix_result = doctor_sync();
+        set_server_url(None);
+        assert!(result.unwrap_err().contains("Server URL 'not a url' is not valid"));
+        assert!(unix_result.unwrap_err().contains("Error connecting to server"));
+    }
 
+    #[test]
+    fn test_error_chain() {
+        let err = std::io::Error::other("invalid peer certificate: UnknownIssuer");
+        assert_eq!(error_chain(&err), "invalid peer certificate: UnknownIssuer");
+        let err = Status::from_error(Box::new(err));
+        assert!(error_chain(&err).ends_with(": invalid peer certificate: UnknownIssuer"));
+        assert!(read_tls_file("/not/existing/ca.pem").unwrap_err().contains("TLS file '/not/existing/ca.pem' can not be read"));
+    }
 
+    #[test]
+    fn test_daemon_error() {
+        let error = DaemonError::from_response(12, "Cryptsetup error: No key available".to_string());
+        assert_eq!(error.code, 12);
+        assert_eq!(error.to_string(), "Cryptsetup error: No key available");
+        // An older daemon sends no code.
+        assert_eq!(DaemonError::from_response(0, "Container open".to_string()).code, 21);
+        assert_eq!(DaemonError::from("Error connecting".to_string()).code, error_handling::EXIT_CODE_UNKNOWN);
+        assert_eq!(DaemonError::from(TIMEOUT_ERROR.to_string()).code, error_handling::EXIT_CODE_DEADLINE_EXCEEDED);
+    }
 
+    #[test]
+    fn test_provision_container() {
+        let dir = tempfile::tempdir().unwrap();
+        let mount_point = dir.path().join("mnt").join("data");
+        let spec = ContainerSpec {
+            size: 64,
+            mount_point: mount_point.to_string_lossy().to_string(),
+            path: dir.path().to_string_lossy().to_string(),
+            namespace: "ProvisionTest".to_string(),
+            id: "test".to_string(),
+            auto_open: false,
+            fs_type: "ext4".to_string(),
+            create_mount_point: true,
+        };
+        // No daemon is listening in the tests, but the mount point is created before connecting.
+        assert!(provision_container_sync(spec).is_err());
+        assert!(mount_point.is_dir());
+    }
+}
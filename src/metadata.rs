@@ -0,0 +1,364 @@
+//! # Metadata
+//! This module stores the public metadata of a container, it contains no secrets.
+//! The metadata is stored as a token of the type `secure-container` in the LUKS2 header,
+//! so it travels inside the container itself.
+//! LUKS1 containers have no tokens, if they are stored in a file
+//! they get a sidecar file next to the container instead, it is named `<container>.json`.
+//! Block devices never get a sidecar, `/dev` is a tmpfs that does not keep it and that other processes may write to.
+//!
+//! ## Format
+//! ```json
+//! {
+//!   "version": 1,
+//!   "namespace": "MyContainer",
+//!   "deviceName": "MyContainer",
+//!   "uuid": "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11",
+//!   "cipher": "aes-xts-plain64",
+//!   "integrity": "hmac-sha256",
+//!   "pbkdf": "argon2id",
+//!   "iterTime": 2000,
+//!   "created": "2024-03-01T12:00",
+//!   "transferSalt": "q0MeF2bY7Zq8hXo1GJ4bXg",
+//!   "fileSystem": "ext4",
+//!   "keySalt": "x2C9nV4kYq1Jd7uXo3RbWw",
+//!   "keyDerivation": 1
+//! }
+//! ```
+//! `keySalt` is the base64 encoded random salt the password of the container is derived with,
+//! `HKDF-SHA256(libuta key of the id, keySalt)` (see `utilities::get_salted_password`).
+//! It is stored with the container, so an exported container can be opened with its id on another system.
+//! Containers without `keySalt` use the libuta key of the id as password.
+//! `keyDerivation` is the version of the libuta key derivation of the id (see `KEY_DERIVATION`),
+//! containers without it derive the key from the first 8 bytes of the id.
+//! The token contains the same fields and additionally the fields `type` and `keyslots`,
+//! which are required by LUKS2.
+//!
+
+use crate::error_handling;
+use error_handling::{Result, SecureContainerErr};
+
+use crate::cryptsetup_backend::{backend, TOKEN_TYPE};
+use crate::cryptsetup_wrapper::luks_version;
+use crate::file_system_operations::check_if_file_exists;
+
+use serde::{Deserialize, Serialize};
+
+/// The version of the metadata format.
+pub const METADATA_VERSION: u32 = 1;
+
+/// The key derivation of containers that derive the libuta key from the first 8 bytes of the id,
+/// all containers that were created before the key derivation was recorded use it.
+pub const LEGACY_KEY_DERIVATION: u32 = 0;
+
+/// The key derivation of new containers, the libuta key is derived from the whole id if it is longer than 8 bytes.
+/// Shorter ids derive the same key as with `LEGACY_KEY_DERIVATION`.
+pub const KEY_DERIVATION: u32 = 1;
+
+/// The public metadata of a container.
+/// * `version` - The version of the metadata format.
+/// * `namespace` - The name of the container, it may contain any UTF-8 characters.
+/// * `device_name` - The device-mapper name of the container (see `input_validation::device_name`).
+/// * `uuid` - The UUID of the LUKS header.
+/// * `cipher` - The cipher of the encrypted data.
+/// * `integrity` - The algorithm of the integrity protection.
+/// * `pbkdf` - The PBKDF of the key slot, empty for metadata that was written before it was recorded.
+/// * `iter_time` - The milliseconds the PBKDF of the key slot takes, 0 if it was not recorded.
+/// * `created` - The local time the container was created in the format `TIME_FORMAT`, empty if it was not recorded.
+/// * `transfer_salt` -
+/// The salt of the key derivation the container was exported with, empty if it was not exported with a secret.
+/// * `file_system` -
+/// The file system the container was created with (see `input_validation::FILE_SYSTEMS`),
+/// empty if it was created without one or before it was recorded.
+/// * `key_salt` -
+/// The salt the password of the container is derived with, empty if it was created before the salt was introduced.
+/// * `key_derivation` -
+/// The version of the key derivation of the id, `LEGACY_KEY_DERIVATION` if it was created before it was recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerMetadata {
+    pub version: u32,
+    pub namespace: String,
+    #[serde(default)]
+    pub device_name: String,
+    pub uuid: String,
+    pub cipher: String,
+    pub integrity: String,
+    #[serde(default)]
+    pub pbkdf: String,
+    #[serde(default)]
+    pub iter_time: u32,
+    #[serde(default)]
+    pub created: String,
+    #[serde(default)]
+    pub transfer_salt: String,
+    #[serde(default)]
+    pub file_system: String,
+    #[serde(default)]
+    pub key_salt: String,
+    #[serde(default)]
+    pub key_derivation: u32,
+}
+
+/// The LUKS2 token that holds the metadata of a container.
+/// * `token_type` - The type of the token, always `TOKEN_TYPE`.
+/// * `keyslots` - The key slots the token belongs to, the metadata belongs to none.
+/// * `metadata` - The metadata of the container.
+#[derive(Debug, Serialize, Deserialize)]
+struct MetadataToken {
+    #[serde(rename = "type")]
+    token_type: String,
+    keyslots: Vec<String>,
+    #[serde(flatten)]
+    metadata: ContainerMetadata,
+}
+
+/// Returns the path of the metadata sidecar of a container.
+/// # Arguments
+/// * `container_path` - The path to the container.
+/// # Returns
+/// * `String` - The path of the sidecar.
+/// # Example
+/// ```
+/// assert_eq!(metadata_path("/home/Container"), "/home/Container.json");
+/// ```
+///
+pub fn metadata_path(container_path: &str) -> String {
+    format!("{}.json", container_path)
+}
+
+/// Writes the metadata sidecar of a container, an existing sidecar is replaced.
+/// # Arguments
+/// * `container_path` - The path to the container.
+/// * `metadata` - The metadata of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the sidecar was written successfully otherwise an error is returned.
+/// # Errors
+/// * `FileWriteError` - An error occurred while writing the sidecar.
+/// # Example
+/// ```
+/// let metadata = ContainerMetadata {
+///     version: METADATA_VERSION,
+///     namespace: "MyContainer".to_string(),
+///     device_name: "MyContainer".to_string(),
+///     uuid: "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11".to_string(),
+///     cipher: "aes-xts-plain64".to_string(),
+///     integrity: "hmac-sha256".to_string(),
+///     pbkdf: "argon2id".to_string(),
+///     iter_time: 2000,
+///     created: "2024-03-01T12:00".to_string(),
+///     transfer_salt: String::new(),
+///     file_system: "ext4".to_string(),
+///     key_salt: String::new(),
+///     key_derivation: KEY_DERIVATION,
+/// };
+/// let result = write_metadata("/home/Container", &metadata);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn write_metadata(container_path: &str, metadata: &ContainerMetadata) -> Result<()> {
+    let json = match serde_json::to_string_pretty(metadata) {
+        Ok(json) => json,
+        Err(err) => return Err(SecureContainerErr::FileWriteError(err.to_string())),
+    };
+    match std::fs::write(metadata_path(container_path), json) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(SecureContainerErr::FileWriteError(err.to_string())),
+    }
+}
+
+/// Stores the metadata of a container in its LUKS2 header, an existing metadata token is replaced.
+/// The metadata of LUKS1 containers in a file is written to the sidecar instead.
+/// Block devices have no sidecar, so for them the error of the token is returned.
+/// # Arguments
+/// * `container_path` - The path to the container.
+/// * `metadata` - The metadata of the container.
+/// # Returns
+/// * `Result<()>` -
+/// Returns OK(()) if the metadata was stored successfully otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while storing the token of a block device.
+/// * `FileWriteError` - An error occurred while writing the sidecar.
+/// # Example
+/// ```
+/// let metadata = ContainerMetadata {
+///     version: METADATA_VERSION,
+///     namespace: "MyContainer".to_string(),
+///     device_name: "MyContainer".to_string(),
+///     uuid: "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11".to_string(),
+///     cipher: "aes-xts-plain64".to_string(),
+///     integrity: "hmac-sha256".to_string(),
+///     pbkdf: "argon2id".to_string(),
+///     iter_time: 2000,
+///     created: "2024-03-01T12:00".to_string(),
+///     transfer_salt: String::new(),
+///     file_system: "ext4".to_string(),
+///     key_salt: String::new(),
+///     key_derivation: KEY_DERIVATION,
+/// };
+/// let result = store_metadata("/home/Container", &metadata);
+/// assert!(result.is_ok());
+/// ```
+///
+pub fn store_metadata(container_path: &str, metadata: &ContainerMetadata) -> Result<()> {
+    match backend().set_token(container_path, &to_token(metadata)?) {
+        Ok(_) => Ok(()),
+        Err(err) if !uses_sidecar(container_path) => Err(err),
+        Err(_) => write_metadata(container_path, metadata),
+    }
+}
+
+/// Reads the metadata of a container from its LUKS2 header or, if it has no metadata token, from the sidecar.
+/// The sidecar of a block device is never read.
+/// # Arguments
+/// * `container_path` - The path to the container.
+/// # Returns
+/// * `Result<Option<ContainerMetadata>>` -
+/// Returns the metadata or `None` if the container has neither a metadata token nor a sidecar.
+/// # Errors
+/// * `CryptsetupError` - The header of a LUKS2 container or a block device could not be read.
+/// * `FileReadError` - The sidecar could not be read or the metadata is not valid.
+/// # Example
+/// ```
+/// let metadata = read_metadata("/home/Container").unwrap();
+/// assert_eq!(metadata.unwrap().namespace, "MyContainer");
+/// ```
+///
+pub fn read_metadata(container_path: &str) -> Result<Option<ContainerMetadata>> {
+    match backend().get_token(container_path) {
+        Ok(Some(token)) => return from_token(&token).map(Some),
+        Ok(None) => (),
+        // The password of a container depends on its metadata, so a header that can not be read is not treated
+        // like a container without metadata.
+        Err(err) if !uses_sidecar(container_path) => return Err(err),
+        Err(_) => (),
+    }
+    let sidecar = metadata_path(container_path);
+    if !check_if_file_exists(container_path) || !check_if_file_exists(&sidecar) {
+        return Ok(None);
+    }
+    let json = match std::fs::read_to_string(sidecar) {
+        Ok(json) => json,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
+    };
+    match serde_json::from_str(&json) {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(err) => Err(SecureContainerErr::FileReadError(err.to_string())),
+    }
+}
+
+/// Returns true if the metadata of a container is kept in the sidecar,
+/// which is only the case for LUKS1 containers in a file.
+/// # Note
+/// This function is not meant to be called directly.
+fn uses_sidecar(container_path: &str) -> bool {
+    check_if_file_exists(container_path) && luks_version(container_path) == Ok(1)
+}
+
+/// Converts the metadata of a container into a LUKS2 token.
+/// # Note
+/// This function is not meant to be called directly.
+fn to_token(metadata: &ContainerMetadata) -> Result<String> {
+    let token = MetadataToken {
+        token_type: TOKEN_TYPE.to_string(),
+        keyslots: Vec::new(),
+        metadata: metadata.clone(),
+    };
+    match serde_json::to_string(&token) {
+        Ok(json) => Ok(json),
+        Err(err) => Err(SecureContainerErr::FileWriteError(err.to_string())),
+    }
+}
+
+/// Reads the metadata of a container from a LUKS2 token.
+/// # Note
+/// This function is not meant to be called directly.
+fn from_token(json: &str) -> Result<ContainerMetadata> {
+    match serde_json::from_str::<MetadataToken>(json) {
+        Ok(token) => Ok(token.metadata),
+        Err(err) => Err(SecureContainerErr::FileReadError(err.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let metadata = ContainerMetadata {
+            version: METADATA_VERSION,
+            namespace: "Überweisungen".to_string(),
+            device_name: "%C3%9Cberweisungen".to_string(),
+            uuid: "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11".to_string(),
+            cipher: "aes-xts-plain64".to_string(),
+            integrity: "hmac-sha256".to_string(),
+            pbkdf: "argon2id".to_string(),
+            iter_time: 2000,
+            created: "2024-03-01T12:00".to_string(),
+            transfer_salt: String::new(),
+            file_system: "ext4".to_string(),
+            key_salt: "x2C9nV4kYq1Jd7uXo3RbWw".to_string(),
+            key_derivation: KEY_DERIVATION,
+        };
+        let token = to_token(&metadata).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&token).unwrap();
+        assert_eq!(json["type"], TOKEN_TYPE);
+        assert_eq!(json["keyslots"], serde_json::json!([]));
+        assert_eq!(json["deviceName"], "%C3%9Cberweisungen");
+        assert_eq!(from_token(&token), Ok(metadata));
+        assert!(from_token(r#"{"type": "secure-container", "keyslots": []}"#).is_err());
+        // Metadata that was written before the PBKDF was recorded is still read.
+        let mut json = json;
+        json.as_object_mut().unwrap().remove("pbkdf");
+        json.as_object_mut().unwrap().remove("iterTime");
+        json.as_object_mut().unwrap().remove("created");
+        json.as_object_mut().unwrap().remove("transferSalt");
+        json.as_object_mut().unwrap().remove("fileSystem");
+        json.as_object_mut().unwrap().remove("keySalt");
+        json.as_object_mut().unwrap().remove("keyDerivation");
+        let metadata = from_token(&json.to_string()).unwrap();
+        assert_eq!((metadata.pbkdf.as_str(), metadata.iter_time), ("", 0));
+        assert_eq!(
+            (metadata.created.as_str(), metadata.transfer_salt.as_str()),
+            ("", "")
+        );
+        assert_eq!(
+            (metadata.file_system.as_str(), metadata.key_salt.as_str()),
+            ("", "")
+        );
+        assert_eq!(metadata.key_derivation, LEGACY_KEY_DERIVATION);
+    }
+
+    #[test]
+    fn test_read_metadata_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Container");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "not a container").unwrap();
+        assert!(read_metadata(path).is_err());
+        // A LUKS1 header has no tokens, so its metadata is kept in the sidecar.
+        std::fs::write(path, b"LUKS\xba\xbe\x00\x01").unwrap();
+        assert_eq!(read_metadata(path), Ok(None));
+        let metadata = ContainerMetadata {
+            version: METADATA_VERSION,
+            namespace: "test".to_string(),
+            device_name: "test".to_string(),
+            uuid: "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11".to_string(),
+            cipher: "aes-xts-plain64".to_string(),
+            integrity: "hmac-sha256".to_string(),
+            pbkdf: "argon2id".to_string(),
+            iter_time: 2000,
+            created: "2024-03-01T12:00".to_string(),
+            transfer_salt: String::new(),
+            file_system: "ext4".to_string(),
+            key_salt: String::new(),
+            key_derivation: KEY_DERIVATION,
+        };
+        assert!(store_metadata(path, &metadata).is_ok());
+        assert_eq!(read_metadata(path), Ok(Some(metadata.clone())));
+        // A device never gets a sidecar.
+        assert!(read_metadata("/dev/null").is_err());
+        assert!(store_metadata("/dev/null", &metadata).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! # Operations
+//! This module serializes the operations of the daemon per container.
+//! Only one operation runs at a time for a namespace, further operations wait until it is finished.
+//! The operation that is in progress is reported by the status of the container,
+//! so a long running operation, e.g. creating a large container, is visible to the clients.
+//! The operations wait on an asynchronous lock, so a waiting request does not block a thread of the runtime.
+//!
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+use tokio::sync::OwnedMutexGuard;
+
+/// The operations that are in progress, by the namespace of their container.
+/// * `locks` -
+/// The lock of every container an operation is running or waiting for, by namespace.
+/// A lock is removed when its last operation is finished.
+/// * `in_progress` - The name of the operation that holds the lock of a container, by namespace.
+#[derive(Debug, Default)]
+pub struct Operations {
+    locks: Arc<Mutex<Locks>>,
+    in_progress: Arc<Mutex<HashMap<String, &'static str>>>,
+}
+
+/// The locks of the containers, by namespace.
+type Locks = HashMap<String, Arc<tokio::sync::Mutex<()>>>;
+
+/// Marks an operation as in progress until it is dropped.
+/// It does not borrow the operations, so it can be moved to a blocking thread.
+#[derive(Debug)]
+pub struct OperationGuard {
+    locks: Arc<Mutex<Locks>>,
+    in_progress: Arc<Mutex<HashMap<String, &'static str>>>,
+    namespace: String,
+    lock: Option<OwnedMutexGuard<()>>,
+}
+
+impl Operations {
+    /// Starts an operation on a container, it waits until the running operation of the container is finished.
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// * `operation` - The name of the operation, e.g. `create`.
+    /// * `deadline` - The time until the operation may wait, `None` to wait without a limit.
+    /// # Returns
+    /// * `Option<OperationGuard>` -
+    /// Returns a guard that finishes the operation when it is dropped,
+    /// `None` if the deadline expired before the running operation was finished.
+    /// # Example
+    /// ```
+    /// let operations = Operations::default();
+    /// let guard = operations.begin("MyContainer", "open", None).await;
+    /// assert_eq!(operations.busy("MyContainer"), Some("open".to_string()));
+    /// ```
+    ///
+    pub async fn begin(
+        &self,
+        namespace: &str,
+        operation: &'static str,
+        deadline: Option<Instant>,
+    ) -> Option<OperationGuard> {
+        let lock = Arc::clone(
+            lock_map(&self.locks)
+                .entry(namespace.to_string())
+                .or_default(),
+        );
+        let lock = match deadline {
+            Some(deadline) => {
+                match tokio::time::timeout_at(deadline.into(), lock.lock_owned()).await {
+                    Ok(lock) => lock,
+                    Err(_) => {
+                        remove_unused_lock(&self.locks, namespace);
+                        return None;
+                    }
+                }
+            }
+            None => lock.lock_owned().await,
+        };
+        lock_map(&self.in_progress).insert(namespace.to_string(), operation);
+        Some(OperationGuard {
+            locks: Arc::clone(&self.locks),
+            in_progress: Arc::clone(&self.in_progress),
+            namespace: namespace.to_string(),
+            lock: Some(lock),
+        })
+    }
+
+    /// Returns the operation that is in progress for a container.
+    /// # Arguments
+    /// * `namespace` - The name of the container.
+    /// # Returns
+    /// * `Option<String>` - The name of the operation or `None` if no operation is in progress.
+    pub fn busy(&self, namespace: &str) -> Option<String> {
+        lock_map(&self.in_progress)
+            .get(namespace)
+            .map(|operation| operation.to_string())
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        // The lock of the container is released after the operation is removed,
+        // so the next operation is never reported as finished.
+        lock_map(&self.in_progress).remove(&self.namespace);
+        drop(self.lock.take());
+        remove_unused_lock(&self.locks, &self.namespace);
+    }
+}
+
+/// Removes the lock of a container if no operation holds or waits for it,
+/// otherwise the map would keep a lock for every container an operation was ever started for.
+/// # Note
+/// This function is not meant to be called directly.
+fn remove_unused_lock(locks: &Mutex<Locks>, namespace: &str) {
+    let mut locks = lock_map(locks);
+    // Every running or waiting operation holds a clone of the lock, the map holds the last one.
+    if locks
+        .get(namespace)
+        .is_some_and(|lock| Arc::strong_count(lock) == 1)
+    {
+        locks.remove(namespace);
+    }
+}
+
+/// Locks a map of the operations, the maps are only held for a lookup, so a poisoned map is still used.
+/// # Note
+/// This function is not meant to be called directly.
+fn lock_map<T>(map: &Mutex<T>) -> MutexGuard<'_, T> {
+    map.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_begin() {
+        let operations = Operations::default();
+        let guard = operations.begin("first", "create", None).await.unwrap();
+        assert_eq!(operations.busy("first"), Some("create".to_string()));
+        assert_eq!(operations.busy("second"), None);
+        assert!(operations.begin("second", "open", None).await.is_some());
+        let deadline = Instant::now() + Duration::from_millis(20);
+        assert!(operations
+            .begin("first", "open", Some(deadline))
+            .await
+            .is_none());
+        drop(guard);
+        assert_eq!(operations.busy("first"), None);
+    }
+
+    #[tokio::test]
+    async fn test_unused_locks_are_removed() {
+        let operations = Arc::new(Operations::default());
+        let guard = operations.begin("first", "create", None).await.unwrap();
+        let expired = Some(Instant::now() + Duration::from_millis(20));
+        assert!(operations.begin("first", "open", expired).await.is_none());
+        let waiting = tokio::spawn({
+            let operations = Arc::clone(&operations);
+            async move { operations.begin("first", "close", None).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // The waiting operation still needs the lock after the first one is finished.
+        drop(guard);
+        let waiting = waiting.await.unwrap().unwrap();
+        assert_eq!(lock_map(&operations.locks).len(), 1);
+        drop(waiting);
+        assert!(lock_map(&operations.locks).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_begin_waits() {
+        let operations = Arc::new(Operations::default());
+        let guard = operations.begin("first", "create", None).await.unwrap();
+        let waiting = tokio::spawn({
+            let operations = Arc::clone(&operations);
+            async move {
+                let _guard = operations.begin("first", "close", None).await.unwrap();
+                operations.busy("first")
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(operations.busy("first"), Some("create".to_string()));
+        drop(guard);
+        assert_eq!(waiting.await.unwrap(), Some("close".to_string()));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_begin_does_not_block_the_runtime() {
+        // A waiting operation must not block the only thread of the runtime, otherwise the guard is never dropped.
+        let operations = Arc::new(Operations::default());
+        let guard = operations.begin("first", "create", None).await.unwrap();
+        let waiting = tokio::spawn({
+            let operations = Arc::clone(&operations);
+            async move { operations.begin("first", "open", None).await.is_some() }
+        });
+        tokio::task::yield_now().await;
+        drop(guard);
+        assert!(waiting.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_begin_serializes_same_namespace() {
+        let operations = Arc::new(Operations::default());
+        let open = Arc::new(Mutex::new(false));
+        // Two concurrent opens of the same container, only the first one may open it.
+        let open_container = || {
+            let operations = Arc::clone(&operations);
+            let open = Arc::clone(&open);
+            tokio::spawn(async move {
+                let _guard = operations.begin("first", "open", None).await.unwrap();
+                if *open.lock().unwrap() {
+                    return false;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                *open.lock().unwrap() = true;
+                true
+            })
+        };
+        let first = open_container();
+        let second = open_container();
+        let opened = [first.await.unwrap(), second.await.unwrap()];
+        assert_eq!(opened.iter().filter(|opened| **opened).count(), 1);
+        assert_eq!(operations.busy("first"), None);
+    }
+}
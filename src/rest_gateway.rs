@@ -0,0 +1,617 @@
+//! # REST Gateway
+//! This module provides an HTTP/JSON gateway for clients that can not use gRPC.
+//! It is only built with the `rest` feature.
+//! The JSON bodies are the gRPC request messages with camelCase field names,
+//! they are forwarded to the same handlers as the gRPC requests.
+//! The response is the gRPC response message as JSON,
+//! the HTTP status code is derived from the error of the response.
+//!
+//! ## Endpoints
+//! ```bash
+//! POST /containers                               CreateContainerRequest
+//! GET  /containers?namespace=<NAME>&all=<BOOL>   ListContainersRequest
+//! POST /containers/{namespace}/open              OpenContainerRequest
+//! POST /containers/{namespace}/close             CloseContainerRequest
+//! POST /containers/{namespace}/close-by-namespace
+//! POST /containers/{namespace}/export            ExportContainerRequest
+//! POST /containers/{namespace}/import            ImportContainerRequest
+//! POST /containers/{namespace}/add-auto-open     AddToAutoOpenRequest
+//! POST /containers/{namespace}/remove-auto-open  RemoveFromAutoOpenRequest
+//! POST /containers/{namespace}/export-bundle     ExportBundleRequest
+//! POST /bundles/import                           ImportBundleRequest
+//! POST /rotate-keys                              RotateKeysRequest
+//! POST /containers/{namespace}/verify            VerifyIntegrityRequest
+//! POST /validate                                 ValidateInputRequest
+//! POST /containers/{namespace}/bind-mount        BindMountRequest
+//! POST /swap                                     CreateSwapContainerRequest
+//! POST /test-open                                TestOpenRequest
+//! POST /rebuild-auto-open                        RebuildAutoOpenRequest
+//! POST /containers/{namespace}/update-auto-open  UpdateAutoOpenRequest
+//! POST /scan                                     ScanDirectoryRequest
+//! POST /containers/{namespace}/open-unmounted    OpenUnmountedRequest
+//! POST /containers/{namespace}/mount             MountOpenRequest
+//! GET  /containers/{namespace}/status
+//! POST /header-backup                            BackupHeaderRequest
+//! POST /header-restore                           RestoreHeaderRequest
+//! POST /info                                     ContainerInfoRequest
+//! POST /rekey                                    RekeyContainerRequest
+//! POST /key-slot-add                             AddKeySlotRequest
+//! POST /key-slot-remove                          RemoveKeySlotRequest
+//! GET  /diff
+//! GET  /inventory
+//! GET  /doctor
+//! ```
+//! The namespace of the path replaces the namespace of the body.
+//!
+//! ## Usage
+//! The gateway is only started if `SECURE_CONTAINER_REST_SOCKET` or `SECURE_CONTAINER_REST_ADDRESS` is set (see `config`).
+//! It has no authentication of its own, so it listens on a Unix socket that only root can connect to,
+//! or on an address with the TLS certificate, key and client CA of the gRPC server.
+//! ```bash
+//! curl --unix-socket /run/secure_container_rest.sock -X POST http://localhost/containers/MyContainer/close -d '{"mountPoint": "/home/MountMe"}' -H 'Content-Type: application/json'
+//! ```
+//!
+
+use crate::config::config;
+use crate::secure_container_service::container_server::Container;
+use crate::secure_container_service::{
+    AddKeySlotRequest, AddToAutoOpenRequest, BackupHeaderRequest, BindMountRequest,
+    CloseByNamespaceRequest, CloseContainerRequest, ContainerInfoRequest, CreateContainerRequest,
+    CreateSwapContainerRequest, DiffStateRequest, DoctorRequest, ExportBundleRequest,
+    ExportContainerRequest, ImportBundleRequest, ImportContainerRequest, InventoryRequest,
+    ListContainersRequest, MountOpenRequest, OpenContainerRequest, OpenUnmountedRequest,
+    RebuildAutoOpenRequest, RekeyContainerRequest, RemoveFromAutoOpenRequest, RemoveKeySlotRequest,
+    RestoreHeaderRequest, RotateKeysRequest, ScanDirectoryRequest, StatusContainerRequest,
+    TestOpenRequest, UpdateAutoOpenRequest, ValidateInputRequest, VerifyIntegrityRequest,
+};
+use crate::MySecureContainer;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use hyper::server::conn::Http;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// Serves the gateway until the daemon is stopped.
+/// The gateway listens on the Unix socket `rest_socket_path` of the configuration, which only root can connect to,
+/// or on `rest_address` with the TLS certificate, key and client CA of the gRPC server.
+/// # Arguments
+/// * `container` - The service that also handles the gRPC requests, so both share the operations in progress.
+/// # Returns
+/// * `Result<(), Box<dyn std::error::Error>>` - Returns an error if the gateway could not be started.
+/// # Example
+/// ```
+/// if config().rest_enabled() {
+///     tokio::spawn(serve(Arc::new(MySecureContainer::default())));
+/// }
+/// ```
+///
+pub async fn serve(
+    container: Arc<MySecureContainer>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let router = Router::new()
+        .route("/containers", post(create_container).get(list_containers))
+        .route("/containers/:namespace/open", post(open_container))
+        .route("/containers/:namespace/close", post(close_container))
+        .route(
+            "/containers/:namespace/close-by-namespace",
+            post(close_by_namespace),
+        )
+        .route("/containers/:namespace/export", post(export_container))
+        .route("/containers/:namespace/import", post(import_container))
+        .route(
+            "/containers/:namespace/add-auto-open",
+            post(add_to_auto_open),
+        )
+        .route(
+            "/containers/:namespace/remove-auto-open",
+            post(remove_from_auto_open),
+        )
+        .route("/containers/:namespace/export-bundle", post(export_bundle))
+        .route("/bundles/import", post(import_bundle))
+        .route("/rotate-keys", post(rotate_keys))
+        .route("/containers/:namespace/verify", post(verify_integrity))
+        .route("/validate", post(validate_input))
+        .route("/containers/:namespace/bind-mount", post(bind_mount))
+        .route("/swap", post(create_swap_container))
+        .route("/test-open", post(test_open))
+        .route("/rebuild-auto-open", post(rebuild_auto_open))
+        .route(
+            "/containers/:namespace/update-auto-open",
+            post(update_auto_open),
+        )
+        .route("/scan", post(scan_directory))
+        .route(
+            "/containers/:namespace/open-unmounted",
+            post(open_unmounted),
+        )
+        .route("/containers/:namespace/mount", post(mount_open))
+        .route("/containers/:namespace/status", get(status_container))
+        .route("/header-backup", post(backup_header))
+        .route("/header-restore", post(restore_header))
+        .route("/info", post(container_info))
+        .route("/rekey", post(rekey_container))
+        .route("/key-slot-add", post(add_key_slot))
+        .route("/key-slot-remove", post(remove_key_slot))
+        .route("/diff", get(diff_state))
+        .route("/inventory", get(inventory))
+        .route("/doctor", get(doctor))
+        .with_state(container);
+    if !config().rest_socket_path.is_empty() {
+        let listener = crate::bind_socket(&config().rest_socket_path)?;
+        loop {
+            let (stream, _) = listener.accept().await?;
+            serve_connection(stream, router.clone());
+        }
+    }
+    let address: SocketAddr = config().rest_address.parse()?;
+    let acceptor = tls_acceptor()?;
+    let listener = TcpListener::bind(address).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        tokio::spawn(async move {
+            match acceptor.accept(stream).await {
+                Ok(stream) => serve_connection(stream, router),
+                Err(err) => tracing::debug!(error = %err, "TLS handshake of REST client failed"),
+            }
+        });
+    }
+}
+
+/// Serves the requests of one connection of the gateway in its own task.
+/// # Arguments
+/// * `stream` - The connection, a Unix socket or a TLS stream.
+/// * `router` - The routes of the gateway.
+fn serve_connection<S>(stream: S, router: Router)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        if let Err(err) = Http::new().serve_connection(stream, router).await {
+            tracing::debug!(error = %err, "REST connection failed");
+        }
+    });
+}
+
+/// Creates the TLS acceptor of the gateway from the certificate, key and client CA of the configuration,
+/// so the gateway accepts the same clients as the gRPC server.
+/// # Returns
+/// * `Result<TlsAcceptor, Box<dyn std::error::Error>>` -
+/// Returns an error if no certificate is configured or a file can not be read.
+fn tls_acceptor() -> Result<TlsAcceptor, Box<dyn std::error::Error + Send + Sync>> {
+    if config().tls_cert_path.is_empty() {
+        return Err("REST address needs a TLS certificate and key".into());
+    }
+    let certs = rustls_pemfile::certs(&mut std::fs::read(&config().tls_cert_path)?.as_slice())
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut std::fs::read(&config().tls_key_path)?.as_slice())?
+        .ok_or("TLS key file contains no private key")?;
+    let builder = ServerConfig::builder();
+    let builder = if config().tls_client_ca_path.is_empty() {
+        builder.with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        for cert in
+            rustls_pemfile::certs(&mut std::fs::read(&config().tls_client_ca_path)?.as_slice())
+        {
+            roots.add(cert?)?;
+        }
+        builder.with_client_cert_verifier(WebPkiClientVerifier::builder(Arc::new(roots)).build()?)
+    };
+    Ok(TlsAcceptor::from(Arc::new(
+        builder.with_single_cert(certs, key)?,
+    )))
+}
+
+/// Maps the error of a response to an HTTP status code.
+/// The kind of the error is the part of the message before the first colon.
+/// # Arguments
+/// * `error` - The error of the response, `OK` if the operation was successful.
+/// # Returns
+/// * `StatusCode` -
+/// `200` for `OK`, `400` for invalid input, `404` for missing containers,
+/// `409` if the state of the container does not allow the operation,
+/// `503` if a dependency of the daemon is missing and `500` for every other error.
+fn http_status(error: &str) -> StatusCode {
+    match error.split(':').next().unwrap_or_default() {
+        "OK" => StatusCode::OK,
+        "Size of container to small"
+        | "Mountpoint wrong"
+        | "Not valid namespace"
+        | "Not valid id"
+        | "Secret not valid"
+        | "Path not valid"
+        | "Path not writable"
+        | "Bundle not valid"
+        | "Time not valid"
+        | "Mount point conflicts with path"
+        | "File system not supported"
+        | "Mount option not valid"
+        | "Key slot not valid" => StatusCode::BAD_REQUEST,
+        "Not valid path"
+        | "Path is not a luks container"
+        | "Path is not a luks device"
+        | "Container not in auto open"
+        | "Timed out waiting for path" => StatusCode::NOT_FOUND,
+        "Container mounted"
+        | "Container open"
+        | "Container with that name already exists"
+        | "File already exists"
+        | "Mount point busy"
+        | "Container mounted elsewhere"
+        | "No free keyslots"
+        | "Container not open"
+        | "Container not mounted at mount point"
+        | "Mount point not empty"
+        | "Last keyslot" => StatusCode::CONFLICT,
+        "Essential dependency missing" => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Turns the result of a handler into an HTTP response.
+/// # Arguments
+/// * `result` - The result of the gRPC handler.
+/// * `error` - Returns the error of the response message.
+/// # Returns
+/// * `Response` - The response message as JSON with the status code of its error.
+fn respond<T: Serialize>(
+    result: Result<tonic::Response<T>, tonic::Status>,
+    error: impl Fn(&T) -> &str,
+) -> Response {
+    match result {
+        Ok(response) => {
+            let response = response.into_inner();
+            (http_status(error(&response)), Json(response)).into_response()
+        }
+        Err(status) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            status.message().to_string(),
+        )
+            .into_response(),
+    }
+}
+
+async fn create_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<CreateContainerRequest>,
+) -> Response {
+    let result = container
+        .create_container(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn list_containers(
+    State(container): State<Arc<MySecureContainer>>,
+    Query(request): Query<ListContainersRequest>,
+) -> Response {
+    let result = container
+        .list_containers(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn open_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<OpenContainerRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container.open_container(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn close_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<CloseContainerRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container
+        .close_container(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn close_by_namespace(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+) -> Response {
+    let request = CloseByNamespaceRequest { namespace };
+    let result = container
+        .close_by_namespace(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn export_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<ExportContainerRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container
+        .export_container(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn import_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<ImportContainerRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container
+        .import_container(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn add_to_auto_open(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<AddToAutoOpenRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container
+        .add_to_auto_open(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn remove_from_auto_open(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<RemoveFromAutoOpenRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container
+        .remove_from_auto_open(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn export_bundle(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<ExportBundleRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container.export_bundle(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn import_bundle(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<ImportBundleRequest>,
+) -> Response {
+    let result = container.import_bundle(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn rotate_keys(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<RotateKeysRequest>,
+) -> Response {
+    let result = container.rotate_keys(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn verify_integrity(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<VerifyIntegrityRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container
+        .verify_integrity(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn validate_input(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<ValidateInputRequest>,
+) -> Response {
+    let result = container.validate_input(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn bind_mount(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<BindMountRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container.bind_mount(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn create_swap_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<CreateSwapContainerRequest>,
+) -> Response {
+    let result = container
+        .create_swap_container(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn test_open(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<TestOpenRequest>,
+) -> Response {
+    let result = container.test_open(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn rebuild_auto_open(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<RebuildAutoOpenRequest>,
+) -> Response {
+    let result = container
+        .rebuild_auto_open(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn update_auto_open(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<UpdateAutoOpenRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container
+        .update_auto_open(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn scan_directory(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<ScanDirectoryRequest>,
+) -> Response {
+    let result = container.scan_directory(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn diff_state(State(container): State<Arc<MySecureContainer>>) -> Response {
+    let result = container
+        .diff_state(tonic::Request::new(DiffStateRequest {}))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn open_unmounted(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<OpenUnmountedRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container.open_unmounted(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn mount_open(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+    Json(mut request): Json<MountOpenRequest>,
+) -> Response {
+    request.namespace = namespace;
+    let result = container.mount_open(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn inventory(State(container): State<Arc<MySecureContainer>>) -> Response {
+    let result = container
+        .inventory(tonic::Request::new(InventoryRequest {}))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn status_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Path(namespace): Path<String>,
+) -> Response {
+    let request = StatusContainerRequest { namespace };
+    let result = container
+        .status_container(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn backup_header(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<BackupHeaderRequest>,
+) -> Response {
+    let result = container.backup_header(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn restore_header(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<RestoreHeaderRequest>,
+) -> Response {
+    let result = container.restore_header(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn container_info(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<ContainerInfoRequest>,
+) -> Response {
+    let result = container.container_info(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn rekey_container(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<RekeyContainerRequest>,
+) -> Response {
+    let result = container
+        .rekey_container(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn add_key_slot(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<AddKeySlotRequest>,
+) -> Response {
+    let result = container.add_key_slot(tonic::Request::new(request)).await;
+    respond(result, |response| &response.error)
+}
+
+async fn remove_key_slot(
+    State(container): State<Arc<MySecureContainer>>,
+    Json(request): Json<RemoveKeySlotRequest>,
+) -> Response {
+    let result = container
+        .remove_key_slot(tonic::Request::new(request))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+async fn doctor(State(container): State<Arc<MySecureContainer>>) -> Response {
+    let result = container
+        .doctor(tonic::Request::new(DoctorRequest {}))
+        .await;
+    respond(result, |response| &response.error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_status() {
+        assert_eq!(http_status("OK"), StatusCode::OK);
+        assert_eq!(http_status("Not valid id"), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            http_status("Path is not a luks device: not a LUKS device"),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(http_status("Container open"), StatusCode::CONFLICT);
+        assert_eq!(
+            http_status("Essential dependency missing"),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            http_status("Cryptsetup error: failed"),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
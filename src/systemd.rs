@@ -0,0 +1,87 @@
+//! # Systemd
+//! This module tells systemd about the state of the daemon, so it can run as a `Type=notify` service.
+//! The daemon sends `READY=1` once its listener is bound and `STOPPING=1` when it shuts down.
+//! If the service has a `WatchdogSec`, the daemon sends `WATCHDOG=1` in half of the interval.
+//! The messages are only sent if the daemon was started by systemd (`NOTIFY_SOCKET` is set),
+//! otherwise every function does nothing.
+//!
+
+use sd_notify::NotifyState;
+use std::env;
+use std::time::Duration;
+
+/// Checks if the daemon was started by systemd with a notify socket.
+/// # Returns
+/// * `bool` - True if `NOTIFY_SOCKET` is set.
+fn notify_socket_set() -> bool {
+    env::var_os("NOTIFY_SOCKET").is_some()
+}
+
+/// Sends a state to systemd, an error is logged but does not stop the daemon.
+/// # Arguments
+/// * `state` - The state of the daemon.
+fn notify(state: NotifyState) {
+    if !notify_socket_set() {
+        return;
+    }
+    if let Err(err) = sd_notify::notify(false, &[state]) {
+        tracing::warn!(error = %err, "Error notifying systemd");
+    }
+}
+
+/// Tells systemd that the daemon is ready to serve requests.
+pub fn ready() {
+    notify(NotifyState::Ready);
+}
+
+/// Tells systemd that the daemon is shutting down.
+pub fn stopping() {
+    notify(NotifyState::Stopping);
+}
+
+/// Returns the interval in which the daemon has to ping the watchdog of systemd.
+/// # Returns
+/// * `Option<Duration>` -
+/// The interval or `None` if the daemon was not started by systemd or the service has no watchdog.
+pub fn watchdog_interval() -> Option<Duration> {
+    if !notify_socket_set() {
+        return None;
+    }
+    let mut usec = 0;
+    if !sd_notify::watchdog_enabled(false, &mut usec) {
+        return None;
+    }
+    Some(ping_interval(usec))
+}
+
+/// Calculates the interval of the pings, it is half of the timeout of the watchdog,
+/// so a ping that is late does not get the daemon killed. It is at least one microsecond.
+/// # Arguments
+/// * `usec` - The timeout of the watchdog in microseconds (`WATCHDOG_USEC`).
+/// # Returns
+/// * `Duration` - The interval of the pings.
+fn ping_interval(usec: u64) -> Duration {
+    Duration::from_micros((usec / 2).max(1))
+}
+
+/// Pings the watchdog of systemd in the given interval, it runs until the daemon exits.
+/// # Arguments
+/// * `interval` - The interval of the pings, see `watchdog_interval`.
+pub async fn watchdog(interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        ticks.tick().await;
+        notify(NotifyState::Watchdog);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_interval() {
+        assert_eq!(ping_interval(30_000_000), Duration::from_secs(15));
+        assert_eq!(ping_interval(1), Duration::from_micros(1));
+    }
+}
@@ -6,21 +6,190 @@ use crate::error_handling;
 use error_handling::{Result, SecureContainerErr};
 
 extern crate libuta_rs;
-use libuta_rs::libuta_derive_key;
+use libuta_rs::{
+    libuta_derive_key, libuta_derive_key_full, libuta_get_random, LEGACY_DERIVATION_LENGTH,
+};
 
 use crate::file_io_operations;
-use file_io_operations::auto_open_read;
+use file_io_operations::{auto_open_change_id, auto_open_read, auto_open_replace, WAIT_FOR_PATH};
 
 use crate::cryptsetup_wrapper;
-use cryptsetup_wrapper::{close_container, open_container};
+use cryptsetup_wrapper::{
+    close_container, free_keyslots, get_container_uuid, open_container, rotate_key, test_open,
+};
 
+use crate::cryptsetup_backend::{INTEGRITY, LUKS2_KEYSLOTS};
+use crate::metadata::{read_metadata, ContainerMetadata, LEGACY_KEY_DERIVATION};
+
+use crate::file_system_operations::{
+    check_container_mounted, check_container_open, get_backing_path, get_mount_point,
+    list_crypt_devices, BlockDevice,
+};
+
+use crate::config::config;
+use crate::deadline;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::error_handling::check_input;
+use crate::input_validation::{check_input, device_name, namespace_of_device};
 use base64::engine::general_purpose;
 use base64::{alphabet, engine, Engine as _};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
+use ring::hkdf;
+
+/// The result of checking a single dependency of the project.
+/// * `name` - The name of the dependency.
+/// * `essential` - True if the project can not work without this dependency.
+/// * `available` - True if the dependency is present and working.
+/// * `details` - The version of the dependency or the reason why the check failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyCheck {
+    pub name: String,
+    pub essential: bool,
+    pub available: bool,
+    pub details: String,
+}
+
+/// Here the `Display` trait for the `DependencyCheck` type is implemented.
+/// Essential dependencies that are not available are reported as `FAIL`,
+/// optional ones as `WARN`.
+impl fmt::Display for DependencyCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match (self.available, self.essential) {
+            (true, _) => "PASS",
+            (false, true) => "FAIL",
+            (false, false) => "WARN",
+        };
+        write!(f, "[{}] {}: {}", state, self.name, self.details)
+    }
+}
+
+/// Checks if all dependencies that are needed by the project are present.
+/// This checks the external binaries configured in `config` and their versions,
+/// if the libuta device responds and if the kernel supports the integrity check.
+/// # Arguments
+/// # Returns
+/// * `Vec<DependencyCheck>` -
+/// Returns a `Vec<DependencyCheck>` with one entry for every checked dependency.
+/// # Example
+/// ```
+/// let report = check_dependencies();
+/// for check in report {
+///     println!("{}", check);
+/// }
+/// ```
+///
+pub fn check_dependencies() -> Vec<DependencyCheck> {
+    let config = config();
+    let required_binaries = [
+        ("cryptsetup", &config.cryptsetup_path, "--version"),
+        ("lsblk", &config.lsblk_path, "--version"),
+        ("mount", &config.mount_path, "--version"),
+        ("umount", &config.umount_path, "--version"),
+        ("mkfs.ext4", &config.mkfs_ext4_path, "-V"),
+        ("dmesg", &config.dmesg_path, "--version"),
+    ];
+    let mut report: Vec<DependencyCheck> = required_binaries
+        .iter()
+        .map(|(name, binary, version_arg)| check_binary(name, binary, version_arg))
+        .collect();
+    // The swap binaries are only needed for swap containers,
+    // mkfs.xfs and mkfs.btrfs only for containers with these file systems,
+    // dmsetup only reads the mismatch counters in addition to the kernel log.
+    let optional_binaries = [
+        ("mkfs.xfs", &config.mkfs_xfs_path, "-V"),
+        ("mkfs.btrfs", &config.mkfs_btrfs_path, "--version"),
+        ("dmsetup", &config.dmsetup_path, "--version"),
+        ("mkswap", &config.mkswap_path, "--version"),
+        ("swapon", &config.swapon_path, "--version"),
+        ("swapoff", &config.swapoff_path, "--version"),
+    ];
+    report.extend(
+        optional_binaries
+            .iter()
+            .map(|(name, binary, version_arg)| DependencyCheck {
+                essential: false,
+                ..check_binary(name, binary, version_arg)
+            }),
+    );
 
-/// Get the password for a container.
+    let (available, details) = match libuta_derive_key("doctor") {
+        Ok(_) => (true, "device responds".to_string()),
+        Err(err) => (false, err),
+    };
+    report.push(DependencyCheck {
+        name: "libuta".to_string(),
+        essential: true,
+        available,
+        details,
+    });
+
+    let (available, details) = match check_functionality_of_integrity(&config.cipher, INTEGRITY) {
+        Ok(true) => (true, "supported by the kernel".to_string()),
+        Ok(false) => (false, "not supported by the kernel".to_string()),
+        Err(err) => (false, err.to_string()),
+    };
+    report.push(DependencyCheck {
+        name: "integrity".to_string(),
+        essential: false,
+        available,
+        details,
+    });
+    report
+}
+
+/// Checks if a binary is present by executing it with the argument that prints its version.
+/// # Arguments
+/// * `name` - The name of the dependency.
+/// * `binary` - The binary that is executed.
+/// * `version_arg` - The argument that lets the binary print its version.
+/// # Returns
+/// * `DependencyCheck` -
+/// Returns a `DependencyCheck` containing the first line of the version output
+/// or the reason why the binary could not be executed.
+/// A binary that exits with a non-zero code is not available, its first line of output and its exit status are reported.
+/// # Note
+/// This function is not meant to be called directly.
+fn check_binary(name: &str, binary: &str, version_arg: &str) -> DependencyCheck {
+    let (available, details) = match Command::new(binary).arg(version_arg).output() {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let version = stdout
+                .lines()
+                .chain(stderr.lines())
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty())
+                .unwrap_or("unknown version")
+                .to_string();
+            if output.status.success() {
+                (true, version)
+            } else {
+                (false, format!("{} ({})", version, output.status))
+            }
+        }
+        Err(err) => (false, err.to_string()),
+    };
+    DependencyCheck {
+        name: name.to_string(),
+        essential: true,
+        available,
+        details,
+    }
+}
+
+/// Serializes the access to the libuta device.
+static LIBUTA_LOCK: Mutex<()> = Mutex::new(());
+
+/// Get the password for a container that was created before the key salt was introduced.
+/// The libuta key is derived from the first 8 bytes of the id (see `LEGACY_KEY_DERIVATION`).
 /// # Arguments
 /// * `id` - The id of the container.
 /// # Returns
@@ -35,25 +204,175 @@ use base64::{alphabet, engine, Engine as _};
 /// println!("{:?}", result.unwrap());
 /// ```
 ///
+#[cfg_attr(not(test), allow(dead_code))]
 pub fn get_password(id: &str) -> Result<String> {
-    let key = match libuta_derive_key(id) {
-        Ok(key) => key,
-        Err(err) => return Err(SecureContainerErr::LibutaDeriveKeyError(err.to_string())),
-    };
-    let password = convert_to_base64(key);
+    let password = convert_to_base64(derive_key(id, LEGACY_KEY_DERIVATION)?);
     Ok(password)
 }
 
+/// The info of the HKDF that derives the password of a container from the libuta key and the key salt.
+const KEY_SALT_INFO: &[u8] = b"secure-container key salt";
+
+/// Get the password for a container that was created with a key salt.
+/// The password is derived with HKDF-SHA256 from the libuta key of the id and the salt,
+/// so the same id gives different passwords for different containers.
+/// Containers that were created before the key salt was introduced have no salt,
+/// for them the password of `get_password` is returned.
+/// # Arguments
+/// * `id` - The id of the container.
+/// * `salt` - The base64 encoded key salt of the container (see `get_random_salt`), empty if it has none.
+/// * `key_derivation` - The key derivation of the id that is recorded in the metadata of the container.
+/// # Returns
+/// * `Result<String>` -
+/// Returns a `String` containing the password if successful otherwise an error is returned.
+/// # Errors
+/// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
+/// * `Base64Error` - The salt is not valid base64.
+/// # Example
+/// ```
+/// let salt = get_random_salt().unwrap();
+/// let result = get_salted_password("test", &salt, KEY_DERIVATION);
+/// assert_ne!(result.unwrap(), get_password("test").unwrap());
+/// ```
+///
+pub fn get_salted_password(id: &str, salt: &str, key_derivation: u32) -> Result<String> {
+    if salt.is_empty() {
+        return Ok(convert_to_base64(derive_key(id, key_derivation)?));
+    }
+    let salt = convert_from_base64(salt)?;
+    let key = derive_key(id, key_derivation)?;
+    let mut password = [0u8; RANDOM_PASSWORD_LENGTH];
+    let derived = hkdf::Salt::new(hkdf::HKDF_SHA256, &salt)
+        .extract(&key)
+        .expand(&[KEY_SALT_INFO], hkdf::HKDF_SHA256)
+        .and_then(|okm| okm.fill(&mut password));
+    match derived {
+        Ok(_) => Ok(convert_to_base64(password.to_vec())),
+        Err(_) => Err(SecureContainerErr::LibutaDeriveKeyError(
+            "Error expanding the key with the salt".to_string(),
+        )),
+    }
+}
+
+/// Derives the key of an id with libuta.
+/// Ids that fit into the legacy derivation always use it, so the passwords of existing containers do not change.
+/// Longer ids only use the whole id if the container records a newer key derivation.
+/// # Arguments
+/// * `id` - The id of the container.
+/// * `key_derivation` - The key derivation of the id (see `metadata::KEY_DERIVATION`).
+/// # Returns
+/// * `Result<Vec<u8>>` - Returns the raw key otherwise an error is returned.
+/// # Errors
+/// * `LibutaDeriveKeyError` - An error occurred while deriving the key.
+/// # Note
+/// This function is not meant to be called directly.
+fn derive_key(id: &str, key_derivation: u32) -> Result<Vec<u8>> {
+    // libuta does not document that it is thread-safe, so only one key is derived at a time.
+    let _guard = LIBUTA_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let derived = if key_derivation == LEGACY_KEY_DERIVATION || id.len() <= LEGACY_DERIVATION_LENGTH
+    {
+        libuta_derive_key(id)
+    } else {
+        libuta_derive_key_full(id)
+    };
+    match derived {
+        Ok(key) => Ok(key),
+        Err(err) => Err(SecureContainerErr::LibutaDeriveKeyError(err.to_string())),
+    }
+}
+
+/// The number of random bytes of a random password, the same as the length of a derived key.
+const RANDOM_PASSWORD_LENGTH: usize = 32;
+
+/// Get a random password, e.g. for a container that is only used until the next reboot.
+/// The random data is taken from the random number generator of libuta.
+/// # Arguments
+/// # Returns
+/// * `Result<String>` -
+/// Returns a `String` containing the password if successful otherwise an error is returned.
+/// # Errors
+/// * `LibutaRandomError` - An error occurred while getting the random data.
+/// # Example
+/// ```
+/// let result = get_random_password();
+/// assert_ne!(result.unwrap(), get_random_password().unwrap());
+/// ```
+///
+pub fn get_random_password() -> Result<String> {
+    let _guard = LIBUTA_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let random = match libuta_get_random(RANDOM_PASSWORD_LENGTH) {
+        Ok(random) => random,
+        Err(err) => return Err(SecureContainerErr::LibutaRandomError(err)),
+    };
+    Ok(convert_to_base64(random))
+}
+
+/// The number of random bytes of a salt.
+const RANDOM_SALT_LENGTH: usize = 16;
+
+/// Get a random salt, e.g. for the key derivation of a new or an exported container.
+/// The random data is taken from the random number generator of libuta.
+/// # Returns
+/// * `Result<String>` -
+/// Returns a `String` containing the base64 encoded salt if successful otherwise an error is returned.
+/// # Errors
+/// * `LibutaRandomError` - An error occurred while getting the random data.
+/// # Example
+/// ```
+/// let result = get_random_salt();
+/// assert_ne!(result.unwrap(), get_random_salt().unwrap());
+/// ```
+///
+pub fn get_random_salt() -> Result<String> {
+    let _guard = LIBUTA_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let random = match libuta_get_random(RANDOM_SALT_LENGTH) {
+        Ok(random) => random,
+        Err(err) => return Err(SecureContainerErr::LibutaRandomError(err)),
+    };
+    Ok(convert_to_base64(random))
+}
+
+/// The result of opening a single container.
+/// * `namespace` - The name of the container.
+/// * `result` - OK(()) if the container was opened, otherwise the error.
+#[derive(Debug, PartialEq)]
+pub struct OpenResult {
+    pub namespace: String,
+    pub result: Result<()>,
+}
+
+/// The containers that were opened by auto open, each with its mount point and namespace.
+/// Only these containers are closed by auto close.
+static AUTO_OPENED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// The interval in which auto open checks if the path of a container has appeared.
+const WAIT_FOR_PATH_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Function that is called by the daemon to automatically open all containers in autoOpen file.
+/// A container that fails to open does not stop the other containers from being opened,
+/// its error is returned in its `OpenResult`.
+/// The containers are opened concurrently by up to `auto_open_parallelism` threads (see `config`).
+/// For a container that is marked with `wait` in the autoOpen file,
+/// auto open waits up to `auto_open_wait_timeout` seconds for its path to appear before it is opened.
+/// The containers that were opened are recorded, so `auto_close` only closes them.
 /// # Arguments
 /// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if all containers were opened successfully, otherwise an error is returned.
+/// * `Result<Vec<OpenResult>>` -
+/// Returns the result of opening every container in the autoOpen file.
+/// An error is returned if the autoOpen file could not be read.
 /// # Errors
 /// * `FileReadError` - An error occurred while reading a file.
+/// ### Errors of the single containers:
+/// * `PathWaitTimeout` - The path of a container marked with `wait` did not appear in time.
 /// * `MountPointNotExists` - The given mount point does not exist.
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
-/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than 8 characters.
+/// * `IdNotValid` - The given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
 /// * `PathNotValid` - The given path contains non-ascii characters or a pipe.
 /// * `PathNotExists` - The given path does not exist.
 /// * `PathNotLuksContainer` - The given path is not a LUKS container.
@@ -69,72 +388,799 @@ pub fn get_password(id: &str) -> Result<String> {
 /// # Example
 /// ```
 /// let result = auto_open();
-/// assert_eq!(result.is_ok(), true);
+/// for container in result.unwrap() {
+///     assert!(container.result.is_ok());
+/// }
 /// ```
 ///
-pub fn auto_open() -> Result<()> {
-    let containers = auto_open_read();
-    if containers.is_err() {
-        return Err(SecureContainerErr::FileReadError(
-            "Error reading auto open file".to_string(),
-        ));
+pub fn auto_open() -> Result<Vec<OpenResult>> {
+    let containers = match auto_open_read() {
+        Ok(containers) => containers,
+        Err(_) => {
+            return Err(SecureContainerErr::FileReadError(
+                "Error reading auto open file".to_string(),
+            ))
+        }
+    };
+    let containers: Vec<Vec<String>> = containers
+        .into_iter()
+        .filter(|container| container.len() > 3)
+        .collect();
+    let results = opening_auto_open(&containers, config().auto_open_parallelism, |container| {
+        wait_if_marked(container)
+            .and_then(|_| {
+                check_input(
+                    None,
+                    Some(&container[0]),
+                    Some(&container[1]),
+                    Some(&container[2]),
+                    Some(&container[3]),
+                )
+            })
+            .and_then(|_| {
+                open_container(
+                    &container[0],
+                    &container[1],
+                    &container[2],
+                    &container[3],
+                    false,
+                    &[],
+                    false,
+                )
+            })
+    });
+    let mut opened = AUTO_OPENED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    for (container, result) in containers.iter().zip(&results) {
+        if result.result.is_ok() {
+            opened.push((container[0].clone(), container[2].clone()));
+        }
     }
-    for container in containers.unwrap() {
-        match check_input(
-            None,
-            Some(&container[0]),
-            Some(&container[1]),
-            Some(&container[2]),
-            Some(&container[3]),
-        ) {
-            Ok(_) => (),
-            Err(err) => return Err(err),
-        };
-        match open_container(&container[0], &container[1], &container[2], &container[3]) {
-            Ok(_) => (),
-            Err(err) => return Err(err),
-        };
+    Ok(results)
+}
+
+/// Opens every entry of the autoOpen file, an entry that fails does not stop the others from being opened.
+/// # Arguments
+/// * `containers` - The entries of the autoOpen file, each with at least four fields.
+/// * `parallelism` - The maximum number of entries that are opened at the same time.
+/// * `open` - The operation that opens a single entry.
+/// # Returns
+/// * `Vec<OpenResult>` - The result of every entry in the order of the entries.
+/// # Note
+/// This function is not meant to be called directly.
+fn opening_auto_open(
+    containers: &[Vec<String>],
+    parallelism: usize,
+    open: impl Fn(&[String]) -> Result<()> + Sync,
+) -> Vec<OpenResult> {
+    run_parallel(containers, parallelism, |container| OpenResult {
+        namespace: container[2].clone(),
+        result: open(container),
+    })
+}
+
+/// Waits for the path of an autoOpen entry that is marked with `wait`, other entries are not waited for.
+/// # Arguments
+/// * `container` - The entry of the autoOpen file.
+/// # Returns
+/// * `Result<()>` - Returns OK(()) if the path exists or the entry is not marked otherwise an error is returned.
+/// # Errors
+/// * `PathWaitTimeout` - The path did not appear within `auto_open_wait_timeout` seconds.
+/// # Note
+/// This function is not meant to be called directly.
+fn wait_if_marked(container: &[String]) -> Result<()> {
+    if container.get(4).map(String::as_str) != Some(WAIT_FOR_PATH) {
+        return Ok(());
+    }
+    wait_for_path(
+        &container[1],
+        Duration::from_secs(config().auto_open_wait_timeout),
+        WAIT_FOR_PATH_INTERVAL,
+    )
+}
+
+/// Waits for a path to appear, e.g. a container on a network file system that is mounted after the daemon has started.
+/// # Arguments
+/// * `path` - The path that is waited for.
+/// * `timeout` - The longest time that is waited.
+/// * `interval` - The time between two checks.
+/// # Returns
+/// * `Result<()>` - Returns OK(()) as soon as the path exists otherwise an error is returned.
+/// # Errors
+/// * `PathWaitTimeout` - The path did not appear within the timeout.
+/// # Note
+/// This function is not meant to be called directly.
+fn wait_for_path(path: &str, timeout: Duration, interval: Duration) -> Result<()> {
+    let start = Instant::now();
+    while !Path::new(path).exists() {
+        if start.elapsed() >= timeout {
+            return Err(SecureContainerErr::PathWaitTimeout);
+        }
+        thread::sleep(interval);
     }
     Ok(())
 }
 
-/// Function that is called by the daemon to close all containers in autoOpen file.
+/// Applies an operation to every item with up to `parallelism` threads.
+/// # Arguments
+/// * `items` - The items.
+/// * `parallelism` - The maximum number of threads, at least one thread is used.
+/// * `operation` - The operation that is applied to every item.
+/// # Returns
+/// * `Vec<R>` - The results in the order of the items.
+/// # Note
+/// This function is not meant to be called directly.
+fn run_parallel<T: Sync, R: Send>(
+    items: &[T],
+    parallelism: usize,
+    operation: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<R>>> = items.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..parallelism.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = operation(item);
+                *results[index].lock().unwrap() = Some(result);
+            });
+        }
+    });
+    results
+        .into_iter()
+        .filter_map(|result| result.into_inner().unwrap())
+        .collect()
+}
+
+/// The number of attempts to close a container before auto_close gives up.
+const AUTO_CLOSE_ATTEMPTS: usize = 3;
+
+/// The time auto close waits after the first failed attempt, it is doubled after every further attempt.
+const AUTO_CLOSE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The result of closing a single container.
+/// * `mount_point` - The mount point of the container.
+/// * `namespace` - The name of the container.
+/// * `result` - OK(()) if the container was closed, otherwise the error of the last attempt.
+#[derive(Debug, PartialEq)]
+pub struct CloseResult {
+    pub mount_point: String,
+    pub namespace: String,
+    pub result: Result<()>,
+}
+
+/// Function that is called by the daemon to close all containers that were opened by `auto_open`.
+/// Containers that were opened or closed by the user are not touched, containers that are not open anymore are skipped.
+/// Every other container is tried to be closed up to `AUTO_CLOSE_ATTEMPTS` times,
+/// the time between two attempts starts at `AUTO_CLOSE_BACKOFF` and is doubled after every attempt.
 /// # Arguments
 /// # Returns
-/// * `Result<()>` -
-/// Returns OK(()) if all containers were closed successfully, otherwise an error is returned.
+/// * `Vec<CloseResult>` -
+/// Returns the result of closing every open container that was opened by `auto_open`.
 /// # Errors
+/// ### Errors of the single containers:
 /// * `MountPointNotExists` - The given mount point does not exist.
 /// * `NamespaceNotValid` - The given namespace contains non-ascii characters or a pipe.
 /// * `UmountError` - An error occurred while the container was unmounted.
 /// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
 /// # Example
 /// ```
-/// let result = auto_close();
-/// assert_eq!(result.is_ok(), true);
+/// let results = auto_close();
+/// for container in results {
+///     assert!(container.result.is_ok());
+/// }
 /// ```
 ///
-pub fn auto_close() -> Result<()> {
-    let containers = auto_open_read();
-    if containers.is_err() {
+pub fn auto_close() -> Vec<CloseResult> {
+    let containers = std::mem::take(
+        &mut *AUTO_OPENED
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+    let mut results = Vec::new();
+    for (mount_point, namespace) in containers {
+        // A container that was closed by the user in the meantime does not need to be closed.
+        if let Ok(false) = check_container_open(&device_name(&namespace)) {
+            continue;
+        }
+        let result = close_with_retries(
+            || close_container(&mount_point, &namespace),
+            AUTO_CLOSE_ATTEMPTS,
+            AUTO_CLOSE_BACKOFF,
+        );
+        results.push(CloseResult {
+            mount_point,
+            namespace,
+            result,
+        });
+    }
+    results
+}
+
+/// Tries to close a container until it succeeds or the attempts are used up.
+/// # Arguments
+/// * `close` - The operation that closes the container.
+/// * `attempts` - The maximum number of attempts, at least one attempt is made.
+/// * `backoff` - The time that is waited after the first failed attempt, it is doubled after every further attempt.
+/// # Returns
+/// * `Result<()>` - Returns OK(()) if an attempt succeeded, otherwise the error of the last attempt.
+/// # Note
+/// This function is not meant to be called directly.
+fn close_with_retries(
+    mut close: impl FnMut() -> Result<()>,
+    attempts: usize,
+    backoff: Duration,
+) -> Result<()> {
+    let mut result = close();
+    let mut wait = backoff;
+    for _ in 1..attempts {
+        if result.is_ok() {
+            break;
+        }
+        thread::sleep(wait);
+        wait *= 2;
+        result = close();
+    }
+    result
+}
+
+/// The result of rotating the key of a single container.
+/// * `namespace` - The name of the container.
+/// * `result` - OK(()) if the container and its autoOpen entry use the new id, otherwise the error.
+#[derive(Debug, PartialEq)]
+pub struct RotateResult {
+    pub namespace: String,
+    pub result: Result<()>,
+}
+
+/// Rotates the libuta-derived password of every container in the autoOpen file to a new id.
+/// Every container is rotated on its own, first its password is changed, then its autoOpen entry.
+/// A container whose rotation failed either still uses its old id in both places,
+/// or accepts the new password while its entry has the old id,
+/// which the next rotation detects and only updates the entry.
+/// # Arguments
+/// * `new_id` - The id the new passwords are derived from.
+/// # Returns
+/// * `Result<Vec<RotateResult>>` -
+/// Returns the result of every container in the autoOpen file.
+/// An error is returned if the new id is not valid or the autoOpen file could not be read.
+/// # Errors
+/// * `IdNotValid` - The new id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `FileReadError` - An error occurred while reading the autoOpen file.
+/// ### Errors of the single containers:
+/// * `ContainerOpen` - The container is open, it has to be closed to be rotated.
+/// * `LsblkError` - An error occurred while checking if the container is open.
+/// * `LibutaDeriveKeyError` - An error occurred while deriving a key.
+/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `FileWriteError` - The autoOpen entry could not be updated.
+/// # Example
+/// ```
+/// let result = rotate_keys("newId");
+/// for container in result.unwrap() {
+///     assert!(container.result.is_ok());
+/// }
+/// ```
+///
+pub fn rotate_keys(new_id: &str) -> Result<Vec<RotateResult>> {
+    check_input(None, None, None, None, Some(new_id))?;
+    let containers = match auto_open_read() {
+        Ok(containers) => containers,
+        Err(_) => {
+            return Err(SecureContainerErr::FileReadError(
+                "Error reading auto open file".to_string(),
+            ))
+        }
+    };
+    Ok(containers
+        .iter()
+        .filter(|container| container.len() > 3)
+        .map(|container| RotateResult {
+            namespace: container[2].clone(),
+            result: rotate_container(&container[1], &container[2], &container[3], new_id),
+        })
+        .collect())
+}
+
+/// Rotates the password and the autoOpen entry of a single container.
+/// # Note
+/// This function is not meant to be called directly.
+fn rotate_container(path: &str, namespace: &str, id: &str, new_id: &str) -> Result<()> {
+    if id == new_id {
+        return Ok(());
+    }
+    if check_container_open(&device_name(namespace))? {
+        return Err(SecureContainerErr::ContainerOpen);
+    }
+    rotate_key(path, id, new_id)?;
+    auto_open_change_id(namespace, new_id)
+}
+
+/// The result of rebuilding the autoOpen entry of a single container.
+/// * `namespace` - The name of the container.
+/// * `mount_point` - The path where the container is mounted, empty if it is not mounted.
+/// * `path` - The file or block device the container is stored in, empty if it could not be found.
+/// * `result` - OK(()) if the entry was written, otherwise the reason why it was skipped.
+#[derive(Debug, PartialEq)]
+pub struct RebuildResult {
+    pub namespace: String,
+    pub mount_point: String,
+    pub path: String,
+    pub result: Result<()>,
+}
+
+/// Rebuilds the autoOpen file from the containers that are currently open and mounted,
+/// e.g. after the autoOpen file was lost or damaged.
+/// The ids can not be read from the system, so they have to be given.
+/// An entry is only written if the password of its id unlocks the container,
+/// all entries of the autoOpen file are replaced by the rebuilt ones.
+/// If no entry could be rebuilt, the autoOpen file is not changed.
+/// # Arguments
+/// * `ids` - The id of every container, by namespace.
+/// * `default_id` - The id of the containers that are not listed in `ids`.
+/// # Returns
+/// * `Result<Vec<RebuildResult>>` -
+/// Returns the result of every open container.
+/// An error is returned if an id is not valid, the open containers could not be listed
+/// or the autoOpen file could not be written.
+/// # Errors
+/// * `IdNotValid` - A given id contains non-ascii characters, a pipe or is longer than `MAX_ID_LENGTH` characters.
+/// * `LsblkError` - An error occurred while listing the open containers.
+/// * `FileOpenError` - An error occurred while opening the autoOpen file.
+/// * `FileWriteError` - An error occurred while writing the autoOpen file.
+/// ### Errors of the single containers:
+/// * `MountPointNotExists` - The container is not mounted.
+/// * `IdNotValid` - No id was given for the container or its password does not unlock the container.
+/// * `FileReadError` - The file or block device of the container could not be found.
+/// * `LibutaDeriveKeyError` - An error occurred while deriving a key.
+/// # Example
+/// ```
+/// let ids = HashMap::from([("MyContainer".to_string(), "myId".to_string())]);
+/// let result = rebuild_auto_open(&ids, None);
+/// for container in result.unwrap() {
+///     assert!(container.result.is_ok());
+/// }
+/// ```
+///
+pub fn rebuild_auto_open(
+    ids: &HashMap<String, String>,
+    default_id: Option<&str>,
+) -> Result<Vec<RebuildResult>> {
+    for id in ids.values().map(String::as_str).chain(default_id) {
+        check_input(None, None, None, None, Some(id))?;
+    }
+    let mut results = Vec::new();
+    let mut entries = Vec::new();
+    for device in list_crypt_devices()? {
+        let namespace = namespace_of_device(&device.name);
+        let mount_point = device.mountpoint.unwrap_or_default();
+        let path = get_backing_path(&device.name).unwrap_or_default();
+        let id = ids.get(&namespace).map(String::as_str).or(default_id);
+        let result = rebuild_entry(&mount_point, &path, id);
+        if let (Ok(_), Some(id)) = (&result, id) {
+            entries.push([
+                mount_point.clone(),
+                path.clone(),
+                namespace.clone(),
+                id.to_string(),
+            ]);
+        }
+        results.push(RebuildResult {
+            namespace,
+            mount_point,
+            path,
+            result,
+        });
+    }
+    if !entries.is_empty() {
+        auto_open_replace(&entries)?;
+    }
+    Ok(results)
+}
+
+/// Checks if an open container can be written to the autoOpen file.
+/// # Note
+/// This function is not meant to be called directly.
+fn rebuild_entry(mount_point: &str, path: &str, id: Option<&str>) -> Result<()> {
+    if mount_point.is_empty() {
+        return Err(SecureContainerErr::MountPointNotExists);
+    }
+    if path.is_empty() {
         return Err(SecureContainerErr::FileReadError(
-            "Error reading auto open file".to_string(),
+            "The file of the container was not found".to_string(),
         ));
     }
-    let containers = containers.unwrap();
-    let mut is_closed = vec![false; containers.len()];
+    let id = id.ok_or(SecureContainerErr::IdNotValid)?;
+    // A wrong id would only be noticed on the next start, when the container can not be opened.
+    if !test_open(path, id)? {
+        return Err(SecureContainerErr::IdNotValid);
+    }
+    Ok(())
+}
 
-    while is_closed.contains(&false) {
-        for container in &containers {
-            if !is_closed[containers.iter().position(|x| x == container).unwrap()] {
-                let returncode = close_container(&container[0], &container[2]);
-                if returncode.is_ok() {
-                    is_closed[containers.iter().position(|x| x == container).unwrap()] = true;
-                }
+/// Where the daemon knows a container from.
+/// * `Registered` - The container is listed in the autoOpen file.
+/// * `AdHoc` - The container is open but not listed in the autoOpen file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerSource {
+    Registered,
+    AdHoc,
+}
+
+/// Here the `Display` trait for the `ContainerSource` type is implemented.
+impl fmt::Display for ContainerSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContainerSource::Registered => write!(f, "registered"),
+            ContainerSource::AdHoc => write!(f, "ad-hoc"),
+        }
+    }
+}
+
+/// The status of a single container.
+/// * `namespace` - The name of the container.
+/// * `path` - The path to the container, empty for ad-hoc containers.
+/// * `mount_point` - The configured mount point, for ad-hoc containers the current mount point.
+/// * `open` - True if the container is open.
+/// * `mounted` - True if the container is mounted.
+/// * `source` - Where the container is known from.
+/// * `used_keyslots` - The number of used key slots, 0 if the header could not be read.
+/// * `free_keyslots` - The number of free key slots, 0 if the header could not be read.
+/// * `cipher` - The cipher from the metadata of the container, empty if it has no metadata.
+/// * `integrity` - The integrity algorithm from the metadata of the container, empty if it has no metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerStatus {
+    pub namespace: String,
+    pub path: String,
+    pub mount_point: String,
+    pub open: bool,
+    pub mounted: bool,
+    pub source: ContainerSource,
+    pub used_keyslots: u32,
+    pub free_keyslots: u32,
+    pub cipher: String,
+    pub integrity: String,
+}
+
+/// Collects the status of the containers.
+/// # Arguments
+/// * `namespace` - If set, only the container with this name is returned.
+/// * `all` -
+/// If true, the open crypt devices that are not in the autoOpen file are returned as well.
+/// This is always the case if a namespace is given.
+/// # Returns
+/// * `Result<Vec<ContainerStatus>>` -
+/// Returns the status of the registered containers and, if requested, the ad-hoc containers.
+/// For registered containers the used and free key slots are counted
+/// and the cipher and integrity algorithm are read from their metadata as well.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading the autoOpen file.
+/// * `LsblkError` - An error occurred executing lsblk.
+/// # Example
+/// ```
+/// let result = container_status(None, true);
+/// for container in result.unwrap() {
+///     println!("{} {}", container.namespace, container.source);
+/// }
+/// ```
+///
+pub fn container_status(namespace: Option<&str>, all: bool) -> Result<Vec<ContainerStatus>> {
+    let registered = match auto_open_read() {
+        Ok(registered) => registered,
+        // Without an autoOpen file no container is registered.
+        Err(SecureContainerErr::FileOpenError(_)) => Vec::new(),
+        Err(err) => return Err(err),
+    };
+    let devices = list_crypt_devices()?;
+    let mut status =
+        merge_container_status(registered, devices, namespace, all || namespace.is_some());
+    // Only registered containers have a known path, so the key slots and metadata of ad-hoc containers stay unknown.
+    for container in status
+        .iter_mut()
+        .filter(|container| !container.path.is_empty())
+    {
+        if let Ok(free) = free_keyslots(&container.path) {
+            container.free_keyslots = free.len() as u32;
+            container.used_keyslots = LUKS2_KEYSLOTS as u32 - container.free_keyslots;
+        }
+        if let Ok(Some(metadata)) = read_metadata(&container.path) {
+            container.cipher = metadata.cipher;
+            container.integrity = metadata.integrity;
+        }
+    }
+    Ok(status)
+}
+
+/// The state of a single container.
+/// * `open` - True if the container is open.
+/// * `mounted` - True if the container is mounted.
+/// * `mount_point` - The path where the container is mounted, empty if it is not mounted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerState {
+    pub open: bool,
+    pub mounted: bool,
+    pub mount_point: String,
+}
+
+/// Checks if a container is open and mounted, the container and the autoOpen file are not changed.
+/// # Arguments
+/// * `namespace` - The name of the container.
+/// # Returns
+/// * `Result<ContainerState>` -
+/// Returns the state of the container, a container that does not exist is closed.
+/// # Errors
+/// * `NamespaceNotValid` - The given namespace contains a pipe or control characters.
+/// * `LsblkError` - An error occurred executing lsblk.
+/// * `FileReadError` - An error occurred while reading `/proc/mounts`.
+/// # Example
+/// ```
+/// let state = container_state("MyContainer").unwrap();
+/// println!("open: {} mounted: {} {}", state.open, state.mounted, state.mount_point);
+/// ```
+///
+pub fn container_state(namespace: &str) -> Result<ContainerState> {
+    check_input(None, None, None, Some(namespace), None)?;
+    let device = device_name(namespace);
+    let open = check_container_open(&device)?;
+    let mut mount_point = String::new();
+    if open && check_container_mounted(&device)? {
+        mount_point = get_mount_point(&device)?.unwrap_or_default();
+    }
+    Ok(ContainerState {
+        open,
+        mounted: !mount_point.is_empty(),
+        mount_point,
+    })
+}
+
+/// Merges the entries of the autoOpen file with the open crypt devices.
+/// # Arguments
+/// * `registered` - The entries of the autoOpen file.
+/// * `devices` - The open crypt devices.
+/// * `namespace` - If set, only the container with this name is returned.
+/// * `all` - If true, the devices that are not in the autoOpen file are returned as well.
+/// # Returns
+/// * `Vec<ContainerStatus>` - The registered containers followed by the ad-hoc containers.
+/// # Note
+/// This function is not meant to be called directly.
+fn merge_container_status(
+    registered: Vec<Vec<String>>,
+    devices: Vec<BlockDevice>,
+    namespace: Option<&str>,
+    all: bool,
+) -> Vec<ContainerStatus> {
+    let mut status: Vec<ContainerStatus> = Vec::new();
+    for container in registered.iter().filter(|container| container.len() > 3) {
+        let device = devices
+            .iter()
+            .find(|device| device.name == device_name(&container[2]));
+        status.push(ContainerStatus {
+            namespace: container[2].clone(),
+            path: container[1].clone(),
+            mount_point: container[0].clone(),
+            open: device.is_some(),
+            mounted: device.is_some_and(|device| device.mountpoint.is_some()),
+            source: ContainerSource::Registered,
+            used_keyslots: 0,
+            free_keyslots: 0,
+            cipher: String::new(),
+            integrity: String::new(),
+        });
+    }
+    if all {
+        for device in devices {
+            let namespace = namespace_of_device(&device.name);
+            if status
+                .iter()
+                .any(|container| container.namespace == namespace)
+            {
+                continue;
             }
+            status.push(ContainerStatus {
+                namespace,
+                path: String::new(),
+                mounted: device.mountpoint.is_some(),
+                mount_point: device.mountpoint.unwrap_or_default(),
+                open: true,
+                source: ContainerSource::AdHoc,
+                used_keyslots: 0,
+                free_keyslots: 0,
+                cipher: String::new(),
+                integrity: String::new(),
+            });
         }
     }
-    Ok(())
+    if let Some(namespace) = namespace {
+        status.retain(|container| container.namespace == namespace);
+    }
+    status
+}
+
+/// The difference between the autoOpen file and the containers that are open, by namespace.
+/// * `open` - The registered containers that are open.
+/// * `closed` - The registered containers that are closed.
+/// * `missing` - The registered containers whose file or block device does not exist.
+/// * `unregistered` - The open crypt devices that are not in the autoOpen file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StateDiff {
+    pub open: Vec<String>,
+    pub closed: Vec<String>,
+    pub missing: Vec<String>,
+    pub unregistered: Vec<String>,
+}
+
+/// Compares the autoOpen file with the open crypt devices, e.g. to find containers that have to be reconciled.
+/// # Returns
+/// * `Result<StateDiff>` -
+/// Returns the registered containers by state and the open containers that are not registered.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading the autoOpen file.
+/// * `LsblkError` - An error occurred executing lsblk.
+/// # Example
+/// ```
+/// let diff = diff_state().unwrap();
+/// println!("{} containers are not registered", diff.unregistered.len());
+/// ```
+///
+pub fn diff_state() -> Result<StateDiff> {
+    let registered = match auto_open_read() {
+        Ok(registered) => registered,
+        // Without an autoOpen file no container is registered.
+        Err(SecureContainerErr::FileOpenError(_)) => Vec::new(),
+        Err(err) => return Err(err),
+    };
+    let status = merge_container_status(registered, list_crypt_devices()?, None, true);
+    Ok(diff_container_status(status, |path| {
+        std::path::Path::new(path).exists()
+    }))
+}
+
+/// Sorts the status of the containers into the states of `StateDiff`.
+/// # Arguments
+/// * `status` - The registered and ad-hoc containers (see `merge_container_status`).
+/// * `exists` - Checks if the file or block device of a registered container exists.
+/// # Returns
+/// * `StateDiff` - The containers by state, a missing file takes precedence over the open state.
+/// # Note
+/// This function is not meant to be called directly.
+fn diff_container_status(status: Vec<ContainerStatus>, exists: impl Fn(&str) -> bool) -> StateDiff {
+    let mut diff = StateDiff::default();
+    for container in status {
+        let list = match container.source {
+            ContainerSource::AdHoc => &mut diff.unregistered,
+            ContainerSource::Registered if !exists(&container.path) => &mut diff.missing,
+            ContainerSource::Registered if container.open => &mut diff.open,
+            ContainerSource::Registered => &mut diff.closed,
+        };
+        list.push(container.namespace);
+    }
+    diff
+}
+
+/// The public metadata and the state of a single container for an inventory report, it contains no secrets.
+/// * `namespace` - The name of the container.
+/// * `path` - The path to the container, for ad-hoc containers the file or block device they are opened on.
+/// * `mount_point` - The configured mount point, for ad-hoc containers the current mount point.
+/// * `uuid` - The UUID of the LUKS header, empty if the header could not be read.
+/// * `cipher` - The cipher from the metadata of the container, empty if it has no metadata.
+/// * `integrity` - The integrity algorithm from the metadata of the container, empty if it has no metadata.
+/// * `created` -
+/// The time the container was created in the format `TIME_FORMAT`.
+/// For containers without a recorded time the creation time of the file is used, if the file system records it.
+/// * `open` - True if the container is open.
+/// * `mounted` - True if the container is mounted.
+/// * `source` - Where the container is known from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerInventoryItem {
+    pub namespace: String,
+    pub path: String,
+    pub mount_point: String,
+    pub uuid: String,
+    pub cipher: String,
+    pub integrity: String,
+    pub created: String,
+    pub open: bool,
+    pub mounted: bool,
+    pub source: ContainerSource,
+}
+
+/// Collects the public metadata and the state of every container the system manages,
+/// the registered containers of the autoOpen file and the open ad-hoc containers.
+/// # Arguments
+/// # Returns
+/// * `Result<Vec<ContainerInventoryItem>>` -
+/// Returns the registered containers followed by the ad-hoc containers.
+/// Values that could not be read, e.g. of a container whose file does not exist, are left empty.
+/// # Errors
+/// * `FileReadError` - An error occurred while reading the autoOpen file.
+/// * `LsblkError` - An error occurred executing lsblk.
+/// # Example
+/// ```
+/// let result = inventory();
+/// for container in result.unwrap() {
+///     println!("{} {} {}", container.namespace, container.uuid, container.created);
+/// }
+/// ```
+///
+pub fn inventory() -> Result<Vec<ContainerInventoryItem>> {
+    let status = container_status(None, true)?;
+    Ok(status
+        .into_iter()
+        .map(|container| {
+            // The path of an ad-hoc container is taken from the device it is opened on.
+            let path = if container.path.is_empty() {
+                get_backing_path(&device_name(&container.namespace)).unwrap_or_default()
+            } else {
+                container.path.clone()
+            };
+            let metadata = match path.is_empty() {
+                true => None,
+                false => read_metadata(&path).ok().flatten(),
+            };
+            let mut item = inventory_item(container, path, metadata);
+            if item.uuid.is_empty() && !item.path.is_empty() {
+                item.uuid = get_container_uuid(&item.path).unwrap_or_default();
+            }
+            if item.created.is_empty() {
+                item.created = file_created_time(&item.path);
+            }
+            item
+        })
+        .collect())
+}
+
+/// Combines the status of a container with its metadata.
+/// # Arguments
+/// * `status` - The status of the container.
+/// * `path` - The path to the container.
+/// * `metadata` - The metadata of the container, if it has any.
+/// # Returns
+/// * `ContainerInventoryItem` - The item, the values of the metadata take precedence over the status.
+/// # Note
+/// This function is not meant to be called directly.
+fn inventory_item(
+    status: ContainerStatus,
+    path: String,
+    metadata: Option<ContainerMetadata>,
+) -> ContainerInventoryItem {
+    let mut item = ContainerInventoryItem {
+        namespace: status.namespace,
+        path,
+        mount_point: status.mount_point,
+        uuid: String::new(),
+        cipher: status.cipher,
+        integrity: status.integrity,
+        created: String::new(),
+        open: status.open,
+        mounted: status.mounted,
+        source: status.source,
+    };
+    if let Some(metadata) = metadata {
+        item.uuid = metadata.uuid;
+        item.cipher = metadata.cipher;
+        item.integrity = metadata.integrity;
+        item.created = metadata.created;
+    }
+    item
+}
+
+/// Returns the creation time of a file in the format `TIME_FORMAT`.
+/// # Arguments
+/// * `path` - The path to the file.
+/// # Returns
+/// * `String` - The creation time, empty if the file does not exist or the file system does not record it.
+/// # Note
+/// This function is not meant to be called directly.
+fn file_created_time(path: &str) -> String {
+    match std::fs::metadata(path).and_then(|metadata| metadata.created()) {
+        Ok(created) => chrono::DateTime::<chrono::Local>::from(created)
+            .format(TIME_FORMAT)
+            .to_string(),
+        Err(_) => String::new(),
+    }
 }
 
 /// Converts a byte stream to a base64 string.
@@ -152,13 +1198,38 @@ pub fn auto_close() -> Result<()> {
 /// ```
 ///
 pub fn convert_to_base64(binary: Vec<u8>) -> String {
+    base64_engine().encode(binary)
+}
+
+/// Converts a base64 string of `convert_to_base64` back to the byte stream, e.g. to recover the raw key bytes.
+/// # Arguments
+/// * `string` - The base64 string without padding.
+/// # Returns
+/// * `Result<Vec<u8>>` -
+/// Returns the decoded byte stream otherwise an error is returned.
+/// # Errors
+/// * `Base64Error` - The string contains a character that is not in the alphabet or has padding.
+/// # Example
+/// ```
+/// let output = convert_from_base64("AAECAwQFBgcICQ");
+/// assert_eq!(output, Ok(vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+/// ```
+///
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn convert_from_base64(string: &str) -> Result<Vec<u8>> {
+    base64_engine()
+        .decode(string)
+        .map_err(|err| SecureContainerErr::Base64Error(err.to_string()))
+}
+
+/// Returns the base64 engine of the passwords, the standard alphabet without padding.
+/// # Note
+/// This function is not meant to be called directly.
+fn base64_engine() -> engine::GeneralPurpose {
     let alphabet =
         alphabet::Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/")
             .unwrap();
-    let engine: engine::GeneralPurpose =
-        engine::GeneralPurpose::new(&alphabet, general_purpose::NO_PAD);
-    let password = engine.encode(binary);
-    password
+    engine::GeneralPurpose::new(&alphabet, general_purpose::NO_PAD)
 }
 
 /// Converts MB in bytes.
@@ -179,25 +1250,122 @@ pub fn mb_in_bytes(mb: i32) -> u64 {
     (mb * 1024 * 1024) as u64
 }
 
+/// The format of the times that are compared with the kernel log, e.g. `2024-03-01T12:00`.
+pub const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Parses a local time that is used as cutoff for the integrity check.
+/// # Arguments
+/// * `time` - The time in the format `TIME_FORMAT`, optionally followed by seconds (`:%S`).
+/// # Returns
+/// * `Result<DateTime<FixedOffset>>` - Returns the time with the offset of the local timezone otherwise an error is returned.
+/// # Errors
+/// * `TimeNotValid` - The given time does not match the format or does not exist in the local timezone.
+/// # Example
+/// ```
+/// let time = parse_time("2024-03-01T12:00").unwrap();
+/// assert_eq!(time.format(TIME_FORMAT).to_string(), "2024-03-01T12:00");
+/// ```
+///
+pub fn parse_time(time: &str) -> Result<DateTime<FixedOffset>> {
+    let format_with_seconds = format!("{}:%S", TIME_FORMAT);
+    let naive = NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(time, &format_with_seconds))
+        .map_err(|_| SecureContainerErr::TimeNotValid)?;
+    // A time that is skipped by a change to daylight saving time does not exist.
+    match Local.from_local_datetime(&naive).earliest() {
+        Some(time) => Ok(time.fixed_offset()),
+        None => Err(SecureContainerErr::TimeNotValid),
+    }
+}
+
+/// The suffix cryptsetup appends to the name of the dm-integrity device below an open container.
+const INTEGRITY_DEVICE_SUFFIX: &str = "_dif";
+
 /// Check the integrity of the container.
+/// The mismatch counter of the dm-integrity device of the container is read with `dmsetup status`,
+/// it counts every mismatch since the container was opened, even if the kernel log has already been overwritten.
+/// The counter only contains the mismatches that dm-integrity detects itself,
+/// the errors of the authenticated encryption are only logged by dm-crypt,
+/// so the kernel log is searched as well if it can be read.
+/// If neither is available, the error of reading the kernel log is returned.
 /// # Arguments
-/// * `current_time` - The current time.
+/// * `device` - The name of the device mapper device of the container.
+/// * `since` -
+/// Only integrity errors logged at or after this time are considered,
+/// e.g. the time that was taken before the container was opened.
+/// If `None`, every integrity error in the kernel log is considered.
 /// # Returns
 /// * `Result<bool>` -
 /// Returns true if the container passed the integrity check otherwise false.
 /// In case of an error, this error is returned.
 /// # Errors
-/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
+/// * `CryptsetupError` - An error occurred while executing the dmesg command.
 /// * `ReadingStdoutError` - An error occurred while reading stdout.
 /// # Example
 /// ```
-/// let current_time = chrono::Local::now().format("%Y-%m-%dT%H:%M").to_string();
-/// let result = check_integrity(&current_time);
+/// let opened = chrono::Local::now().fixed_offset();
+/// let result = check_integrity("MyContainer", Some(opened));
 /// assert_eq!(result.is_ok(), true);
 /// ```
 ///
-pub fn check_integrity(current_time: &str) -> Result<bool> {
-    let output = match Command::new("dmesg").args(["--time-format=iso"]).output() {
+pub fn check_integrity(device: &str, since: Option<DateTime<FixedOffset>>) -> Result<bool> {
+    let mismatches = integrity_mismatches(device);
+    if mismatches.is_some_and(|mismatches| mismatches > 0) {
+        return Ok(false);
+    }
+    match read_kernel_log() {
+        Ok(log) => Ok(!integrity_error_since(&log, since)),
+        Err(_) if mismatches.is_some() => Ok(true),
+        Err(err) => Err(err),
+    }
+}
+
+/// Reads the number of mismatches the dm-integrity device of a container counted since it was opened.
+/// # Arguments
+/// * `device` - The name of the device mapper device of the container.
+/// # Returns
+/// * `Option<u64>` - The number of mismatches or `None` if the status of the device can not be read.
+/// # Note
+/// This function is not meant to be called directly.
+fn integrity_mismatches(device: &str) -> Option<u64> {
+    let integrity_device = format!("{}{}", device, INTEGRITY_DEVICE_SUFFIX);
+    let output = deadline::output_or_kill(
+        Command::new(&config().dmsetup_path).args(["status", &integrity_device]),
+    )
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_integrity_status(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parses the mismatch counter from the status of a dm-integrity device.
+/// # Arguments
+/// * `status` - The output of `dmsetup status`, e.g. `0 2093056 integrity 0 2093056 -`.
+/// # Returns
+/// * `Option<u64>` - The number of mismatches or `None` if the device is no dm-integrity device.
+/// # Note
+/// This function is not meant to be called directly.
+fn parse_integrity_status(status: &str) -> Option<u64> {
+    let mut fields = status
+        .split_whitespace()
+        .skip_while(|field| *field != "integrity");
+    fields.next()?;
+    fields.next()?.parse().ok()
+}
+
+/// Reads the kernel log with ISO times.
+/// # Returns
+/// * `Result<String>` - The output of `dmesg --time-format=iso` otherwise an error is returned.
+/// # Errors
+/// * `CryptsetupError` - An error occurred while executing the dmesg command.
+/// * `ReadingStdoutError` - An error occurred while reading stdout.
+/// # Note
+/// This function is not meant to be called directly.
+fn read_kernel_log() -> Result<String> {
+    let output = match deadline::output_or_kill(
+        Command::new(&config().dmesg_path).args(["--time-format=iso"]),
+    ) {
         Ok(output) => output,
         Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
     };
@@ -209,67 +1377,560 @@ pub fn check_integrity(current_time: &str) -> Result<bool> {
         Ok(stdout) => stdout,
         Err(err) => return Err(SecureContainerErr::ReadingStdoutError(err)),
     };
-    let lines: Vec<&str> = stdout.split('\n').collect();
+    Ok(stdout)
+}
 
-    for line in lines {
-        if line.contains("INTEGRITY AEAD ERROR") {
-            let time = line.split(' ').collect::<Vec<&str>>()[0];
-            let time = time.split(',').collect::<Vec<&str>>()[0];
+/// Reads the time of a line of the kernel log.
+/// # Arguments
+/// * `line` - A line of `dmesg --time-format=iso`, e.g. `2024-03-01T11:59:59,000000+01:00 message`.
+/// # Returns
+/// * `Option<DateTime<FixedOffset>>` - The time of the line or `None` if the line does not start with a time.
+/// # Note
+/// This function is not meant to be called directly.
+fn kernel_log_time(line: &str) -> Option<DateTime<FixedOffset>> {
+    let time = line.split(' ').next()?;
+    // dmesg separates the fraction of the seconds with a comma, RFC 3339 with a dot.
+    DateTime::parse_from_rfc3339(&time.replacen(',', ".", 1)).ok()
+}
 
-            if time >= current_time {
-                return Ok(false);
-            }
-        }
-    }
-    Ok(true)
+/// Searches the kernel log for integrity errors.
+/// The times are compared as points in time, so the timezone of the log does not matter.
+/// # Arguments
+/// * `log` - The output of `dmesg --time-format=iso`.
+/// * `since` - The cutoff time, if `None` every integrity error is considered.
+/// # Returns
+/// * `bool` - true if an integrity error was logged at or after the cutoff time.
+/// # Note
+/// This function is not meant to be called directly.
+fn integrity_error_since(log: &str, since: Option<DateTime<FixedOffset>>) -> bool {
+    log.lines()
+        .filter(|line| line.contains("INTEGRITY AEAD ERROR"))
+        .any(|line| match (since, kernel_log_time(line)) {
+            (Some(since), Some(time)) => time >= since,
+            // An error without a readable time is reported, so no error is missed.
+            _ => true,
+        })
 }
 
+/// The file in which the kernel lists the algorithms of its crypto API.
+const PROC_CRYPTO: &str = "/proc/crypto";
+
 /// Check if integrity check is supported by operating system.
+/// The kernel crypto API is queried through `/proc/crypto` for the algorithms of the given pair.
+/// Templates like `xts` or `hmac` are instantiated on first use,
+/// so an algorithm is supported if it or its underlying algorithm is listed and passed its self test.
 /// # Arguments
+/// * `cipher` - The cipher in the format of cryptsetup, e.g. `aes-xts-plain64`.
+/// * `integrity` - The integrity algorithm in the format of cryptsetup, e.g. `hmac-sha256`.
 /// # Returns
 /// * `Result<bool>` -
 /// Returns true if the integrity check is supported by the operating system otherwise false.
 /// In case of an error, this error is returned.
 /// # Errors
-/// * `CryptsetupError` - An error occurred while executing the cryptsetup command.
-/// * `ReadingStdoutError` - An error occurred while reading stdout.
+/// * `FileReadError` - An error occurred while reading `/proc/crypto`.
 /// # Example
 /// ```
-/// let result = check_functionality_of_integrity();
+/// let result = check_functionality_of_integrity("aes-xts-plain64", "hmac-sha256");
 /// assert_eq!(result.unwrap(), true);
 /// ```
 ///
-pub fn check_functionality_of_integrity() -> Result<bool> {
-    let output = match Command::new("dmesg").args(["--time-format=iso"]).output() {
-        Ok(output) => output,
-        Err(err) => return Err(SecureContainerErr::CryptsetupError(err.to_string())),
-    };
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SecureContainerErr::CryptsetupError(stderr.to_string()));
-    }
-    let stdout = match String::from_utf8(output.stdout) {
-        Ok(stdout) => stdout,
-        Err(err) => return Err(SecureContainerErr::ReadingStdoutError(err)),
+pub fn check_functionality_of_integrity(cipher: &str, integrity: &str) -> Result<bool> {
+    let crypto = match std::fs::read_to_string(PROC_CRYPTO) {
+        Ok(crypto) => crypto,
+        Err(err) => return Err(SecureContainerErr::FileReadError(err.to_string())),
     };
-    let lines: Vec<&str> = stdout.split('\n').collect();
+    Ok(integrity_supported(&crypto, cipher, integrity))
+}
 
-    for line in lines {
-        if line.contains("alg: No test for authenc(hmac(sha256),xts(aes)) (authenc(hmac(sha256-avx2),xts-aes-aesni))") {
-            return Ok(false);
+/// Checks the content of `/proc/crypto` for the algorithms of a cipher and integrity pair.
+/// # Arguments
+/// * `crypto` - The content of `/proc/crypto`.
+/// * `cipher` - The cipher in the format of cryptsetup, e.g. `aes-xts-plain64`.
+/// * `integrity` - The integrity algorithm in the format of cryptsetup, e.g. `hmac-sha256`.
+/// # Returns
+/// * `bool` -
+/// Returns true if both algorithms are available and none of them,
+/// nor their combination as `authenc`, failed its self test.
+/// # Note
+/// This function is not meant to be called directly.
+fn integrity_supported(crypto: &str, cipher: &str, integrity: &str) -> bool {
+    let mut algorithms: Vec<(&str, &str)> = Vec::new();
+    for entry in crypto.split("\n\n") {
+        let mut name = "";
+        let mut selftest = "";
+        for line in entry.lines() {
+            match line.split_once(':') {
+                Some((key, value)) if key.trim() == "name" => name = value.trim(),
+                Some((key, value)) if key.trim() == "selftest" => selftest = value.trim(),
+                _ => (),
+            }
+        }
+        if !name.is_empty() {
+            algorithms.push((name, selftest));
         }
     }
-    Ok(true)
+
+    // cryptsetup names a cipher `<cipher>-<mode>-<iv>` and the kernel `<mode>(<cipher>)`.
+    let (cipher, cipher_base) = match cipher.split('-').collect::<Vec<&str>>()[..] {
+        [base, mode, ..] => (format!("{}({})", mode, base), base),
+        _ => (cipher.to_string(), cipher),
+    };
+    let (integrity, integrity_base) = match integrity.split_once('-') {
+        Some((template, base)) => (format!("{}({})", template, base), base),
+        None => (integrity.to_string(), integrity),
+    };
+    let authenc = format!("authenc({},{})", integrity, cipher);
+    if algorithms.iter().any(|(name, selftest)| {
+        [authenc.as_str(), cipher.as_str(), integrity.as_str()].contains(name)
+            && *selftest == "failed"
+    }) {
+        return false;
+    }
+    let available = |algorithm: &str, base: &str| {
+        algorithms
+            .iter()
+            .any(|(name, selftest)| (*name == algorithm || *name == base) && *selftest != "failed")
+    };
+    available(&cipher, cipher_base) && available(&integrity, integrity_base)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input_validation::MAX_ID_LENGTH;
+    use crate::metadata::KEY_DERIVATION;
     #[test]
     fn test_check_functionality_of_integrity() {
-        let output = check_functionality_of_integrity();
-        assert_eq!(output.is_ok(), false);
+        let output = check_functionality_of_integrity("aes-xts-plain64", "hmac-sha256");
+        assert!(output.is_ok());
+        let crypto = "name         : aes\n\
+            driver       : aes-generic\n\
+            selftest     : passed\n\
+            \n\
+            name         : hmac(sha256)\n\
+            driver       : hmac(sha256-generic)\n\
+            selftest     : passed\n";
+        assert!(integrity_supported(
+            crypto,
+            "aes-xts-plain64",
+            "hmac-sha256"
+        ));
+        assert!(!integrity_supported(
+            crypto,
+            "aes-xts-plain64",
+            "hmac-sha512"
+        ));
+        assert!(!integrity_supported(
+            crypto,
+            "serpent-xts-plain64",
+            "hmac-sha256"
+        ));
+        let failed = format!(
+            "{}\nname         : authenc(hmac(sha256),xts(aes))\nselftest     : failed\n",
+            crypto
+        );
+        assert!(!integrity_supported(
+            &failed,
+            "aes-xts-plain64",
+            "hmac-sha256"
+        ));
     }
+    #[test]
+    fn test_check_dependencies() {
+        let report = check_dependencies();
+        let names: Vec<&str> = report.iter().map(|check| check.name.as_str()).collect();
+        for name in [
+            "cryptsetup",
+            "lsblk",
+            "mount",
+            "umount",
+            "mkfs.ext4",
+            "dmesg",
+        ] {
+            assert!(names.contains(&name));
+        }
+        assert!(names.contains(&"libuta"));
+        for name in [
+            "integrity",
+            "mkswap",
+            "swapon",
+            "swapoff",
+            "mkfs.xfs",
+            "mkfs.btrfs",
+        ] {
+            assert!(
+                !report
+                    .iter()
+                    .find(|check| check.name == name)
+                    .unwrap()
+                    .essential
+            );
+        }
+
+        let missing = check_binary("missing", "/not/existing/binary", "--version");
+        assert!(!missing.available);
+        assert!(missing.to_string().starts_with("[FAIL] missing:"));
+
+        // A binary that runs but fails is not available.
+        let failing = check_binary("ls", "ls", "/not/existing/path");
+        assert!(!failing.available);
+        assert!(failing.details.contains("exit status"));
+    }
+
+    #[test]
+    fn test_container_state() {
+        assert_eq!(
+            container_state("test|"),
+            Err(SecureContainerErr::NamespaceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_merge_container_status() {
+        let registered = vec![
+            vec![
+                "/mnt/a".to_string(),
+                "/srv/a".to_string(),
+                "a".to_string(),
+                "id".to_string(),
+            ],
+            vec![
+                "/mnt/b".to_string(),
+                "/srv/b".to_string(),
+                "b".to_string(),
+                "id".to_string(),
+            ],
+        ];
+        let devices = vec![
+            BlockDevice {
+                name: "a".to_string(),
+                device_type: "crypt".to_string(),
+                mountpoint: Some("/mnt/a".to_string()),
+                children: Vec::new(),
+            },
+            BlockDevice {
+                name: "manual".to_string(),
+                device_type: "crypt".to_string(),
+                mountpoint: None,
+                children: Vec::new(),
+            },
+        ];
+
+        let status = merge_container_status(registered.clone(), devices.clone(), None, false);
+        assert_eq!(status.len(), 2);
+        assert!(status[0].open && status[0].mounted);
+        assert!(!status[1].open && !status[1].mounted);
+
+        let status = merge_container_status(registered.clone(), devices.clone(), None, true);
+        assert_eq!(
+            diff_container_status(status.clone(), |path| path != "/srv/b"),
+            StateDiff {
+                open: vec!["a".to_string()],
+                closed: Vec::new(),
+                missing: vec!["b".to_string()],
+                unregistered: vec!["manual".to_string()],
+            }
+        );
+        assert_eq!(
+            diff_container_status(status.clone(), |_| true).closed,
+            vec!["b".to_string()]
+        );
+        assert_eq!(status.len(), 3);
+        assert_eq!(status[2].namespace, "manual");
+        assert_eq!(status[2].source, ContainerSource::AdHoc);
+        assert!(status[2].open && !status[2].mounted);
+
+        let status = merge_container_status(registered, devices, Some("manual"), true);
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].source.to_string(), "ad-hoc");
+
+        let registered = vec![vec![
+            "/mnt/u".to_string(),
+            "/srv/u".to_string(),
+            "Übung".to_string(),
+            "id".to_string(),
+        ]];
+        let devices = vec![BlockDevice {
+            name: "%C3%9Cbung".to_string(),
+            device_type: "crypt".to_string(),
+            mountpoint: None,
+            children: Vec::new(),
+        }];
+        let status = merge_container_status(registered, devices.clone(), None, true);
+        assert_eq!(status.len(), 1);
+        assert!(status[0].open);
+        let status = merge_container_status(Vec::new(), devices, None, true);
+        assert_eq!(status[0].namespace, "Übung");
+    }
+
+    #[test]
+    fn test_integrity_error_since() {
+        let log = "2024-03-01T11:59:59,000000+01:00 device-mapper: crypt: dm-0: INTEGRITY AEAD ERROR, sector 8\n\
+                   2024-03-01T12:05:00,000000+01:00 EXT4-fs (dm-0): mounted filesystem\n";
+        let time = |time: &str| Some(DateTime::parse_from_rfc3339(time).unwrap());
+        assert!(integrity_error_since(log, None));
+        assert!(integrity_error_since(
+            log,
+            time("2024-03-01T11:59:00+01:00")
+        ));
+        assert!(integrity_error_since(
+            log,
+            time("2024-03-01T11:59:59+01:00")
+        ));
+        assert!(!integrity_error_since(
+            log,
+            time("2024-03-01T11:59:59.5+01:00")
+        ));
+        // The same point in time in another timezone, a string comparison would report the error.
+        assert!(!integrity_error_since(
+            log,
+            time("2024-03-01T11:00:00+00:00")
+        ));
+        assert!(integrity_error_since(
+            log,
+            time("2024-03-01T10:59:00+00:00")
+        ));
+        assert!(!integrity_error_since("", None));
+        assert!(integrity_error_since(
+            "[   12.345678] INTEGRITY AEAD ERROR",
+            time("2024-03-01T12:00:00+01:00")
+        ));
+    }
+
+    #[test]
+    fn test_parse_integrity_status() {
+        assert_eq!(
+            parse_integrity_status("0 2093056 integrity 0 2093056 -\n"),
+            Some(0)
+        );
+        assert_eq!(
+            parse_integrity_status("0 2093056 integrity 3 2093056 -\n"),
+            Some(3)
+        );
+        assert_eq!(parse_integrity_status("0 2093056 crypt \n"), None);
+        assert_eq!(parse_integrity_status(""), None);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert!(parse_time("2024-03-01T12:00").is_ok());
+        assert!(parse_time("2024-03-01T12:00:30").is_ok());
+        assert_eq!(
+            parse_time("2024-03-01 12:00"),
+            Err(SecureContainerErr::TimeNotValid)
+        );
+        assert_eq!(parse_time(""), Err(SecureContainerErr::TimeNotValid));
+        assert_eq!(
+            parse_time("2024-03-01T12:00:30").unwrap().naive_local(),
+            NaiveDateTime::parse_from_str("2024-03-01T12:00:30", "%Y-%m-%dT%H:%M:%S").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_wait_for_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("container");
+        let path_str = path.to_str().unwrap().to_string();
+        let interval = Duration::from_millis(10);
+        assert_eq!(
+            wait_for_path(&path_str, Duration::from_millis(50), interval),
+            Err(SecureContainerErr::PathWaitTimeout)
+        );
+
+        let creator = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            std::fs::write(path, b"").unwrap();
+        });
+        assert_eq!(
+            wait_for_path(&path_str, Duration::from_secs(10), interval),
+            Ok(())
+        );
+        creator.join().unwrap();
+
+        let entry = ["/mnt", "/not/existing/container", "namespace", "id"].map(String::from);
+        assert_eq!(wait_if_marked(&entry), Ok(()));
+    }
+
+    #[test]
+    fn test_inventory_item() {
+        let status = ContainerStatus {
+            namespace: "MyContainer".to_string(),
+            path: "/home/Container".to_string(),
+            mount_point: "/home/MountMe".to_string(),
+            open: true,
+            mounted: true,
+            source: ContainerSource::Registered,
+            used_keyslots: 1,
+            free_keyslots: 31,
+            cipher: String::new(),
+            integrity: String::new(),
+        };
+        let item = inventory_item(status.clone(), status.path.clone(), None);
+        assert_eq!(item.namespace, "MyContainer");
+        assert_eq!((item.uuid.as_str(), item.created.as_str()), ("", ""));
+        assert!(item.open && item.mounted);
+
+        let metadata = ContainerMetadata {
+            version: crate::metadata::METADATA_VERSION,
+            namespace: "MyContainer".to_string(),
+            device_name: "MyContainer".to_string(),
+            uuid: "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11".to_string(),
+            cipher: "aes-xts-plain64".to_string(),
+            integrity: "hmac-sha256".to_string(),
+            pbkdf: "argon2id".to_string(),
+            iter_time: 2000,
+            created: "2024-03-01T12:00".to_string(),
+            transfer_salt: String::new(),
+            file_system: "ext4".to_string(),
+            key_salt: String::new(),
+            key_derivation: crate::metadata::KEY_DERIVATION,
+        };
+        let item = inventory_item(status, "/home/Container".to_string(), Some(metadata));
+        assert_eq!(item.uuid, "5e5a4f0a-3e0c-4b8e-9d0e-0f6d0f6b8a11");
+        assert_eq!(item.cipher, "aes-xts-plain64");
+        assert_eq!(item.integrity, "hmac-sha256");
+        assert_eq!(item.created, "2024-03-01T12:00");
+        assert_eq!(file_created_time("/not/existing/container"), "");
+    }
+
+    #[test]
+    fn test_run_parallel() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let items: Vec<usize> = (0..20).collect();
+        let results = run_parallel(&items, 3, |item| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            running.fetch_sub(1, Ordering::SeqCst);
+            item * 2
+        });
+        assert_eq!(
+            results,
+            items.iter().map(|item| item * 2).collect::<Vec<_>>()
+        );
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+        assert!(max_running.load(Ordering::SeqCst) > 1);
+
+        assert_eq!(
+            run_parallel(&items[..0], 3, |item| *item),
+            Vec::<usize>::new()
+        );
+        assert_eq!(run_parallel(&items[..2], 0, |item| *item), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_opening_auto_open() {
+        let containers: Vec<Vec<String>> = [
+            ["/mnt/a", "/srv/a", "a", "id"],
+            ["/mnt/b", "/srv/b", "b|", "id"],
+            ["/mnt/c", "/srv/c", "c", "id"],
+        ]
+        .iter()
+        .map(|container| container.map(String::from).to_vec())
+        .collect();
+        let opened = Mutex::new(Vec::new());
+        let results = opening_auto_open(&containers, 1, |container| {
+            check_input(None, None, None, Some(&container[2]), None)?;
+            opened.lock().unwrap().push(container[2].clone());
+            Ok(())
+        });
+        assert_eq!(
+            results,
+            vec![
+                OpenResult {
+                    namespace: "a".to_string(),
+                    result: Ok(()),
+                },
+                OpenResult {
+                    namespace: "b|".to_string(),
+                    result: Err(SecureContainerErr::NamespaceNotValid),
+                },
+                OpenResult {
+                    namespace: "c".to_string(),
+                    result: Ok(()),
+                },
+            ]
+        );
+        assert_eq!(*opened.lock().unwrap(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_close_with_retries() {
+        let mut attempts = 0;
+        let result = close_with_retries(
+            || {
+                attempts += 1;
+                Err(SecureContainerErr::UmountError("busy".to_string()))
+            },
+            AUTO_CLOSE_ATTEMPTS,
+            Duration::from_millis(1),
+        );
+        assert_eq!(
+            result,
+            Err(SecureContainerErr::UmountError("busy".to_string()))
+        );
+        assert_eq!(attempts, AUTO_CLOSE_ATTEMPTS);
+
+        let mut attempts = 0;
+        let result = close_with_retries(
+            || {
+                attempts += 1;
+                if attempts < 2 {
+                    Err(SecureContainerErr::UmountError("busy".to_string()))
+                } else {
+                    Ok(())
+                }
+            },
+            AUTO_CLOSE_ATTEMPTS,
+            Duration::from_millis(1),
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_auto_close_without_auto_open() {
+        assert_eq!(auto_close(), Vec::new());
+    }
+
+    #[test]
+    fn test_rotate_keys() {
+        assert_eq!(rotate_keys("test|"), Err(SecureContainerErr::IdNotValid));
+        assert_eq!(
+            rotate_keys(&"a".repeat(MAX_ID_LENGTH + 1)),
+            Err(SecureContainerErr::IdNotValid)
+        );
+        assert_eq!(
+            rotate_container("/path", "namespace", "test", "test"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_rebuild_entry() {
+        assert_eq!(
+            rebuild_entry("", "/srv/a", Some("id")),
+            Err(SecureContainerErr::MountPointNotExists)
+        );
+        assert!(matches!(
+            rebuild_entry("/mnt/a", "", Some("id")),
+            Err(SecureContainerErr::FileReadError(_))
+        ));
+        assert_eq!(
+            rebuild_entry("/mnt/a", "/not/existing/container", None),
+            Err(SecureContainerErr::IdNotValid)
+        );
+        assert_eq!(
+            rebuild_entry("/mnt/a", "/not/existing/container", Some("id")),
+            Err(SecureContainerErr::PathNotExists)
+        );
+        assert_eq!(
+            rebuild_auto_open(&HashMap::new(), Some("id|")),
+            Err(SecureContainerErr::IdNotValid)
+        );
+    }
+
     #[test]
     fn test_get_password() {
         let input = "test";
@@ -279,6 +1940,63 @@ mod tests {
         //assert_eq!(output.is_ok(), true);
     }
 
+    #[test]
+    fn test_get_random_password() {
+        let first = get_random_password().unwrap();
+        let second = get_random_password().unwrap();
+        assert_eq!(first.len(), get_password("test").unwrap().len());
+        assert_ne!(first, second);
+        let salt = get_random_salt().unwrap();
+        assert_eq!(salt.len(), 22);
+        assert_ne!(salt, get_random_salt().unwrap());
+    }
+
+    #[test]
+    fn test_get_salted_password() {
+        let salt = get_random_salt().unwrap();
+        let password = get_salted_password("test", &salt, KEY_DERIVATION).unwrap();
+        assert_eq!(
+            password,
+            get_salted_password("test", &salt, KEY_DERIVATION).unwrap()
+        );
+        assert_eq!(password.len(), get_password("test").unwrap().len());
+        assert_ne!(password, get_password("test").unwrap());
+        assert_ne!(
+            password,
+            get_salted_password("test", &get_random_salt().unwrap(), KEY_DERIVATION).unwrap()
+        );
+        assert_eq!(
+            get_salted_password("test", "", LEGACY_KEY_DERIVATION).unwrap(),
+            get_password("test").unwrap()
+        );
+        assert!(matches!(
+            get_salted_password("test", "not base64!", KEY_DERIVATION),
+            Err(SecureContainerErr::Base64Error(_))
+        ));
+    }
+
+    #[test]
+    fn test_derive_key_versions() {
+        // Ids that fit into the legacy derivation keep their key, so existing containers can still be opened.
+        assert_eq!(
+            derive_key("testtest", KEY_DERIVATION).unwrap(),
+            derive_key("testtest", LEGACY_KEY_DERIVATION).unwrap()
+        );
+        assert_eq!(
+            derive_key("test", KEY_DERIVATION).unwrap(),
+            libuta_derive_key("test").unwrap()
+        );
+        // Longer ids only use the whole id with the newer key derivation.
+        assert_eq!(
+            derive_key("testtest1", LEGACY_KEY_DERIVATION).unwrap(),
+            derive_key("testtest2", LEGACY_KEY_DERIVATION).unwrap()
+        );
+        assert_ne!(
+            derive_key("testtest1", KEY_DERIVATION).unwrap(),
+            derive_key("testtest2", KEY_DERIVATION).unwrap()
+        );
+    }
+
     #[test]
     fn test_convert_to_base64() {
         let input = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
@@ -286,6 +2004,32 @@ mod tests {
         assert_eq!(output, "AAECAwQFBgcICQ");
     }
 
+    #[test]
+    fn test_convert_from_base64() {
+        let inputs: [Vec<u8>; 6] = [
+            vec![],
+            vec![0],
+            vec![255, 254],
+            vec![0, 1, 2],
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+            (0..=255).collect(),
+        ];
+        for input in inputs {
+            assert_eq!(
+                convert_from_base64(&convert_to_base64(input.clone())),
+                Ok(input)
+            );
+        }
+        assert!(matches!(
+            convert_from_base64("AA=="),
+            Err(SecureContainerErr::Base64Error(_))
+        ));
+        assert!(matches!(
+            convert_from_base64("AA*"),
+            Err(SecureContainerErr::Base64Error(_))
+        ));
+    }
+
     #[test]
     fn test_mb_in_bytes() {
         let input = 10;